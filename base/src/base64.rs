@@ -0,0 +1,92 @@
+//! Standard base64 (RFC 4648) used by text formats to represent binary values
+
+use crate::error::bail;
+use crate::result::LoadumResult;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// Decodes base64, ignoring ASCII whitespace and accepting missing padding
+pub fn decode(text: &str) -> LoadumResult<Vec<u8>> {
+    let mut output = Vec::with_capacity(text.len() / 4 * 3);
+    let mut n = 0u32;
+    let mut bits = 0;
+    let mut padding = false;
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => {
+                padding = true;
+                continue;
+            }
+            c if c.is_ascii_whitespace() => continue,
+            _ => bail!("Invalid base64 character '{}'", c.escape_ascii()),
+        };
+        if padding {
+            bail!("Invalid base64: data after padding");
+        }
+        n = n << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((n >> bits) as u8);
+        }
+    }
+    if bits >= 6 {
+        bail!("Invalid base64: truncated input");
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base64::{decode, encode};
+
+    #[test]
+    fn roundtrip() {
+        for (bytes, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"fooba", "Zm9vYmE="),
+            (b"foobar", "Zm9vYmFy"),
+            (&[0, 0xff, 0xfe], "AP/+"),
+        ] {
+            assert_eq!(encode(bytes), text);
+            assert_eq!(decode(text).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn decode_lenient() {
+        assert_eq!(decode("Zm9v\nYmE").unwrap(), b"fooba");
+        assert!(decode("Zm9v!").is_err());
+        assert!(decode("Zg==Zg").is_err());
+        assert!(decode("Z").is_err());
+    }
+}
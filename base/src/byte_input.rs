@@ -0,0 +1,199 @@
+//! Input of binary formats, either borrowed from a byte slice or pulled from a reader
+
+use crate::error::bail;
+use crate::read_buffer::ReadBuffer;
+use crate::result::LoadumResult;
+use crate::value::Value;
+use std::borrow::Cow;
+use std::io::Read;
+
+pub struct ByteInput<'source> {
+    source: Source<'source>,
+}
+
+enum Source<'source> {
+    Slice {
+        source: &'source [u8],
+        position: usize,
+    },
+    Read(ReadBuffer<Box<dyn Read + 'source>>),
+}
+
+impl<'source> ByteInput<'source> {
+    pub fn from_slice(source: &'source [u8]) -> ByteInput<'source> {
+        ByteInput {
+            source: Source::Slice {
+                source,
+                position: 0,
+            },
+        }
+    }
+
+    /// Reads incrementally, only buffering the input needed for the next value
+    pub fn from_reader(read: impl Read + 'source) -> ByteInput<'source> {
+        ByteInput {
+            source: Source::Read(ReadBuffer::new(Box::new(read))),
+        }
+    }
+
+    /// Offset of the next byte in the input
+    pub fn offset(&self) -> u64 {
+        match &self.source {
+            Source::Slice { position, .. } => *position as u64,
+            Source::Read(buffer) => buffer.offset(),
+        }
+    }
+
+    /// Returns true if there is no more input
+    pub fn is_at_end(&mut self) -> LoadumResult<bool> {
+        Ok(self.available(1)?.is_empty())
+    }
+
+    pub fn peek_u8(&mut self) -> LoadumResult<Option<u8>> {
+        Ok(self.available(1)?.first().copied())
+    }
+
+    pub fn read_u8(&mut self) -> LoadumResult<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> LoadumResult<[u8; N]> {
+        let array = self.require(N)?[..N].try_into().unwrap();
+        self.consume(N);
+        Ok(array)
+    }
+
+    /// Reads bytes, borrowing them if the input is a slice
+    pub fn read_bytes(&mut self, length: usize) -> LoadumResult<Cow<'source, [u8]>> {
+        self.require(length)?;
+        let bytes = match &mut self.source {
+            Source::Slice { source, position } => {
+                let source: &'source [u8] = source;
+                Cow::Borrowed(&source[*position..*position + length])
+            }
+            Source::Read(buffer) => Cow::Owned(buffer.data()[..length].to_vec()),
+        };
+        self.consume(length);
+        Ok(bytes)
+    }
+
+    /// Reads a UTF-8 string, borrowing it if the input is a slice
+    pub fn read_string(&mut self, length: usize) -> LoadumResult<Value<'source>> {
+        let offset = self.offset();
+        let value = match self.read_bytes(length)? {
+            Cow::Borrowed(bytes) => std::str::from_utf8(bytes).map(Value::BorrowedString).ok(),
+            Cow::Owned(bytes) => String::from_utf8(bytes).map(Value::string).ok(),
+        };
+        let Some(value) = value else {
+            bail!("Invalid UTF-8 in string at offset {}", offset);
+        };
+        Ok(value)
+    }
+
+    /// Reads a null terminated UTF-8 string, consuming the terminator
+    pub fn read_c_string(&mut self) -> LoadumResult<Value<'source>> {
+        let offset = self.offset();
+        let mut length = 0;
+        loop {
+            let available = self.available(length + 1)?;
+            if available.len() <= length {
+                bail!("Unterminated string at offset {}", offset);
+            }
+            match available[length..].iter().position(|&byte| byte == 0) {
+                Some(position) => break length += position,
+                None => length = available.len(),
+            }
+        }
+        let value = self.read_string(length)?;
+        self.consume(1);
+        Ok(value)
+    }
+
+    /// Returns at least the given number of bytes, or fails at the end of input
+    fn require(&mut self, length: usize) -> LoadumResult<&[u8]> {
+        let offset = self.offset();
+        let available = self.available(length)?;
+        if available.len() < length {
+            bail!("Unexpected end of input at offset {}", offset);
+        }
+        Ok(available)
+    }
+
+    /// Returns the buffered input, at least the given number of bytes unless the input ends
+    fn available(&mut self, length: usize) -> LoadumResult<&[u8]> {
+        match &mut self.source {
+            Source::Slice { source, position } => Ok(&source[*position..]),
+            Source::Read(buffer) => {
+                while buffer.data().len() < length && buffer.fill()? {}
+                Ok(buffer.data())
+            }
+        }
+    }
+
+    fn consume(&mut self, length: usize) {
+        match &mut self.source {
+            Source::Slice { position, .. } => *position += length,
+            Source::Read(buffer) => buffer.consume(length),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::byte_input::ByteInput;
+    use crate::value::Value;
+    use std::borrow::Cow;
+
+    #[test]
+    fn read_slice() {
+        let mut input = ByteInput::from_slice(b"\x01\x02\x03abcdef");
+        assert_eq!(input.peek_u8().unwrap(), Some(1));
+        assert_eq!(input.read_u8().unwrap(), 1);
+        assert_eq!(input.read_array::<2>().unwrap(), [2, 3]);
+        assert!(matches!(input.read_bytes(2).unwrap(), Cow::Borrowed(b"ab")));
+        assert!(matches!(
+            input.read_string(3).unwrap(),
+            Value::BorrowedString("cde")
+        ));
+        assert_eq!(input.offset(), 8);
+        assert!(!input.is_at_end().unwrap());
+        assert_eq!(
+            input.read_array::<2>().unwrap_err().to_string(),
+            "Unexpected end of input at offset 8"
+        );
+        input.read_u8().unwrap();
+        assert!(input.is_at_end().unwrap());
+        assert_eq!(input.peek_u8().unwrap(), None);
+    }
+
+    #[test]
+    fn read_c_string() {
+        let mut slice_input = ByteInput::from_slice(b"abc\0\0x");
+        let mut reader_input = ByteInput::from_reader(&b"abc\0\0x"[..]);
+        for input in [&mut slice_input, &mut reader_input] {
+            assert_eq!(input.read_c_string().unwrap().as_str(), Some("abc"));
+            assert_eq!(input.read_c_string().unwrap().as_str(), Some(""));
+            assert_eq!(
+                input.read_c_string().unwrap_err().to_string(),
+                "Unterminated string at offset 5"
+            );
+        }
+        assert!(matches!(
+            ByteInput::from_slice(b"a\0").read_c_string().unwrap(),
+            Value::BorrowedString("a")
+        ));
+    }
+
+    #[test]
+    fn read_reader() {
+        let mut input = ByteInput::from_reader(&b"\x01abc\xff"[..]);
+        assert_eq!(input.read_u8().unwrap(), 1);
+        assert_eq!(input.read_string(3).unwrap(), Value::string("abc"));
+        assert_eq!(input.offset(), 4);
+        assert_eq!(
+            input.read_string(1).unwrap_err().to_string(),
+            "Invalid UTF-8 in string at offset 4"
+        );
+        assert!(input.is_at_end().unwrap());
+    }
+}
@@ -0,0 +1,436 @@
+//! Date and time values shared by formats with native temporal types (YAML, TOML, CBOR, ...)
+
+use crate::error::bail;
+use crate::result::LoadumResult;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Date {
+    year: i32,
+    month: u8,
+    day: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Time {
+    hour: u8,
+    minute: u8,
+    second: u8,
+    nanosecond: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateTime {
+    /// A point in time with an offset from UTC in minutes
+    OffsetDateTime {
+        date: Date,
+        time: Time,
+        offset_minutes: i16,
+    },
+    LocalDateTime {
+        date: Date,
+        time: Time,
+    },
+    LocalDate(Date),
+    LocalTime(Time),
+}
+
+impl Date {
+    pub fn new(year: i32, month: u8, day: u8) -> LoadumResult<Date> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            bail!("Invalid date {:04}-{:02}-{:02}", year, month, day);
+        }
+        Ok(Date { year, month, day })
+    }
+
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Days since 1970-01-01
+    fn days_since_epoch(&self) -> i64 {
+        // Algorithm from http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = self.month as i64;
+        let day_of_year =
+            (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146097 + day_of_era - 719468
+    }
+
+    /// Returns None if the year does not fit
+    fn from_days_since_epoch(days: i64) -> Option<Date> {
+        // Algorithm from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Some(Date {
+            year: i32::try_from(year).ok()?,
+            month: month as u8,
+            day: day as u8,
+        })
+    }
+}
+
+impl Time {
+    pub fn new(hour: u8, minute: u8, second: u8, nanosecond: u32) -> LoadumResult<Time> {
+        // Allow a leap second
+        if hour > 23 || minute > 59 || second > 60 || nanosecond > 999_999_999 {
+            bail!(
+                "Invalid time {:02}:{:02}:{:02}.{:09}",
+                hour,
+                minute,
+                second,
+                nanosecond
+            );
+        }
+        Ok(Time {
+            hour,
+            minute,
+            second,
+            nanosecond,
+        })
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u8 {
+        self.second
+    }
+
+    pub fn nanosecond(&self) -> u32 {
+        self.nanosecond
+    }
+
+    fn seconds_since_midnight(&self) -> i64 {
+        self.hour as i64 * 3600 + self.minute as i64 * 60 + self.second as i64
+    }
+}
+
+impl DateTime {
+    /// Parses RFC 3339 date-times, as well as local date-times, dates and times
+    ///
+    /// A space may be used instead of the `T` separating date and time, as permitted by RFC 3339
+    /// and used by YAML and TOML.
+    pub fn parse(text: &str) -> LoadumResult<DateTime> {
+        let mut parser = Parser {
+            text: text.as_bytes(),
+            position: 0,
+        };
+        let result = parser.parse_date_time();
+        match result {
+            Ok(date_time) if parser.position == text.len() => Ok(date_time),
+            _ => bail!("Invalid date/time '{}'", text),
+        }
+    }
+
+    /// Creates a UTC date-time from seconds and nanoseconds since the unix epoch
+    ///
+    /// Fails if the year does not fit an `i32` or the nanoseconds exceed a second.
+    pub fn from_unix_timestamp(seconds: i64, nanosecond: u32) -> LoadumResult<DateTime> {
+        let days = seconds.div_euclid(86400);
+        let seconds_of_day = seconds.rem_euclid(86400);
+        let Some(date) = Date::from_days_since_epoch(days) else {
+            bail!("Unix timestamp {} is out of the date range", seconds);
+        };
+        let time = Time::new(
+            (seconds_of_day / 3600) as u8,
+            (seconds_of_day / 60 % 60) as u8,
+            (seconds_of_day % 60) as u8,
+            nanosecond,
+        )?;
+        Ok(DateTime::OffsetDateTime {
+            date,
+            time,
+            offset_minutes: 0,
+        })
+    }
+
+    /// Returns seconds and nanoseconds since the unix epoch, if this is an offset date-time
+    pub fn unix_timestamp(&self) -> Option<(i64, u32)> {
+        let DateTime::OffsetDateTime {
+            date,
+            time,
+            offset_minutes,
+        } = self
+        else {
+            return None;
+        };
+        let seconds = date.days_since_epoch() * 86400 + time.seconds_since_midnight()
+            - *offset_minutes as i64 * 60;
+        Some((seconds, time.nanosecond))
+    }
+}
+
+fn days_in_month(year: i32, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn parse_date_time(&mut self) -> LoadumResult<DateTime> {
+        if self.text.get(2) == Some(&b':') {
+            return Ok(DateTime::LocalTime(self.parse_time()?));
+        }
+        let date = self.parse_date()?;
+        match self.text.get(self.position) {
+            Some(b'T' | b't' | b' ') if self.position + 1 < self.text.len() => {
+                self.position += 1;
+            }
+            _ => return Ok(DateTime::LocalDate(date)),
+        }
+        let time = self.parse_time()?;
+        let offset_minutes = match self.text.get(self.position) {
+            None => return Ok(DateTime::LocalDateTime { date, time }),
+            Some(b'Z' | b'z') => {
+                self.position += 1;
+                0
+            }
+            Some(&sign @ (b'+' | b'-')) => {
+                self.position += 1;
+                let hours = self.parse_digits(2)?;
+                self.expect(b':')?;
+                let minutes = self.parse_digits(2)?;
+                if hours > 23 || minutes > 59 {
+                    bail!("Invalid offset");
+                }
+                let offset = (hours * 60 + minutes) as i16;
+                if sign == b'-' { -offset } else { offset }
+            }
+            Some(_) => bail!("Invalid offset"),
+        };
+        Ok(DateTime::OffsetDateTime {
+            date,
+            time,
+            offset_minutes,
+        })
+    }
+
+    fn parse_date(&mut self) -> LoadumResult<Date> {
+        let year = self.parse_digits(4)?;
+        self.expect(b'-')?;
+        let month = self.parse_digits(2)?;
+        self.expect(b'-')?;
+        let day = self.parse_digits(2)?;
+        Date::new(year as i32, month as u8, day as u8)
+    }
+
+    fn parse_time(&mut self) -> LoadumResult<Time> {
+        let hour = self.parse_digits(2)?;
+        self.expect(b':')?;
+        let minute = self.parse_digits(2)?;
+        self.expect(b':')?;
+        let second = self.parse_digits(2)?;
+        let mut nanosecond = 0;
+        if self.text.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            let start = self.position;
+            while let Some(digit @ b'0'..=b'9') = self.text.get(self.position) {
+                // Digits beyond nanosecond precision are truncated
+                if self.position - start < 9 {
+                    nanosecond = nanosecond * 10 + (digit - b'0') as u32;
+                }
+                self.position += 1;
+            }
+            let digits = self.position - start;
+            if digits == 0 {
+                bail!("Missing fraction digits");
+            }
+            for _ in digits..9 {
+                nanosecond *= 10;
+            }
+        }
+        Time::new(hour as u8, minute as u8, second as u8, nanosecond)
+    }
+
+    fn parse_digits(&mut self, count: usize) -> LoadumResult<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            let Some(digit @ b'0'..=b'9') = self.text.get(self.position) else {
+                bail!("Expected digit");
+            };
+            value = value * 10 + (digit - b'0') as u32;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn expect(&mut self, expected: u8) -> LoadumResult<()> {
+        if self.text.get(self.position) != Some(&expected) {
+            bail!("Expected '{}'", expected as char);
+        }
+        self.position += 1;
+        Ok(())
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}:{:02}", self.hour, self.minute, self.second)?;
+        if self.nanosecond != 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats as RFC 3339, using `Z` for UTC
+impl Display for DateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DateTime::OffsetDateTime {
+                date,
+                time,
+                offset_minutes,
+            } => {
+                write!(f, "{}T{}", date, time)?;
+                if *offset_minutes == 0 {
+                    write!(f, "Z")
+                } else {
+                    let sign = if *offset_minutes < 0 { '-' } else { '+' };
+                    let offset = offset_minutes.unsigned_abs();
+                    write!(f, "{}{:02}:{:02}", sign, offset / 60, offset % 60)
+                }
+            }
+            DateTime::LocalDateTime { date, time } => write!(f, "{}T{}", date, time),
+            DateTime::LocalDate(date) => write!(f, "{}", date),
+            DateTime::LocalTime(time) => write!(f, "{}", time),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::datetime::DateTime;
+    use expect_test::expect;
+
+    #[test]
+    fn parse_and_format() {
+        let mut output = String::new();
+        for text in [
+            "1979-05-27T07:32:00Z",
+            "1979-05-27T00:32:00.999999-07:00",
+            "1979-05-27 07:32:00+05:30",
+            "1979-05-27t07:32:00.5z",
+            "1979-05-27T07:32:00",
+            "1979-05-27",
+            "07:32:00",
+            "00:32:00.123456789123",
+            "2000-02-29",
+            "1979-05-27T07:32:60Z",
+        ] {
+            let date_time = DateTime::parse(text).unwrap();
+            output.push_str(&format!("{} -> {}\n", text, date_time));
+        }
+        expect![[r#"
+            1979-05-27T07:32:00Z -> 1979-05-27T07:32:00Z
+            1979-05-27T00:32:00.999999-07:00 -> 1979-05-27T00:32:00.999999-07:00
+            1979-05-27 07:32:00+05:30 -> 1979-05-27T07:32:00+05:30
+            1979-05-27t07:32:00.5z -> 1979-05-27T07:32:00.5Z
+            1979-05-27T07:32:00 -> 1979-05-27T07:32:00
+            1979-05-27 -> 1979-05-27
+            07:32:00 -> 07:32:00
+            00:32:00.123456789123 -> 00:32:00.123456789
+            2000-02-29 -> 2000-02-29
+            1979-05-27T07:32:60Z -> 1979-05-27T07:32:60Z
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        for text in [
+            "",
+            "1979",
+            "1979-13-01",
+            "1979-02-29",
+            "1979-05-27T",
+            "1979-05-27T25:00:00",
+            "1979-05-27T07:32",
+            "1979-05-27T07:32:00.",
+            "1979-05-27T07:32:00+0100",
+            "1979-05-27T07:32:00Zfoo",
+        ] {
+            assert!(DateTime::parse(text).is_err(), "{} should be invalid", text);
+        }
+    }
+
+    #[test]
+    fn unix_timestamp() {
+        for (seconds, text) in [
+            (0, "1970-01-01T00:00:00Z"),
+            (1_000_000_000, "2001-09-09T01:46:40Z"),
+            (-1, "1969-12-31T23:59:59Z"),
+            (951_782_400, "2000-02-29T00:00:00Z"),
+        ] {
+            let date_time = DateTime::from_unix_timestamp(seconds, 0).unwrap();
+            assert_eq!(date_time.to_string(), text);
+            assert_eq!(date_time.unix_timestamp(), Some((seconds, 0)));
+        }
+        let date_time = DateTime::parse("2001-09-09T03:46:40.25+02:00").unwrap();
+        assert_eq!(
+            date_time.unix_timestamp(),
+            Some((1_000_000_000, 250_000_000))
+        );
+        assert_eq!(
+            DateTime::parse("2001-09-09").unwrap().unix_timestamp(),
+            None
+        );
+        for (seconds, nanosecond) in [(i64::MAX, 0), (i64::MIN, 0), (0, 1_000_000_000)] {
+            assert!(DateTime::from_unix_timestamp(seconds, nanosecond).is_err());
+        }
+        let DateTime::OffsetDateTime { date, time, .. } =
+            DateTime::from_unix_timestamp(-62_135_596_800, 5).unwrap()
+        else {
+            panic!("Unix timestamps are offset date-times");
+        };
+        assert_eq!((date.year(), date.month(), date.day()), (1, 1, 1));
+        assert_eq!(
+            (time.hour(), time.minute(), time.second(), time.nanosecond()),
+            (0, 0, 0, 5)
+        );
+    }
+}
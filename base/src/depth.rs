@@ -0,0 +1,53 @@
+//! Nesting limit of recursive loaders and dumpers, so that deep documents fail with an error
+//! instead of overflowing the stack
+
+use crate::error::bail;
+use crate::result::LoadumResult;
+
+/// Maximum number of nested containers
+pub const MAX_DEPTH: usize = 128;
+
+/// Fails if a container at `depth` would be nested deeper than [`MAX_DEPTH`]
+pub fn check_depth(depth: usize) -> LoadumResult<()> {
+    if depth >= MAX_DEPTH {
+        bail!("Document is nested deeper than {} levels", MAX_DEPTH);
+    }
+    Ok(())
+}
+
+/// Nesting depth of a recursive parser, entered and exited around each container
+#[derive(Debug, Default)]
+pub struct Depth(usize);
+
+impl Depth {
+    /// Enters a container, fails if it is nested too deeply
+    pub fn enter(&mut self) -> LoadumResult<()> {
+        check_depth(self.0)?;
+        self.0 += 1;
+        Ok(())
+    }
+
+    pub fn exit(&mut self) {
+        self.0 -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::depth::{Depth, MAX_DEPTH};
+
+    #[test]
+    fn depth() {
+        let mut depth = Depth::default();
+        for _ in 0..MAX_DEPTH {
+            depth.enter().unwrap();
+        }
+        let error = depth.enter().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Document is nested deeper than 128 levels"
+        );
+        depth.exit();
+        depth.enter().unwrap();
+    }
+}
@@ -1,6 +1,91 @@
+use crate::error::bail;
 use crate::event::Event;
+use crate::node::Node;
 use crate::result::LoadumResult;
+use crate::value::Value;
+use std::borrow::Cow;
 
 pub trait Dumper {
     fn emit(&mut self, event: &Event) -> LoadumResult<()>;
 }
+
+/// Asynchronous counterpart of [`Dumper`], for output written to async streams
+#[cfg(feature = "async")]
+pub trait AsyncDumper {
+    /// Emits an event, output may be buffered until [`AsyncDumper::flush`] is called
+    fn emit(&mut self, event: &Event) -> impl Future<Output = LoadumResult<()>> + Send;
+    fn flush(&mut self) -> impl Future<Output = LoadumResult<()>> + Send;
+}
+
+/// How a dumper handles map keys its format cannot represent natively
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyPolicy {
+    /// Convert the keys to strings, composite keys are written in the format of the dumper
+    #[default]
+    Stringify,
+    /// Fail with an error
+    Reject,
+}
+
+impl KeyPolicy {
+    /// Returns a map key as a string, `keys` names the keys in errors, e.g. `BSON keys`
+    pub fn key_string<'node>(self, key: &'node Node, keys: &str) -> LoadumResult<Cow<'node, str>> {
+        match key.untagged() {
+            Node::Scalar(scalar) if scalar.as_str().is_some() || self == KeyPolicy::Stringify => {
+                self.scalar_key_string(scalar, keys)
+            }
+            _ if self == KeyPolicy::Reject => {
+                bail!("{} must be strings, but found {:?}", keys, key)
+            }
+            _ => bail!("{} cannot be composite, but found {:?}", keys, key),
+        }
+    }
+
+    /// Returns a scalar map key as a string
+    pub fn scalar_key_string<'value>(
+        self,
+        key: &'value Value,
+        keys: &str,
+    ) -> LoadumResult<Cow<'value, str>> {
+        if let Some(key) = key.as_str() {
+            return Ok(Cow::Borrowed(key));
+        }
+        if self == KeyPolicy::Reject {
+            bail!("{} must be strings, but found {:?}", keys, key);
+        }
+        Ok(Cow::Owned(key.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dumper::KeyPolicy;
+    use crate::node::Node;
+    use crate::value::Value;
+
+    #[test]
+    fn key_string() {
+        let string = Node::Scalar(Value::string("a"));
+        let boolean = Node::Scalar(Value::Boolean(true));
+        let list = Node::List(vec![]);
+        for policy in [KeyPolicy::Stringify, KeyPolicy::Reject] {
+            assert_eq!(policy.key_string(&string, "Keys").unwrap(), "a");
+        }
+        let tagged = Node::Tagged("tag".into(), Box::new(string));
+        assert_eq!(KeyPolicy::Reject.key_string(&tagged, "Keys").unwrap(), "a");
+        assert_eq!(
+            KeyPolicy::Stringify.key_string(&boolean, "Keys").unwrap(),
+            "true"
+        );
+        let error = KeyPolicy::Reject.key_string(&boolean, "Keys").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Keys must be strings, but found Scalar(Boolean(true))"
+        );
+        let error = KeyPolicy::Stringify.key_string(&list, "Keys").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Keys cannot be composite, but found List([])"
+        );
+    }
+}
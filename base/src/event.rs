@@ -1,35 +1,75 @@
-use crate::LoadumString;
+use crate::datetime::DateTime;
 use crate::value::Value;
+use crate::{LoadumBytes, LoadumString};
 
-pub enum Event {
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<'source> {
     DocumentStart,
     DocumentEnd,
     MapStart,
     MapEnd,
     ListStart,
     ListEnd,
-    MapKey(Value),
-    Literal(Value),
+    /// A scalar map key, followed by the entry value
+    MapKey(Value<'source>),
+    /// Announces that the following map or list is a map key, the entry value follows after it
+    ComplexKey,
+    Literal(Value<'source>),
+    /// Tags the following node or map key with a format specific type, e.g. a MessagePack ext type
+    ///
+    /// Dumpers for formats without tags ignore it.
+    Tag(LoadumString),
 }
 
-impl Event {
-    pub fn null() -> Event {
+/// Tag of integers beyond the range of [`Value::Integer`], loaded as strings of their digits
+pub const BIGINT_TAG: &str = "bigint";
+
+impl<'source> Event<'source> {
+    pub fn null() -> Self {
         Event::Literal(Value::Null)
     }
-    pub fn bool(value: bool) -> Event {
+    pub fn bool(value: bool) -> Self {
         Event::Literal(Value::Boolean(value))
     }
 
-    pub fn string(s: impl Into<LoadumString>) -> Event {
+    pub fn string(s: impl Into<LoadumString>) -> Self {
         Event::Literal(Value::string(s))
     }
-    pub fn number(value: impl Into<f64>) -> Event {
+    pub fn integer(value: impl Into<i64>) -> Self {
+        Event::Literal(Value::integer(value))
+    }
+    pub fn number(value: impl Into<f64>) -> Self {
         Event::Literal(Value::number(value))
     }
+    pub fn bytes(value: impl Into<LoadumBytes>) -> Self {
+        Event::Literal(Value::bytes(value))
+    }
+    pub fn date_time(value: DateTime) -> Self {
+        Event::Literal(Value::DateTime(value))
+    }
 
-    pub fn map_key(s: impl Into<LoadumString>) -> Event {
+    pub fn map_key(s: impl Into<LoadumString>) -> Self {
         Event::MapKey(Value::string(s))
     }
+    pub fn tag(tag: impl Into<LoadumString>) -> Self {
+        Event::Tag(tag.into())
+    }
+
+    /// Detaches the event from the loader input by copying borrowed strings
+    pub fn into_owned(self) -> Event<'static> {
+        match self {
+            Event::DocumentStart => Event::DocumentStart,
+            Event::DocumentEnd => Event::DocumentEnd,
+            Event::MapStart => Event::MapStart,
+            Event::MapEnd => Event::MapEnd,
+            Event::ListStart => Event::ListStart,
+            Event::ListEnd => Event::ListEnd,
+            Event::MapKey(value) => Event::MapKey(value.into_owned()),
+            Event::ComplexKey => Event::ComplexKey,
+            Event::Literal(value) => Event::Literal(value.into_owned()),
+            Event::Tag(tag) => Event::Tag(tag),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,10 +1,19 @@
-use ecow::EcoString;
+use ecow::{EcoString, EcoVec};
 
+pub mod base64;
+pub mod byte_input;
+pub mod datetime;
+pub mod depth;
 pub mod dumper;
 pub mod error;
 pub mod event;
 pub mod loader;
+pub mod node;
+pub mod read_buffer;
 pub mod result;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod value;
 
 pub type LoadumString = EcoString;
+pub type LoadumBytes = EcoVec<u8>;
@@ -1,3 +1,11 @@
 use crate::event::Event;
+use crate::result::LoadumResult;
 
-pub trait Loader: Iterator<Item = Event> {}
+pub trait Loader<'source>: Iterator<Item = LoadumResult<Event<'source>>> {}
+
+/// Asynchronous counterpart of [`Loader`], for input read from async streams
+#[cfg(feature = "async")]
+pub trait AsyncLoader<'source> {
+    /// Returns the next event, or None once the input is exhausted
+    fn next_event(&mut self) -> impl Future<Output = Option<LoadumResult<Event<'source>>>> + Send;
+}
@@ -0,0 +1,304 @@
+//! In-memory tree of a document, for formats that cannot be written or read in event order
+
+use crate::LoadumString;
+use crate::error::bail;
+use crate::event::Event;
+use crate::result::LoadumResult;
+use crate::value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node<'source> {
+    Scalar(Value<'source>),
+    /// Map entries in document order, keys are scalars unless built from complex keys
+    Map(Vec<(Node<'source>, Node<'source>)>),
+    List(Vec<Node<'source>>),
+    Tagged(LoadumString, Box<Node<'source>>),
+}
+
+impl<'source> Node<'source> {
+    /// Returns the string content, if this is a string scalar
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::Scalar(value) => value.as_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the node without its tags
+    pub fn untagged(&self) -> &Node<'source> {
+        match self {
+            Node::Tagged(_, node) => node.untagged(),
+            node => node,
+        }
+    }
+
+    fn tagged(self, tags: Vec<LoadumString>) -> Node<'source> {
+        tags.into_iter()
+            .rev()
+            .fold(self, |node, tag| Node::Tagged(tag, Box::new(node)))
+    }
+
+    /// Appends the events describing this node (without document start and end)
+    pub fn to_events(&self, events: &mut Vec<Event<'source>>) {
+        match self {
+            Node::Scalar(value) => events.push(Event::Literal(value.clone())),
+            Node::Map(entries) => {
+                events.push(Event::MapStart);
+                for (key, value) in entries {
+                    if matches!(key.untagged(), Node::Scalar(_)) {
+                        key.to_key_events(events);
+                    } else {
+                        events.push(Event::ComplexKey);
+                        key.to_events(events);
+                    }
+                    value.to_events(events);
+                }
+                events.push(Event::MapEnd);
+            }
+            Node::List(items) => {
+                events.push(Event::ListStart);
+                for item in items {
+                    item.to_events(events);
+                }
+                events.push(Event::ListEnd);
+            }
+            Node::Tagged(tag, node) => {
+                events.push(Event::Tag(tag.clone()));
+                node.to_events(events);
+            }
+        }
+    }
+
+    fn to_key_events(&self, events: &mut Vec<Event<'source>>) {
+        match self {
+            Node::Tagged(tag, node) => {
+                events.push(Event::Tag(tag.clone()));
+                node.to_key_events(events);
+            }
+            Node::Scalar(value) => events.push(Event::MapKey(value.clone())),
+            _ => unreachable!("Only called for scalar keys"),
+        }
+    }
+}
+
+enum Frame<'source> {
+    Map {
+        entries: Vec<(Node<'source>, Node<'source>)>,
+        key: Option<Node<'source>>,
+        /// Whether the next node is a complex key
+        complex_key: bool,
+        tags: Vec<LoadumString>,
+    },
+    List {
+        items: Vec<Node<'source>>,
+        tags: Vec<LoadumString>,
+    },
+}
+
+/// Builds nodes from a stream of events
+#[derive(Default)]
+pub struct NodeBuilder<'source> {
+    stack: Vec<Frame<'source>>,
+    root: Option<Node<'source>>,
+    /// Tags for the next node
+    tags: Vec<LoadumString>,
+}
+
+impl<'source> NodeBuilder<'source> {
+    pub fn push(&mut self, event: Event<'source>) -> LoadumResult<()> {
+        let node = match event {
+            Event::DocumentStart | Event::DocumentEnd => return Ok(()),
+            Event::ComplexKey => {
+                let Some(Frame::Map {
+                    key: None,
+                    complex_key: complex_key @ false,
+                    ..
+                }) = self.stack.last_mut()
+                else {
+                    bail!("Unexpected complex key");
+                };
+                *complex_key = true;
+                return Ok(());
+            }
+            Event::Tag(tag) => {
+                self.tags.push(tag);
+                return Ok(());
+            }
+            Event::MapStart => {
+                self.stack.push(Frame::Map {
+                    entries: vec![],
+                    key: None,
+                    complex_key: false,
+                    tags: std::mem::take(&mut self.tags),
+                });
+                return Ok(());
+            }
+            Event::ListStart => {
+                self.stack.push(Frame::List {
+                    items: vec![],
+                    tags: std::mem::take(&mut self.tags),
+                });
+                return Ok(());
+            }
+            Event::MapEnd => match self.stack.pop() {
+                Some(Frame::Map {
+                    entries,
+                    key: None,
+                    complex_key: false,
+                    tags,
+                }) => Node::Map(entries).tagged(tags),
+                _ => bail!("Unexpected end of map"),
+            },
+            Event::ListEnd => match self.stack.pop() {
+                Some(Frame::List { items, tags }) => Node::List(items).tagged(tags),
+                _ => bail!("Unexpected end of list"),
+            },
+            Event::MapKey(value) => {
+                let tags = std::mem::take(&mut self.tags);
+                let Some(Frame::Map {
+                    key: key @ None,
+                    complex_key: false,
+                    ..
+                }) = self.stack.last_mut()
+                else {
+                    bail!("Unexpected map key");
+                };
+                *key = Some(Node::Scalar(value).tagged(tags));
+                return Ok(());
+            }
+            Event::Literal(value) => Node::Scalar(value).tagged(std::mem::take(&mut self.tags)),
+        };
+        self.add(node)
+    }
+
+    /// Takes the completed root node
+    pub fn finish(&mut self) -> Option<Node<'source>> {
+        self.root.take()
+    }
+
+    fn add(&mut self, node: Node<'source>) -> LoadumResult<()> {
+        match self.stack.last_mut() {
+            None => {
+                if self.root.is_some() {
+                    bail!("Multiple root nodes in document");
+                }
+                self.root = Some(node);
+            }
+            Some(Frame::List { items, .. }) => items.push(node),
+            Some(Frame::Map {
+                entries,
+                key,
+                complex_key,
+                ..
+            }) => match key.take() {
+                Some(key) => entries.push((key, node)),
+                None if *complex_key => {
+                    *complex_key = false;
+                    *key = Some(node);
+                }
+                None => bail!("Expected a map key, but found {:?}", node),
+            },
+        }
+        Ok(())
+    }
+}
+
+impl NodeBuilder<'static> {
+    /// Pushes a copy of an event, returns the document once it ends
+    ///
+    /// Dumpers that need the whole document before writing it buffer the events with this.
+    pub fn buffer(&mut self, event: &Event) -> LoadumResult<Option<Node<'static>>> {
+        self.push(event.clone().into_owned())?;
+        if *event != Event::DocumentEnd {
+            return Ok(None);
+        }
+        match self.finish() {
+            Some(root) => Ok(Some(root)),
+            None => bail!("Document is empty"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::Event;
+    use crate::node::{Node, NodeBuilder};
+    use crate::value::Value;
+
+    #[test]
+    fn roundtrip() {
+        let events = vec![
+            Event::MapStart,
+            Event::map_key("a"),
+            Event::ListStart,
+            Event::number(1),
+            Event::MapStart,
+            Event::MapEnd,
+            Event::ListEnd,
+            Event::ComplexKey,
+            Event::ListStart,
+            Event::null(),
+            Event::ListEnd,
+            Event::string("b"),
+            Event::MapKey(Value::Boolean(true)),
+            Event::bool(false),
+            Event::tag("key"),
+            Event::map_key("c"),
+            Event::tag("outer"),
+            Event::tag("inner"),
+            Event::ListStart,
+            Event::tag("value"),
+            Event::null(),
+            Event::ListEnd,
+            Event::ComplexKey,
+            Event::tag("complex"),
+            Event::MapStart,
+            Event::MapEnd,
+            Event::null(),
+            Event::MapEnd,
+        ];
+        let mut builder = NodeBuilder::default();
+        builder.push(Event::DocumentStart).unwrap();
+        for event in events.clone() {
+            builder.push(event).unwrap();
+        }
+        builder.push(Event::DocumentEnd).unwrap();
+        let node = builder.finish().unwrap();
+        let mut output = vec![];
+        node.to_events(&mut output);
+        assert_eq!(output, events);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut builder = NodeBuilder::default();
+        assert!(builder.push(Event::MapEnd).is_err());
+        assert!(builder.push(Event::map_key("a")).is_err());
+        builder.push(Event::null()).unwrap();
+        assert!(builder.push(Event::null()).is_err());
+
+        let mut builder = NodeBuilder::default();
+        builder.push(Event::MapStart).unwrap();
+        assert!(builder.push(Event::null()).is_err());
+        builder.push(Event::ComplexKey).unwrap();
+        assert!(builder.push(Event::ComplexKey).is_err());
+        assert!(builder.push(Event::map_key("a")).is_err());
+        builder.push(Event::null()).unwrap();
+        assert!(builder.push(Event::ComplexKey).is_err());
+        assert!(builder.push(Event::MapEnd).is_err());
+    }
+
+    #[test]
+    fn buffer() {
+        let mut builder = NodeBuilder::default();
+        assert_eq!(builder.buffer(&Event::DocumentStart).unwrap(), None);
+        assert_eq!(builder.buffer(&Event::null()).unwrap(), None);
+        assert_eq!(
+            builder.buffer(&Event::DocumentEnd).unwrap(),
+            Some(Node::Scalar(Value::Null))
+        );
+        builder.buffer(&Event::DocumentStart).unwrap();
+        let error = builder.buffer(&Event::DocumentEnd).unwrap_err();
+        assert_eq!(error.to_string(), "Document is empty");
+    }
+}
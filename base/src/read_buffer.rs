@@ -0,0 +1,148 @@
+use crate::result::LoadumResult;
+use std::io::{ErrorKind, Read};
+
+const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+/// Buffers bytes from a [`Read`] so loaders can pull input incrementally.
+///
+/// Only the unconsumed part of the input is kept in memory, the buffer grows
+/// beyond its initial capacity only if a single token does not fit into it.
+pub struct ReadBuffer<R> {
+    read: R,
+    /// Initialized memory of the buffer, its length is the capacity
+    buffer: Vec<u8>,
+    position: usize,
+    /// End of the buffered data
+    end: usize,
+    offset: u64,
+    eof: bool,
+}
+
+impl<R> ReadBuffer<R> {
+    pub fn new(read: R) -> Self {
+        Self::with_capacity(read, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(read: R, capacity: usize) -> Self {
+        Self {
+            read,
+            buffer: vec![0; capacity.max(1)],
+            position: 0,
+            end: 0,
+            offset: 0,
+            eof: false,
+        }
+    }
+
+    /// The buffered bytes that have not been consumed yet
+    pub fn data(&self) -> &[u8] {
+        &self.buffer[self.position..self.end]
+    }
+
+    /// Returns true once the underlying reader is exhausted
+    pub fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    /// Absolute offset of the first unconsumed byte in the input
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn consume(&mut self, amount: usize) {
+        assert!(
+            amount <= self.end - self.position,
+            "Cannot consume more than is buffered"
+        );
+        self.position += amount;
+        self.offset += amount as u64;
+    }
+
+    /// Compacts the buffer and makes room for more data
+    ///
+    /// The buffer doubles when it is full, so the memory is only initialized once.
+    fn prepare_fill(&mut self) {
+        if self.position > 0 {
+            self.buffer.copy_within(self.position..self.end, 0);
+            self.end -= self.position;
+            self.position = 0;
+        }
+        if self.end == self.buffer.len() {
+            self.buffer.resize(self.buffer.len() * 2, 0);
+        }
+    }
+
+    fn finish_fill(&mut self, result: std::io::Result<usize>) -> LoadumResult<bool> {
+        let read = result?;
+        self.end += read;
+        if read == 0 {
+            self.eof = true;
+        }
+        Ok(read > 0)
+    }
+}
+
+impl<R: Read> ReadBuffer<R> {
+    /// Reads more data from the underlying reader, keeping unconsumed bytes
+    ///
+    /// Returns false if no more data is available
+    pub fn fill(&mut self) -> LoadumResult<bool> {
+        if self.eof {
+            return Ok(false);
+        }
+        self.prepare_fill();
+        let result = loop {
+            match self.read.read(&mut self.buffer[self.end..]) {
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                result => break result,
+            }
+        };
+        self.finish_fill(result)
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncRead + Unpin> ReadBuffer<R> {
+    /// Asynchronous counterpart of [`ReadBuffer::fill`]
+    pub async fn fill_async(&mut self) -> LoadumResult<bool> {
+        use tokio::io::AsyncReadExt;
+        if self.eof {
+            return Ok(false);
+        }
+        self.prepare_fill();
+        let result = self.read.read(&mut self.buffer[self.end..]).await;
+        self.finish_fill(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::read_buffer::ReadBuffer;
+
+    #[test]
+    fn read_all() {
+        let mut buffer = ReadBuffer::with_capacity(&b"hello world"[..], 4);
+        let mut output = vec![];
+        while buffer.fill().unwrap() {
+            output.extend_from_slice(buffer.data());
+            let length = buffer.data().len();
+            buffer.consume(length);
+        }
+        assert!(buffer.is_eof());
+        assert_eq!(buffer.offset(), 11);
+        assert_eq!(output, b"hello world");
+    }
+
+    #[test]
+    fn keep_unconsumed() {
+        let mut buffer = ReadBuffer::with_capacity(&b"hello world"[..], 4);
+        buffer.fill().unwrap();
+        assert_eq!(buffer.data(), b"hell");
+        buffer.consume(2);
+        buffer.fill().unwrap();
+        assert_eq!(buffer.data(), b"llo ");
+        buffer.fill().unwrap();
+        assert!(buffer.data().starts_with(b"llo w"));
+        assert_eq!(buffer.offset(), 2);
+    }
+}
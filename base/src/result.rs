@@ -13,7 +13,6 @@ macro_rules! context {
 
 #[cfg(test)]
 mod tests {
-    use crate::context;
     use crate::error::{LoadumError, bail};
     use crate::result::LoadumResult;
     use std::env::set_var;
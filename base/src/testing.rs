@@ -0,0 +1,75 @@
+//! Helpers for the tests of format crates, enabled by the `testing` feature
+
+use crate::dumper::Dumper;
+use crate::event::Event;
+use crate::result::LoadumResult;
+use crate::value::Value;
+use std::fmt::{Display, Write};
+
+/// Collects loaded events with errors as their messages, so that loads can be compared
+pub fn collect_events<'source>(
+    events: impl IntoIterator<Item = LoadumResult<Event<'source>>>,
+) -> Vec<Result<Event<'source>, String>> {
+    events
+        .into_iter()
+        .map(|event| event.map_err(|error| error.to_string()))
+        .collect()
+}
+
+/// Formats events one per line, errors as `Error: <message>`
+pub fn format_events(events: &[Result<Event, String>]) -> String {
+    let mut output = String::new();
+    for event in events {
+        match event {
+            Ok(event) => writeln!(output, "{:?}", event).unwrap(),
+            Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+        }
+    }
+    output
+}
+
+/// Emits events until one fails, the dumper is dropped afterwards to flush its output
+pub fn emit_events<'event>(
+    mut dumper: impl Dumper,
+    events: impl IntoIterator<Item = &'event Event<'event>>,
+) -> LoadumResult<()> {
+    events.into_iter().try_for_each(|event| dumper.emit(event))
+}
+
+/// Formats the output of a dump, or `Error: <message>` if it failed
+pub fn format_dump(result: LoadumResult<()>, output: impl Display) -> String {
+    match result {
+        Ok(()) => output.to_string(),
+        Err(error) => format!("Error: {}", error),
+    }
+}
+
+/// Documents that dumpers without composite keys reject: a composite key and a missing node
+pub fn invalid_documents() -> [Vec<Event<'static>>; 2] {
+    [
+        vec![
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::ComplexKey,
+            Event::ListStart,
+            Event::ListEnd,
+            Event::integer(1),
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ],
+        vec![Event::DocumentStart, Event::DocumentEnd],
+    ]
+}
+
+/// Document with an integer map key, rejected by dumpers with
+/// [`KeyPolicy::Reject`](crate::dumper::KeyPolicy::Reject)
+pub fn integer_key_document() -> Vec<Event<'static>> {
+    vec![
+        Event::DocumentStart,
+        Event::MapStart,
+        Event::MapKey(Value::Integer(1)),
+        Event::integer(1),
+        Event::MapEnd,
+        Event::DocumentEnd,
+    ]
+}
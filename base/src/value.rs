@@ -1,25 +1,144 @@
-use crate::LoadumString;
+use crate::base64;
+use crate::datetime::DateTime;
+use crate::{LoadumBytes, LoadumString};
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
 
-pub enum Value {
+#[derive(Debug, Clone)]
+pub enum Value<'source> {
     Null,
     Boolean(bool),
+    /// An integer, for formats that distinguish integers from floating point numbers
+    Integer(i64),
     Number(f64),
     String(LoadumString),
+    /// A string borrowed from the loader input, avoiding an allocation
+    BorrowedString(&'source str),
+    /// Raw binary data, text formats usually encode it as base64
+    Bytes(LoadumBytes),
+    DateTime(DateTime),
 }
 
-impl Value {
-    pub fn string(value: impl Into<LoadumString>) -> Value {
+impl<'source> Value<'source> {
+    pub fn string(value: impl Into<LoadumString>) -> Self {
         Value::String(value.into()) //value.into()) //Value::String(value.into())
     }
-    pub fn number(value: impl Into<f64>) -> Value {
+    pub fn borrowed_string(value: &'source str) -> Self {
+        Value::BorrowedString(value)
+    }
+    pub fn integer(value: impl Into<i64>) -> Self {
+        Value::Integer(value.into())
+    }
+    pub fn number(value: impl Into<f64>) -> Self {
         Value::Number(value.into())
     }
+    pub fn bytes(value: impl Into<LoadumBytes>) -> Self {
+        Value::Bytes(value.into())
+    }
+
+    /// Returns the string content of owned and borrowed strings
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            Value::BorrowedString(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Detaches the value from the loader input by copying borrowed strings
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Null => Value::Null,
+            Value::Boolean(b) => Value::Boolean(b),
+            Value::Integer(i) => Value::Integer(i),
+            Value::Number(n) => Value::Number(n),
+            Value::String(s) => Value::String(s),
+            Value::BorrowedString(s) => Value::String(s.into()),
+            Value::Bytes(b) => Value::Bytes(b),
+            Value::DateTime(d) => Value::DateTime(d),
+        }
+    }
+}
+
+/// Owned and borrowed strings are equal if their contents are, so values compare the same whether
+/// they were loaded from a string or a reader. NaN numbers are equal, a loaded value always equals
+/// itself.
+impl PartialEq for Value<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Null, Value::Null) => true,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Integer(a), Value::Integer(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b || a.is_nan() && b.is_nan(),
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::DateTime(a), Value::DateTime(b)) => a == b,
+            _ => match (self.as_str(), other.as_str()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Consistent with equality, owned and borrowed strings hash alike and so do `0.0` and `-0.0`
+impl Hash for Value<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Value::Null => state.write_u8(0),
+            Value::Boolean(b) => {
+                state.write_u8(1);
+                b.hash(state);
+            }
+            Value::Integer(i) => {
+                state.write_u8(2);
+                i.hash(state);
+            }
+            Value::Number(n) => {
+                state.write_u8(3);
+                // Adding zero turns -0.0 into 0.0, all NaNs hash alike
+                if n.is_nan() {
+                    f64::NAN.to_bits().hash(state);
+                } else {
+                    (n + 0.0).to_bits().hash(state);
+                }
+            }
+            Value::String(_) | Value::BorrowedString(_) => {
+                state.write_u8(4);
+                self.as_str().hash(state);
+            }
+            Value::Bytes(b) => {
+                state.write_u8(5);
+                b.hash(state);
+            }
+            Value::DateTime(d) => {
+                state.write_u8(6);
+                d.hash(state);
+            }
+        }
+    }
+}
+
+/// Plain textual representation, e.g. for formats that only support string keys
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::BorrowedString(s) => write!(f, "{}", s),
+            Value::Bytes(b) => write!(f, "{}", base64::encode(b)),
+            Value::DateTime(d) => write!(f, "{}", d),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::value::Value;
     use ecow::EcoString;
+    use std::hash::{BuildHasher, RandomState};
 
     #[test]
     fn value_size() {
@@ -28,4 +147,34 @@ mod tests {
         assert_eq!(size_of::<String>(), 24);
         assert_eq!(size_of::<EcoString>(), 16);
     }
+
+    #[test]
+    fn into_owned() {
+        let source = String::from("foo");
+        let value = Value::borrowed_string(&source).into_owned();
+        drop(source);
+        assert_eq!(value, Value::string("foo"));
+        assert_eq!(value.as_str(), Some("foo"));
+    }
+
+    #[test]
+    fn string_equality() {
+        let hasher = RandomState::new();
+        let owned = Value::string("foo");
+        let borrowed = Value::borrowed_string("foo");
+        assert_eq!(owned, borrowed);
+        assert_eq!(hasher.hash_one(&owned), hasher.hash_one(&borrowed));
+        assert_ne!(owned, Value::borrowed_string("bar"));
+        assert_ne!(owned, Value::bytes(b"foo".as_slice()));
+        assert_eq!(Value::number(0.0), Value::number(-0.0));
+        assert_eq!(Value::number(f64::NAN), Value::number(-f64::NAN));
+        assert_eq!(
+            hasher.hash_one(Value::number(0.0)),
+            hasher.hash_one(Value::number(-0.0))
+        );
+        assert_eq!(
+            hasher.hash_one(Value::number(f64::NAN)),
+            hasher.hash_one(Value::number(-f64::NAN))
+        );
+    }
 }
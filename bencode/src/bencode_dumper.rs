@@ -0,0 +1,265 @@
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Dumps bencode, each document is written as a top level value
+///
+/// Dictionary keys are sorted by their raw bytes, as the format requires. Booleans are written
+/// as the integers 1 and 0, date-times as strings and tags are ignored. Bencode has no null and
+/// no fractional numbers, so these are rejected.
+pub struct BencodeDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> BencodeDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> BencodeDumper<'write> {
+        BencodeDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::default(),
+        }
+    }
+
+    /// Sets how keys other than strings and bytes are handled
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    fn write_node(&self, output: &mut Vec<u8>, node: &Node) -> LoadumResult<()> {
+        match node {
+            Node::Scalar(value) => write_value(output, value)?,
+            Node::List(items) => {
+                output.push(b'l');
+                for item in items {
+                    self.write_node(output, item)?;
+                }
+                output.push(b'e');
+            }
+            Node::Map(entries) => {
+                let mut sorted = entries
+                    .iter()
+                    .map(|(key, value)| Ok((self.key_bytes(key)?, value)))
+                    .collect::<LoadumResult<Vec<_>>>()?;
+                sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+                if let Some(pair) = sorted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                    bail!("Duplicate dictionary key '{}'", pair[0].0.escape_ascii());
+                }
+                output.push(b'd');
+                for (key, value) in sorted {
+                    write_byte_string(output, &key);
+                    self.write_node(output, value)?;
+                }
+                output.push(b'e');
+            }
+            Node::Tagged(_, node) => self.write_node(output, node)?,
+        }
+        Ok(())
+    }
+
+    fn key_bytes<'node>(&self, key: &'node Node) -> LoadumResult<Cow<'node, [u8]>> {
+        if let Node::Scalar(Value::Bytes(key)) = key.untagged() {
+            return Ok(Cow::Borrowed(key));
+        }
+        Ok(match self.key_policy.key_string(key, "Bencode keys")? {
+            Cow::Borrowed(key) => Cow::Borrowed(key.as_bytes()),
+            Cow::Owned(key) => Cow::Owned(key.into_bytes()),
+        })
+    }
+}
+
+impl Dumper for BencodeDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut output = vec![];
+        self.write_node(&mut output, &root)?;
+        self.write.write_all(&output)?;
+        Ok(())
+    }
+}
+
+fn write_value(output: &mut Vec<u8>, value: &Value) -> LoadumResult<()> {
+    match value {
+        Value::Null => bail!("Bencode cannot contain null"),
+        Value::Boolean(b) => write_integer(output, *b as i64),
+        Value::Integer(i) => write_integer(output, *i),
+        Value::Number(n) => {
+            if n.fract() != 0.0 || !(-9.2e18..9.2e18).contains(n) {
+                bail!("Bencode numbers must be integers, but found {}", n);
+            }
+            write_integer(output, *n as i64);
+        }
+        Value::String(s) => write_byte_string(output, s.as_bytes()),
+        Value::BorrowedString(s) => write_byte_string(output, s.as_bytes()),
+        Value::Bytes(bytes) => write_byte_string(output, bytes),
+        Value::DateTime(date_time) => write_byte_string(output, date_time.to_string().as_bytes()),
+    }
+    Ok(())
+}
+
+fn write_integer(output: &mut Vec<u8>, i: i64) {
+    output.extend_from_slice(format!("i{}e", i).as_bytes());
+}
+
+fn write_byte_string(output: &mut Vec<u8>, bytes: &[u8]) {
+    output.extend_from_slice(format!("{}:", bytes.len()).as_bytes());
+    output.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencode_dumper::BencodeDumper;
+    use crate::bencode_loader::BencodeLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump_with(dumper: impl FnOnce(&mut Vec<u8>) -> BencodeDumper, events: &[Event]) -> String {
+        let mut output = vec![];
+        let mut dumper = dumper(&mut output);
+        let result = events.iter().try_for_each(|event| dumper.emit(event));
+        drop(dumper);
+        match result {
+            Ok(()) => output.escape_ascii().to_string(),
+            Err(error) => format!("Error: {}", error),
+        }
+    }
+
+    fn dump(events: &[Event]) -> String {
+        dump_with(|output| BencodeDumper::new(output), events)
+    }
+
+    #[test]
+    fn test_values() {
+        let output = dump(&[
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::integer(-42),
+            Event::number(3.0),
+            Event::bool(true),
+            Event::string("spam"),
+            Event::string(""),
+            Event::bytes(&b"\x00\xff"[..]),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05Z").unwrap()),
+            Event::tag("ignored"),
+            Event::integer(7),
+            Event::MapStart,
+            Event::MapEnd,
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]);
+        expect![[r#"li-42ei3ei1e4:spam0:2:\x00\xff20:2024-01-02T03:04:05Zi7edee"#]]
+            .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_sorted_keys() {
+        let output = dump(&[
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::map_key("name"),
+            Event::string("file"),
+            Event::map_key("Zeta"),
+            Event::integer(1),
+            Event::MapKey(Value::bytes(&b"\xff"[..])),
+            Event::integer(2),
+            Event::map_key("length"),
+            Event::integer(3),
+            Event::MapKey(Value::Integer(10)),
+            Event::integer(4),
+            Event::map_key("info"),
+            Event::MapStart,
+            Event::map_key("b"),
+            Event::integer(5),
+            Event::map_key("a"),
+            Event::integer(6),
+            Event::MapEnd,
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ]);
+        expect![[r#"d2:10i4e4:Zetai1e4:infod1:ai6e1:bi5ee6:lengthi3e4:name4:file1:\xffi2ee"#]]
+            .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = b"d8:announce23:http://tracker/announce4:infod6:lengthi1024e\
+                      4:name8:file.bin6:pieces4:\x00\x01\x02\xffee\
+                      li-1ei0e0:lee";
+        let mut output = vec![];
+        let mut dumper = BencodeDumper::new(&mut output);
+        for event in BencodeLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(
+            output.escape_ascii().to_string(),
+            input.escape_ascii().to_string()
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let document = |events: &[Event<'static>]| {
+            let mut document = vec![Event::DocumentStart];
+            document.extend_from_slice(events);
+            document.push(Event::DocumentEnd);
+            document
+        };
+        let mut output = String::new();
+        for events in [
+            document(&[Event::null()]),
+            document(&[Event::number(1.5)]),
+            document(&[Event::number(f64::INFINITY)]),
+            document(&[
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::integer(1),
+                Event::MapKey(Value::bytes(&b"a"[..])),
+                Event::integer(2),
+                Event::MapEnd,
+            ]),
+            document(&[
+                Event::MapStart,
+                Event::ComplexKey,
+                Event::ListStart,
+                Event::ListEnd,
+                Event::integer(1),
+                Event::MapEnd,
+            ]),
+            vec![Event::DocumentStart, Event::DocumentEnd],
+        ] {
+            output.push_str(&dump(&events));
+            output.push('\n');
+        }
+        output.push_str(&dump_with(
+            |output| BencodeDumper::new(output).with_key_policy(KeyPolicy::Reject),
+            &document(&[
+                Event::MapStart,
+                Event::MapKey(Value::Integer(1)),
+                Event::integer(1),
+                Event::MapEnd,
+            ]),
+        ));
+        expect![[r#"
+            Error: Bencode cannot contain null
+            Error: Bencode numbers must be integers, but found 1.5
+            Error: Bencode numbers must be integers, but found inf
+            Error: Duplicate dictionary key 'a'
+            Error: Bencode keys cannot be composite, but found List([])
+            Error: Document is empty
+            Error: Bencode keys must be strings, but found Scalar(Integer(1))"#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,399 @@
+use loadum::byte_input::ByteInput;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::io::Read;
+
+/// Loads bencode, each top level value in the input is loaded as a separate document
+///
+/// Key order is not checked, dictionaries with unsorted keys are loaded in input order.
+pub struct BencodeLoader<'source> {
+    input: ByteInput<'source>,
+    state: LoaderState,
+    stack: Vec<Container>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    BetweenDocuments,
+    InDocument,
+    DocumentEnd,
+    Done,
+}
+
+struct Container {
+    is_map: bool,
+    /// True if a dictionary expects a key or its end, rather than a value
+    expects_key: bool,
+}
+
+impl<'source> BencodeLoader<'source> {
+    pub fn new(source: &'source [u8]) -> BencodeLoader<'source> {
+        Self::with_input(ByteInput::from_slice(source))
+    }
+
+    /// Loads bencode incrementally from a reader
+    pub fn from_reader(read: impl Read + 'source) -> BencodeLoader<'source> {
+        Self::with_input(ByteInput::from_reader(read))
+    }
+
+    fn with_input(input: ByteInput<'source>) -> BencodeLoader<'source> {
+        BencodeLoader {
+            input,
+            state: LoaderState::BetweenDocuments,
+            stack: vec![],
+        }
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        match self.state {
+            LoaderState::BetweenDocuments => {
+                if self.input.is_at_end()? {
+                    self.state = LoaderState::Done;
+                    return Ok(None);
+                }
+                self.state = LoaderState::InDocument;
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::DocumentEnd => {
+                self.state = LoaderState::BetweenDocuments;
+                return Ok(Some(Event::DocumentEnd));
+            }
+            LoaderState::Done => return Ok(None),
+            LoaderState::InDocument => {}
+        }
+        let offset = self.input.offset();
+        let marker = self.input.read_u8()?;
+        let is_key = match self.stack.last_mut() {
+            Some(container) if marker == b'e' && !container.expects_key && container.is_map => {
+                bail!("Missing value of dictionary key at offset {}", offset);
+            }
+            Some(container) if marker == b'e' => {
+                let event = if container.is_map {
+                    Event::MapEnd
+                } else {
+                    Event::ListEnd
+                };
+                self.stack.pop();
+                self.end_value();
+                return Ok(Some(event));
+            }
+            Some(container) if container.is_map => {
+                container.expects_key = !container.expects_key;
+                !container.expects_key
+            }
+            _ => false,
+        };
+        if is_key && !marker.is_ascii_digit() {
+            bail!(
+                "Dictionary keys must be byte strings, but found '{}' at offset {}",
+                marker.escape_ascii(),
+                offset
+            );
+        }
+        let event = match marker {
+            b'd' | b'l' => {
+                let is_map = marker == b'd';
+                self.stack.push(Container {
+                    is_map,
+                    expects_key: is_map,
+                });
+                if is_map {
+                    Event::MapStart
+                } else {
+                    Event::ListStart
+                }
+            }
+            b'i' => {
+                let value = self.read_integer(offset)?;
+                self.end_value();
+                Event::integer(value)
+            }
+            b'0'..=b'9' => {
+                let value = self.read_byte_string(marker, offset)?;
+                self.end_value();
+                if is_key {
+                    Event::MapKey(value)
+                } else {
+                    Event::Literal(value)
+                }
+            }
+            _ => bail!(
+                "Unexpected '{}' at offset {}",
+                marker.escape_ascii(),
+                offset
+            ),
+        };
+        Ok(Some(event))
+    }
+
+    /// Ends the document once the top level value is complete
+    fn end_value(&mut self) {
+        if self.stack.is_empty() {
+            self.state = LoaderState::DocumentEnd;
+        }
+    }
+
+    /// Reads the digits of an integer up to the terminating `e`, without leading zeros
+    fn read_integer(&mut self, offset: u64) -> LoadumResult<i64> {
+        let mut text = String::new();
+        loop {
+            match self.input.read_u8()? {
+                b'e' => break,
+                byte @ (b'-' | b'0'..=b'9') => text.push(byte as char),
+                byte => bail!(
+                    "Unexpected '{}' in integer at offset {}",
+                    byte.escape_ascii(),
+                    offset
+                ),
+            }
+        }
+        let digits = text.strip_prefix('-').unwrap_or(&text);
+        let is_valid = !digits.is_empty()
+            && digits.bytes().all(|byte| byte.is_ascii_digit())
+            && (digits == "0" || !digits.starts_with('0'))
+            && text != "-0";
+        if !is_valid {
+            bail!("Invalid integer '{}' at offset {}", text, offset);
+        }
+        match text.parse() {
+            Ok(value) => Ok(value),
+            Err(_) => bail!("Integer {} at offset {} is out of range", text, offset),
+        }
+    }
+
+    /// Reads the rest of the length and the content, which is a string if it is valid UTF-8
+    fn read_byte_string(&mut self, first_digit: u8, offset: u64) -> LoadumResult<Value<'source>> {
+        let mut length = (first_digit - b'0') as usize;
+        loop {
+            match self.input.read_u8()? {
+                b':' => break,
+                byte @ b'0'..=b'9' => {
+                    let Some(next) = length
+                        .checked_mul(10)
+                        .and_then(|length| length.checked_add((byte - b'0') as usize))
+                    else {
+                        bail!("Byte string length at offset {} is out of range", offset);
+                    };
+                    length = next;
+                }
+                byte => bail!(
+                    "Unexpected '{}' in byte string length at offset {}",
+                    byte.escape_ascii(),
+                    offset
+                ),
+            }
+        }
+        let value = match self.input.read_bytes(length)? {
+            Cow::Borrowed(bytes) => match std::str::from_utf8(bytes) {
+                Ok(string) => Value::BorrowedString(string),
+                Err(_) => Value::bytes(bytes),
+            },
+            Cow::Owned(bytes) => match String::from_utf8(bytes) {
+                Ok(string) => Value::string(string),
+                Err(error) => Value::bytes(error.into_bytes()),
+            },
+        };
+        Ok(value)
+    }
+}
+
+impl<'source> Loader<'source> for BencodeLoader<'source> {}
+
+impl<'source> Iterator for BencodeLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bencode_loader::BencodeLoader;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::Dumper;
+    use loadum_json::json_dumper::JsonDumper;
+    use std::fmt::Write;
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let events: Vec<_> = BencodeLoader::new(input)
+            .map(|event| event.map_err(|error| error.to_string()))
+            .collect();
+        let mut output = String::new();
+        for event in &events {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        expected.assert_eq(&output);
+        let reader_events: Vec<_> = BencodeLoader::from_reader(input)
+            .map(|event| event.map_err(|error| error.to_string()))
+            .collect();
+        assert_eq!(events, reader_events);
+    }
+
+    #[test]
+    fn test_values() {
+        test_loader(
+            b"d4:listli0ei-42ei9223372036854775807e0:e3:map\
+              d1:bde1:alee4:name4:spam5:bytes2:\xff\x00e",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("list"))
+                ListStart
+                Literal(Integer(0))
+                Literal(Integer(-42))
+                Literal(Integer(9223372036854775807))
+                Literal(BorrowedString(""))
+                ListEnd
+                MapKey(BorrowedString("map"))
+                MapStart
+                MapKey(BorrowedString("b"))
+                MapStart
+                MapEnd
+                MapKey(BorrowedString("a"))
+                ListStart
+                ListEnd
+                MapEnd
+                MapKey(BorrowedString("name"))
+                Literal(BorrowedString("spam"))
+                MapKey(BorrowedString("bytes"))
+                Literal(Bytes([255, 0]))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        test_loader(
+            b"i1e3:abcle",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                Literal(BorrowedString("abc"))
+                DocumentEnd
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b"", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            b"li1e",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(1))
+                Error: Unexpected end of input at offset 4
+            "#]],
+        );
+        test_loader(
+            b"di1ei2ee",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Dictionary keys must be byte strings, but found 'i' at offset 1
+            "#]],
+        );
+        test_loader(
+            b"d1:ae",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("a"))
+                Error: Missing value of dictionary key at offset 4
+            "#]],
+        );
+        test_loader(
+            b"i03e",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid integer '03' at offset 0
+            "#]],
+        );
+        test_loader(
+            b"i-0e",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid integer '-0' at offset 0
+            "#]],
+        );
+        test_loader(
+            b"i1.5e",
+            expect![[r#"
+                DocumentStart
+                Error: Unexpected '.' in integer at offset 0
+            "#]],
+        );
+        test_loader(
+            b"i9223372036854775808e",
+            expect![[r#"
+                DocumentStart
+                Error: Integer 9223372036854775808 at offset 0 is out of range
+            "#]],
+        );
+        test_loader(
+            b"5:abc",
+            expect![[r#"
+                DocumentStart
+                Error: Unexpected end of input at offset 2
+            "#]],
+        );
+        test_loader(
+            b"3x",
+            expect![[r#"
+                DocumentStart
+                Error: Unexpected 'x' in byte string length at offset 0
+            "#]],
+        );
+        test_loader(
+            b"e",
+            expect![[r#"
+                DocumentStart
+                Error: Unexpected 'e' at offset 0
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        let input = b"d8:announce23:http://tracker/announce4:infod6:lengthi1024e\
+                      4:name8:file.bin6:pieces4:\x00\x01\x02\xffee";
+        for event in BencodeLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"announce": "http://tracker/announce",
+            	"info": {
+            		"length": 1024,
+            		"name": "file.bin",
+            		"pieces": "AAEC/w=="
+            	}
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
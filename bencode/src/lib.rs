@@ -0,0 +1,8 @@
+//! Bencode, the encoding of BitTorrent metainfo files
+//!
+//! Dictionaries are maps, lists are lists and integers are integers. Byte strings are loaded as
+//! strings if they are valid UTF-8 and as bytes otherwise, such as the `pieces` hashes of a
+//! torrent.
+
+pub mod bencode_dumper;
+pub mod bencode_loader;
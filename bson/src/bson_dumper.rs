@@ -0,0 +1,656 @@
+use crate::element_type::*;
+use crate::{
+    BINARY_TAG_PREFIX, DB_POINTER_TAG, DECIMAL128_TAG, JAVASCRIPT_TAG, JAVASCRIPT_WITH_SCOPE_TAG,
+    MAX_KEY_TAG, MIN_KEY_TAG, OBJECT_ID_TAG, REGEX_TAG, SYMBOL_TAG, TIMESTAMP_TAG, UNDEFINED_TAG,
+    decimal128,
+};
+use loadum::datetime::DateTime;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Dumps BSON, each document is written as a top level BSON document
+///
+/// Values tagged like the ones of [`BsonLoader`](crate::bson_loader::BsonLoader) are written as
+/// their BSON types, other tags are ignored. The canonical Extended JSON maps `{"$oid": ...}`,
+/// `{"$numberDecimal": ...}` and `{"$date": ...}` are written as their BSON types as well, other
+/// Extended JSON maps as documents. Integers are written as 32 bit integers if they fit, local
+/// date-times as strings.
+pub struct BsonDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> BsonDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> BsonDumper<'write> {
+        BsonDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for BsonDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let Node::Map(entries) = root.untagged() else {
+            bail!("BSON documents must be maps, but found {:?}", root);
+        };
+        let mut writer = BsonWriter {
+            output: vec![],
+            key_policy: self.key_policy,
+        };
+        writer.write_document(entries)?;
+        self.write.write_all(&writer.output)?;
+        Ok(())
+    }
+}
+
+struct BsonWriter {
+    output: Vec<u8>,
+    key_policy: KeyPolicy,
+}
+
+impl BsonWriter {
+    fn write_document(&mut self, entries: &[(Node, Node)]) -> LoadumResult<()> {
+        let start = self.start_length();
+        for (key, value) in entries {
+            let name = self.key_policy.key_string(key, "BSON keys")?;
+            self.write_element(&name, value)?;
+        }
+        self.output.push(0);
+        self.end_length(start)
+    }
+
+    fn write_array(&mut self, items: &[Node]) -> LoadumResult<()> {
+        let start = self.start_length();
+        for (index, item) in items.iter().enumerate() {
+            self.write_element(&index.to_string(), item)?;
+        }
+        self.output.push(0);
+        self.end_length(start)
+    }
+
+    /// Writes the element type, which is known after writing the value, the name and the value
+    fn write_element(&mut self, name: &str, value: &Node) -> LoadumResult<()> {
+        let type_offset = self.output.len();
+        self.output.push(0);
+        self.write_c_string(name)?;
+        self.output[type_offset] = self.write_value(value)?;
+        Ok(())
+    }
+
+    /// Writes a value and returns its element type
+    fn write_value(&mut self, node: &Node) -> LoadumResult<u8> {
+        match node {
+            Node::Scalar(value) => self.write_scalar(value),
+            Node::Map(entries) => match self.write_extended(entries)? {
+                Some(element_type) => Ok(element_type),
+                None => {
+                    self.write_document(entries)?;
+                    Ok(DOCUMENT)
+                }
+            },
+            Node::List(items) => {
+                self.write_array(items)?;
+                Ok(ARRAY)
+            }
+            Node::Tagged(tag, node) => match self.write_typed(tag, node.untagged())? {
+                Some(element_type) => Ok(element_type),
+                // Other tags have no BSON representation, the tagged value is written as is
+                None => self.write_value(node),
+            },
+        }
+    }
+
+    fn write_scalar(&mut self, value: &Value) -> LoadumResult<u8> {
+        let element_type = match value {
+            Value::Null => NULL,
+            Value::Boolean(b) => {
+                self.output.push(*b as u8);
+                BOOLEAN
+            }
+            Value::Integer(i) => match i32::try_from(*i) {
+                Ok(i) => {
+                    self.output.extend_from_slice(&i.to_le_bytes());
+                    INT32
+                }
+                Err(_) => {
+                    self.output.extend_from_slice(&i.to_le_bytes());
+                    INT64
+                }
+            },
+            Value::Number(n) => {
+                self.output.extend_from_slice(&n.to_le_bytes());
+                DOUBLE
+            }
+            Value::String(s) => self.write_string(s)?,
+            Value::BorrowedString(s) => self.write_string(s)?,
+            Value::Bytes(bytes) => self.write_binary(0, bytes)?,
+            Value::DateTime(date_time) => match self.write_date_time(date_time)? {
+                Some(element_type) => element_type,
+                // BSON date-times are absolute, local dates and times are written as strings
+                None => self.write_string(&date_time.to_string())?,
+            },
+        };
+        Ok(element_type)
+    }
+
+    /// Writes an absolute date-time, returns None for local dates and times
+    fn write_date_time(&mut self, date_time: &DateTime) -> LoadumResult<Option<u8>> {
+        let Some((seconds, nanosecond)) = date_time.unix_timestamp() else {
+            return Ok(None);
+        };
+        let Some(milliseconds) = seconds
+            .checked_mul(1000)
+            .and_then(|milliseconds| milliseconds.checked_add(nanosecond as i64 / 1_000_000))
+        else {
+            bail!("Date-time {} is out of the BSON range", date_time);
+        };
+        self.output.extend_from_slice(&milliseconds.to_le_bytes());
+        Ok(Some(DATE_TIME))
+    }
+
+    /// Writes a single entry Extended JSON map as its BSON type, returns None for other maps
+    fn write_extended(&mut self, entries: &[(Node, Node)]) -> LoadumResult<Option<u8>> {
+        let [(key, value)] = entries else {
+            return Ok(None);
+        };
+        let element_type = match (key.untagged().as_str(), value.untagged()) {
+            (Some("$oid"), Node::Scalar(value)) => match value.as_str() {
+                Some(hex) => self.write_object_id(hex)?,
+                None => return Ok(None),
+            },
+            (Some("$numberDecimal"), Node::Scalar(value)) => match value.as_str() {
+                Some(text) => self.write_decimal128(text)?,
+                None => return Ok(None),
+            },
+            // Relaxed Extended JSON writes dates as ISO 8601 strings
+            (Some("$date"), Node::Scalar(value)) => {
+                let date_time = match value {
+                    Value::DateTime(date_time) => Some(*date_time),
+                    value => value.as_str().and_then(|text| DateTime::parse(text).ok()),
+                };
+                match date_time {
+                    Some(date_time) => return self.write_date_time(&date_time),
+                    None => return Ok(None),
+                }
+            }
+            // Canonical Extended JSON writes them as milliseconds, `{"$numberLong": "..."}`
+            (Some("$date"), Node::Map(milliseconds)) if milliseconds.len() == 1 => {
+                let milliseconds = field_str(milliseconds, "$numberLong")
+                    .and_then(|milliseconds| milliseconds.parse::<i64>().ok());
+                let Some(milliseconds) = milliseconds else {
+                    return Ok(None);
+                };
+                self.output.extend_from_slice(&milliseconds.to_le_bytes());
+                DATE_TIME
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(element_type))
+    }
+
+    /// Writes a value tagged with a BSON type, returns None if the tag or value do not match
+    fn write_typed(&mut self, tag: &str, node: &Node) -> LoadumResult<Option<u8>> {
+        let element_type = match (tag, node) {
+            (OBJECT_ID_TAG, Node::Scalar(value)) => match value.as_str() {
+                Some(hex) => self.write_object_id(hex)?,
+                None => return Ok(None),
+            },
+            (DECIMAL128_TAG, Node::Scalar(value)) => {
+                let text = match value {
+                    Value::Integer(i) => Cow::Owned(i.to_string()),
+                    value => match value.as_str() {
+                        Some(text) => Cow::Borrowed(text),
+                        None => return Ok(None),
+                    },
+                };
+                self.write_decimal128(&text)?
+            }
+            (tag, Node::Scalar(Value::Bytes(bytes))) => {
+                let subtype = tag.strip_prefix(BINARY_TAG_PREFIX);
+                match subtype.and_then(|subtype| subtype.parse().ok()) {
+                    Some(subtype) => self.write_binary(subtype, bytes)?,
+                    None => return Ok(None),
+                }
+            }
+            (REGEX_TAG, Node::Map(entries)) => {
+                let (Some(pattern), Some(options)) =
+                    (field_str(entries, "pattern"), field_str(entries, "options"))
+                else {
+                    return Ok(None);
+                };
+                self.write_c_string(pattern)?;
+                self.write_c_string(options)?;
+                REGEX
+            }
+            (TIMESTAMP_TAG, Node::Map(entries)) => {
+                let (Some(time), Some(increment)) =
+                    (field_u32(entries, "t"), field_u32(entries, "i"))
+                else {
+                    return Ok(None);
+                };
+                self.output.extend_from_slice(&increment.to_le_bytes());
+                self.output.extend_from_slice(&time.to_le_bytes());
+                TIMESTAMP
+            }
+            (JAVASCRIPT_TAG | SYMBOL_TAG, Node::Scalar(value)) => match value.as_str() {
+                Some(s) => {
+                    self.write_string(s)?;
+                    if tag == JAVASCRIPT_TAG {
+                        JAVASCRIPT
+                    } else {
+                        SYMBOL
+                    }
+                }
+                None => return Ok(None),
+            },
+            (JAVASCRIPT_WITH_SCOPE_TAG, Node::Map(entries)) => {
+                let code = field_str(entries, "code");
+                let scope = field(entries, "scope").map(Node::untagged);
+                let (Some(code), Some(Node::Map(scope))) = (code, scope) else {
+                    return Ok(None);
+                };
+                let start = self.start_length();
+                self.write_string(code)?;
+                self.write_document(scope)?;
+                self.end_length(start)?;
+                JAVASCRIPT_WITH_SCOPE
+            }
+            (DB_POINTER_TAG, Node::Map(entries)) => {
+                let (Some(namespace), Some(id)) =
+                    (field_str(entries, "$ref"), field_str(entries, "$id"))
+                else {
+                    return Ok(None);
+                };
+                self.write_string(namespace)?;
+                self.write_object_id(id)?;
+                DB_POINTER
+            }
+            (UNDEFINED_TAG, Node::Scalar(Value::Null)) => UNDEFINED,
+            (MIN_KEY_TAG, Node::Scalar(Value::Null)) => MIN_KEY,
+            (MAX_KEY_TAG, Node::Scalar(Value::Null)) => MAX_KEY,
+            _ => return Ok(None),
+        };
+        Ok(Some(element_type))
+    }
+
+    fn write_object_id(&mut self, hex: &str) -> LoadumResult<u8> {
+        if hex.len() != 24 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            bail!("Invalid ObjectId '{}', expected 24 hex digits", hex);
+        }
+        for index in (0..24).step_by(2) {
+            self.output
+                .push(u8::from_str_radix(&hex[index..index + 2], 16)?);
+        }
+        Ok(OBJECT_ID)
+    }
+
+    fn write_decimal128(&mut self, text: &str) -> LoadumResult<u8> {
+        let bits = decimal128::parse(text)?;
+        self.output.extend_from_slice(&bits.to_le_bytes());
+        Ok(DECIMAL128)
+    }
+
+    fn write_binary(&mut self, subtype: u8, bytes: &[u8]) -> LoadumResult<u8> {
+        self.output
+            .extend_from_slice(&length_i32(bytes.len())?.to_le_bytes());
+        self.output.push(subtype);
+        self.output.extend_from_slice(bytes);
+        Ok(BINARY)
+    }
+
+    /// Writes a string with a length prefix, which counts the terminating null byte
+    fn write_string(&mut self, s: &str) -> LoadumResult<u8> {
+        self.output
+            .extend_from_slice(&length_i32(s.len() + 1)?.to_le_bytes());
+        self.output.extend_from_slice(s.as_bytes());
+        self.output.push(0);
+        Ok(STRING)
+    }
+
+    fn write_c_string(&mut self, s: &str) -> LoadumResult<()> {
+        if s.contains('\0') {
+            bail!(
+                "BSON keys and regular expressions cannot contain null characters, but found {:?}",
+                s
+            );
+        }
+        self.output.extend_from_slice(s.as_bytes());
+        self.output.push(0);
+        Ok(())
+    }
+
+    /// Reserves the length of a document, returns the offset to pass to `end_length`
+    fn start_length(&mut self) -> usize {
+        let start = self.output.len();
+        self.output.extend_from_slice(&[0; 4]);
+        start
+    }
+
+    fn end_length(&mut self, start: usize) -> LoadumResult<()> {
+        let length = length_i32(self.output.len() - start)?;
+        self.output[start..start + 4].copy_from_slice(&length.to_le_bytes());
+        Ok(())
+    }
+}
+
+fn length_i32(length: usize) -> LoadumResult<i32> {
+    match i32::try_from(length) {
+        Ok(length) => Ok(length),
+        Err(_) => bail!("Length {} exceeds the BSON limit", length),
+    }
+}
+
+fn field<'node, 'source>(
+    entries: &'node [(Node<'source>, Node<'source>)],
+    key: &str,
+) -> Option<&'node Node<'source>> {
+    entries
+        .iter()
+        .find(|(entry_key, _)| entry_key.untagged().as_str() == Some(key))
+        .map(|(_, value)| value)
+}
+
+fn field_str<'node>(entries: &'node [(Node, Node)], key: &str) -> Option<&'node str> {
+    field(entries, key)?.untagged().as_str()
+}
+
+fn field_u32(entries: &[(Node, Node)], key: &str) -> Option<u32> {
+    match field(entries, key)?.untagged() {
+        Node::Scalar(Value::Integer(i)) => u32::try_from(*i).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bson_dumper::BsonDumper;
+    use crate::bson_loader::BsonLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump(events: &[Event], key_policy: KeyPolicy) -> String {
+        let mut output = vec![];
+        let mut dumper = BsonDumper::new(&mut output).with_key_policy(key_policy);
+        let result = [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+            .try_for_each(|event| dumper.emit(event));
+        drop(dumper);
+        match result {
+            Ok(()) => output.escape_ascii().to_string(),
+            Err(error) => format!("Error: {}", error),
+        }
+    }
+
+    fn assert_roundtrip(events: &[Event]) {
+        let mut output = vec![];
+        let mut dumper = BsonDumper::new(&mut output);
+        for event in events {
+            dumper.emit(event).unwrap();
+        }
+        drop(dumper);
+        let loaded: Vec<Event> = BsonLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        let expected: Vec<Event> = events
+            .iter()
+            .map(|event| event.clone().into_owned())
+            .collect();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let output = dump(
+            &[
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::integer(1),
+                Event::map_key("b"),
+                Event::ListStart,
+                Event::string("x"),
+                Event::integer(1i64 << 40),
+                Event::ListEnd,
+                Event::MapKey(Value::integer(2)),
+                Event::date_time(DateTime::parse("1979-05-27").unwrap()),
+                Event::MapEnd,
+            ],
+            KeyPolicy::Stringify,
+        );
+        expect![[r#":\x00\x00\x00\x10a\x00\x01\x00\x00\x00\x04b\x00\x19\x00\x00\x00\x020\x00\x02\x00\x00\x00x\x00\x121\x00\x00\x00\x00\x00\x00\x01\x00\x00\x00\x022\x00\x0b\x00\x00\x001979-05-27\x00\x00"#]].assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let object_id = || {
+            [
+                Event::tag("objectId"),
+                Event::string("507f1f77bcf86cd799439011"),
+            ]
+        };
+        let mut events = vec![Event::DocumentStart, Event::MapStart, Event::map_key("_id")];
+        events.extend(object_id());
+        events.extend([
+            Event::map_key("values"),
+            Event::ListStart,
+            Event::null(),
+            Event::bool(true),
+            Event::integer(-1),
+            Event::integer(i64::MIN),
+            Event::number(0.1),
+            Event::number(f64::INFINITY),
+            Event::string(""),
+            Event::string("null \0 inside"),
+            Event::bytes(&b"\x00\xff"[..]),
+            Event::date_time(DateTime::from_unix_timestamp(-1, 500_000_000).unwrap()),
+            Event::tag("binary:4"),
+            Event::bytes(vec![7u8; 16]),
+            Event::tag("decimal128"),
+            Event::string("-1.50E+100"),
+            Event::tag("regex"),
+            Event::MapStart,
+            Event::map_key("pattern"),
+            Event::string("^a"),
+            Event::map_key("options"),
+            Event::string("i"),
+            Event::MapEnd,
+            Event::tag("timestamp"),
+            Event::MapStart,
+            Event::map_key("t"),
+            Event::integer(u32::MAX),
+            Event::map_key("i"),
+            Event::integer(1),
+            Event::MapEnd,
+            Event::tag("javascript"),
+            Event::string("f()"),
+            Event::tag("symbol"),
+            Event::string("s"),
+            Event::tag("javascriptWithScope"),
+            Event::MapStart,
+            Event::map_key("code"),
+            Event::string("x"),
+            Event::map_key("scope"),
+            Event::MapStart,
+            Event::map_key("x"),
+            Event::integer(1),
+            Event::MapEnd,
+            Event::MapEnd,
+            Event::tag("dbPointer"),
+            Event::MapStart,
+            Event::map_key("$ref"),
+            Event::string("db.collection"),
+            Event::map_key("$id"),
+        ]);
+        events.extend(object_id());
+        events.extend([
+            Event::MapEnd,
+            Event::tag("undefined"),
+            Event::null(),
+            Event::tag("minKey"),
+            Event::null(),
+            Event::tag("maxKey"),
+            Event::null(),
+            Event::ListEnd,
+            Event::map_key("nested"),
+            Event::MapStart,
+            Event::map_key("empty"),
+            Event::MapStart,
+            Event::MapEnd,
+            Event::MapEnd,
+            Event::MapEnd,
+            Event::DocumentEnd,
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ]);
+        assert_roundtrip(&events);
+    }
+
+    #[test]
+    fn test_extended_json() {
+        let mut input = vec![];
+        let mut dumper = BsonDumper::new(&mut input);
+        for event in [
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::map_key("_id"),
+            Event::tag("objectId"),
+            Event::string("507f1f77bcf86cd799439011"),
+            Event::map_key("price"),
+            Event::tag("decimal128"),
+            Event::string("0.15"),
+            Event::map_key("relaxed"),
+            Event::date_time(DateTime::from_unix_timestamp(1, 0).unwrap()),
+            Event::map_key("canonical"),
+            Event::date_time(DateTime::from_unix_timestamp(-1, 999_000_000).unwrap()),
+            Event::map_key("ignored"),
+            Event::MapStart,
+            Event::map_key("$oid"),
+            Event::integer(1),
+            Event::MapEnd,
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ] {
+            dumper.emit(&event).unwrap();
+        }
+        drop(dumper);
+        let mut output = vec![];
+        let mut dumper = BsonDumper::new(&mut output);
+        for event in BsonLoader::new(&input).with_extended_json(true) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(
+            output.escape_ascii().to_string(),
+            input.escape_ascii().to_string()
+        );
+    }
+
+    #[test]
+    fn test_ignored_tags() {
+        let output = dump(
+            &[
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::tag("objectId"),
+                Event::integer(1),
+                Event::map_key("b"),
+                Event::tag("binary:x"),
+                Event::bytes(&b"\x01"[..]),
+                Event::map_key("c"),
+                Event::tag("tag:yaml.org,2002:str"),
+                Event::string("s"),
+                Event::MapEnd,
+            ],
+            KeyPolicy::Stringify,
+        );
+        expect![[r#"\x1e\x00\x00\x00\x10a\x00\x01\x00\x00\x00\x05b\x00\x01\x00\x00\x00\x00\x01\x02c\x00\x02\x00\x00\x00s\x00\x00"#]].assert_eq(&output);
+    }
+
+    #[test]
+    fn test_errors() {
+        let output = [
+            dump(&[Event::ListStart, Event::ListEnd], KeyPolicy::Stringify),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::MapKey(Value::integer(1)),
+                    Event::null(),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Reject,
+            ),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::ComplexKey,
+                    Event::ListStart,
+                    Event::ListEnd,
+                    Event::null(),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Stringify,
+            ),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::map_key("a\0b"),
+                    Event::null(),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Stringify,
+            ),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::map_key("id"),
+                    Event::tag("objectId"),
+                    Event::string("xyz"),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Stringify,
+            ),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::map_key("decimal"),
+                    Event::tag("decimal128"),
+                    Event::string("0.1e"),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Stringify,
+            ),
+        ]
+        .join("\n");
+        expect![[r#"
+            Error: BSON documents must be maps, but found List([])
+            Error: BSON keys must be strings, but found Scalar(Integer(1))
+            Error: BSON keys cannot be composite, but found List([])
+            Error: BSON keys and regular expressions cannot contain null characters, but found "a\0b"
+            Error: Invalid ObjectId 'xyz', expected 24 hex digits
+            Error: Invalid decimal '0.1e'"#]].assert_eq(&output);
+    }
+}
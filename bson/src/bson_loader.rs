@@ -0,0 +1,734 @@
+use crate::element_type::*;
+use crate::{
+    BINARY_TAG_PREFIX, DB_POINTER_TAG, DECIMAL128_TAG, JAVASCRIPT_TAG, JAVASCRIPT_WITH_SCOPE_TAG,
+    MAX_KEY_TAG, MIN_KEY_TAG, OBJECT_ID_TAG, REGEX_TAG, SYMBOL_TAG, TIMESTAMP_TAG, UNDEFINED_TAG,
+    decimal128,
+};
+use loadum::base64;
+use loadum::byte_input::ByteInput;
+use loadum::datetime::DateTime;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::io::Read;
+
+/// Loads BSON, each top level document in the input is loaded as a separate document
+///
+/// This reads the concatenated documents of `mongodump` files. Array indexes are not checked,
+/// arrays are loaded as lists in the order of their elements.
+pub struct BsonLoader<'source> {
+    input: ByteInput<'source>,
+    state: LoaderState,
+    stack: Vec<Container>,
+    /// Events to return before reading more input
+    pending: VecDeque<Event<'source>>,
+    extended_json: bool,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    BetweenDocuments,
+    InDocument,
+    Done,
+}
+
+struct Container {
+    is_array: bool,
+    /// Offset after the terminating null byte
+    end: u64,
+    /// Whether the map of a JavaScript code with scope ends with this scope document
+    ends_code_with_scope: bool,
+}
+
+impl<'source> BsonLoader<'source> {
+    pub fn new(source: &'source [u8]) -> BsonLoader<'source> {
+        Self::with_input(ByteInput::from_slice(source))
+    }
+
+    /// Loads BSON incrementally from a reader
+    pub fn from_reader(read: impl Read + 'source) -> BsonLoader<'source> {
+        Self::with_input(ByteInput::from_reader(read))
+    }
+
+    fn with_input(input: ByteInput<'source>) -> BsonLoader<'source> {
+        BsonLoader {
+            input,
+            state: LoaderState::BetweenDocuments,
+            stack: vec![],
+            pending: VecDeque::new(),
+            extended_json: false,
+        }
+    }
+
+    /// Loads BSON types as the maps of relaxed MongoDB Extended JSON instead of tagged values
+    ///
+    /// For example ObjectIds are loaded as `{"$oid": "..."}` and date-times as `{"$date": "..."}`,
+    /// so dumping the events as JSON writes Extended JSON.
+    pub fn with_extended_json(mut self, extended_json: bool) -> Self {
+        self.extended_json = extended_json;
+        self
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+        match self.state {
+            LoaderState::BetweenDocuments => {
+                if self.input.is_at_end()? {
+                    self.state = LoaderState::Done;
+                    return Ok(None);
+                }
+                self.state = LoaderState::InDocument;
+                self.start_document(false, false)?;
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::Done => return Ok(None),
+            LoaderState::InDocument => {}
+        }
+        self.read_element()?;
+        Ok(self.pending.pop_front())
+    }
+
+    /// Reads the length of a document or array and queues its start event
+    fn start_document(&mut self, is_array: bool, ends_code_with_scope: bool) -> LoadumResult<()> {
+        let offset = self.input.offset();
+        let length = i32::from_le_bytes(self.input.read_array()?);
+        if length < 5 {
+            bail!("Invalid document length {} at offset {}", length, offset);
+        }
+        let end = offset + length as u64;
+        if self.stack.last().is_some_and(|parent| end > parent.end) {
+            bail!("Document at offset {} exceeds its parent", offset);
+        }
+        self.stack.push(Container {
+            is_array,
+            end,
+            ends_code_with_scope,
+        });
+        self.pending.push_back(if is_array {
+            Event::ListStart
+        } else {
+            Event::MapStart
+        });
+        Ok(())
+    }
+
+    fn read_element(&mut self) -> LoadumResult<()> {
+        let offset = self.input.offset();
+        let element_type = self.input.read_u8()?;
+        let container = self.stack.last().unwrap();
+        let (is_array, end) = (container.is_array, container.end);
+        if element_type == 0 {
+            if self.input.offset() != end {
+                bail!("Unexpected end of document at offset {}", offset);
+            }
+            let container = self.stack.pop().unwrap();
+            self.pending.push_back(if is_array {
+                Event::ListEnd
+            } else {
+                Event::MapEnd
+            });
+            if container.ends_code_with_scope {
+                self.pending.push_back(Event::MapEnd);
+            }
+            if self.stack.is_empty() {
+                self.pending.push_back(Event::DocumentEnd);
+                self.state = LoaderState::BetweenDocuments;
+            }
+            return Ok(());
+        }
+        let key = self.input.read_c_string()?;
+        if !is_array {
+            self.pending.push_back(Event::MapKey(key));
+        }
+        self.read_value(element_type, offset)?;
+        if self.input.offset() > end {
+            bail!("Element at offset {} exceeds its document", offset);
+        }
+        Ok(())
+    }
+
+    fn read_value(&mut self, element_type: u8, offset: u64) -> LoadumResult<()> {
+        let events = match element_type {
+            DOUBLE => {
+                let number = f64::from_le_bytes(self.input.read_array()?);
+                if self.extended_json && !number.is_finite() {
+                    let text = if number.is_nan() {
+                        "NaN"
+                    } else if number > 0.0 {
+                        "Infinity"
+                    } else {
+                        "-Infinity"
+                    };
+                    self.wrap("$numberDouble", vec![Event::string(text)])
+                } else {
+                    vec![Event::number(number)]
+                }
+            }
+            STRING => vec![Event::Literal(self.read_string()?)],
+            DOCUMENT | ARRAY => return self.start_document(element_type == ARRAY, false),
+            BINARY => {
+                let length = self.read_length()?;
+                let subtype = self.input.read_u8()?;
+                let data = self.input.read_bytes(length)?;
+                if self.extended_json {
+                    self.wrap(
+                        "$binary",
+                        vec![
+                            Event::MapStart,
+                            Event::map_key("base64"),
+                            Event::string(base64::encode(&data)),
+                            Event::map_key("subType"),
+                            Event::string(format!("{:02x}", subtype)),
+                            Event::MapEnd,
+                        ],
+                    )
+                } else if subtype == 0 {
+                    vec![Event::bytes(&*data)]
+                } else {
+                    let tag = format!("{}{}", BINARY_TAG_PREFIX, subtype);
+                    vec![Event::tag(tag), Event::bytes(&*data)]
+                }
+            }
+            UNDEFINED => {
+                let value = if self.extended_json {
+                    Event::bool(true)
+                } else {
+                    Event::null()
+                };
+                self.typed(UNDEFINED_TAG, "$undefined", vec![value])
+            }
+            OBJECT_ID => self.read_object_id()?,
+            BOOLEAN => match self.input.read_u8()? {
+                0 => vec![Event::bool(false)],
+                1 => vec![Event::bool(true)],
+                byte => bail!("Invalid boolean 0x{:02x} at offset {}", byte, offset),
+            },
+            DATE_TIME => {
+                let milliseconds = i64::from_le_bytes(self.input.read_array()?);
+                let date_time = DateTime::from_unix_timestamp(
+                    milliseconds.div_euclid(1000),
+                    milliseconds.rem_euclid(1000) as u32 * 1_000_000,
+                )?;
+                match date_time {
+                    _ if !self.extended_json => vec![Event::date_time(date_time)],
+                    // Relaxed Extended JSON uses ISO 8601 only for years with four digits
+                    DateTime::OffsetDateTime { date, .. }
+                        if (1970..=9999).contains(&date.year()) =>
+                    {
+                        self.wrap("$date", vec![Event::string(date_time.to_string())])
+                    }
+                    _ => {
+                        let milliseconds =
+                            self.wrap("$numberLong", vec![Event::string(milliseconds.to_string())]);
+                        self.wrap("$date", milliseconds)
+                    }
+                }
+            }
+            NULL => vec![Event::null()],
+            REGEX => {
+                let pattern = self.input.read_c_string()?;
+                let options = self.input.read_c_string()?;
+                let events = vec![
+                    Event::MapStart,
+                    Event::map_key("pattern"),
+                    Event::Literal(pattern),
+                    Event::map_key("options"),
+                    Event::Literal(options),
+                    Event::MapEnd,
+                ];
+                self.typed(REGEX_TAG, "$regularExpression", events)
+            }
+            DB_POINTER => {
+                let namespace = self.read_string()?;
+                let mut events = vec![
+                    Event::MapStart,
+                    Event::map_key("$ref"),
+                    Event::Literal(namespace),
+                    Event::map_key("$id"),
+                ];
+                events.extend(self.read_object_id()?);
+                events.push(Event::MapEnd);
+                self.typed(DB_POINTER_TAG, "$dbPointer", events)
+            }
+            JAVASCRIPT => {
+                let code = self.read_string()?;
+                self.typed(JAVASCRIPT_TAG, "$code", vec![Event::Literal(code)])
+            }
+            SYMBOL => {
+                let symbol = self.read_string()?;
+                self.typed(SYMBOL_TAG, "$symbol", vec![Event::Literal(symbol)])
+            }
+            JAVASCRIPT_WITH_SCOPE => {
+                // The total length is redundant, the scope document ends the value
+                self.read_length()?;
+                let code = self.read_string()?;
+                let (code_key, scope_key) = if self.extended_json {
+                    ("$code", "$scope")
+                } else {
+                    self.pending
+                        .push_back(Event::tag(JAVASCRIPT_WITH_SCOPE_TAG));
+                    ("code", "scope")
+                };
+                self.pending.extend([
+                    Event::MapStart,
+                    Event::map_key(code_key),
+                    Event::Literal(code),
+                    Event::map_key(scope_key),
+                ]);
+                return self.start_document(false, true);
+            }
+            INT32 => vec![Event::integer(i32::from_le_bytes(self.input.read_array()?))],
+            TIMESTAMP => {
+                let increment = u32::from_le_bytes(self.input.read_array()?);
+                let time = u32::from_le_bytes(self.input.read_array()?);
+                let events = vec![
+                    Event::MapStart,
+                    Event::map_key("t"),
+                    Event::integer(time),
+                    Event::map_key("i"),
+                    Event::integer(increment),
+                    Event::MapEnd,
+                ];
+                self.typed(TIMESTAMP_TAG, "$timestamp", events)
+            }
+            INT64 => vec![Event::integer(i64::from_le_bytes(self.input.read_array()?))],
+            DECIMAL128 => {
+                let decimal = decimal128::format(u128::from_le_bytes(self.input.read_array()?));
+                self.typed(
+                    DECIMAL128_TAG,
+                    "$numberDecimal",
+                    vec![Event::string(decimal)],
+                )
+            }
+            MIN_KEY | MAX_KEY => {
+                let (tag, key) = if element_type == MIN_KEY {
+                    (MIN_KEY_TAG, "$minKey")
+                } else {
+                    (MAX_KEY_TAG, "$maxKey")
+                };
+                let value = if self.extended_json {
+                    Event::integer(1)
+                } else {
+                    Event::null()
+                };
+                self.typed(tag, key, vec![value])
+            }
+            _ => bail!(
+                "Invalid BSON element type 0x{:02x} at offset {}",
+                element_type,
+                offset
+            ),
+        };
+        self.pending.extend(events);
+        Ok(())
+    }
+
+    /// Tags the events of a value with the BSON type, or wraps them in an Extended JSON map
+    fn typed(
+        &self,
+        tag: &'static str,
+        key: &'static str,
+        events: Vec<Event<'source>>,
+    ) -> Vec<Event<'source>> {
+        if self.extended_json {
+            return self.wrap(key, events);
+        }
+        let mut tagged = vec![Event::tag(tag)];
+        tagged.extend(events);
+        tagged
+    }
+
+    /// Wraps the events of a value in a map with a single key
+    fn wrap(&self, key: &'static str, events: Vec<Event<'source>>) -> Vec<Event<'source>> {
+        let mut wrapped = vec![Event::MapStart, Event::map_key(key)];
+        wrapped.extend(events);
+        wrapped.push(Event::MapEnd);
+        wrapped
+    }
+
+    fn read_object_id(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        let bytes: [u8; 12] = self.input.read_array()?;
+        let mut hex = String::with_capacity(24);
+        for byte in bytes {
+            write!(hex, "{:02x}", byte).unwrap();
+        }
+        Ok(self.typed(OBJECT_ID_TAG, "$oid", vec![Event::string(hex)]))
+    }
+
+    fn read_length(&mut self) -> LoadumResult<usize> {
+        let offset = self.input.offset();
+        let length = i32::from_le_bytes(self.input.read_array()?);
+        if length < 0 {
+            bail!("Invalid length {} at offset {}", length, offset);
+        }
+        Ok(length as usize)
+    }
+
+    /// Reads a string with a length prefix, which counts the terminating null byte
+    fn read_string(&mut self) -> LoadumResult<Value<'source>> {
+        let offset = self.input.offset();
+        let length = self.read_length()?;
+        if length == 0 {
+            bail!("Invalid string length 0 at offset {}", offset);
+        }
+        let value = self.input.read_string(length - 1)?;
+        if self.input.read_u8()? != 0 {
+            bail!("String at offset {} is not null terminated", offset);
+        }
+        Ok(value)
+    }
+}
+
+impl<'source> Loader<'source> for BsonLoader<'source> {}
+
+impl<'source> Iterator for BsonLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                self.pending.clear();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bson_loader::BsonLoader;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::Dumper;
+    use loadum_json::json_dumper::JsonDumper;
+    use std::fmt::Write;
+
+    /// Prefixes the elements of a document with its length and appends the terminating null byte
+    fn document(elements: &[u8]) -> Vec<u8> {
+        let mut document = ((elements.len() + 5) as i32).to_le_bytes().to_vec();
+        document.extend_from_slice(elements);
+        document.push(0);
+        document
+    }
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let events: Vec<_> = BsonLoader::new(input)
+            .map(|event| event.map_err(|error| error.to_string()))
+            .collect();
+        let mut output = String::new();
+        for event in &events {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        expected.assert_eq(&output);
+        let reader_events: Vec<_> = BsonLoader::from_reader(input)
+            .map(|event| event.map_err(|error| error.to_string()))
+            .collect();
+        assert_eq!(events, reader_events);
+    }
+
+    /// Elements of all BSON types
+    fn all_types() -> Vec<u8> {
+        let mut elements = vec![];
+        elements.extend(b"\x01double\0\x00\x00\x00\x00\x00\x00\xf8\x3f");
+        elements.extend(b"\x01inf\0\x00\x00\x00\x00\x00\x00\xf0\xff");
+        elements.extend(b"\x02string\0\x04\x00\x00\x00abc\0");
+        elements.extend(b"\x03document\0");
+        elements.extend(document(b"\x0anull\0"));
+        elements.extend(b"\x04array\0");
+        elements.extend(document(b"\x100\0\x01\x00\x00\x00\x081\0\x01"));
+        elements.extend(b"\x05binary\0\x02\x00\x00\x00\x00\x01\x02");
+        elements.extend(b"\x05uuid\0\x02\x00\x00\x00\x04\x01\x02");
+        elements.extend(b"\x06undefined\0");
+        elements.extend(b"\x07_id\0\x50\x7f\x1f\x77\xbc\xf8\x6c\xd7\x99\x43\x90\x11");
+        elements.extend(b"\x09date\0\xe8\x03\x00\x00\x00\x00\x00\x00");
+        elements.extend(b"\x09old\0\xff\xff\xff\xff\xff\xff\xff\xff");
+        elements.extend(b"\x0bregex\0^a\0i\0");
+        elements.extend(
+            b"\x0cpointer\0\x03\x00\x00\x00db\0\x50\x7f\x1f\x77\xbc\xf8\x6c\xd7\x99\x43\x90\x11",
+        );
+        elements.extend(b"\x0dcode\0\x04\x00\x00\x00f()\0");
+        elements.extend(b"\x0esymbol\0\x02\x00\x00\x00s\0");
+        elements.extend(b"\x0fscoped\0\x16\x00\x00\x00\x02\x00\x00\x00x\0");
+        elements.extend(document(b"\x10x\0\x01\x00\x00\x00"));
+        elements.extend(b"\x11timestamp\0\x02\x00\x00\x00\x01\x00\x00\x00");
+        elements.extend(b"\x12long\0\x00\x00\x00\x00\x01\x00\x00\x00");
+        elements.extend(
+            b"\x13decimal\0\x0f\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x3c\x30",
+        );
+        elements.extend(b"\xffmin\0\x7fmax\0");
+        document(&elements)
+    }
+
+    #[test]
+    fn test_types() {
+        test_loader(
+            &all_types(),
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("double"))
+                Literal(Number(1.5))
+                MapKey(BorrowedString("inf"))
+                Literal(Number(-inf))
+                MapKey(BorrowedString("string"))
+                Literal(BorrowedString("abc"))
+                MapKey(BorrowedString("document"))
+                MapStart
+                MapKey(BorrowedString("null"))
+                Literal(Null)
+                MapEnd
+                MapKey(BorrowedString("array"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Boolean(true))
+                ListEnd
+                MapKey(BorrowedString("binary"))
+                Literal(Bytes([1, 2]))
+                MapKey(BorrowedString("uuid"))
+                Tag("binary:4")
+                Literal(Bytes([1, 2]))
+                MapKey(BorrowedString("undefined"))
+                Tag("undefined")
+                Literal(Null)
+                MapKey(BorrowedString("_id"))
+                Tag("objectId")
+                Literal(String("507f1f77bcf86cd799439011"))
+                MapKey(BorrowedString("date"))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1970, month: 1, day: 1 }, time: Time { hour: 0, minute: 0, second: 1, nanosecond: 0 }, offset_minutes: 0 }))
+                MapKey(BorrowedString("old"))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1969, month: 12, day: 31 }, time: Time { hour: 23, minute: 59, second: 59, nanosecond: 999000000 }, offset_minutes: 0 }))
+                MapKey(BorrowedString("regex"))
+                Tag("regex")
+                MapStart
+                MapKey(String("pattern"))
+                Literal(BorrowedString("^a"))
+                MapKey(String("options"))
+                Literal(BorrowedString("i"))
+                MapEnd
+                MapKey(BorrowedString("pointer"))
+                Tag("dbPointer")
+                MapStart
+                MapKey(String("$ref"))
+                Literal(BorrowedString("db"))
+                MapKey(String("$id"))
+                Tag("objectId")
+                Literal(String("507f1f77bcf86cd799439011"))
+                MapEnd
+                MapKey(BorrowedString("code"))
+                Tag("javascript")
+                Literal(BorrowedString("f()"))
+                MapKey(BorrowedString("symbol"))
+                Tag("symbol")
+                Literal(BorrowedString("s"))
+                MapKey(BorrowedString("scoped"))
+                Tag("javascriptWithScope")
+                MapStart
+                MapKey(String("code"))
+                Literal(BorrowedString("x"))
+                MapKey(String("scope"))
+                MapStart
+                MapKey(BorrowedString("x"))
+                Literal(Integer(1))
+                MapEnd
+                MapEnd
+                MapKey(BorrowedString("timestamp"))
+                Tag("timestamp")
+                MapStart
+                MapKey(String("t"))
+                Literal(Integer(1))
+                MapKey(String("i"))
+                Literal(Integer(2))
+                MapEnd
+                MapKey(BorrowedString("long"))
+                Literal(Integer(4294967296))
+                MapKey(BorrowedString("decimal"))
+                Tag("decimal128")
+                Literal(String("0.15"))
+                MapKey(BorrowedString("min"))
+                Tag("minKey")
+                Literal(Null)
+                MapKey(BorrowedString("max"))
+                Tag("maxKey")
+                Literal(Null)
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        let mut input = document(b"\x10a\0\x01\x00\x00\x00");
+        input.extend(document(b""));
+        test_loader(
+            &input,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("a"))
+                Literal(Integer(1))
+                MapEnd
+                DocumentEnd
+                DocumentStart
+                MapStart
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b"", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            &document(b"\x10a\0\x01\x00"),
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Unexpected end of input at offset 7
+            "#]],
+        );
+        test_loader(
+            &document(b"\x20a\0"),
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Invalid BSON element type 0x20 at offset 4
+            "#]],
+        );
+        test_loader(
+            &document(b"\x08a\0\x02"),
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Invalid boolean 0x02 at offset 4
+            "#]],
+        );
+        test_loader(
+            &document(b"\x02a\0\x02\x00\x00\x00ab"),
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: String at offset 7 is not null terminated
+            "#]],
+        );
+        test_loader(
+            b"\x04\x00\x00\x00",
+            expect![[r#"
+                Error: Invalid document length 4 at offset 0
+            "#]],
+        );
+        test_loader(
+            &document(&document(b"")),
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Unexpected end of input at offset 10
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_extended_json() {
+        let input = all_types();
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        for event in BsonLoader::new(&input).with_extended_json(true) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"double": 1.5,
+            	"inf": {
+            		"$numberDouble": "-Infinity"
+            	},
+            	"string": "abc",
+            	"document": {
+            		"null": null
+            	},
+            	"array": [
+            		1,
+            		true
+            	],
+            	"binary": {
+            		"$binary": {
+            			"base64": "AQI=",
+            			"subType": "00"
+            		}
+            	},
+            	"uuid": {
+            		"$binary": {
+            			"base64": "AQI=",
+            			"subType": "04"
+            		}
+            	},
+            	"undefined": {
+            		"$undefined": true
+            	},
+            	"_id": {
+            		"$oid": "507f1f77bcf86cd799439011"
+            	},
+            	"date": {
+            		"$date": "1970-01-01T00:00:01Z"
+            	},
+            	"old": {
+            		"$date": {
+            			"$numberLong": "-1"
+            		}
+            	},
+            	"regex": {
+            		"$regularExpression": {
+            			"pattern": "^a",
+            			"options": "i"
+            		}
+            	},
+            	"pointer": {
+            		"$dbPointer": {
+            			"$ref": "db",
+            			"$id": {
+            				"$oid": "507f1f77bcf86cd799439011"
+            			}
+            		}
+            	},
+            	"code": {
+            		"$code": "f()"
+            	},
+            	"symbol": {
+            		"$symbol": "s"
+            	},
+            	"scoped": {
+            		"$code": "x",
+            		"$scope": {
+            			"x": 1
+            		}
+            	},
+            	"timestamp": {
+            		"$timestamp": {
+            			"t": 1,
+            			"i": 2
+            		}
+            	},
+            	"long": 4294967296,
+            	"decimal": {
+            		"$numberDecimal": "0.15"
+            	},
+            	"min": {
+            		"$minKey": 1
+            	},
+            	"max": {
+            		"$maxKey": 1
+            	}
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,180 @@
+//! Conversion between IEEE 754 Decimal128 values in binary integer decimal encoding and strings
+
+use loadum::error::bail;
+use loadum::result::LoadumResult;
+
+const EXPONENT_BIAS: i64 = 6176;
+const MIN_EXPONENT: i64 = -6176;
+const MAX_EXPONENT: i64 = 6111;
+const MAX_DIGITS: usize = 34;
+const MAX_SIGNIFICAND: u128 = 10u128.pow(MAX_DIGITS as u32) - 1;
+const INFINITY: u128 = 0b11110 << 122;
+const NAN: u128 = 0b11111 << 122;
+
+/// Formats a Decimal128 like the BSON specification, in scientific notation for large exponents
+pub(crate) fn format(bits: u128) -> String {
+    let sign = if bits >> 127 == 1 { "-" } else { "" };
+    let (biased_exponent, significand) = if (bits >> 125) & 0b11 == 0b11 {
+        match bits & NAN {
+            NAN => return "NaN".to_string(),
+            INFINITY => return format!("{}Infinity", sign),
+            // The significand of this form exceeds 34 digits, which makes it a non-canonical zero
+            _ => ((bits >> 111) & 0x3fff, 0),
+        }
+    } else {
+        let significand = bits & ((1 << 113) - 1);
+        let significand = if significand > MAX_SIGNIFICAND {
+            0
+        } else {
+            significand
+        };
+        ((bits >> 113) & 0x3fff, significand)
+    };
+    let exponent = biased_exponent as i64 - EXPONENT_BIAS;
+    let digits = significand.to_string();
+    let adjusted_exponent = exponent + digits.len() as i64 - 1;
+    if exponent > 0 || adjusted_exponent < -6 {
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        return format!("{}{}{}{}E{:+}", sign, first, point, rest, adjusted_exponent);
+    }
+    let integer_digits = digits.len() as i64 + exponent;
+    if exponent == 0 {
+        format!("{}{}", sign, digits)
+    } else if integer_digits > 0 {
+        let (integer, fraction) = digits.split_at(integer_digits as usize);
+        format!("{}{}.{}", sign, integer, fraction)
+    } else {
+        let zeros = "0".repeat(-integer_digits as usize);
+        format!("{}0.{}{}", sign, zeros, digits)
+    }
+}
+
+/// Parses a decimal number, infinity or NaN, failing if it cannot be represented exactly
+pub(crate) fn parse(text: &str) -> LoadumResult<u128> {
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (1 << 127, unsigned),
+        None => (0, text.strip_prefix('+').unwrap_or(text)),
+    };
+    if unsigned.eq_ignore_ascii_case("infinity") || unsigned.eq_ignore_ascii_case("inf") {
+        return Ok(sign | INFINITY);
+    }
+    if unsigned.eq_ignore_ascii_case("nan") {
+        return Ok(NAN);
+    }
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()),
+        None => (unsigned, Some(0)),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let is_valid = !(integer.is_empty() && fraction.is_empty())
+        && integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|c| c.is_ascii_digit());
+    let (Some(exponent), true) = (exponent, is_valid) else {
+        bail!("Invalid decimal '{}'", text);
+    };
+    let mut exponent = exponent.saturating_sub(fraction.len() as i64);
+    let digits = format!("{}{}", integer, fraction);
+    let mut digits = digits.trim_start_matches('0');
+    // Trailing zeros can be dropped without rounding
+    while (digits.len() > MAX_DIGITS || exponent < MIN_EXPONENT) && digits.ends_with('0') {
+        digits = &digits[..digits.len() - 1];
+        exponent = exponent.saturating_add(1);
+    }
+    if digits.len() > MAX_DIGITS {
+        bail!("Decimal '{}' has more than {} digits", text, MAX_DIGITS);
+    }
+    let mut significand: u128 = if digits.is_empty() {
+        exponent = exponent.clamp(MIN_EXPONENT, MAX_EXPONENT);
+        0
+    } else {
+        digits.parse()?
+    };
+    // Large exponents can be reduced by appending zeros to the significand
+    while exponent > MAX_EXPONENT && significand * 10 <= MAX_SIGNIFICAND {
+        significand *= 10;
+        exponent -= 1;
+    }
+    if !(MIN_EXPONENT..=MAX_EXPONENT).contains(&exponent) {
+        bail!("Decimal '{}' is out of the Decimal128 range", text);
+    }
+    Ok(sign | ((exponent + EXPONENT_BIAS) as u128) << 113 | significand)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decimal128::{format, parse};
+    use expect_test::expect;
+
+    #[test]
+    fn test_format_and_parse() {
+        let mut output = String::new();
+        for text in [
+            "0",
+            "-0",
+            "1",
+            "-1.5",
+            "0.001234",
+            "0.0000001234",
+            "1234E+3",
+            "1.000000000000000000000000000000000E+6144",
+            "12345678901234567890123456789012340000",
+            "1E-6176",
+            "1E6144",
+            "0E-7000",
+            "-Infinity",
+            "nan",
+        ] {
+            let formatted = parse(text).map(format);
+            output.push_str(&format!("{} -> {:?}\n", text, formatted));
+            if let Ok(formatted) = formatted {
+                assert_eq!(parse(&formatted).map(format).unwrap(), formatted);
+            }
+        }
+        for text in [
+            "",
+            ".",
+            "1e",
+            "1.2.3",
+            "0x10",
+            "12345678901234567890123456789012345",
+            "1E-6177",
+            "1E6145",
+        ] {
+            output.push_str(&format!("{} -> {}\n", text, parse(text).unwrap_err()));
+        }
+        // Significands beyond 34 digits are zero
+        output.push_str(&format!(
+            "{}\n",
+            format(0x3040_0000_0000_0000 << 64 | u128::MAX >> 15)
+        ));
+        expect![[r#"
+            0 -> Ok("0")
+            -0 -> Ok("-0")
+            1 -> Ok("1")
+            -1.5 -> Ok("-1.5")
+            0.001234 -> Ok("0.001234")
+            0.0000001234 -> Ok("1.234E-7")
+            1234E+3 -> Ok("1.234E+6")
+            1.000000000000000000000000000000000E+6144 -> Ok("1.000000000000000000000000000000000E+6144")
+            12345678901234567890123456789012340000 -> Ok("1.234567890123456789012345678901234E+37")
+            1E-6176 -> Ok("1E-6176")
+            1E6144 -> Ok("1.000000000000000000000000000000000E+6144")
+            0E-7000 -> Ok("0E-6176")
+            -Infinity -> Ok("-Infinity")
+            nan -> Ok("NaN")
+             -> Invalid decimal ''
+            . -> Invalid decimal '.'
+            1e -> Invalid decimal '1e'
+            1.2.3 -> Invalid decimal '1.2.3'
+            0x10 -> Invalid decimal '0x10'
+            12345678901234567890123456789012345 -> Decimal '12345678901234567890123456789012345' has more than 34 digits
+            1E-6177 -> Decimal '1E-6177' is out of the Decimal128 range
+            1E6145 -> Decimal '1E6145' is out of the Decimal128 range
+            0
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,59 @@
+//! BSON documents, as written by MongoDB
+//!
+//! BSON types without a counterpart in loadum values are loaded as tagged values: ObjectIds as
+//! tagged hex strings, Decimal128 values as tagged decimal strings and binary data with subtypes
+//! other than generic as tagged bytes. Alternatively the loader produces the maps of MongoDB
+//! Extended JSON, so the JSON dumper writes Extended JSON. Of these maps the BSON dumper only
+//! recognizes `$oid`, `$numberDecimal` and `$date`, the others are written as documents.
+
+pub mod bson_dumper;
+pub mod bson_loader;
+mod decimal128;
+
+/// Tag of ObjectIds, loaded as 24 lowercase hex digits
+pub(crate) const OBJECT_ID_TAG: &str = "objectId";
+/// Tag of Decimal128 values, loaded as strings
+pub(crate) const DECIMAL128_TAG: &str = "decimal128";
+/// Prefix of tags of binary data, followed by the subtype in decimal
+pub(crate) const BINARY_TAG_PREFIX: &str = "binary:";
+/// Tag of regular expressions, loaded as maps of `pattern` and `options`
+pub(crate) const REGEX_TAG: &str = "regex";
+/// Tag of MongoDB internal timestamps, loaded as maps of `t` and `i`
+pub(crate) const TIMESTAMP_TAG: &str = "timestamp";
+/// Tag of JavaScript code, loaded as strings
+pub(crate) const JAVASCRIPT_TAG: &str = "javascript";
+/// Tag of JavaScript code with scope, loaded as maps of `code` and `scope`
+pub(crate) const JAVASCRIPT_WITH_SCOPE_TAG: &str = "javascriptWithScope";
+/// Tag of symbols, loaded as strings
+pub(crate) const SYMBOL_TAG: &str = "symbol";
+/// Tag of DBPointers, loaded as maps of `$ref` and `$id`
+pub(crate) const DB_POINTER_TAG: &str = "dbPointer";
+/// Tags of the undefined value and the min and max keys, loaded as null
+pub(crate) const UNDEFINED_TAG: &str = "undefined";
+pub(crate) const MIN_KEY_TAG: &str = "minKey";
+pub(crate) const MAX_KEY_TAG: &str = "maxKey";
+
+/// Element type bytes
+pub(crate) mod element_type {
+    pub(crate) const DOUBLE: u8 = 0x01;
+    pub(crate) const STRING: u8 = 0x02;
+    pub(crate) const DOCUMENT: u8 = 0x03;
+    pub(crate) const ARRAY: u8 = 0x04;
+    pub(crate) const BINARY: u8 = 0x05;
+    pub(crate) const UNDEFINED: u8 = 0x06;
+    pub(crate) const OBJECT_ID: u8 = 0x07;
+    pub(crate) const BOOLEAN: u8 = 0x08;
+    pub(crate) const DATE_TIME: u8 = 0x09;
+    pub(crate) const NULL: u8 = 0x0a;
+    pub(crate) const REGEX: u8 = 0x0b;
+    pub(crate) const DB_POINTER: u8 = 0x0c;
+    pub(crate) const JAVASCRIPT: u8 = 0x0d;
+    pub(crate) const SYMBOL: u8 = 0x0e;
+    pub(crate) const JAVASCRIPT_WITH_SCOPE: u8 = 0x0f;
+    pub(crate) const INT32: u8 = 0x10;
+    pub(crate) const TIMESTAMP: u8 = 0x11;
+    pub(crate) const INT64: u8 = 0x12;
+    pub(crate) const DECIMAL128: u8 = 0x13;
+    pub(crate) const MIN_KEY: u8 = 0xff;
+    pub(crate) const MAX_KEY: u8 = 0x7f;
+}
@@ -0,0 +1,397 @@
+use crate::SIMPLE_TAG;
+use crate::cbor_loader::DATE_TIME_TAG;
+use loadum::dumper::Dumper;
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Write;
+
+/// Dumps CBOR (RFC 8949), each document is written as a top level item
+///
+/// By default maps and lists are written as they arrive, using indefinite lengths. Tags with a
+/// decimal tag number are written as semantic tags. Digits tagged with `bigint` and numbers tagged
+/// with `simple` are written as the integers and simple values they were loaded from, other tags
+/// are ignored.
+pub struct CborDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    /// `bigint` or `simple` tag of the next value, when writing events as they arrive
+    value_tag: Option<&'static str>,
+    /// Collects documents for deterministic encoding
+    builder: Option<NodeBuilder<'static>>,
+    output: Vec<u8>,
+}
+
+impl<'write> CborDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> CborDumper<'write> {
+        CborDumper {
+            write: Box::new(write),
+            value_tag: None,
+            builder: None,
+            output: vec![],
+        }
+    }
+
+    /// Sets whether to use the core deterministic encoding (RFC 8949 section 4.2.1)
+    ///
+    /// Lengths are definite, numbers use their shortest form and map keys are sorted, so each
+    /// document is collected and written once it is complete.
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.builder = deterministic.then(NodeBuilder::default);
+        self
+    }
+}
+
+impl Dumper for CborDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        self.output.clear();
+        if let Some(builder) = &mut self.builder {
+            let Some(root) = builder.buffer(event)? else {
+                return Ok(());
+            };
+            write_node(&mut self.output, &root)?;
+        } else {
+            let value_tag = self.value_tag.take();
+            match event {
+                Event::DocumentStart | Event::DocumentEnd | Event::ComplexKey => {}
+                Event::MapStart => self.output.push(0xbf),
+                Event::ListStart => self.output.push(0x9f),
+                Event::MapEnd | Event::ListEnd => self.output.push(0xff),
+                Event::MapKey(value) | Event::Literal(value) => {
+                    match value_tag.and_then(|tag| tagged_head(tag, value)) {
+                        Some((major_type, argument)) => {
+                            write_head(&mut self.output, major_type, argument)
+                        }
+                        None => write_value(&mut self.output, value, false)?,
+                    }
+                }
+                Event::Tag(tag) => {
+                    self.value_tag = [BIGINT_TAG, SIMPLE_TAG]
+                        .into_iter()
+                        .find(|value_tag| tag == value_tag);
+                    write_tag(&mut self.output, tag);
+                }
+            }
+        }
+        self.write.write_all(&self.output)?;
+        Ok(())
+    }
+}
+
+/// Writes a node with the core deterministic encoding
+fn write_node(output: &mut Vec<u8>, node: &Node) -> LoadumResult<()> {
+    match node {
+        Node::Scalar(value) => write_value(output, value, true)?,
+        Node::List(items) => {
+            write_head(output, 4, items.len() as u64);
+            for item in items {
+                write_node(output, item)?;
+            }
+        }
+        Node::Map(entries) => {
+            let mut encoded = vec![];
+            for (key, value) in entries {
+                let mut key_output = vec![];
+                write_node(&mut key_output, key)?;
+                let mut value_output = vec![];
+                write_node(&mut value_output, value)?;
+                encoded.push((key_output, value_output));
+            }
+            encoded.sort();
+            write_head(output, 5, encoded.len() as u64);
+            for (key, value) in encoded {
+                output.extend_from_slice(&key);
+                output.extend_from_slice(&value);
+            }
+        }
+        Node::Tagged(tag, node) => {
+            let head = match &**node {
+                Node::Scalar(value) => tagged_head(tag, value),
+                _ => None,
+            };
+            match head {
+                Some((major_type, argument)) => write_head(output, major_type, argument),
+                None => {
+                    write_tag(output, tag);
+                    write_node(output, node)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the head of big integers and simple values, which the loader tags
+fn tagged_head(tag: &str, value: &Value) -> Option<(u8, u64)> {
+    match (tag, value) {
+        (SIMPLE_TAG, Value::Integer(simple @ (0..=19 | 32..=255))) => Some((7, *simple as u64)),
+        (BIGINT_TAG, value) => {
+            let digits = value.as_str()?;
+            match digits.strip_prefix('-') {
+                Some(digits) => {
+                    let value = digits.parse::<u128>().ok()?.checked_sub(1)?;
+                    Some((1, u64::try_from(value).ok()?))
+                }
+                None => Some((0, digits.parse().ok()?)),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn write_tag(output: &mut Vec<u8>, tag: &str) {
+    // Tags of other formats have no CBOR representation, the tagged value is written as is
+    if let Ok(tag) = tag.parse() {
+        write_head(output, 6, tag);
+    }
+}
+
+fn write_value(output: &mut Vec<u8>, value: &Value, shortest_floats: bool) -> LoadumResult<()> {
+    match value {
+        Value::Null => output.push(0xf6),
+        Value::Boolean(b) => output.push(if *b { 0xf5 } else { 0xf4 }),
+        Value::Integer(i) if *i >= 0 => write_head(output, 0, *i as u64),
+        Value::Integer(i) => write_head(output, 1, !*i as u64),
+        Value::Number(n) if shortest_floats => write_shortest_float(output, *n),
+        Value::Number(n) => {
+            output.push(0xfb);
+            output.extend_from_slice(&n.to_be_bytes());
+        }
+        Value::String(s) => write_string(output, s),
+        Value::BorrowedString(s) => write_string(output, s),
+        Value::Bytes(bytes) => {
+            write_head(output, 2, bytes.len() as u64);
+            output.extend_from_slice(bytes);
+        }
+        Value::DateTime(date_time) => {
+            // Only absolute date/times can be tagged, local ones are written as plain strings
+            if date_time.unix_timestamp().is_some() {
+                write_head(output, 6, DATE_TIME_TAG);
+            }
+            write_string(output, &date_time.to_string());
+        }
+    }
+    Ok(())
+}
+
+fn write_string(output: &mut Vec<u8>, s: &str) {
+    write_head(output, 3, s.len() as u64);
+    output.extend_from_slice(s.as_bytes());
+}
+
+/// Writes the initial byte and argument in their shortest form
+fn write_head(output: &mut Vec<u8>, major_type: u8, argument: u64) {
+    let major_type = major_type << 5;
+    if argument < 24 {
+        output.push(major_type | argument as u8);
+    } else if let Ok(argument) = u8::try_from(argument) {
+        output.extend_from_slice(&[major_type | 24, argument]);
+    } else if let Ok(argument) = u16::try_from(argument) {
+        output.push(major_type | 25);
+        output.extend_from_slice(&argument.to_be_bytes());
+    } else if let Ok(argument) = u32::try_from(argument) {
+        output.push(major_type | 26);
+        output.extend_from_slice(&argument.to_be_bytes());
+    } else {
+        output.push(major_type | 27);
+        output.extend_from_slice(&argument.to_be_bytes());
+    }
+}
+
+/// Writes the shortest float that preserves the value, NaN is written as half precision
+fn write_shortest_float(output: &mut Vec<u8>, n: f64) {
+    if n.is_nan() {
+        output.extend_from_slice(&[0xf9, 0x7e, 0x00]);
+    } else if let Some(half) = f64_to_f16(n) {
+        output.push(0xf9);
+        output.extend_from_slice(&half.to_be_bytes());
+    } else if n as f32 as f64 == n {
+        output.push(0xfa);
+        output.extend_from_slice(&(n as f32).to_be_bytes());
+    } else {
+        output.push(0xfb);
+        output.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+/// Converts to half precision, if that is exact
+fn f64_to_f16(n: f64) -> Option<u16> {
+    let single = n as f32;
+    if single as f64 != n {
+        return None;
+    }
+    let bits = single.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mantissa = bits & 0x7f_ffff;
+    if n == 0.0 {
+        return Some(sign);
+    }
+    if n.is_infinite() {
+        return Some(sign | 0x7c00);
+    }
+    if (-14..=15).contains(&exponent) {
+        if mantissa & 0x1fff != 0 {
+            return None;
+        }
+        return Some(sign | ((exponent + 15) as u16) << 10 | (mantissa >> 13) as u16);
+    }
+    if (-24..-14).contains(&exponent) {
+        // Subnormal half precision values, including the implicit leading bit
+        let mantissa = mantissa | 0x80_0000;
+        let shift = -exponent - 1;
+        if mantissa & ((1 << shift) - 1) != 0 {
+            return None;
+        }
+        return Some(sign | (mantissa >> shift) as u16);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor_dumper::CborDumper;
+    use crate::cbor_loader::CborLoader;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump(events: &[Event], deterministic: bool) -> Vec<u8> {
+        let mut output = vec![];
+        let mut dumper = CborDumper::new(&mut output).with_deterministic(deterministic);
+        let mut all_events = vec![Event::DocumentStart];
+        all_events.extend_from_slice(events);
+        all_events.push(Event::DocumentEnd);
+        for event in &all_events {
+            dumper.emit(event).unwrap();
+        }
+        drop(dumper);
+        output
+    }
+
+    fn load(input: &[u8]) -> Vec<Event<'static>> {
+        CborLoader::new(input)
+            .map(|event| event.unwrap().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn test_streaming() {
+        let events = [
+            Event::MapStart,
+            Event::map_key("b"),
+            Event::ListStart,
+            Event::integer(1),
+            Event::integer(-500),
+            Event::number(1.5),
+            Event::ListEnd,
+            Event::map_key("a"),
+            Event::tag("32"),
+            Event::string("http://example.com"),
+            Event::ComplexKey,
+            Event::ListStart,
+            Event::ListEnd,
+            Event::bytes(&b"\x01"[..]),
+            Event::MapEnd,
+        ];
+        let output = dump(&events, false);
+        assert_eq!(&output[..10], b"\xbf\x61b\x9f\x01\x39\x01\xf3\xfb\x3f");
+        let mut expected = vec![Event::DocumentStart];
+        expected.extend_from_slice(&events);
+        expected.push(Event::DocumentEnd);
+        assert_eq!(load(&output), expected);
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let output = dump(
+            &[
+                Event::MapStart,
+                Event::map_key("bb"),
+                Event::number(1.5),
+                Event::MapKey(Value::Integer(-1)),
+                Event::number(100000),
+                Event::map_key("a"),
+                Event::number(1.1),
+                Event::MapKey(Value::Integer(10)),
+                Event::ListStart,
+                Event::number(f64::NAN),
+                Event::number(f64::NEG_INFINITY),
+                Event::number(5.960464477539063e-8),
+                Event::number(-0.0),
+                Event::integer(1000000),
+                Event::ListEnd,
+                Event::MapEnd,
+            ],
+            true,
+        );
+        assert_eq!(
+            output,
+            b"\xa4\x0a\x85\xf9\x7e\x00\xf9\xfc\x00\xf9\x00\x01\xf9\x80\x00\x1a\x00\x0f\x42\x40\
+              \x20\xfa\x47\xc3\x50\x00\
+              \x61a\xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a\
+              \x62bb\xf9\x3e\x00"
+        );
+    }
+
+    #[test]
+    fn test_big_integers_and_simple_values() {
+        let events = [
+            Event::ListStart,
+            Event::tag("bigint"),
+            Event::string("18446744073709551615"),
+            Event::tag("bigint"),
+            Event::string("-18446744073709551616"),
+            Event::tag("simple"),
+            Event::integer(16),
+            Event::tag("simple"),
+            Event::integer(255),
+            Event::ListEnd,
+        ];
+        let mut expected = vec![Event::DocumentStart];
+        expected.extend_from_slice(&events);
+        expected.push(Event::DocumentEnd);
+        let output = dump(&events, true);
+        assert_eq!(
+            output,
+            b"\x84\x1b\xff\xff\xff\xff\xff\xff\xff\xff\x3b\xff\xff\xff\xff\xff\xff\xff\xff\xf0\xf8\xff"
+        );
+        assert_eq!(load(&output), expected);
+        assert_eq!(load(&dump(&events, false)), expected);
+    }
+
+    #[test]
+    fn test_tags_and_date_times() {
+        let date_time = DateTime::parse("2013-03-21T20:04:00Z").unwrap();
+        let output = dump(
+            &[
+                Event::ListStart,
+                Event::date_time(date_time),
+                Event::date_time(DateTime::parse("2013-03-21").unwrap()),
+                Event::tag("ext:5"),
+                Event::tag("2"),
+                Event::bytes(&b"\x01"[..]),
+                Event::ListEnd,
+            ],
+            true,
+        );
+        assert_eq!(
+            output,
+            b"\x83\xc0\x742013-03-21T20:04:00Z\x6a2013-03-21\xc2\x41\x01"
+        );
+        assert_eq!(
+            load(&output),
+            [
+                Event::DocumentStart,
+                Event::ListStart,
+                Event::date_time(date_time),
+                Event::string("2013-03-21"),
+                Event::tag("2"),
+                Event::bytes(&b"\x01"[..]),
+                Event::ListEnd,
+                Event::DocumentEnd,
+            ]
+        );
+    }
+}
@@ -0,0 +1,608 @@
+use crate::SIMPLE_TAG;
+use loadum::byte_input::ByteInput;
+use loadum::datetime::DateTime;
+use loadum::error::bail;
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Read;
+
+/// Tag of RFC 3339 date/time strings
+pub(crate) const DATE_TIME_TAG: u64 = 0;
+/// Tag of date/times as seconds since the unix epoch
+pub(crate) const EPOCH_TAG: u64 = 1;
+
+/// Loads CBOR (RFC 8949), each top level item is loaded as a separate document (RFC 8742)
+///
+/// Date/time tags are loaded as date-times, other semantic tags become tag events with the
+/// tag number in decimal. Integers beyond the signed 64 bit range are loaded as strings of their
+/// digits tagged with `bigint`, simple values other than booleans, null and undefined as their
+/// number tagged with `simple`.
+pub struct CborLoader<'source> {
+    input: ByteInput<'source>,
+    state: LoaderState,
+    stack: Vec<Container>,
+    /// Event to return before reading more input
+    pending: Option<Event<'source>>,
+    /// Item read after a tag, while looking for date/times
+    pending_item: Option<Item<'source>>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    BetweenDocuments,
+    InDocument,
+    DocumentEnd,
+    Done,
+}
+
+struct Container {
+    is_map: bool,
+    /// Number of items, map entries count as two items, None for indefinite length
+    length: Option<u64>,
+    count: u64,
+}
+
+enum Item<'source> {
+    Scalar(Value<'source>),
+    /// A scalar with a tag of the loader
+    Tagged(&'static str, Value<'source>),
+    Tag(u64),
+    List(Option<u64>),
+    Map(Option<u64>),
+    Break,
+}
+
+impl<'source> CborLoader<'source> {
+    pub fn new(source: &'source [u8]) -> CborLoader<'source> {
+        Self::with_input(ByteInput::from_slice(source))
+    }
+
+    /// Loads CBOR incrementally from a reader
+    pub fn from_reader(read: impl Read + 'source) -> CborLoader<'source> {
+        Self::with_input(ByteInput::from_reader(read))
+    }
+
+    fn with_input(input: ByteInput<'source>) -> CborLoader<'source> {
+        CborLoader {
+            input,
+            state: LoaderState::BetweenDocuments,
+            stack: vec![],
+            pending: None,
+            pending_item: None,
+        }
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        if let Some(event) = self.pending.take() {
+            return Ok(Some(event));
+        }
+        match self.state {
+            LoaderState::BetweenDocuments => {
+                if self.input.is_at_end()? {
+                    self.state = LoaderState::Done;
+                    return Ok(None);
+                }
+                self.state = LoaderState::InDocument;
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::DocumentEnd => {
+                self.state = LoaderState::BetweenDocuments;
+                return Ok(Some(Event::DocumentEnd));
+            }
+            LoaderState::Done => return Ok(None),
+            LoaderState::InDocument => {}
+        }
+        let offset = self.input.offset();
+        let item = match self.pending_item.take() {
+            Some(item) => item,
+            None => {
+                let is_complete = self
+                    .stack
+                    .last()
+                    .is_some_and(|container| container.length == Some(container.count));
+                if is_complete {
+                    return Ok(Some(self.end_container()));
+                }
+                self.read_item()?
+            }
+        };
+        let is_key = matches!(self.stack.last(), Some(container) if container.is_map && container.count % 2 == 0);
+        let event = match item {
+            Item::Scalar(value) => self.scalar_event(value, is_key),
+            Item::Tagged(tag, value) => {
+                self.pending = Some(self.scalar_event(value, is_key));
+                Event::tag(tag)
+            }
+            Item::Tag(tag) => {
+                let item = self.read_item()?;
+                if let Some(date_time) = tagged_date_time(tag, &item) {
+                    return Ok(Some(self.scalar_event(Value::DateTime(date_time), is_key)));
+                }
+                self.pending_item = Some(item);
+                Event::tag(tag.to_string())
+            }
+            Item::List(length) => self.start_container(false, length, is_key),
+            Item::Map(length) => {
+                let Some(length) =
+                    length.map_or(Some(None), |length| length.checked_mul(2).map(Some))
+                else {
+                    bail!("Invalid map length at offset {}", offset);
+                };
+                self.start_container(true, length, is_key)
+            }
+            Item::Break => match self.stack.last() {
+                Some(Container {
+                    length: None,
+                    is_map,
+                    count,
+                }) => {
+                    if *is_map && count % 2 == 1 {
+                        bail!("Missing map value before break at offset {}", offset);
+                    }
+                    self.end_container()
+                }
+                _ => bail!("Unexpected break at offset {}", offset),
+            },
+        };
+        Ok(Some(event))
+    }
+
+    fn scalar_event(&mut self, value: Value<'source>, is_key: bool) -> Event<'source> {
+        self.count_item();
+        if is_key {
+            Event::MapKey(value)
+        } else {
+            Event::Literal(value)
+        }
+    }
+
+    fn start_container(
+        &mut self,
+        is_map: bool,
+        length: Option<u64>,
+        is_key: bool,
+    ) -> Event<'source> {
+        if let Some(container) = self.stack.last_mut() {
+            container.count += 1;
+        }
+        self.stack.push(Container {
+            is_map,
+            length,
+            count: 0,
+        });
+        let event = if is_map {
+            Event::MapStart
+        } else {
+            Event::ListStart
+        };
+        if is_key {
+            self.pending = Some(event);
+            return Event::ComplexKey;
+        }
+        event
+    }
+
+    fn end_container(&mut self) -> Event<'source> {
+        let container = self.stack.pop().unwrap();
+        if self.stack.is_empty() {
+            self.state = LoaderState::DocumentEnd;
+        }
+        if container.is_map {
+            Event::MapEnd
+        } else {
+            Event::ListEnd
+        }
+    }
+
+    /// Counts a completed item in the current container, ending the document at the top level
+    fn count_item(&mut self) {
+        match self.stack.last_mut() {
+            Some(container) => container.count += 1,
+            None => self.state = LoaderState::DocumentEnd,
+        }
+    }
+
+    fn read_item(&mut self) -> LoadumResult<Item<'source>> {
+        let offset = self.input.offset();
+        let initial = self.input.read_u8()?;
+        let major_type = initial >> 5;
+        let info = initial & 0x1f;
+        if major_type == 7 {
+            return self.read_simple(info, offset);
+        }
+        if info == 31 {
+            return match major_type {
+                2 | 3 => self.read_indefinite_string(major_type),
+                4 => Ok(Item::List(None)),
+                5 => Ok(Item::Map(None)),
+                _ => bail!(
+                    "Invalid indefinite length for major type {} at offset {}",
+                    major_type,
+                    offset
+                ),
+            };
+        }
+        let argument = self.read_argument(info, offset)?;
+        let value = match major_type {
+            0 => match i64::try_from(argument) {
+                Ok(value) => Value::Integer(value),
+                Err(_) => {
+                    let digits = Value::string(argument.to_string());
+                    return Ok(Item::Tagged(BIGINT_TAG, digits));
+                }
+            },
+            1 => match i64::try_from(argument) {
+                Ok(value) => Value::Integer(-1 - value),
+                Err(_) => {
+                    let digits = Value::string(format!("-{}", argument as u128 + 1));
+                    return Ok(Item::Tagged(BIGINT_TAG, digits));
+                }
+            },
+            2 => Value::bytes(&*self.input.read_bytes(usize::try_from(argument)?)?),
+            3 => self.input.read_string(usize::try_from(argument)?)?,
+            4 => return Ok(Item::List(Some(argument))),
+            5 => return Ok(Item::Map(Some(argument))),
+            _ => return Ok(Item::Tag(argument)),
+        };
+        Ok(Item::Scalar(value))
+    }
+
+    fn read_argument(&mut self, info: u8, offset: u64) -> LoadumResult<u64> {
+        Ok(match info {
+            0..=23 => info as u64,
+            24 => self.input.read_u8()? as u64,
+            25 => u16::from_be_bytes(self.input.read_array()?) as u64,
+            26 => u32::from_be_bytes(self.input.read_array()?) as u64,
+            27 => u64::from_be_bytes(self.input.read_array()?),
+            _ => bail!(
+                "Invalid additional information {} at offset {}",
+                info,
+                offset
+            ),
+        })
+    }
+
+    fn read_simple(&mut self, info: u8, offset: u64) -> LoadumResult<Item<'source>> {
+        let value = match info {
+            20 => Value::Boolean(false),
+            21 => Value::Boolean(true),
+            // Undefined is loaded as null
+            22 | 23 => Value::Null,
+            25 => Value::Number(f16_to_f64(u16::from_be_bytes(self.input.read_array()?))),
+            26 => Value::Number(f32::from_be_bytes(self.input.read_array()?) as f64),
+            27 => Value::Number(f64::from_be_bytes(self.input.read_array()?)),
+            31 => return Ok(Item::Break),
+            0..=19 => return Ok(Item::Tagged(SIMPLE_TAG, Value::Integer(info as i64))),
+            24 => match self.input.read_u8()? {
+                // Simple values below 32 must use the short form
+                simple @ 0..=31 => bail!("Invalid simple value {} at offset {}", simple, offset),
+                simple => return Ok(Item::Tagged(SIMPLE_TAG, Value::Integer(simple as i64))),
+            },
+            _ => bail!(
+                "Invalid additional information {} at offset {}",
+                info,
+                offset
+            ),
+        };
+        Ok(Item::Scalar(value))
+    }
+
+    /// Reads the chunks of an indefinite-length byte or text string
+    fn read_indefinite_string(&mut self, major_type: u8) -> LoadumResult<Item<'source>> {
+        let offset = self.input.offset();
+        let mut bytes = vec![];
+        loop {
+            let chunk_offset = self.input.offset();
+            let initial = self.input.read_u8()?;
+            if initial == 0xff {
+                break;
+            }
+            if initial >> 5 != major_type || initial & 0x1f == 31 {
+                bail!(
+                    "Invalid chunk in indefinite-length string at offset {}",
+                    chunk_offset
+                );
+            }
+            let length = self.read_argument(initial & 0x1f, chunk_offset)?;
+            bytes.extend_from_slice(&self.input.read_bytes(usize::try_from(length)?)?);
+        }
+        if major_type == 2 {
+            return Ok(Item::Scalar(Value::bytes(bytes)));
+        }
+        let Ok(string) = String::from_utf8(bytes) else {
+            bail!("Invalid UTF-8 in string at offset {}", offset);
+        };
+        Ok(Item::Scalar(Value::string(string)))
+    }
+}
+
+/// Converts the standard date/time tags, other tags and malformed content are kept as is
+fn tagged_date_time(tag: u64, item: &Item) -> Option<DateTime> {
+    let Item::Scalar(value) = item else {
+        return None;
+    };
+    match (tag, value) {
+        (DATE_TIME_TAG, value) => DateTime::parse(value.as_str()?).ok(),
+        (EPOCH_TAG, Value::Integer(seconds)) => DateTime::from_unix_timestamp(*seconds, 0).ok(),
+        (EPOCH_TAG, Value::Number(seconds)) if seconds.is_finite() => {
+            let whole_seconds = seconds.floor();
+            let nanosecond = ((seconds - whole_seconds) * 1e9).round().min(999_999_999.0);
+            DateTime::from_unix_timestamp(whole_seconds as i64, nanosecond as u32).ok()
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (bits >> 10) & 0x1f;
+    let mantissa = (bits & 0x3ff) as f64;
+    sign * match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f64.powi(exponent as i32 - 15),
+    }
+}
+
+impl<'source> Loader<'source> for CborLoader<'source> {}
+
+impl<'source> Iterator for CborLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                self.pending = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbor_loader::CborLoader;
+    use expect_test::{Expect, expect};
+    use loadum::testing::{collect_events, format_events};
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let events = collect_events(CborLoader::new(input));
+        expected.assert_eq(&format_events(&events));
+        assert_eq!(events, collect_events(CborLoader::from_reader(input)));
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            b"\x8b\x00\x17\x18\x18\x19\x03\xe8\x20\x38\x63\x3b\x7f\xff\xff\xff\xff\xff\xff\xff\
+              \xf4\xf5\xf6\xf7",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(0))
+                Literal(Integer(23))
+                Literal(Integer(24))
+                Literal(Integer(1000))
+                Literal(Integer(-1))
+                Literal(Integer(-100))
+                Literal(Integer(-9223372036854775808))
+                Literal(Boolean(false))
+                Literal(Boolean(true))
+                Literal(Null)
+                Literal(Null)
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_big_integers_and_simple_values() {
+        test_loader(
+            b"\x86\x1b\xff\xff\xff\xff\xff\xff\xff\xff\x3b\x80\x00\x00\x00\x00\x00\x00\x00\
+              \x3b\xff\xff\xff\xff\xff\xff\xff\xff\xe0\xf3\xf8\xff",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Tag("bigint")
+                Literal(String("18446744073709551615"))
+                Tag("bigint")
+                Literal(String("-9223372036854775809"))
+                Tag("bigint")
+                Literal(String("-18446744073709551616"))
+                Tag("simple")
+                Literal(Integer(0))
+                Tag("simple")
+                Literal(Integer(19))
+                Tag("simple")
+                Literal(Integer(255))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_floats_and_strings() {
+        test_loader(
+            b"\x88\xf9\x3c\x00\xf9\x00\x01\xf9\x7c\x00\xf9\xfe\x00\xfa\x47\xc3\x50\x00\
+              \xfb\x3f\xf1\x99\x99\x99\x99\x99\x9a\x44\x01\x02\x03\x04\x62\xc3\xbc",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Number(1.0))
+                Literal(Number(5.960464477539063e-8))
+                Literal(Number(inf))
+                Literal(Number(NaN))
+                Literal(Number(100000.0))
+                Literal(Number(1.1))
+                Literal(Bytes([1, 2, 3, 4]))
+                Literal(BorrowedString("ü"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_indefinite_length() {
+        test_loader(
+            b"\xbf\x63Fun\xf5\x63Amt\x9f\x01\x9f\xff\xff\
+              \x5f\x42\x01\x02\x41\x03\xff\x7f\x62st\x63ing\xff\xff",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("Fun"))
+                Literal(Boolean(true))
+                MapKey(BorrowedString("Amt"))
+                ListStart
+                Literal(Integer(1))
+                ListStart
+                ListEnd
+                ListEnd
+                MapKey(Bytes([1, 2, 3]))
+                Literal(String("sting"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_maps() {
+        test_loader(
+            b"\xa3\x01\xa0\x80\x02\xa1\x01\x02\x80",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(Integer(1))
+                MapStart
+                MapEnd
+                ComplexKey
+                ListStart
+                ListEnd
+                Literal(Integer(2))
+                ComplexKey
+                MapStart
+                MapKey(Integer(1))
+                Literal(Integer(2))
+                MapEnd
+                ListStart
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        test_loader(
+            b"\x86\xc0\x74\x32\x30\x31\x33\x2d\x30\x33\x2d\x32\x31\x54\x32\x30\x3a\x30\x34\x3a\x30\x30\x5a\
+              \xc1\x1a\x51\x4b\x67\xb0\xc1\xfb\x41\xd4\x52\xd9\xec\x20\x00\x00\
+              \xd8\x20\x63\x61\x2f\x62\xd9\xd9\xf7\xc2\x41\x01\xc0\x61x",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2013, month: 3, day: 21 }, time: Time { hour: 20, minute: 4, second: 0, nanosecond: 0 }, offset_minutes: 0 }))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2013, month: 3, day: 21 }, time: Time { hour: 20, minute: 4, second: 0, nanosecond: 0 }, offset_minutes: 0 }))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2013, month: 3, day: 21 }, time: Time { hour: 20, minute: 4, second: 0, nanosecond: 500000000 }, offset_minutes: 0 }))
+                Tag("32")
+                Literal(BorrowedString("a/b"))
+                Tag("55799")
+                Tag("2")
+                Literal(Bytes([1]))
+                Tag("0")
+                Literal(BorrowedString("x"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_sequence() {
+        test_loader(
+            b"\x01\x9f\xff",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b"", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            b"\x82\x01",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(1))
+                Error: Unexpected end of input at offset 2
+            "#]],
+        );
+        test_loader(
+            b"\x81\xff",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Unexpected break at offset 1
+            "#]],
+        );
+        test_loader(
+            b"\xbf\x01\xff",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(Integer(1))
+                Error: Missing map value before break at offset 2
+            "#]],
+        );
+        test_loader(
+            b"\x1c",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid additional information 28 at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\x5f\x61a\xff",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid chunk in indefinite-length string at offset 1
+            "#]],
+        );
+        test_loader(
+            b"\xf8\x18",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid simple value 24 at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\xfc",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid additional information 28 at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\x3f",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid indefinite length for major type 1 at offset 0
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,5 @@
+pub mod cbor_dumper;
+pub mod cbor_loader;
+
+/// Tag of simple values without a loadum counterpart, loaded as their number
+pub(crate) const SIMPLE_TAG: &str = "simple";
@@ -0,0 +1,513 @@
+use loadum::LoadumString;
+use loadum::base64;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Dumps a list of rows as CSV
+///
+/// Rows are either flat maps, whose keys are written as a header row, or lists of fields.
+/// Nested maps and lists cannot be represented and fail.
+pub struct CsvDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    delimiter: u8,
+    quote: u8,
+    quoting: Quoting,
+    has_header: bool,
+    key_policy: KeyPolicy,
+    state: DumperState,
+    /// Keys of the first map row
+    header: Option<Vec<LoadumString>>,
+    row_index: usize,
+}
+
+/// When fields are quoted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quoting {
+    /// Quote fields containing delimiters, quotes or newlines
+    #[default]
+    Necessary,
+    Always,
+    /// Never quote, fail on fields that would need quoting
+    Never,
+}
+
+#[derive(Debug)]
+enum DumperState {
+    Initial,
+    WantRows,
+    Rows,
+    MapRow {
+        fields: Vec<(LoadumString, Value<'static>)>,
+        key: Option<LoadumString>,
+    },
+    ListRow(Vec<Value<'static>>),
+    RowsDone,
+}
+
+impl<'write> CsvDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> CsvDumper<'write> {
+        CsvDumper {
+            write: Box::new(write),
+            delimiter: b',',
+            quote: b'"',
+            quoting: Quoting::Necessary,
+            has_header: true,
+            key_policy: KeyPolicy::Stringify,
+            state: DumperState::Initial,
+            header: None,
+            row_index: 0,
+        }
+    }
+
+    /// Sets the field delimiter, e.g. `b'\t'` for TSV, by default fields are separated by commas
+    ///
+    /// The delimiter and the quote must be ASCII characters, otherwise dumping fails.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the quote character, by default `"` is used
+    pub fn with_quote(mut self, quote: u8) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn with_quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Sets whether the keys of map rows are written as a header row
+    pub fn with_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    fn nested_error(&self, kind: &str) -> LoadumResult<()> {
+        let column = match &self.state {
+            DumperState::MapRow { key: Some(key), .. } => format!("column '{}'", key),
+            DumperState::MapRow { .. } => "a key".to_string(),
+            DumperState::ListRow(fields) => format!("column {}", fields.len() + 1),
+            _ => unreachable!("Only called within rows"),
+        };
+        bail!(
+            "CSV cannot represent nested values, found a {} in row {} at {}",
+            kind,
+            self.row_index + 1,
+            column
+        );
+    }
+
+    fn write_map_row(&mut self, fields: Vec<(LoadumString, Value)>) -> LoadumResult<()> {
+        let Some(header) = &self.header else {
+            let header: Vec<LoadumString> = fields.iter().map(|(key, _)| key.clone()).collect();
+            if self.has_header {
+                let header_fields: Vec<Value> = header
+                    .iter()
+                    .map(|key| Value::String(key.clone()))
+                    .collect();
+                self.write_record(&header_fields)?;
+            }
+            self.header = Some(header);
+            let values: Vec<Value> = fields.into_iter().map(|(_, value)| value).collect();
+            return self.write_record(&values);
+        };
+        let mut values = vec![Value::Null; header.len()];
+        for (key, value) in fields {
+            let Some(index) = header.iter().position(|column| *column == key) else {
+                bail!(
+                    "Row {} has key '{}', which is not in the header",
+                    self.row_index + 1,
+                    key
+                );
+            };
+            values[index] = value;
+        }
+        self.write_record(&values)
+    }
+
+    fn write_record(&mut self, values: &[Value]) -> LoadumResult<()> {
+        let mut line = Vec::new();
+        for (index, value) in values.iter().enumerate() {
+            if index > 0 {
+                line.push(self.delimiter);
+            }
+            let text: Cow<str> = match value {
+                Value::Null => Cow::Borrowed(""),
+                Value::String(s) => Cow::Borrowed(s),
+                Value::BorrowedString(s) => Cow::Borrowed(s),
+                // CSV has no binary type, so bytes are written as base64
+                Value::Bytes(bytes) => Cow::Owned(base64::encode(bytes)),
+                value => Cow::Owned(value.to_string()),
+            };
+            // The loader skips empty lines, so a lone empty field is quoted
+            let must_quote = values.len() == 1 && text.is_empty();
+            self.write_field(&mut line, &text, must_quote)?;
+        }
+        line.push(b'\n');
+        self.write.write_all(&line)?;
+        Ok(())
+    }
+
+    fn write_field(&self, line: &mut Vec<u8>, text: &str, must_quote: bool) -> LoadumResult<()> {
+        let needs_quotes = must_quote
+            || text
+                .bytes()
+                .any(|c| c == self.delimiter || c == self.quote || c == b'\n' || c == b'\r');
+        let quoted = match self.quoting {
+            Quoting::Always => true,
+            Quoting::Necessary => needs_quotes,
+            Quoting::Never if needs_quotes => {
+                bail!(
+                    "Field {:?} in row {} cannot be written without quoting",
+                    text,
+                    self.row_index + 1
+                );
+            }
+            Quoting::Never => false,
+        };
+        if !quoted {
+            line.extend_from_slice(text.as_bytes());
+            return Ok(());
+        }
+        line.push(self.quote);
+        for c in text.bytes() {
+            // Quotes are escaped by doubling them
+            if c == self.quote {
+                line.push(c);
+            }
+            line.push(c);
+        }
+        line.push(self.quote);
+        Ok(())
+    }
+}
+
+impl Dumper for CsvDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        match (&mut self.state, event) {
+            (DumperState::Initial, Event::DocumentStart) => {
+                if !self.delimiter.is_ascii() || !self.quote.is_ascii() {
+                    bail!("CSV delimiters and quotes must be ASCII characters");
+                }
+                self.state = DumperState::WantRows;
+                self.header = None;
+                self.row_index = 0;
+            }
+            (DumperState::WantRows, Event::ListStart) => self.state = DumperState::Rows,
+            (DumperState::WantRows, Event::DocumentEnd) => bail!("Document is empty"),
+            (DumperState::WantRows, Event::MapStart | Event::Literal(_)) => {
+                bail!("CSV documents must be lists of rows");
+            }
+            (DumperState::Rows, Event::MapStart) => {
+                self.state = DumperState::MapRow {
+                    fields: vec![],
+                    key: None,
+                };
+            }
+            (DumperState::Rows, Event::ListStart) => self.state = DumperState::ListRow(vec![]),
+            (DumperState::Rows, Event::ListEnd) => self.state = DumperState::RowsDone,
+            (DumperState::Rows, Event::Literal(_)) => {
+                bail!(
+                    "CSV rows must be maps or lists, found a scalar in row {}",
+                    self.row_index + 1
+                );
+            }
+            (DumperState::MapRow { .. } | DumperState::ListRow(_), Event::MapStart) => {
+                self.nested_error("map")?;
+            }
+            (DumperState::MapRow { .. } | DumperState::ListRow(_), Event::ListStart) => {
+                self.nested_error("list")?;
+            }
+            (DumperState::MapRow { .. }, Event::ComplexKey) => {
+                bail!(
+                    "CSV cannot represent nested values, found a composite key in row {}",
+                    self.row_index + 1
+                );
+            }
+            (DumperState::MapRow { key: None, .. }, Event::MapKey(key)) => {
+                let key = self
+                    .key_policy
+                    .scalar_key_string(key, "CSV header fields")?
+                    .into();
+                if let DumperState::MapRow { key: row_key, .. } = &mut self.state {
+                    *row_key = Some(key);
+                }
+            }
+            (DumperState::MapRow { fields, key }, Event::Literal(value)) => {
+                let Some(key) = key.take() else {
+                    bail!("Missing key for map value in row {}", self.row_index + 1);
+                };
+                fields.push((key, value.clone().into_owned()));
+            }
+            (DumperState::ListRow(fields), Event::Literal(value)) => {
+                fields.push(value.clone().into_owned());
+            }
+            (DumperState::MapRow { fields, .. }, Event::MapEnd) => {
+                let fields = std::mem::take(fields);
+                self.state = DumperState::Rows;
+                self.write_map_row(fields)?;
+                self.row_index += 1;
+            }
+            (DumperState::ListRow(fields), Event::ListEnd) => {
+                let fields = std::mem::take(fields);
+                self.state = DumperState::Rows;
+                self.write_record(&fields)?;
+                self.row_index += 1;
+            }
+            (DumperState::RowsDone, Event::DocumentEnd) => self.state = DumperState::Initial,
+            // CSV has no tags, the tagged value is written as is
+            (_, Event::Tag(_)) => {}
+            (state, event) => bail!("Unexpected event {:?} in state {:?}", event, state),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv_dumper::{CsvDumper, Quoting};
+    use crate::csv_loader::CsvLoader;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::testing::emit_events;
+    use loadum::value::Value;
+
+    fn dump(dumper: impl FnOnce(&mut Vec<u8>) -> CsvDumper, events: &[Event]) -> String {
+        let mut output = vec![];
+        let result = emit_events(
+            dumper(&mut output),
+            [&Event::DocumentStart]
+                .into_iter()
+                .chain(events)
+                .chain([&Event::DocumentEnd]),
+        );
+        let mut output = String::from_utf8(output).unwrap();
+        if let Err(error) = result {
+            output.push_str(&format!("Error: {}\n", error));
+        }
+        output
+    }
+
+    fn test_dump(events: &[Event], expected: Expect) {
+        expected.assert_eq(&dump(|output| CsvDumper::new(output), events));
+    }
+
+    fn row(fields: &[(&str, Event<'static>)]) -> Vec<Event<'static>> {
+        let mut events = vec![Event::MapStart];
+        for (key, value) in fields {
+            events.push(Event::map_key(*key));
+            events.push(value.clone());
+        }
+        events.push(Event::MapEnd);
+        events
+    }
+
+    #[test]
+    fn test_map_rows() {
+        let mut events = vec![Event::ListStart];
+        events.extend(row(&[
+            ("name", Event::string("Alice")),
+            ("age", Event::integer(30)),
+        ]));
+        events.extend(row(&[
+            ("age", Event::number(4.5)),
+            ("name", Event::string("Bob \"B\", Jr.")),
+        ]));
+        events.extend(row(&[("name", Event::string("line\nbreak"))]));
+        events.extend(row(&[("name", Event::null()), ("age", Event::bool(true))]));
+        events.push(Event::ListEnd);
+        test_dump(
+            &events,
+            expect![[r#"
+                name,age
+                Alice,30
+                "Bob ""B"", Jr.",4.5
+                "line
+                break",
+                ,true
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_list_rows() {
+        let events = [
+            Event::ListStart,
+            Event::ListStart,
+            Event::string("a"),
+            Event::string("b\tc"),
+            Event::ListEnd,
+            Event::ListStart,
+            Event::bytes(&b"\x01"[..]),
+            Event::ListEnd,
+            Event::ListEnd,
+        ];
+        expect![[r#"
+            a	'b	c'
+            AQ==
+        "#]]
+        .assert_eq(&dump(
+            |output| {
+                CsvDumper::new(output)
+                    .with_delimiter(b'\t')
+                    .with_quote(b'\'')
+            },
+            &events,
+        ));
+        expect![[r#"
+            "a"	"b	c"
+            "AQ=="
+        "#]]
+        .assert_eq(&dump(
+            |output| {
+                CsvDumper::new(output)
+                    .with_delimiter(b'\t')
+                    .with_quoting(Quoting::Always)
+            },
+            &events,
+        ));
+        expect![[r#"
+            Error: Field "b\tc" in row 1 cannot be written without quoting
+        "#]]
+        .assert_eq(&dump(
+            |output| {
+                CsvDumper::new(output)
+                    .with_delimiter(b'\t')
+                    .with_quoting(Quoting::Never)
+            },
+            &events,
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        test_dump(
+            &[Event::MapStart],
+            expect![[r#"
+                Error: CSV documents must be lists of rows
+            "#]],
+        );
+        test_dump(
+            &[],
+            expect![[r#"
+                Error: Document is empty
+            "#]],
+        );
+        test_dump(
+            &[Event::ListStart, Event::MapEnd],
+            expect![[r#"
+                Error: Unexpected event MapEnd in state Rows
+            "#]],
+        );
+        expect![[r#"
+            Error: CSV delimiters and quotes must be ASCII characters
+        "#]]
+        .assert_eq(&dump(
+            |output| CsvDumper::new(output).with_delimiter(0xa7),
+            &[],
+        ));
+        let mut events = vec![Event::ListStart];
+        events.extend(row(&[("a", Event::integer(1))]));
+        events.extend([Event::MapStart, Event::map_key("a"), Event::ListStart]);
+        test_dump(
+            &events,
+            expect![[r#"
+                a
+                1
+                Error: CSV cannot represent nested values, found a list in row 2 at column 'a'
+            "#]],
+        );
+        test_dump(
+            &[Event::ListStart, Event::ListStart, Event::MapStart],
+            expect![[r#"
+                Error: CSV cannot represent nested values, found a map in row 1 at column 1
+            "#]],
+        );
+        let mut events = vec![Event::ListStart];
+        events.extend(row(&[("a", Event::integer(1))]));
+        events.extend(row(&[("b", Event::integer(1))]));
+        test_dump(
+            &events,
+            expect![[r#"
+                a
+                1
+                Error: Row 2 has key 'b', which is not in the header
+            "#]],
+        );
+        expect![[r#"
+            Error: CSV header fields must be strings, but found Integer(1)
+        "#]]
+        .assert_eq(&dump(
+            |output| CsvDumper::new(output).with_key_policy(KeyPolicy::Reject),
+            &[
+                Event::ListStart,
+                Event::MapStart,
+                Event::MapKey(Value::Integer(1)),
+            ],
+        ));
+    }
+
+    #[test]
+    fn test_empty_fields() {
+        let events = [
+            Event::ListStart,
+            Event::ListStart,
+            Event::string(""),
+            Event::ListEnd,
+            Event::ListStart,
+            Event::null(),
+            Event::ListEnd,
+            Event::ListStart,
+            Event::string(""),
+            Event::string(""),
+            Event::ListEnd,
+            Event::ListEnd,
+        ];
+        let output = dump(|output| CsvDumper::new(output), &events);
+        expect![[r#"
+            ""
+            ""
+            ,
+        "#]]
+        .assert_eq(&output);
+        let loaded = CsvLoader::new(&output)
+            .with_header(false)
+            .map(|event| event.unwrap().into_owned())
+            .filter(|event| *event != Event::DocumentStart && *event != Event::DocumentEnd)
+            .collect::<Vec<_>>();
+        assert_eq!(loaded.len(), events.len());
+        expect![[r#"
+            Error: Field "" in row 1 cannot be written without quoting
+        "#]]
+        .assert_eq(&dump(
+            |output| CsvDumper::new(output).with_quoting(Quoting::Never),
+            &events,
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = "id,name,note\n1,Alice,\"says \"\"hi\"\"\"\n2,Bob,\"a,b\"\n";
+        let mut output = vec![];
+        let mut dumper = CsvDumper::new(&mut output);
+        for event in CsvLoader::new(input).with_type_inference(true) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+}
@@ -0,0 +1,464 @@
+use loadum::LoadumString;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::collections::VecDeque;
+
+/// Loads CSV (RFC 4180) and similar formats like TSV as a list of rows
+///
+/// By default the first row is a header, and each following row is loaded as a map from the
+/// header fields to the row fields. Empty lines are skipped.
+pub struct CsvLoader<'source> {
+    source: &'source str,
+    position: usize,
+    line: usize,
+    delimiter: u8,
+    quote: Option<u8>,
+    has_header: bool,
+    infer_types: bool,
+    header: Option<Vec<LoadumString>>,
+    state: LoaderState,
+    pending: VecDeque<Event<'source>>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    Initial,
+    Rows,
+    Done,
+}
+
+struct Field<'source> {
+    value: Value<'source>,
+    quoted: bool,
+}
+
+impl<'source> CsvLoader<'source> {
+    pub fn new(source: &'source str) -> CsvLoader<'source> {
+        CsvLoader {
+            source,
+            position: 0,
+            line: 1,
+            delimiter: b',',
+            quote: Some(b'"'),
+            has_header: true,
+            infer_types: false,
+            header: None,
+            state: LoaderState::Initial,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Sets the field delimiter, e.g. `b'\t'` for TSV, by default fields are separated by commas
+    ///
+    /// The delimiter and the quote must be ASCII characters, otherwise loading fails.
+    pub fn with_delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the quote character, or disables quoting with None, by default `"` is used
+    pub fn with_quote(mut self, quote: Option<u8>) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// Sets whether the first row is a header, otherwise rows are loaded as lists
+    pub fn with_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Sets whether unquoted fields are loaded as null, booleans and numbers where possible
+    ///
+    /// Empty fields are loaded as null. Integers with leading zeros, like zip codes, stay strings.
+    pub fn with_type_inference(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        if let Some(event) = self.pending.pop_front() {
+            return Ok(Some(event));
+        }
+        match self.state {
+            LoaderState::Initial => {
+                // Other bytes could split UTF-8 characters
+                if !self.delimiter.is_ascii() || self.quote.is_some_and(|quote| !quote.is_ascii()) {
+                    bail!("CSV delimiters and quotes must be ASCII characters");
+                }
+                self.state = LoaderState::Rows;
+                let header = if self.has_header {
+                    self.parse_record()?
+                } else {
+                    None
+                };
+                self.header = header.map(|header| {
+                    header
+                        .into_iter()
+                        .map(|field| field_string(field.value))
+                        .collect()
+                });
+                self.pending.push_back(Event::ListStart);
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::Rows => {}
+            LoaderState::Done => return Ok(None),
+        }
+        let line = self.line;
+        let Some(record) = self.parse_record()? else {
+            self.state = LoaderState::Done;
+            self.pending.push_back(Event::DocumentEnd);
+            return Ok(Some(Event::ListEnd));
+        };
+        let fields: Vec<Value> = record
+            .into_iter()
+            .map(|field| self.field_value(field))
+            .collect();
+        let Some(header) = &self.header else {
+            self.pending.extend(fields.into_iter().map(Event::Literal));
+            self.pending.push_back(Event::ListEnd);
+            return Ok(Some(Event::ListStart));
+        };
+        if fields.len() != header.len() {
+            bail!(
+                "Row at line {} has {} fields, but the header has {}",
+                line,
+                fields.len(),
+                header.len()
+            );
+        }
+        for (key, value) in header.iter().zip(fields) {
+            self.pending
+                .push_back(Event::MapKey(Value::String(key.clone())));
+            self.pending.push_back(Event::Literal(value));
+        }
+        self.pending.push_back(Event::MapEnd);
+        Ok(Some(Event::MapStart))
+    }
+
+    fn field_value(&self, field: Field<'source>) -> Value<'source> {
+        if !self.infer_types || field.quoted {
+            return field.value;
+        }
+        infer_type(field.value)
+    }
+
+    /// Parses the fields of the next non-empty line, None at the end of input
+    fn parse_record(&mut self) -> LoadumResult<Option<Vec<Field<'source>>>> {
+        while self.skip_newline() {}
+        if self.position == self.source.len() {
+            return Ok(None);
+        }
+        let mut fields = vec![];
+        loop {
+            fields.push(self.parse_field()?);
+            if self.source.as_bytes().get(self.position) == Some(&self.delimiter) {
+                self.position += 1;
+            } else {
+                self.skip_newline();
+                return Ok(Some(fields));
+            }
+        }
+    }
+
+    fn parse_field(&mut self) -> LoadumResult<Field<'source>> {
+        let bytes = self.source.as_bytes();
+        let start = self.position;
+        let Some(quote) = self.quote.filter(|quote| bytes.get(start) == Some(quote)) else {
+            let length = bytes[start..]
+                .iter()
+                .position(|c| *c == self.delimiter || *c == b'\n' || *c == b'\r')
+                .unwrap_or(bytes.len() - start);
+            self.position += length;
+            return Ok(Field {
+                value: Value::BorrowedString(&self.source[start..start + length]),
+                quoted: false,
+            });
+        };
+        let line = self.line;
+        let mut index = start + 1;
+        let mut has_escapes = false;
+        loop {
+            match bytes.get(index) {
+                None => bail!("Unterminated quoted field starting at line {}", line),
+                Some(&c) if c == quote => {
+                    // A doubled quote is an escaped quote
+                    if bytes.get(index + 1) == Some(&quote) {
+                        has_escapes = true;
+                        index += 2;
+                        continue;
+                    }
+                    break;
+                }
+                Some(b'\n') => {
+                    self.line += 1;
+                    index += 1;
+                }
+                Some(_) => index += 1,
+            }
+        }
+        let content = &self.source[start + 1..index];
+        self.position = index + 1;
+        if !matches!(bytes.get(self.position), None | Some(b'\n' | b'\r'))
+            && bytes.get(self.position) != Some(&self.delimiter)
+        {
+            bail!(
+                "Unexpected character after closing quote at line {}",
+                self.line
+            );
+        }
+        let value = if has_escapes {
+            let quote = quote as char;
+            Value::string(content.replace(&format!("{}{}", quote, quote), &quote.to_string()))
+        } else {
+            Value::BorrowedString(content)
+        };
+        Ok(Field {
+            value,
+            quoted: true,
+        })
+    }
+
+    fn skip_newline(&mut self) -> bool {
+        let rest = &self.source.as_bytes()[self.position..];
+        let length = if rest.starts_with(b"\r\n") {
+            2
+        } else if rest.starts_with(b"\n") || rest.starts_with(b"\r") {
+            1
+        } else {
+            return false;
+        };
+        self.position += length;
+        self.line += 1;
+        true
+    }
+}
+
+fn field_string(value: Value) -> LoadumString {
+    match value {
+        Value::BorrowedString(s) => s.into(),
+        Value::String(s) => s,
+        value => value.to_string().into(),
+    }
+}
+
+fn infer_type(value: Value) -> Value {
+    let Some(text) = value.as_str() else {
+        return value;
+    };
+    match text {
+        "" => return Value::Null,
+        "true" => return Value::Boolean(true),
+        "false" => return Value::Boolean(false),
+        _ => {}
+    }
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let is_numeric = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'));
+    let has_leading_zero =
+        digits.len() > 1 && digits.starts_with('0') && !digits[1..].starts_with('.');
+    if !is_numeric || has_leading_zero {
+        return value;
+    }
+    if let Ok(integer) = text.parse() {
+        return Value::Integer(integer);
+    }
+    match text.parse() {
+        Ok(number) => Value::Number(number),
+        Err(_) => value,
+    }
+}
+
+impl<'source> Loader<'source> for CsvLoader<'source> {}
+
+impl<'source> Iterator for CsvLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                self.pending.clear();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::csv_loader::CsvLoader;
+    use expect_test::{Expect, expect};
+    use loadum::testing::{collect_events, format_events};
+
+    fn test_loader(loader: CsvLoader, expected: Expect) {
+        expected.assert_eq(&format_events(&collect_events(loader)));
+    }
+
+    #[test]
+    fn test_header() {
+        test_loader(
+            CsvLoader::new("name,age\r\nAlice,30\r\n\"Bob \"\"B\"\", Jr.\",\"4\n2\"\r\n"),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                MapStart
+                MapKey(String("name"))
+                Literal(BorrowedString("Alice"))
+                MapKey(String("age"))
+                Literal(BorrowedString("30"))
+                MapEnd
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Bob \"B\", Jr."))
+                MapKey(String("age"))
+                Literal(BorrowedString("4\n2"))
+                MapEnd
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_delimiter() {
+        test_loader(
+            CsvLoader::new("a\u{a7}b").with_delimiter(0xa7),
+            expect![[r#"
+                Error: CSV delimiters and quotes must be ASCII characters
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_without_header() {
+        test_loader(
+            CsvLoader::new("a\tb\n\nc\t'd\te'\n")
+                .with_header(false)
+                .with_delimiter(b'\t')
+                .with_quote(Some(b'\'')),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                ListStart
+                Literal(BorrowedString("a"))
+                Literal(BorrowedString("b"))
+                ListEnd
+                ListStart
+                Literal(BorrowedString("c"))
+                Literal(BorrowedString("d\te"))
+                ListEnd
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_without_quoting() {
+        test_loader(
+            CsvLoader::new("\"a,b\"")
+                .with_header(false)
+                .with_quote(None),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                ListStart
+                Literal(BorrowedString("\"a"))
+                Literal(BorrowedString("b\""))
+                ListEnd
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_type_inference() {
+        test_loader(
+            CsvLoader::new(",true,false,42,-7,1.5,1e3,007,0.5,\"12\",inf,1.2.3,x\n")
+                .with_header(false)
+                .with_type_inference(true),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Integer(42))
+                Literal(Integer(-7))
+                Literal(Number(1.5))
+                Literal(Number(1000.0))
+                Literal(BorrowedString("007"))
+                Literal(Number(0.5))
+                Literal(BorrowedString("12"))
+                Literal(BorrowedString("inf"))
+                Literal(BorrowedString("1.2.3"))
+                Literal(BorrowedString("x"))
+                ListEnd
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_empty() {
+        test_loader(
+            CsvLoader::new(""),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(
+            CsvLoader::new("a,b\n"),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            CsvLoader::new("a,b\n1,2\n3\n"),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                MapStart
+                MapKey(String("a"))
+                Literal(BorrowedString("1"))
+                MapKey(String("b"))
+                Literal(BorrowedString("2"))
+                MapEnd
+                Error: Row at line 3 has 1 fields, but the header has 2
+            "#]],
+        );
+        test_loader(
+            CsvLoader::new("a\n\"x\ny"),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Unterminated quoted field starting at line 2
+            "#]],
+        );
+        test_loader(
+            CsvLoader::new("\"a\"b"),
+            expect![[r#"
+                Error: Unexpected character after closing quote at line 1
+            "#]],
+        );
+    }
+}
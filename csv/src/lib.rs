@@ -0,0 +1,2 @@
+pub mod csv_dumper;
+pub mod csv_loader;
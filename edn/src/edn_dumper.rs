@@ -0,0 +1,299 @@
+use crate::{
+    BIGDEC_TAG, CHAR_TAG, KEYWORD_TAG, LIST_TAG, NAMED_CHARS, SET_TAG, SYMBOL_TAG, is_number,
+    is_symbol,
+};
+use loadum::base64;
+use loadum::dumper::Dumper;
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Tags of the loader for EDN types, which are never written as tagged literals
+const TYPE_TAGS: [&str; 7] = [
+    KEYWORD_TAG,
+    SYMBOL_TAG,
+    SET_TAG,
+    LIST_TAG,
+    CHAR_TAG,
+    BIGINT_TAG,
+    BIGDEC_TAG,
+];
+
+/// Dumps EDN, each document is written as a top level value on its own line
+///
+/// The tags of the loader are written as the corresponding EDN types, other tags that are valid
+/// EDN tags are written as tagged literals like `#myapp/Point [1 2]` and the rest are ignored.
+/// Date-times with an offset are written as `#inst` literals, local date-times as strings and
+/// bytes as base64 strings.
+pub struct EdnDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+}
+
+impl<'write> EdnDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> EdnDumper<'write> {
+        EdnDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+        }
+    }
+}
+
+impl Dumper for EdnDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut formatter = EdnFormatter {
+            output: String::new(),
+        };
+        formatter.write_node(&root);
+        formatter.output.push('\n');
+        self.write.write_all(formatter.output.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct EdnFormatter {
+    output: String,
+}
+
+impl EdnFormatter {
+    fn write_node(&mut self, node: &Node) {
+        match node {
+            Node::Tagged(tag, node) => self.write_tagged(tag, node),
+            Node::Scalar(value) => self.write_scalar(value),
+            Node::List(items) => self.write_items("[", ']', items),
+            Node::Map(entries) => {
+                self.output.push('{');
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_node(key);
+                    self.output.push(' ');
+                    self.write_node(value);
+                }
+                self.output.push('}');
+            }
+        }
+    }
+
+    fn write_tagged(&mut self, tag: &str, node: &Node) {
+        let text = match node {
+            Node::Scalar(value) => value.as_str(),
+            _ => None,
+        };
+        match (tag, node, text) {
+            (KEYWORD_TAG, _, Some(name)) if is_symbol(name) => {
+                self.output.push(':');
+                self.output.push_str(name);
+            }
+            (SYMBOL_TAG, _, Some(name))
+                if is_symbol(name) && !matches!(name, "nil" | "true" | "false") =>
+            {
+                self.output.push_str(name)
+            }
+            (CHAR_TAG, _, Some(text)) if text.chars().count() == 1 => {
+                self.write_char(text.chars().next().unwrap())
+            }
+            (BIGINT_TAG, _, Some(digits)) if is_number(digits, false) => {
+                self.output.push_str(digits);
+                self.output.push('N');
+            }
+            (BIGDEC_TAG, _, Some(decimal)) if is_number(decimal, true) => {
+                self.output.push_str(decimal);
+                self.output.push('M');
+            }
+            (SET_TAG, Node::List(items), _) => self.write_items("#{", '}', items),
+            (LIST_TAG, Node::List(items), _) => self.write_items("(", ')', items),
+            _ if !TYPE_TAGS.contains(&tag)
+                && tag.starts_with(|c: char| c.is_alphabetic())
+                && is_symbol(tag) =>
+            {
+                self.output.push('#');
+                self.output.push_str(tag);
+                self.output.push(' ');
+                self.write_node(node);
+            }
+            // Tags of other formats and mismatched values are written without the tag
+            _ => self.write_node(node),
+        }
+    }
+
+    fn write_items(&mut self, open: &str, close: char, items: &[Node]) {
+        self.output.push_str(open);
+        for (index, item) in items.iter().enumerate() {
+            if index > 0 {
+                self.output.push(' ');
+            }
+            self.write_node(item);
+        }
+        self.output.push(close);
+    }
+
+    fn write_scalar(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.output.push_str("nil"),
+            Value::Boolean(b) => self.output.push_str(if *b { "true" } else { "false" }),
+            Value::Integer(i) => write!(self.output, "{}", i).unwrap(),
+            Value::Number(n) if n.is_nan() => self.output.push_str("##NaN"),
+            Value::Number(n) if n.is_infinite() => {
+                self.output
+                    .push_str(if *n > 0.0 { "##Inf" } else { "##-Inf" })
+            }
+            // Debug formatting always includes a fraction or exponent, keeping floats distinct
+            Value::Number(n) => write!(self.output, "{:?}", n).unwrap(),
+            Value::String(s) => self.write_string(s),
+            Value::BorrowedString(s) => self.write_string(s),
+            Value::Bytes(bytes) => self.write_string(&base64::encode(bytes)),
+            Value::DateTime(date_time) => {
+                if date_time.unix_timestamp().is_some() {
+                    self.output.push_str("#inst ");
+                }
+                self.write_string(&date_time.to_string());
+            }
+        }
+    }
+
+    /// Writes a character, characters beyond the basic multilingual plane are written as strings
+    fn write_char(&mut self, c: char) {
+        if let Some((name, _)) = NAMED_CHARS.iter().find(|(_, named)| *named == c) {
+            self.output.push('\\');
+            self.output.push_str(name);
+        } else if c as u32 > 0xffff {
+            self.write_string(&c.to_string());
+        } else if c.is_control() || c.is_whitespace() {
+            write!(self.output, "\\u{:04x}", c as u32).unwrap();
+        } else {
+            self.output.push('\\');
+            self.output.push(c);
+        }
+    }
+
+    fn write_string(&mut self, s: &str) {
+        self.output.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\t' => self.output.push_str("\\t"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                c if c.is_control() => write!(self.output, "\\u{:04x}", c as u32).unwrap(),
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edn_dumper::EdnDumper;
+    use crate::edn_loader::EdnLoader;
+    use expect_test::{Expect, expect};
+    use loadum::datetime::DateTime;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::testing::{emit_events, format_dump};
+
+    fn dump(events: &[Event]) -> String {
+        let mut output = vec![];
+        let result = emit_events(
+            EdnDumper::new(&mut output),
+            [&Event::DocumentStart]
+                .into_iter()
+                .chain(events)
+                .chain([&Event::DocumentEnd]),
+        );
+        format_dump(result, String::from_utf8(output).unwrap())
+    }
+
+    fn test_dump(events: &[Event], expected: Expect) {
+        expected.assert_eq(&dump(events));
+    }
+
+    #[test]
+    fn test_values() {
+        test_dump(
+            &[
+                Event::ListStart,
+                Event::null(),
+                Event::bool(true),
+                Event::integer(-42),
+                Event::number(1),
+                Event::number(6.626e-34),
+                Event::number(f64::NEG_INFINITY),
+                Event::number(f64::NAN),
+                Event::string("quote \" backslash \\ newline \n bell \u{7}"),
+                Event::bytes(&b"\x00\xff"[..]),
+                Event::date_time(DateTime::parse("1979-05-27T07:32:00Z").unwrap()),
+                Event::date_time(DateTime::parse("1979-05-27").unwrap()),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::ListStart,
+                Event::ListEnd,
+                Event::ListEnd,
+            ],
+            expect![[r#"
+                [nil true -42 1.0 6.626e-34 ##-Inf ##NaN "quote \" backslash \\ newline \n bell \u0007" "AP8=" #inst "1979-05-27T07:32:00Z" "1979-05-27" {} []]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_tags() {
+        test_dump(
+            &[
+                Event::ListStart,
+                Event::tag("keyword"),
+                Event::string("ns/kw"),
+                Event::tag("keyword"),
+                Event::string("not a keyword"),
+                Event::tag("symbol"),
+                Event::string("nil"),
+                Event::tag("char"),
+                Event::string(" "),
+                Event::tag("char"),
+                Event::string("\u{7}"),
+                Event::tag("char"),
+                Event::string("😀"),
+                Event::tag("bigint"),
+                Event::string("12345678901234567890"),
+                Event::tag("bigdec"),
+                Event::string("not a number"),
+                Event::tag("set"),
+                Event::integer(1),
+                Event::tag("myapp/Point"),
+                Event::ListStart,
+                Event::integer(1),
+                Event::integer(2),
+                Event::ListEnd,
+                Event::tag("tag:yaml.org,2002:str"),
+                Event::string("ignored"),
+                Event::ListEnd,
+            ],
+            expect![[r#"
+                [:ns/kw "not a keyword" "nil" \space \u0007 "😀" 12345678901234567890N "not a number" 1 #myapp/Point [1 2] "ignored"]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = r#"{:user/name "Ada", :user/roles #{:admin :dev}, :history (inc 2 3.5), [1 2] {:nested true}, "key" \a}
+[nil 12345678901234567890N 1.50M ##Inf #inst "1985-04-12T23:20:50.52Z" #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6" \newline]
+"#;
+        let mut output = vec![];
+        let mut dumper = EdnDumper::new(&mut output);
+        for event in EdnLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+}
@@ -0,0 +1,638 @@
+use crate::{
+    BIGDEC_TAG, CHAR_TAG, INST_TAG, KEYWORD_TAG, LIST_TAG, NAMED_CHARS, SET_TAG, SYMBOL_TAG,
+    is_number, is_symbol,
+};
+use loadum::datetime::DateTime;
+use loadum::depth::Depth;
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+
+/// Loads EDN, each top level value in the input is loaded as a separate document
+///
+/// Keywords, symbols, sets, lists and characters are loaded as tagged values, `#inst` literals
+/// as date-times and other tagged literals as their tagged value. Forms after `#_` are discarded.
+pub struct EdnLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> EdnLoader<'source> {
+    pub fn new(source: &'source str) -> EdnLoader<'source> {
+        EdnLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for EdnLoader<'source> {}
+
+impl<'source> Iterator for EdnLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                bytes: self.source.as_bytes(),
+                position: 0,
+                depth: Depth::default(),
+            };
+            match parser.parse_documents() {
+                Ok(events) => self.events = Some(events.into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_documents(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        let mut events = vec![];
+        loop {
+            self.skip_blank()?;
+            if self.peek().is_none() {
+                return Ok(events);
+            }
+            events.push(Event::DocumentStart);
+            self.parse_value(&mut events)?;
+            events.push(Event::DocumentEnd);
+        }
+    }
+
+    fn parse_value(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        self.skip_blank()?;
+        let value = match self.peek() {
+            None => return Err(self.error("Expected value")),
+            Some(b'[') => return self.parse_sequence(b']', "vector", None, events),
+            Some(b'(') => return self.parse_sequence(b')', "list", Some(LIST_TAG), events),
+            Some(b'{') => return self.parse_map(events),
+            Some(b'#') => return self.parse_dispatch(events),
+            Some(b'"') => self.parse_string()?,
+            Some(b'\\') => {
+                events.push(Event::tag(CHAR_TAG));
+                self.parse_char()?
+            }
+            Some(b':') => {
+                let start = self.position;
+                self.position += 1;
+                let name = self.read_token();
+                if !is_symbol(name) {
+                    return Err(self.error_at(start, format!("Invalid keyword ':{}'", name)));
+                }
+                events.push(Event::tag(KEYWORD_TAG));
+                Value::BorrowedString(name)
+            }
+            Some(c @ (b')' | b']' | b'}')) => {
+                return Err(self.error(format!("Unexpected '{}'", c as char)));
+            }
+            Some(_) => return self.parse_token(events),
+        };
+        events.push(Event::Literal(value));
+        Ok(())
+    }
+
+    /// Parses `nil`, booleans, numbers and symbols
+    fn parse_token(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let start = self.position;
+        let token = self.read_token();
+        let unsigned = token.strip_prefix(['+', '-']).unwrap_or(token);
+        let value = match token {
+            "nil" => Value::Null,
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            _ if unsigned.starts_with(|c: char| c.is_ascii_digit()) => {
+                let Some((tag, value)) = parse_number(token) else {
+                    return Err(self.error_at(start, format!("Invalid number '{}'", token)));
+                };
+                events.extend(tag.map(Event::tag));
+                value
+            }
+            _ if is_symbol(token) => {
+                events.push(Event::tag(SYMBOL_TAG));
+                Value::BorrowedString(token)
+            }
+            _ => return Err(self.error_at(start, format!("Invalid symbol '{}'", token))),
+        };
+        events.push(Event::Literal(value));
+        Ok(())
+    }
+
+    /// Parses forms starting with `#`: sets, symbolic values and tagged literals
+    fn parse_dispatch(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let start = self.position;
+        self.position += 1;
+        match self.peek() {
+            Some(b'{') => return self.parse_sequence(b'}', "set", Some(SET_TAG), events),
+            Some(b'#') => {
+                self.position += 1;
+                let value = match self.read_token() {
+                    "Inf" => f64::INFINITY,
+                    "-Inf" => f64::NEG_INFINITY,
+                    "NaN" => f64::NAN,
+                    name => {
+                        return Err(self.error_at(start, format!("Invalid value '##{}'", name)));
+                    }
+                };
+                events.push(Event::number(value));
+                return Ok(());
+            }
+            _ => {}
+        }
+        let tag = self.read_token();
+        if !(tag.starts_with(|c: char| c.is_alphabetic()) && is_symbol(tag)) {
+            return Err(self.error_at(start, format!("Invalid tag '#{}'", tag)));
+        }
+        if tag != INST_TAG {
+            events.push(Event::tag(tag));
+            // Tags may be stacked on a value
+            self.depth.enter().map_err(|error| self.error(error))?;
+            self.parse_value(events)?;
+            self.depth.exit();
+            return Ok(());
+        }
+        let mut value = vec![];
+        self.parse_value(&mut value)?;
+        let date_time = match value.as_slice() {
+            [Event::Literal(value)] => value.as_str().map(DateTime::parse),
+            _ => None,
+        };
+        match date_time {
+            Some(Ok(date_time)) => events.push(Event::date_time(date_time)),
+            Some(Err(error)) => return Err(self.error_at(start, error)),
+            None => return Err(self.error_at(start, "Expected a string after #inst")),
+        }
+        Ok(())
+    }
+
+    /// Parses a vector, list or set, starting at its opening delimiter
+    fn parse_sequence(
+        &mut self,
+        close: u8,
+        container: &str,
+        tag: Option<&'static str>,
+        events: &mut Vec<Event<'source>>,
+    ) -> LoadumResult<()> {
+        let start = self.position;
+        self.depth.enter().map_err(|error| self.error(error))?;
+        self.position += 1;
+        events.extend(tag.map(Event::tag));
+        events.push(Event::ListStart);
+        loop {
+            self.skip_blank()?;
+            match self.peek() {
+                Some(c) if c == close => break,
+                None => return Err(self.error_at(start, format!("Unterminated {}", container))),
+                Some(_) => self.parse_value(events)?,
+            }
+        }
+        self.position += 1;
+        self.depth.exit();
+        events.push(Event::ListEnd);
+        Ok(())
+    }
+
+    fn parse_map(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let start = self.position;
+        self.depth.enter().map_err(|error| self.error(error))?;
+        self.position += 1;
+        events.push(Event::MapStart);
+        loop {
+            self.skip_blank()?;
+            match self.peek() {
+                Some(b'}') => break,
+                None => return Err(self.error_at(start, "Unterminated map")),
+                Some(_) => self.parse_key(events)?,
+            }
+            self.skip_blank()?;
+            if self.peek() == Some(b'}') {
+                return Err(self.error("Expected a value for the last key of the map"));
+            }
+            self.parse_value(events)?;
+        }
+        self.position += 1;
+        self.depth.exit();
+        events.push(Event::MapEnd);
+        Ok(())
+    }
+
+    /// Parses a map key, which may be any value
+    fn parse_key(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let mut key = vec![];
+        self.parse_value(&mut key)?;
+        match key.pop() {
+            Some(Event::Literal(value))
+                if key.iter().all(|event| matches!(event, Event::Tag(_))) =>
+            {
+                events.extend(key);
+                events.push(Event::MapKey(value));
+            }
+            last => {
+                events.push(Event::ComplexKey);
+                events.extend(key);
+                events.extend(last);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a character like `\a`, `\newline` or `\u00e9`, starting at the backslash
+    fn parse_char(&mut self) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        self.position += 1;
+        // The first character may be a delimiter, as in `\(`
+        let Some(first) = self.source[self.position..].chars().next() else {
+            return Err(self.error_at(start, "Expected a character"));
+        };
+        self.position += first.len_utf8();
+        self.read_token();
+        let name = &self.source[start + 1..self.position];
+        if name.len() == first.len_utf8() {
+            return Ok(Value::BorrowedString(name));
+        }
+        let c = match NAMED_CHARS.iter().find(|(named, _)| *named == name) {
+            Some((_, c)) => Some(*c),
+            None => name
+                .strip_prefix('u')
+                .filter(|digits| digits.len() == 4)
+                .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+                .and_then(char::from_u32),
+        };
+        let Some(c) = c else {
+            return Err(self.error_at(start, format!("Invalid character '\\{}'", name)));
+        };
+        Ok(Value::string(c.to_string()))
+    }
+
+    fn parse_string(&mut self) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        let mut index = start + 1;
+        loop {
+            match self.bytes.get(index) {
+                None => return Err(self.error("Unterminated string")),
+                Some(b'\\') => index += 2,
+                Some(b'"') => break,
+                Some(_) => index += 1,
+            }
+        }
+        self.position = index + 1;
+        let content = &self.source[start + 1..index];
+        if !content.contains('\\') {
+            return Ok(Value::BorrowedString(content));
+        }
+        let unescaped = unescape(content).map_err(|error| self.error_at(start, error))?;
+        Ok(Value::string(unescaped))
+    }
+
+    /// Reads a symbol, number or keyword name up to the next delimiter
+    fn read_token(&mut self) -> &'source str {
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        let length = rest
+            .iter()
+            .position(|c| {
+                c.is_ascii_whitespace()
+                    || matches!(
+                        c,
+                        b',' | b';' | b'"' | b'(' | b')' | b'[' | b']' | b'{' | b'}'
+                    )
+            })
+            .unwrap_or(rest.len());
+        self.position += length;
+        &self.source[start..start + length]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    /// Skips whitespace, commas, comments and discarded forms
+    fn skip_blank(&mut self) -> LoadumResult<()> {
+        loop {
+            let rest = &self.bytes[self.position..];
+            match rest {
+                [b' ' | b'\t' | b'\n' | b'\r' | b',', ..] => self.position += 1,
+                [b';', ..] => {
+                    self.position += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                }
+                [b'#', b'_', ..] => {
+                    self.position += 2;
+                    self.parse_value(&mut vec![])?;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+/// Parses an integer, float, `N` integer or `M` decimal, returning the tag of exact numbers
+fn parse_number(text: &str) -> Option<(Option<&'static str>, Value<'_>)> {
+    if let Some(decimal) = text.strip_suffix('M') {
+        let decimal = decimal.strip_prefix('+').unwrap_or(decimal);
+        return is_number(decimal, true)
+            .then_some((Some(BIGDEC_TAG), Value::BorrowedString(decimal)));
+    }
+    let integer = text.strip_suffix('N');
+    let is_float = integer.is_none() && text.contains(['.', 'e', 'E']);
+    let digits = integer.unwrap_or(text);
+    if !is_number(digits, is_float) {
+        return None;
+    }
+    if is_float {
+        return digits.parse().ok().map(|n| (None, Value::Number(n)));
+    }
+    let digits = digits.strip_prefix('+').unwrap_or(digits);
+    match digits.parse() {
+        Ok(i) => Some((None, Value::Integer(i))),
+        // Clojure promotes integers beyond 64 bits to arbitrary precision integers
+        Err(_) => Some((Some(BIGINT_TAG), Value::BorrowedString(digits))),
+    }
+}
+
+fn unescape(content: &str) -> LoadumResult<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('b') => '\u{0008}',
+            Some('f') => '\u{000c}',
+            Some(c @ ('\\' | '"')) => c,
+            Some('u') => {
+                let digits: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 4)
+                    .and_then(char::from_u32);
+                let Some(c) = c else {
+                    bail!("Invalid unicode escape '\\u{}'", digits);
+                };
+                c
+            }
+            Some(c) => bail!("Invalid escape sequence '\\{}'", c.escape_default()),
+            None => bail!("Incomplete escape sequence"),
+        };
+        output.push(c);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EdnLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::testing::{collect_events, format_events};
+    use loadum_json::json_dumper::JsonDumper;
+
+    fn load(input: &str) -> String {
+        format_events(&collect_events(
+            EdnLoader::new(input).map(|event| event.map(Event::into_owned)),
+        ))
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            r#"[nil true false -42 +7 1.5 -2e3 ##Inf ##NaN 12345678901234567890 42N 1.50M
+                "escapes \"\\\t\u00e9" \a \newline \u00e9 \( :kw :ns/kw sym my.ns/sym + ->
+                #inst "1985-04-12T23:20:50.52Z" #uuid "f81d4fae-7dec-11d0-a765-00a0c91e6bf6"]"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Integer(-42))
+                Literal(Integer(7))
+                Literal(Number(1.5))
+                Literal(Number(-2000.0))
+                Literal(Number(inf))
+                Literal(Number(NaN))
+                Tag("bigint")
+                Literal(String("12345678901234567890"))
+                Literal(Integer(42))
+                Tag("bigdec")
+                Literal(String("1.50"))
+                Literal(String("escapes \"\\\té"))
+                Tag("char")
+                Literal(String("a"))
+                Tag("char")
+                Literal(String("\n"))
+                Tag("char")
+                Literal(String("é"))
+                Tag("char")
+                Literal(String("("))
+                Tag("keyword")
+                Literal(String("kw"))
+                Tag("keyword")
+                Literal(String("ns/kw"))
+                Tag("symbol")
+                Literal(String("sym"))
+                Tag("symbol")
+                Literal(String("my.ns/sym"))
+                Tag("symbol")
+                Literal(String("+"))
+                Tag("symbol")
+                Literal(String("->"))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1985, month: 4, day: 12 }, time: Time { hour: 23, minute: 20, second: 50, nanosecond: 520000000 }, offset_minutes: 0 }))
+                Tag("uuid")
+                Literal(String("f81d4fae-7dec-11d0-a765-00a0c91e6bf6"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_collections() {
+        test_loader(
+            r#"
+; A user with roles
+{:user/name "Ada", :user/roles #{:admin :dev}
+ :history (1 2) [1 2] {:nested true} "string key" 1 #_ :discarded #_ #_ 1 2
+ #myapp/Point [1 2] #{}}
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Tag("keyword")
+                MapKey(String("user/name"))
+                Literal(String("Ada"))
+                Tag("keyword")
+                MapKey(String("user/roles"))
+                Tag("set")
+                ListStart
+                Tag("keyword")
+                Literal(String("admin"))
+                Tag("keyword")
+                Literal(String("dev"))
+                ListEnd
+                Tag("keyword")
+                MapKey(String("history"))
+                Tag("list")
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                ComplexKey
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                MapStart
+                Tag("keyword")
+                MapKey(String("nested"))
+                Literal(Boolean(true))
+                MapEnd
+                MapKey(String("string key"))
+                Literal(Integer(1))
+                ComplexKey
+                Tag("myapp/Point")
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                Tag("set")
+                ListStart
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        test_loader(
+            "1 :a\n[] ; end",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                Tag("keyword")
+                Literal(String("a"))
+                DocumentEnd
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader("; only a comment\n#_ 1", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "[1 2",
+            "{:a}",
+            "{:a 1",
+            ")",
+            "12abc",
+            "1.5N",
+            "::auto",
+            "#inst 1",
+            "#inst \"yesterday\"",
+            "#1 2",
+            "##Foo",
+            "#_",
+            "\\foo",
+            "\"unterminated",
+            "\"\\q\"",
+            "@deref",
+            &"[".repeat(MAX_DEPTH + 1),
+            &"#a ".repeat(MAX_DEPTH + 1),
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Unterminated vector at line 1, column 1
+            Error: Expected a value for the last key of the map at line 1, column 4
+            Error: Unterminated map at line 1, column 1
+            Error: Unexpected ')' at line 1, column 1
+            Error: Invalid number '12abc' at line 1, column 1
+            Error: Invalid number '1.5N' at line 1, column 1
+            Error: Invalid keyword '::auto' at line 1, column 1
+            Error: Expected a string after #inst at line 1, column 1
+            Error: Invalid date/time 'yesterday' at line 1, column 1
+            Error: Invalid tag '#1' at line 1, column 1
+            Error: Invalid value '##Foo' at line 1, column 1
+            Error: Expected value at line 1, column 3
+            Error: Invalid character '\foo' at line 1, column 1
+            Error: Unterminated string at line 1, column 1
+            Error: Invalid escape sequence '\q' at line 1, column 1
+            Error: Invalid symbol '@deref' at line 1, column 1
+            Error: Document is nested deeper than 128 levels at line 1, column 129
+            Error: Document is nested deeper than 128 levels at line 1, column 387
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        let input = r#"{:id 42, :tags #{:a}, :char \x, :at #inst "2024-01-02T03:04:05Z"}"#;
+        for event in EdnLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"id": 42,
+            	"tags": [
+            		"a"
+            	],
+            	"char": "x",
+            	"at": "2024-01-02T03:04:05Z"
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,78 @@
+//! EDN (extensible data notation), the data format of Clojure
+//!
+//! Maps are maps and vectors are lists. EDN types without a counterpart in loadum values are
+//! loaded as tagged values: keywords and symbols as tagged strings without the leading `:`, sets
+//! and lists as tagged lists and characters as tagged strings. `#inst` literals are date-times,
+//! other tagged literals like `#uuid "..."` are loaded as their tagged value.
+//!
+//! Formats without tags lose these distinctions, e.g. converting to JSON writes keywords and
+//! symbols as strings and sets as arrays. Integers beyond 64 bits and `M` decimals are tagged
+//! strings, so they are not rounded.
+
+pub mod edn_dumper;
+pub mod edn_loader;
+
+/// Tag of keywords like `:db/id`, loaded as strings without the `:`
+pub(crate) const KEYWORD_TAG: &str = "keyword";
+/// Tag of symbols like `my.ns/name`, loaded as strings
+pub(crate) const SYMBOL_TAG: &str = "symbol";
+/// Tag of sets `#{...}`, loaded as lists
+pub(crate) const SET_TAG: &str = "set";
+/// Tag of lists `(...)`, vectors `[...]` are untagged lists
+pub(crate) const LIST_TAG: &str = "list";
+/// Tag of characters like `\a`, loaded as strings
+pub(crate) const CHAR_TAG: &str = "char";
+/// Tag of arbitrary precision decimals like `1.5M`, loaded as strings without the `M`
+pub(crate) const BIGDEC_TAG: &str = "bigdec";
+/// Tag of instants, which are loaded as date-times
+pub(crate) const INST_TAG: &str = "inst";
+
+/// Characters with names, which are written as `\newline` rather than the character itself
+pub(crate) const NAMED_CHARS: [(&str, char); 4] = [
+    ("newline", '\n'),
+    ("return", '\r'),
+    ("space", ' '),
+    ("tab", '\t'),
+];
+
+/// Whether a name has the syntax of an EDN symbol, which is shared by keywords and tags
+///
+/// The symbols `nil`, `true` and `false` are reserved, they are not excluded here.
+pub(crate) fn is_symbol(name: &str) -> bool {
+    let is_symbol_char = |c: char| c.is_alphanumeric() || ".*+!-_?$%&=<>:#'".contains(c);
+    let is_valid_part = |part: &str| {
+        let mut chars = part.chars();
+        let Some(first) = chars.next() else {
+            return false;
+        };
+        // Symbols cannot start like numbers, with a digit or a sign or dot followed by one
+        let is_numeric = first.is_ascii_digit()
+            || (matches!(first, '-' | '+' | '.')
+                && chars.next().is_some_and(|c| c.is_ascii_digit()));
+        !is_numeric && !matches!(first, ':' | '#' | '\'') && part.chars().all(is_symbol_char)
+    };
+    match name.split_once('/') {
+        _ if name == "/" => true,
+        Some((prefix, name)) => is_valid_part(prefix) && is_valid_part(name),
+        None => is_valid_part(name),
+    }
+}
+
+/// Whether the text is a decimal integer, or a number with a fraction and exponent if allowed
+pub(crate) fn is_number(text: &str, allow_fraction: bool) -> bool {
+    let is_digits = |digits: &str| !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit());
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    is_digits(integer)
+        && fraction.is_none_or(|fraction| fraction.bytes().all(|c| c.is_ascii_digit()))
+        && exponent
+            .is_none_or(|exponent| is_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)))
+        && (allow_fraction || (fraction.is_none() && exponent.is_none()))
+}
@@ -0,0 +1,1065 @@
+use crate::EXPRESSION_TAG;
+use loadum::depth::{Depth, check_depth};
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Loads HCL documents in the native syntax, such as Terraform `.tf` files
+///
+/// The body of the document is loaded as a map. Numbers, booleans, null and strings without
+/// interpolations are loaded as values, tuples and objects as lists and maps. Other expressions,
+/// like references, function calls and templates, are loaded as strings of their source text,
+/// tagged with `expression`.
+pub struct HclLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> HclLoader<'source> {
+    pub fn new(source: &'source str) -> HclLoader<'source> {
+        HclLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for HclLoader<'source> {}
+
+impl<'source> Iterator for HclLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                bytes: self.source.as_bytes(),
+                position: 0,
+                depth: Depth::default(),
+            };
+            match parser.parse_document() {
+                Ok(events) => self.events = Some(events.into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+/// Attributes and blocks of a body, blocks of the same type are collected in one item
+type Body<'source> = Vec<(&'source str, Item<'source>)>;
+
+enum Item<'source> {
+    Attribute(Vec<Event<'source>>),
+    Blocks(Vec<Block<'source>>),
+}
+
+struct Block<'source> {
+    labels: Vec<Value<'source>>,
+    body: Body<'source>,
+}
+
+struct Heredoc {
+    content: Range<usize>,
+    /// Index after the closing delimiter
+    end: usize,
+    /// True for `<<-` heredocs, which have their indentation removed
+    is_indented: bool,
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        let body = self.parse_body(None)?;
+        let mut events = vec![Event::DocumentStart];
+        emit_body(body, &mut events);
+        events.push(Event::DocumentEnd);
+        Ok(events)
+    }
+
+    /// Parses attributes and blocks, up to the end of the block starting at the given position
+    fn parse_body(&mut self, block_start: Option<usize>) -> LoadumResult<Body<'source>> {
+        let mut body: Body = vec![];
+        loop {
+            self.skip_blank(true)?;
+            match (self.peek(), block_start) {
+                (None, None) => return Ok(body),
+                (None, Some(block_start)) => {
+                    return Err(self.error_at(block_start, "Unterminated block"));
+                }
+                (Some(b'}'), Some(_)) => {
+                    self.position += 1;
+                    return Ok(body);
+                }
+                _ => {}
+            }
+            let start = self.position;
+            let name = self.parse_identifier()?;
+            self.skip_blank(false)?;
+            let existing = body.iter_mut().find(|(existing, _)| *existing == name);
+            if self.peek() == Some(b'=') {
+                if existing.is_some() {
+                    return Err(self.error_at(start, format!("Duplicate attribute '{}'", name)));
+                }
+                self.position += 1;
+                let mut events = vec![];
+                self.parse_expression(&mut events, true)?;
+                body.push((name, Item::Attribute(events)));
+            } else {
+                let block = self.parse_block()?;
+                match existing {
+                    None => body.push((name, Item::Blocks(vec![block]))),
+                    Some((_, Item::Blocks(blocks)))
+                        if blocks[0].labels.len() == block.labels.len() =>
+                    {
+                        blocks.push(block)
+                    }
+                    Some((_, Item::Blocks(_))) => {
+                        return Err(self.error_at(
+                            start,
+                            format!("Blocks of type '{}' have different numbers of labels", name),
+                        ));
+                    }
+                    Some((_, Item::Attribute(_))) => {
+                        return Err(self.error_at(
+                            start,
+                            format!("'{}' is both an attribute and a block", name),
+                        ));
+                    }
+                }
+            }
+            self.skip_blank(false)?;
+            match self.peek() {
+                None | Some(b'\n') => {}
+                Some(b'}') if block_start.is_some() => {}
+                Some(_) => return Err(self.error("Expected a newline")),
+            }
+        }
+    }
+
+    /// Parses the labels and the body of a block
+    fn parse_block(&mut self) -> LoadumResult<Block<'source>> {
+        let mut labels = vec![];
+        loop {
+            self.skip_blank(false)?;
+            match self.peek() {
+                Some(b'{') => break,
+                Some(b'"') => {
+                    let start = self.position;
+                    let Some(label) = self.parse_string()? else {
+                        return Err(self.error_at(start, "Block labels cannot be templates"));
+                    };
+                    labels.push(label);
+                }
+                Some(c) if is_identifier_start(c) => {
+                    labels.push(Value::BorrowedString(self.parse_identifier()?));
+                }
+                _ => return Err(self.error("Expected '=', a block label or '{'")),
+            }
+        }
+        let start = self.position;
+        self.depth.enter().map_err(|error| self.error(error))?;
+        self.position += 1;
+        let body = self.parse_body(Some(start))?;
+        self.depth.exit();
+        Ok(Block { labels, body })
+    }
+
+    /// Parses an expression, which ends at a comma, a closing bracket or optionally a newline
+    ///
+    /// Literals are loaded as values, other expressions as their tagged source text.
+    fn parse_expression(
+        &mut self,
+        events: &mut Vec<Event<'source>>,
+        newline_ends: bool,
+    ) -> LoadumResult<()> {
+        self.skip_blank(!newline_ends)?;
+        let start = self.position;
+        let end = self.scan_expression(start, newline_ends, true, 0)?;
+        let text = self.source[start..end].trim_end();
+        if text.is_empty() {
+            return Err(self.error("Expected expression"));
+        }
+        let mut literal = vec![];
+        if self.parse_literal(&mut literal)? {
+            if newline_ends {
+                self.skip_spaces()?;
+            } else {
+                self.skip_blank(true)?;
+            }
+            if self.position == end {
+                events.extend(literal);
+                return Ok(());
+            }
+        }
+        self.position = end;
+        events.push(Event::tag(EXPRESSION_TAG));
+        events.push(Event::Literal(Value::BorrowedString(text)));
+        Ok(())
+    }
+
+    /// Parses a literal value, tuple or object, returning false if the expression is no literal
+    fn parse_literal(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<bool> {
+        let value = match self.peek() {
+            Some(b'"') => match self.parse_string()? {
+                Some(value) => value,
+                None => return Ok(false),
+            },
+            Some(b'<') => match self.parse_heredoc()? {
+                Some(value) => value,
+                None => return Ok(false),
+            },
+            Some(b'[') => return self.parse_tuple(events),
+            Some(b'{') => return self.parse_object(events),
+            Some(b'-' | b'0'..=b'9') => match self.parse_number() {
+                Some(value) => value,
+                None => return Ok(false),
+            },
+            Some(c) if is_identifier_start(c) => match self.parse_identifier()? {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                _ => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+        events.push(Event::Literal(value));
+        Ok(true)
+    }
+
+    /// Parses a tuple, unless it is a `for` expression
+    fn parse_tuple(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<bool> {
+        self.position += 1;
+        self.skip_blank(true)?;
+        if self.at_keyword("for") {
+            return Ok(false);
+        }
+        self.depth.enter().map_err(|error| self.error(error))?;
+        events.push(Event::ListStart);
+        loop {
+            self.skip_blank(true)?;
+            if self.peek() == Some(b']') {
+                break;
+            }
+            self.parse_expression(events, false)?;
+            self.skip_blank(true)?;
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => break,
+                _ => return Err(self.error("Expected ',' or ']' in tuple")),
+            }
+        }
+        self.position += 1;
+        self.depth.exit();
+        events.push(Event::ListEnd);
+        Ok(true)
+    }
+
+    /// Parses an object, unless it is a `for` expression
+    fn parse_object(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<bool> {
+        self.position += 1;
+        self.skip_blank(true)?;
+        if self.at_keyword("for") {
+            return Ok(false);
+        }
+        self.depth.enter().map_err(|error| self.error(error))?;
+        events.push(Event::MapStart);
+        loop {
+            self.skip_blank(true)?;
+            if self.peek() == Some(b'}') {
+                break;
+            }
+            self.parse_object_key(events)?;
+            self.skip_blank(false)?;
+            if !matches!(self.peek(), Some(b'=' | b':')) {
+                return Err(self.error("Expected '=' or ':' in object"));
+            }
+            self.position += 1;
+            self.parse_expression(events, true)?;
+            self.skip_blank(false)?;
+            match self.peek() {
+                Some(b',' | b'\n') => self.position += 1,
+                Some(b'}') => break,
+                _ => return Err(self.error("Expected ',', a newline or '}' in object")),
+            }
+        }
+        self.position += 1;
+        self.depth.exit();
+        events.push(Event::MapEnd);
+        Ok(true)
+    }
+
+    /// Parses an identifier or string key, or a parenthesized expression as a tagged key
+    fn parse_object_key(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let start = self.position;
+        let key = match self.peek() {
+            Some(c) if is_identifier_start(c) => Value::BorrowedString(self.parse_identifier()?),
+            Some(b'"') => match self.parse_string()? {
+                Some(key) => key,
+                None => {
+                    events.push(Event::tag(EXPRESSION_TAG));
+                    Value::BorrowedString(&self.source[start..self.position])
+                }
+            },
+            Some(b'(') => {
+                self.position = self.scan_expression(start + 1, false, false, 0)? + 1;
+                events.push(Event::tag(EXPRESSION_TAG));
+                Value::BorrowedString(&self.source[start..self.position])
+            }
+            _ => return Err(self.error("Expected object key")),
+        };
+        events.push(Event::MapKey(key));
+        Ok(())
+    }
+
+    /// Parses a quoted string, returning None if it is a template with interpolations
+    fn parse_string(&mut self) -> LoadumResult<Option<Value<'source>>> {
+        let start = self.position;
+        let end = self.skip_template(start, 0)?;
+        self.position = end;
+        let content = &self.source[start + 1..end - 1];
+        if is_template(content, true) {
+            return Ok(None);
+        }
+        if !content.contains(['\\', '$', '%']) {
+            return Ok(Some(Value::BorrowedString(content)));
+        }
+        let unescaped = unescape(content).map_err(|error| self.error_at(start, error))?;
+        Ok(Some(Value::string(unescaped)))
+    }
+
+    /// Parses a heredoc, returning None if it contains interpolations or if there is no heredoc
+    ///
+    /// The indentation of `<<-` heredocs is removed.
+    fn parse_heredoc(&mut self) -> LoadumResult<Option<Value<'source>>> {
+        let Some(heredoc) = self.scan_heredoc(self.position)? else {
+            return Ok(None);
+        };
+        self.position = heredoc.end;
+        let content = &self.source[heredoc.content];
+        if is_template(content, false) {
+            return Ok(None);
+        }
+        let content = content.replace("$${", "${").replace("%%{", "%{");
+        if !heredoc.is_indented {
+            return Ok(Some(Value::string(content)));
+        }
+        let indentation = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+        let stripped: String = content
+            .split_inclusive('\n')
+            .map(|line| line.get(indentation..).unwrap_or(line.trim_start()))
+            .collect();
+        Ok(Some(Value::string(stripped)))
+    }
+
+    fn parse_number(&mut self) -> Option<Value<'source>> {
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        let digits = |from: usize| {
+            rest[from.min(rest.len())..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count()
+        };
+        let mut length = usize::from(rest[0] == b'-');
+        let integer_digits = digits(length);
+        if integer_digits == 0 {
+            return None;
+        }
+        length += integer_digits;
+        let mut is_float = false;
+        if rest.get(length) == Some(&b'.') && digits(length + 1) > 0 {
+            length += 1 + digits(length + 1);
+            is_float = true;
+        }
+        if matches!(rest.get(length), Some(b'e' | b'E')) {
+            let sign = usize::from(matches!(rest.get(length + 1), Some(b'+' | b'-')));
+            let exponent_digits = digits(length + 1 + sign);
+            if exponent_digits > 0 {
+                length += 1 + sign + exponent_digits;
+                is_float = true;
+            }
+        }
+        self.position += length;
+        let text = &self.source[start..start + length];
+        match text.parse() {
+            Ok(i) if !is_float => Some(Value::Integer(i)),
+            // Integers beyond 64 bits are approximated
+            _ => text.parse().ok().map(Value::Number),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> LoadumResult<&'source str> {
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        if !rest.first().is_some_and(|c| is_identifier_start(*c)) {
+            return Err(self.error("Expected attribute or block name"));
+        }
+        let length = rest
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-')))
+            .unwrap_or(rest.len());
+        self.position += length;
+        Ok(&self.source[start..start + length])
+    }
+
+    /// Whether the keyword follows, and not an identifier starting with it
+    fn at_keyword(&self, keyword: &str) -> bool {
+        let rest = &self.bytes[self.position..];
+        rest.starts_with(keyword.as_bytes())
+            && !rest
+                .get(keyword.len())
+                .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-'))
+    }
+
+    /// Returns the end of the expression starting at the given index, without consuming it
+    ///
+    /// The expression ends at a closing bracket or comma outside of brackets, or optionally at a
+    /// newline or comment outside of brackets. `templates` is the number of string templates the
+    /// expression is interpolated in.
+    fn scan_expression(
+        &self,
+        start: usize,
+        newline_ends: bool,
+        comma_ends: bool,
+        templates: usize,
+    ) -> LoadumResult<usize> {
+        let mut depth = 0;
+        let mut index = start;
+        loop {
+            let rest = &self.bytes[index..];
+            let is_line_end = matches!(rest, [b'\n' | b'#', ..] | [b'/', b'/', ..]);
+            match rest {
+                [] if depth == 0 => return Ok(index),
+                [] => return Err(self.error_at(start, "Unterminated expression")),
+                _ if is_line_end && depth == 0 && newline_ends => return Ok(index),
+                [b'\n', ..] => index += 1,
+                [b'#', ..] | [b'/', b'/', ..] => {
+                    index += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                }
+                [b'/', b'*', ..] => index = self.skip_block_comment(index)?,
+                [b'"', ..] => index = self.skip_template(index, templates)?,
+                [b'<', b'<', ..] => match self.scan_heredoc(index)? {
+                    Some(heredoc) => index = heredoc.end,
+                    None => index += 2,
+                },
+                [b'(' | b'[' | b'{', ..] => {
+                    depth += 1;
+                    index += 1;
+                }
+                [b')' | b']' | b'}', ..] if depth == 0 => return Ok(index),
+                [b')' | b']' | b'}', ..] => {
+                    depth -= 1;
+                    index += 1;
+                }
+                [b',', ..] if depth == 0 && comma_ends => return Ok(index),
+                _ => index += 1,
+            }
+        }
+    }
+
+    /// Returns the index after the closing quote of the string starting at the given index
+    fn skip_template(&self, start: usize, templates: usize) -> LoadumResult<usize> {
+        check_depth(templates).map_err(|error| self.error_at(start, error))?;
+        let mut index = start + 1;
+        loop {
+            match &self.bytes[index..] {
+                [] | [b'\n', ..] => return Err(self.error_at(start, "Unterminated string")),
+                // An escaped character, or a trailing backslash at the end of the input
+                [b'\\', ..] => index = (index + 2).min(self.bytes.len()),
+                [b'$', b'$', b'{', ..] | [b'%', b'%', b'{', ..] => index += 3,
+                [b'$' | b'%', b'{', ..] => {
+                    index = self.scan_expression(index + 2, false, false, templates + 1)?;
+                    if self.bytes.get(index) != Some(&b'}') {
+                        return Err(self.error_at(start, "Unterminated template"));
+                    }
+                    index += 1;
+                }
+                [b'"', ..] => return Ok(index + 1),
+                _ => index += 1,
+            }
+        }
+    }
+
+    /// Finds the content and the end of the heredoc starting at the given index
+    ///
+    /// Returns None if the `<<` does not start a heredoc.
+    fn scan_heredoc(&self, start: usize) -> LoadumResult<Option<Heredoc>> {
+        let Some(header) = self.source[start..].strip_prefix("<<") else {
+            return Ok(None);
+        };
+        let is_indented = header.starts_with('-');
+        let header = header.strip_prefix('-').unwrap_or(header);
+        let Some((delimiter, _)) = header.split_once('\n') else {
+            return Ok(None);
+        };
+        let delimiter = delimiter.strip_suffix('\r').unwrap_or(delimiter);
+        let is_identifier = delimiter.bytes().next().is_some_and(is_identifier_start)
+            && delimiter
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-'));
+        if !is_identifier {
+            return Ok(None);
+        }
+        let content_start = self.source.len() - header.len() + header.find('\n').unwrap() + 1;
+        let mut line_start = content_start;
+        for line in self.source[content_start..].split_inclusive('\n') {
+            let line_content = line.trim_end_matches(['\n', '\r']);
+            if line_content.trim() == delimiter {
+                return Ok(Some(Heredoc {
+                    content: content_start..line_start,
+                    end: line_start + line_content.len(),
+                    is_indented,
+                }));
+            }
+            line_start += line.len();
+        }
+        Err(self.error_at(start, "Unterminated heredoc"))
+    }
+
+    fn skip_block_comment(&self, start: usize) -> LoadumResult<usize> {
+        match self.source[start + 2..].find("*/") {
+            Some(length) => Ok(start + 2 + length + 2),
+            None => Err(self.error_at(start, "Unterminated comment")),
+        }
+    }
+
+    /// Skips spaces, comments and optionally newlines
+    fn skip_blank(&mut self, newlines: bool) -> LoadumResult<()> {
+        loop {
+            self.skip_spaces()?;
+            let rest = &self.bytes[self.position..];
+            match rest {
+                [b'\n', ..] if newlines => self.position += 1,
+                [b'#', ..] | [b'/', b'/', ..] => {
+                    self.position += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips spaces and block comments within a line
+    fn skip_spaces(&mut self) -> LoadumResult<()> {
+        loop {
+            match &self.bytes[self.position..] {
+                [b' ' | b'\t' | b'\r', ..] => self.position += 1,
+                [b'/', b'*', ..] => self.position = self.skip_block_comment(self.position)?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+fn is_identifier_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+/// Whether the content of a string or heredoc contains interpolations or directives
+fn is_template(content: &str, has_escapes: bool) -> bool {
+    let bytes = content.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        match &bytes[index..] {
+            [b'\\', ..] if has_escapes => index += 2,
+            [b'$', b'$', b'{', ..] | [b'%', b'%', b'{', ..] => index += 3,
+            [b'$' | b'%', b'{', ..] => return true,
+            _ => index += 1,
+        }
+    }
+    false
+}
+
+/// Resolves escape sequences and the escaped template sequences `$${` and `%%{`
+fn unescape(content: &str) -> LoadumResult<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("$${").or(rest.strip_prefix("%%{")) {
+            output.push(c);
+            output.push('{');
+            rest = after;
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        let mut chars = rest.chars();
+        let c = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some(c @ ('\\' | '"')) => c,
+            Some(prefix @ ('u' | 'U')) => {
+                let length = if prefix == 'u' { 4 } else { 8 };
+                let digits = chars.as_str().get(..length).unwrap_or(chars.as_str());
+                let c = u32::from_str_radix(digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == length)
+                    .and_then(char::from_u32);
+                let Some(c) = c else {
+                    bail!("Invalid unicode escape '\\{}{}'", prefix, digits);
+                };
+                rest = &chars.as_str()[length..];
+                output.push(c);
+                continue;
+            }
+            Some(c) => bail!("Invalid escape sequence '\\{}'", c.escape_default()),
+            None => bail!("Incomplete escape sequence"),
+        };
+        rest = chars.as_str();
+        output.push(c);
+    }
+    Ok(output)
+}
+
+fn emit_body<'source>(body: Body<'source>, events: &mut Vec<Event<'source>>) {
+    events.push(Event::MapStart);
+    for (name, item) in body {
+        events.push(Event::MapKey(Value::BorrowedString(name)));
+        match item {
+            Item::Attribute(value) => events.extend(value),
+            Item::Blocks(blocks) => emit_blocks(blocks, events),
+        }
+    }
+    events.push(Event::MapEnd);
+}
+
+/// Emits blocks of one type as maps keyed by their labels, with a list of their bodies inside
+fn emit_blocks<'source>(blocks: Vec<Block<'source>>, events: &mut Vec<Event<'source>>) {
+    if blocks[0].labels.is_empty() {
+        events.push(Event::ListStart);
+        for block in blocks {
+            emit_body(block.body, events);
+        }
+        events.push(Event::ListEnd);
+        return;
+    }
+    let mut groups: Vec<(Value, Vec<Block>)> = vec![];
+    for mut block in blocks {
+        let label = block.labels.remove(0);
+        match groups
+            .iter_mut()
+            .find(|(existing, _)| existing.as_str() == label.as_str())
+        {
+            Some((_, group)) => group.push(block),
+            None => groups.push((label, vec![block])),
+        }
+    }
+    events.push(Event::MapStart);
+    for (label, group) in groups {
+        events.push(Event::MapKey(label));
+        emit_blocks(group, events);
+    }
+    events.push(Event::MapEnd);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HclLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use loadum::dumper::Dumper;
+    use loadum_json::json_dumper::JsonDumper;
+    use std::fmt::Write;
+
+    fn load(input: &str) -> String {
+        let mut output = String::new();
+        for event in HclLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_blocks() {
+        test_loader(
+            r#"
+# Web servers
+provider "aws" {
+  region = "eu-west-1"
+}
+
+resource "aws_instance" "web" {
+  ami           = "ami-123456"
+  instance_type = var.instance_type // set per environment
+
+  ebs_block_device { device_name = "/dev/sda1" }
+  ebs_block_device {
+    device_name = "/dev/sdb"
+  }
+}
+
+resource "aws_instance" "db" {
+  count = 2
+}
+
+resource aws_s3_bucket logs {}
+
+terraform {
+  required_version = ">= 1.0"
+}
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("provider"))
+                MapStart
+                MapKey(String("aws"))
+                ListStart
+                MapStart
+                MapKey(String("region"))
+                Literal(String("eu-west-1"))
+                MapEnd
+                ListEnd
+                MapEnd
+                MapKey(String("resource"))
+                MapStart
+                MapKey(String("aws_instance"))
+                MapStart
+                MapKey(String("web"))
+                ListStart
+                MapStart
+                MapKey(String("ami"))
+                Literal(String("ami-123456"))
+                MapKey(String("instance_type"))
+                Tag("expression")
+                Literal(String("var.instance_type"))
+                MapKey(String("ebs_block_device"))
+                ListStart
+                MapStart
+                MapKey(String("device_name"))
+                Literal(String("/dev/sda1"))
+                MapEnd
+                MapStart
+                MapKey(String("device_name"))
+                Literal(String("/dev/sdb"))
+                MapEnd
+                ListEnd
+                MapEnd
+                ListEnd
+                MapKey(String("db"))
+                ListStart
+                MapStart
+                MapKey(String("count"))
+                Literal(Integer(2))
+                MapEnd
+                ListEnd
+                MapEnd
+                MapKey(String("aws_s3_bucket"))
+                MapStart
+                MapKey(String("logs"))
+                ListStart
+                MapStart
+                MapEnd
+                ListEnd
+                MapEnd
+                MapEnd
+                MapKey(String("terraform"))
+                ListStart
+                MapStart
+                MapKey(String("required_version"))
+                Literal(String(">= 1.0"))
+                MapEnd
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        test_loader(
+            r#"
+numbers = [0, -42, 1.5, 1e3, 99999999999999999999]
+flags   = [true, false, null]
+strings = ["escapes \"\\\t\u00e9", "literal $${not} %%{template}"]
+tuple = [
+  1, # one
+  "two",
+  [],
+]
+object = { a = 1, "b c": 2
+  d = { nested = true } }
+heredoc = <<EOF
+line 1
+  line 2
+EOF
+indented = <<-EOT
+    indented
+      more
+    EOT
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("numbers"))
+                ListStart
+                Literal(Integer(0))
+                Literal(Integer(-42))
+                Literal(Number(1.5))
+                Literal(Number(1000.0))
+                Literal(Number(1e20))
+                ListEnd
+                MapKey(String("flags"))
+                ListStart
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Null)
+                ListEnd
+                MapKey(String("strings"))
+                ListStart
+                Literal(String("escapes \"\\\té"))
+                Literal(String("literal ${not} %{template}"))
+                ListEnd
+                MapKey(String("tuple"))
+                ListStart
+                Literal(Integer(1))
+                Literal(String("two"))
+                ListStart
+                ListEnd
+                ListEnd
+                MapKey(String("object"))
+                MapStart
+                MapKey(String("a"))
+                Literal(Integer(1))
+                MapKey(String("b c"))
+                Literal(Integer(2))
+                MapKey(String("d"))
+                MapStart
+                MapKey(String("nested"))
+                Literal(Boolean(true))
+                MapEnd
+                MapEnd
+                MapKey(String("heredoc"))
+                Literal(String("line 1\n  line 2\n"))
+                MapKey(String("indented"))
+                Literal(String("indented\n  more\n"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        test_loader(
+            r#"
+reference   = var.name
+call        = join(",", ["a", "b"])
+template    = "${var.prefix}-web"
+directive   = "%{ for k, v in var.map }${k}=${v} %{ endfor }"
+conditional = var.enabled ? 1 : 0
+arithmetic  = -1 + 2
+index       = [1, 2][0]
+for_tuple   = [for s in var.list : upper(s)]
+for_object  = {for k, v in var.map : k => v}
+mixed       = [var.a, 1, { key = local.value }]
+keys        = { (var.key) = 1, "${var.prefix}-name" = 2 }
+multiline   = merge(
+  local.tags,
+  { Name = "web" },
+)
+template_heredoc = <<EOF
+Hello, ${var.name}!
+EOF
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("reference"))
+                Tag("expression")
+                Literal(String("var.name"))
+                MapKey(String("call"))
+                Tag("expression")
+                Literal(String("join(\",\", [\"a\", \"b\"])"))
+                MapKey(String("template"))
+                Tag("expression")
+                Literal(String("\"${var.prefix}-web\""))
+                MapKey(String("directive"))
+                Tag("expression")
+                Literal(String("\"%{ for k, v in var.map }${k}=${v} %{ endfor }\""))
+                MapKey(String("conditional"))
+                Tag("expression")
+                Literal(String("var.enabled ? 1 : 0"))
+                MapKey(String("arithmetic"))
+                Tag("expression")
+                Literal(String("-1 + 2"))
+                MapKey(String("index"))
+                Tag("expression")
+                Literal(String("[1, 2][0]"))
+                MapKey(String("for_tuple"))
+                Tag("expression")
+                Literal(String("[for s in var.list : upper(s)]"))
+                MapKey(String("for_object"))
+                Tag("expression")
+                Literal(String("{for k, v in var.map : k => v}"))
+                MapKey(String("mixed"))
+                ListStart
+                Tag("expression")
+                Literal(String("var.a"))
+                Literal(Integer(1))
+                MapStart
+                MapKey(String("key"))
+                Tag("expression")
+                Literal(String("local.value"))
+                MapEnd
+                ListEnd
+                MapKey(String("keys"))
+                MapStart
+                Tag("expression")
+                MapKey(String("(var.key)"))
+                Literal(Integer(1))
+                Tag("expression")
+                MapKey(String("\"${var.prefix}-name\""))
+                Literal(Integer(2))
+                MapEnd
+                MapKey(String("multiline"))
+                Tag("expression")
+                Literal(String("merge(\n  local.tags,\n  { Name = \"web\" },\n)"))
+                MapKey(String("template_heredoc"))
+                Tag("expression")
+                Literal(String("<<EOF\nHello, ${var.name}!\nEOF"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "a = 1\na = 2",
+            "a = 1\na {}",
+            "a \"x\" {}\na {}",
+            "block {",
+            "a = ",
+            "a = \"unterminated",
+            "a = \"\\",
+            "a \"\\",
+            "a = \"${var.x\"",
+            "a = (1",
+            "a = <<EOF\nno end",
+            "a = \"\\q\"",
+            "label \"${x}\" {}",
+            "a b = 1",
+            "= 1",
+            "a = {b 1}",
+            &"a {\n".repeat(MAX_DEPTH + 1),
+            &format!(
+                "a = {}{}",
+                "[".repeat(MAX_DEPTH + 1),
+                "]".repeat(MAX_DEPTH + 1)
+            ),
+            &format!("a = {}", "\"${".repeat(MAX_DEPTH + 1)),
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Duplicate attribute 'a' at line 2, column 1
+            Error: 'a' is both an attribute and a block at line 2, column 1
+            Error: Blocks of type 'a' have different numbers of labels at line 2, column 1
+            Error: Unterminated block at line 1, column 7
+            Error: Expected expression at line 1, column 5
+            Error: Unterminated string at line 1, column 5
+            Error: Unterminated string at line 1, column 5
+            Error: Unterminated string at line 1, column 3
+            Error: Unterminated string at line 1, column 13
+            Error: Unterminated expression at line 1, column 5
+            Error: Unterminated heredoc at line 1, column 5
+            Error: Invalid escape sequence '\q' at line 1, column 5
+            Error: Block labels cannot be templates at line 1, column 7
+            Error: Expected '=', a block label or '{' at line 1, column 5
+            Error: Expected attribute or block name at line 1, column 1
+            Error: Expected '=' or ':' in object at line 1, column 8
+            Error: Document is nested deeper than 128 levels at line 129, column 3
+            Error: Document is nested deeper than 128 levels at line 1, column 134
+            Error: Document is nested deeper than 128 levels at line 1, column 389
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        let input = r#"
+variable "region" {
+  default = "eu-west-1"
+}
+locals {
+  name = "${var.prefix}-web"
+}
+"#;
+        for event in HclLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"variable": {
+            		"region": [
+            			{
+            				"default": "eu-west-1"
+            			}
+            		]
+            	},
+            	"locals": [
+            		{
+            			"name": "\"${var.prefix}-web\""
+            		}
+            	]
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,12 @@
+//! HCL (HashiCorp Configuration Language), as used by Terraform
+//!
+//! Attributes are map entries and blocks are nested maps keyed by their type and labels, like
+//! in the JSON syntax of HCL. The bodies of blocks with the same type and labels are collected in
+//! a list, so `resource "aws_instance" "web" { ... }` loads as
+//! `{"resource": {"aws_instance": {"web": [{...}]}}}`. Literal expressions are loaded as values,
+//! other expressions as tagged strings of their source text.
+
+pub mod hcl_loader;
+
+/// Tag of expressions other than literals, like `var.name` or `"${var.prefix}-web"`
+pub(crate) const EXPRESSION_TAG: &str = "expression";
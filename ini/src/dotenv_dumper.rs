@@ -0,0 +1,182 @@
+use crate::{document_entries, value_string};
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::NodeBuilder;
+use loadum::result::LoadumResult;
+use std::io::Write;
+
+/// Dumps `.env` files from flat maps
+///
+/// Values are written unquoted where possible and in double quotes otherwise. Keys have to be
+/// valid variable names.
+pub struct DotenvDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+    export: bool,
+}
+
+impl<'write> DotenvDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> DotenvDumper<'write> {
+        DotenvDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::Stringify,
+            export: false,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    /// Sets whether each line is prefixed with `export`, so the file can be sourced by a shell
+    pub fn with_export(mut self, export: bool) -> Self {
+        self.export = export;
+        self
+    }
+}
+
+impl Dumper for DotenvDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut output = String::new();
+        for (key, value) in document_entries(&root, "Dotenv")? {
+            let key = self.key_policy.key_string(key, "Dotenv keys")?;
+            let is_variable_name = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+            if !is_variable_name {
+                bail!("'{}' is not a valid dotenv variable name", key);
+            }
+            let value = value_string(value, &key, "Dotenv")?;
+            let value = value.as_deref().unwrap_or_default();
+            if self.export {
+                output.push_str("export ");
+            }
+            output.push_str(&key);
+            output.push('=');
+            let needs_quotes = value.chars().any(|c| {
+                c.is_whitespace()
+                    || c.is_control()
+                    || matches!(c, '#' | '"' | '\'' | '\\' | '$' | '`')
+            });
+            if needs_quotes {
+                output.push('"');
+                for c in value.chars() {
+                    match c {
+                        '\n' => output.push_str("\\n"),
+                        '\r' => output.push_str("\\r"),
+                        '\t' => output.push_str("\\t"),
+                        '"' | '\\' | '$' | '`' => {
+                            output.push('\\');
+                            output.push(c);
+                        }
+                        c => output.push(c),
+                    }
+                }
+                output.push('"');
+            } else {
+                output.push_str(value);
+            }
+            output.push('\n');
+        }
+        self.write.write_all(output.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dotenv_dumper::DotenvDumper;
+    use crate::dotenv_loader::DotenvLoader;
+    use expect_test::expect;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+
+    fn dump(dumper: DotenvDumper, events: &[Event]) -> Result<(), String> {
+        let mut dumper = dumper;
+        for event in [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+        {
+            dumper.emit(event).map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_quoting() {
+        let events = [
+            Event::MapStart,
+            Event::map_key("DB_HOST"),
+            Event::string("localhost"),
+            Event::map_key("DB_PORT"),
+            Event::integer(5432),
+            Event::map_key("GREETING"),
+            Event::string("Hello \"World\"\n$HOME\\"),
+            Event::map_key("PASSWORD"),
+            Event::string("p@ss#1"),
+            Event::map_key("EMPTY"),
+            Event::null(),
+            Event::MapEnd,
+        ];
+        let mut output = vec![];
+        dump(DotenvDumper::new(&mut output).with_export(true), &events).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        expect![[r#"
+            export DB_HOST=localhost
+            export DB_PORT=5432
+            export GREETING="Hello \"World\"\n\$HOME\\"
+            export PASSWORD="p@ss#1"
+            export EMPTY=
+        "#]]
+        .assert_eq(&output);
+        let loaded: Vec<Event> = DotenvLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        let mut expected = vec![Event::DocumentStart];
+        expected.extend(events.map(|event| match event {
+            Event::Literal(value) => Event::string(value.to_string().replace("null", "")),
+            Event::MapKey(key) => Event::map_key(key.as_str().unwrap()),
+            event => event,
+        }));
+        expected.push(Event::DocumentEnd);
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = vec![];
+        let error = dump(
+            DotenvDumper::new(&mut output),
+            &[
+                Event::MapStart,
+                Event::map_key("NOT VALID"),
+                Event::null(),
+                Event::MapEnd,
+            ],
+        );
+        expect![[r#"Err("'NOT VALID' is not a valid dotenv variable name")"#]]
+            .assert_eq(&format!("{:?}", error));
+        let error = dump(
+            DotenvDumper::new(&mut output),
+            &[
+                Event::MapStart,
+                Event::map_key("LIST"),
+                Event::ListStart,
+                Event::ListEnd,
+                Event::MapEnd,
+            ],
+        );
+        expect![[r#"Err("Dotenv cannot represent nested values, found one at key 'LIST'")"#]]
+            .assert_eq(&format!("{:?}", error));
+    }
+}
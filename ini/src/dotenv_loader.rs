@@ -0,0 +1,273 @@
+use crate::{flat_map_events, insert_last_wins};
+use loadum::error::format_err;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+
+/// Loads `.env` files as a flat map of strings
+///
+/// Lines are `KEY=value` assignments, optionally prefixed with `export`. Unquoted values end at a
+/// ` #` comment, single quoted values are literal and double quoted values support escapes, and
+/// both quoted forms may span lines. Variables like `${HOME}` are not expanded.
+pub struct DotenvLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> DotenvLoader<'source> {
+    pub fn new(source: &'source str) -> DotenvLoader<'source> {
+        DotenvLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for DotenvLoader<'source> {}
+
+impl<'source> Iterator for DotenvLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                position: 0,
+                line: 1,
+            };
+            match parser.parse() {
+                Ok(entries) => self.events = Some(flat_map_events(entries).into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    position: usize,
+    line: usize,
+}
+
+impl<'source> Parser<'source> {
+    fn parse(&mut self) -> LoadumResult<Vec<(Value<'source>, Value<'source>)>> {
+        let mut entries = vec![];
+        while self.position < self.source.len() {
+            self.skip_blanks();
+            let rest = self.rest();
+            if rest.starts_with('#') || rest.starts_with(['\n', '\r']) || rest.is_empty() {
+                self.skip_line();
+                continue;
+            }
+            if rest
+                .strip_prefix("export")
+                .is_some_and(|after| after.starts_with([' ', '\t']))
+            {
+                self.position += "export".len();
+                self.skip_blanks();
+            }
+            let key = self.parse_key()?;
+            self.skip_blanks();
+            if !self.rest().starts_with('=') {
+                return Err(format_err!(
+                    "Expected '=' after '{}' at line {}",
+                    key,
+                    self.line
+                ));
+            }
+            self.position += 1;
+            self.skip_blanks();
+            let value = self.parse_value()?;
+            insert_last_wins(&mut entries, Value::BorrowedString(key), value);
+        }
+        Ok(entries)
+    }
+
+    fn parse_key(&mut self) -> LoadumResult<&'source str> {
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-')))
+            .unwrap_or(rest.len());
+        if length == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(format_err!("Expected variable name at line {}", self.line));
+        }
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    fn parse_value(&mut self) -> LoadumResult<Value<'source>> {
+        let rest = self.rest();
+        let start_line = self.line;
+        let value = match rest.chars().next() {
+            Some('\'') => {
+                let Some(length) = rest[1..].find('\'') else {
+                    return Err(format_err!(
+                        "Unterminated single quoted value starting at line {}",
+                        start_line
+                    ));
+                };
+                self.advance(length + 2);
+                Value::BorrowedString(&rest[1..length + 1])
+            }
+            Some('"') => self.parse_double_quoted()?,
+            _ => {
+                let line_end = rest.find(['\n', '\r']).unwrap_or(rest.len());
+                let line = &rest[..line_end];
+                // A '#' only starts a comment after whitespace, so values like `a#b` stay intact
+                let comment = line
+                    .char_indices()
+                    .find(|(index, c)| *c == '#' && line[..*index].ends_with([' ', '\t']))
+                    .map_or(line.len(), |(index, _)| index);
+                self.position += line_end;
+                return Ok(Value::BorrowedString(line[..comment].trim_end()));
+            }
+        };
+        self.skip_blanks();
+        let rest = self.rest();
+        if !(rest.is_empty() || rest.starts_with(['#', '\n', '\r'])) {
+            return Err(format_err!(
+                "Unexpected text after quoted value at line {}",
+                self.line
+            ));
+        }
+        self.skip_line();
+        Ok(value)
+    }
+
+    fn parse_double_quoted(&mut self) -> LoadumResult<Value<'source>> {
+        let start_line = self.line;
+        let mut output = String::new();
+        let mut chars = self.rest()[1..].char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.advance(index + 2);
+                    return Ok(Value::String(output.into()));
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('n') => output.push('\n'),
+                    Some('r') => output.push('\r'),
+                    Some('t') => output.push('\t'),
+                    Some(c @ ('"' | '\\' | '$' | '\'' | '`')) => output.push(c),
+                    Some(c) => {
+                        // Unknown escapes are kept as written
+                        output.push('\\');
+                        output.push(c);
+                    }
+                    None => break,
+                },
+                c => output.push(c),
+            }
+        }
+        Err(format_err!(
+            "Unterminated double quoted value starting at line {}",
+            start_line
+        ))
+    }
+
+    fn rest(&self) -> &'source str {
+        &self.source[self.position..]
+    }
+
+    /// Advances over text that may contain line breaks
+    fn advance(&mut self, length: usize) {
+        self.line += self.rest()[..length].matches('\n').count();
+        self.position += length;
+    }
+
+    fn skip_blanks(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    }
+
+    fn skip_line(&mut self) {
+        let rest = self.rest();
+        match rest.find('\n') {
+            Some(index) => {
+                self.position += index + 1;
+                self.line += 1;
+            }
+            None => self.position = self.source.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dotenv_loader::DotenvLoader;
+    use expect_test::{Expect, expect};
+    use std::fmt::Write;
+
+    fn load(input: &str) -> String {
+        let mut output = String::new();
+        for event in DotenvLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_dotenv() {
+        test_loader(
+            "# database settings\nDB_HOST=localhost\nexport DB_PORT = 5432 # default port\n\
+             PASSWORD='p@ss #1 \\n'\nGREETING=\"Hello\\n\\\"World\\\" \\$HOME\"\n\
+             MULTILINE=\"first\nsecond\"\nURL=http://example.com/#anchor\nEMPTY=\n\
+             exported=yes\nDB_HOST=override\n",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("DB_HOST"))
+                Literal(String("override"))
+                MapKey(String("DB_PORT"))
+                Literal(String("5432"))
+                MapKey(String("PASSWORD"))
+                Literal(String("p@ss #1 \\n"))
+                MapKey(String("GREETING"))
+                Literal(String("Hello\n\"World\" $HOME"))
+                MapKey(String("MULTILINE"))
+                Literal(String("first\nsecond"))
+                MapKey(String("URL"))
+                Literal(String("http://example.com/#anchor"))
+                MapKey(String("EMPTY"))
+                Literal(String(""))
+                MapKey(String("exported"))
+                Literal(String("yes"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "1KEY=value\n",
+            "KEY value\n",
+            "KEY='open\n",
+            "A=1\nKEY=\"open\n",
+            "KEY='a' b\n",
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Expected variable name at line 1
+            Error: Expected '=' after 'KEY' at line 1
+            Error: Unterminated single quoted value starting at line 1
+            Error: Unterminated double quoted value starting at line 2
+            Error: Unexpected text after quoted value at line 1
+        "#]]
+        .assert_eq(&output);
+    }
+}
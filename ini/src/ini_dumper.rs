@@ -0,0 +1,227 @@
+use crate::{Entries, document_entries, value_string};
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Dumps INI files, nested maps are written as sections
+///
+/// Top level entries have to be written before all sections, so the document is collected and
+/// written once it is complete. Maps nested deeper than sections and lists cannot be represented.
+pub struct IniDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> IniDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> IniDumper<'write> {
+        IniDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    /// Writes the scalar entries and returns the sections, if they are allowed at this level
+    fn write_entries<'node>(
+        &self,
+        output: &mut String,
+        entries: &'node Entries,
+        allow_sections: bool,
+    ) -> LoadumResult<Vec<(Cow<'node, str>, &'node Entries)>> {
+        let mut sections = vec![];
+        for (key, value) in entries {
+            let key = self.key_policy.key_string(key, "INI keys")?;
+            if let (Node::Map(section), true) = (value.untagged(), allow_sections) {
+                if key.contains([']', '\n', '\r']) {
+                    bail!("'{}' cannot be written as an INI section name", key);
+                }
+                sections.push((key, section.as_slice()));
+                continue;
+            }
+            if key.is_empty()
+                || key.starts_with([';', '#', '[', ' ', '\t'])
+                || key.ends_with([' ', '\t'])
+                || key.contains(['=', '\n', '\r'])
+            {
+                bail!("'{}' cannot be written as an INI key", key);
+            }
+            output.push_str(&key);
+            output.push_str(" =");
+            let Some(value) = value_string(value, &key, "INI")? else {
+                output.push('\n');
+                continue;
+            };
+            if value.contains(['\n', '\r']) {
+                bail!(
+                    "INI cannot represent line breaks, found one at key '{}'",
+                    key
+                );
+            }
+            output.push(' ');
+            // Quotes keep surrounding whitespace and values that are quoted themselves intact
+            let needs_quotes =
+                value.starts_with([' ', '\t', '"', '\'']) || value.ends_with([' ', '\t']);
+            if needs_quotes {
+                output.push('"');
+                output.push_str(&value);
+                output.push('"');
+            } else {
+                output.push_str(&value);
+            }
+            output.push('\n');
+        }
+        Ok(sections)
+    }
+}
+
+impl Dumper for IniDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let entries = document_entries(&root, "INI")?;
+        let mut output = String::new();
+        let sections = self.write_entries(&mut output, entries, true)?;
+        for (name, entries) in sections {
+            if !output.is_empty() {
+                output.push('\n');
+            }
+            output.push_str(&format!("[{}]\n", name));
+            self.write_entries(&mut output, entries, false)?;
+        }
+        self.write.write_all(output.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ini_dumper::IniDumper;
+    use crate::ini_loader::IniLoader;
+    use expect_test::expect;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+
+    fn dump(events: &[Event]) -> String {
+        let mut output = vec![];
+        let mut dumper = IniDumper::new(&mut output);
+        let mut result = Ok(());
+        for event in [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+        {
+            result = dumper.emit(event);
+            if result.is_err() {
+                break;
+            }
+        }
+        drop(dumper);
+        let mut output = String::from_utf8(output).unwrap();
+        if let Err(error) = result {
+            output.push_str(&format!("Error: {}\n", error));
+        }
+        output
+    }
+
+    #[test]
+    fn test_sections() {
+        expect![[r#"
+            name = demo
+            debug = false
+            empty =
+
+            [server]
+            port = 8080
+            banner = " welcome "
+        "#]]
+        .assert_eq(&dump(&[
+            Event::MapStart,
+            Event::map_key("server"),
+            Event::MapStart,
+            Event::map_key("port"),
+            Event::integer(8080),
+            Event::map_key("banner"),
+            Event::string(" welcome "),
+            Event::MapEnd,
+            Event::map_key("name"),
+            Event::string("demo"),
+            Event::map_key("debug"),
+            Event::bool(false),
+            Event::map_key("empty"),
+            Event::null(),
+            Event::MapEnd,
+        ]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            vec![Event::ListStart, Event::ListEnd],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::MapStart,
+                Event::map_key("b"),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::ListStart,
+                Event::ListEnd,
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a=b"),
+                Event::null(),
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::string("line\nbreak"),
+                Event::MapEnd,
+            ],
+        ]
+        .iter()
+        .map(|events| dump(events))
+        .collect();
+        expect![[r#"
+            Error: INI documents must be maps, but found List([])
+            Error: INI cannot represent nested values, found one at key 'b'
+            Error: INI cannot represent nested values, found one at key 'a'
+            Error: 'a=b' cannot be written as an INI key
+            Error: INI cannot represent line breaks, found one at key 'a'
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = "name = demo\nhttp://example.com = mirror\n\n\
+                     [database]\nhost = localhost\npassword = \" secret \"\n";
+        let mut output = vec![];
+        let mut dumper = IniDumper::new(&mut output);
+        for event in IniLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+}
@@ -0,0 +1,226 @@
+use loadum::error::format_err;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+
+/// Loads INI files as a map from section names to maps of their entries
+///
+/// Entries before the first section header are loaded into the top level map, so sections cannot
+/// share their names with them. Keys and values are separated by the first `=`, or by the first
+/// `:` in lines without `=`. Lines starting with `;` or `#` are comments and values in matching
+/// quotes are unquoted. All values are strings.
+pub struct IniLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> IniLoader<'source> {
+    pub fn new(source: &'source str) -> IniLoader<'source> {
+        IniLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for IniLoader<'source> {}
+
+impl<'source> Iterator for IniLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            match parse(self.source) {
+                Ok(events) => self.events = Some(events.into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+struct Section<'source> {
+    name: &'source str,
+    entries: Vec<(&'source str, &'source str)>,
+}
+
+fn parse(source: &str) -> LoadumResult<Vec<Event<'_>>> {
+    let mut global = vec![];
+    let mut sections: Vec<Section> = vec![];
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with([';', '#']) {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            let Some(name) = header.strip_suffix(']') else {
+                return Err(format_err!(
+                    "Unterminated section header at line {}",
+                    line_number
+                ));
+            };
+            let name = name.trim();
+            if sections.iter().any(|section| section.name == name) {
+                return Err(format_err!(
+                    "Section '{}' is already defined at line {}",
+                    name,
+                    line_number
+                ));
+            }
+            // Global entries precede all sections, so only sections can collide with them
+            if global.iter().any(|(key, _)| *key == name) {
+                return Err(format_err!(
+                    "Section '{}' is already defined as a key at line {}",
+                    name,
+                    line_number
+                ));
+            }
+            sections.push(Section {
+                name,
+                entries: vec![],
+            });
+            continue;
+        }
+        let Some(separator) = line.find('=').or_else(|| line.find(':')) else {
+            return Err(format_err!(
+                "Expected '=' or ':' after key at line {}",
+                line_number
+            ));
+        };
+        let key = line[..separator].trim_end();
+        if key.is_empty() {
+            return Err(format_err!("Expected key at line {}", line_number));
+        }
+        let entries = match sections.last_mut() {
+            Some(section) => &mut section.entries,
+            None => &mut global,
+        };
+        if entries.iter().any(|(existing, _)| *existing == key) {
+            return Err(format_err!(
+                "Key '{}' is already defined at line {}",
+                key,
+                line_number
+            ));
+        }
+        entries.push((key, unquote(line[separator + 1..].trim_start())));
+    }
+    let mut events = vec![Event::DocumentStart, Event::MapStart];
+    push_entries(&mut events, global);
+    for section in sections {
+        events.push(Event::MapKey(Value::BorrowedString(section.name)));
+        events.push(Event::MapStart);
+        push_entries(&mut events, section.entries);
+        events.push(Event::MapEnd);
+    }
+    events.extend([Event::MapEnd, Event::DocumentEnd]);
+    Ok(events)
+}
+
+fn push_entries<'source>(
+    events: &mut Vec<Event<'source>>,
+    entries: Vec<(&'source str, &'source str)>,
+) {
+    for (key, value) in entries {
+        events.push(Event::MapKey(Value::BorrowedString(key)));
+        events.push(Event::Literal(Value::BorrowedString(value)));
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(unquoted) = value
+            .strip_prefix(quote)
+            .and_then(|value| value.strip_suffix(quote))
+        {
+            return unquoted;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ini_loader::IniLoader;
+    use expect_test::{Expect, expect};
+    use std::fmt::Write;
+
+    fn load(input: &str) -> String {
+        let mut output = String::new();
+        for event in IniLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_sections() {
+        test_loader(
+            "; global settings\nname = demo\n\n[database]\nhost: localhost\nport=5432\n\
+             # quoted values keep their whitespace\npassword = \" secret \"\nempty =\n\n\
+             [ paths ]\nroot = 'C:\\data'\nhttp://example.com = mirror\nproxy: http://proxy\n",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("name"))
+                Literal(String("demo"))
+                MapKey(String("database"))
+                MapStart
+                MapKey(String("host"))
+                Literal(String("localhost"))
+                MapKey(String("port"))
+                Literal(String("5432"))
+                MapKey(String("password"))
+                Literal(String(" secret "))
+                MapKey(String("empty"))
+                Literal(String(""))
+                MapEnd
+                MapKey(String("paths"))
+                MapStart
+                MapKey(String("root"))
+                Literal(String("C:\\data"))
+                MapKey(String("http://example.com"))
+                Literal(String("mirror"))
+                MapKey(String("proxy"))
+                Literal(String("http://proxy"))
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "[section\n",
+            "[a]\n[a]\n",
+            "a = 1\n[a]\n",
+            "key\n",
+            "a = 1\na = 2\n",
+            " = 1\n",
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Unterminated section header at line 1
+            Error: Section 'a' is already defined at line 2
+            Error: Section 'a' is already defined as a key at line 2
+            Error: Expected '=' or ':' after key at line 1
+            Error: Key 'a' is already defined at line 2
+            Error: Expected key at line 1
+        "#]]
+        .assert_eq(&output);
+    }
+}
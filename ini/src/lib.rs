@@ -0,0 +1,75 @@
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::Node;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+
+pub mod dotenv_dumper;
+pub mod dotenv_loader;
+pub mod ini_dumper;
+pub mod ini_loader;
+pub mod properties_dumper;
+pub mod properties_loader;
+
+/// Sets the value of a key, replacing the value of an earlier entry with the same key
+pub(crate) fn insert_last_wins<'source>(
+    entries: &mut Vec<(Value<'source>, Value<'source>)>,
+    key: Value<'source>,
+    value: Value<'source>,
+) {
+    match entries
+        .iter_mut()
+        .find(|(existing, _)| existing.as_str() == key.as_str())
+    {
+        Some((_, existing)) => *existing = value,
+        None => entries.push((key, value)),
+    }
+}
+
+/// Returns the events of a document consisting of a single flat map
+pub(crate) fn flat_map_events<'source>(
+    entries: Vec<(Value<'source>, Value<'source>)>,
+) -> Vec<Event<'source>> {
+    let mut events = vec![Event::DocumentStart, Event::MapStart];
+    for (key, value) in entries {
+        events.push(Event::MapKey(key));
+        events.push(Event::Literal(value));
+    }
+    events.extend([Event::MapEnd, Event::DocumentEnd]);
+    events
+}
+
+/// Entries of a map collected by a dumper
+pub(crate) type Entries = [(Node<'static>, Node<'static>)];
+
+/// Returns the entries of a document, which has to be a map
+pub(crate) fn document_entries<'node>(
+    root: &'node Node<'static>,
+    format: &str,
+) -> LoadumResult<&'node Entries> {
+    let Node::Map(entries) = root.untagged() else {
+        bail!("{} documents must be maps, but found {:?}", format, root);
+    };
+    Ok(entries)
+}
+
+/// Returns the value of a scalar entry as a string, or None for null
+pub(crate) fn value_string<'node>(
+    value: &'node Node,
+    key: &str,
+    format: &str,
+) -> LoadumResult<Option<Cow<'node, str>>> {
+    match value.untagged() {
+        Node::Scalar(Value::Null) => Ok(None),
+        Node::Scalar(value) => Ok(Some(match value.as_str() {
+            Some(value) => Cow::Borrowed(value),
+            None => Cow::Owned(value.to_string()),
+        })),
+        _ => bail!(
+            "{} cannot represent nested values, found one at key '{}'",
+            format,
+            key
+        ),
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{document_entries, value_string};
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::event::Event;
+use loadum::node::NodeBuilder;
+use loadum::result::LoadumResult;
+use std::io::Write;
+
+/// Dumps Java `.properties` files from flat maps
+///
+/// Output is UTF-8, as read by `java.util.PropertyResourceBundle` since Java 9, with only
+/// separators, comment characters, whitespace and control characters escaped.
+pub struct PropertiesDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> PropertiesDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> PropertiesDumper<'write> {
+        PropertiesDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for PropertiesDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut output = String::new();
+        for (key, value) in document_entries(&root, "Properties")? {
+            let key = self.key_policy.key_string(key, "Properties keys")?;
+            let value = value_string(value, &key, "Properties")?;
+            escape(&mut output, &key, true);
+            output.push('=');
+            escape(&mut output, value.as_deref().unwrap_or_default(), false);
+            output.push('\n');
+        }
+        self.write.write_all(output.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn escape(output: &mut String, text: &str, is_key: bool) {
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '\t' => output.push_str("\\t"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\x0c' => output.push_str("\\f"),
+            // Leading whitespace of values and all separators in keys would be lost
+            ' ' if is_key || index == 0 => output.push_str("\\ "),
+            '=' | ':' if is_key => {
+                output.push('\\');
+                output.push(c);
+            }
+            '#' | '!' if index == 0 => {
+                output.push('\\');
+                output.push(c);
+            }
+            c if c.is_control() => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::properties_dumper::PropertiesDumper;
+    use crate::properties_loader::PropertiesLoader;
+    use expect_test::expect;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+
+    fn dump(events: &[Event]) -> String {
+        let mut output = vec![];
+        let mut dumper = PropertiesDumper::new(&mut output);
+        let mut result = Ok(());
+        for event in [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+        {
+            result = dumper.emit(event);
+            if result.is_err() {
+                break;
+            }
+        }
+        drop(dumper);
+        let mut output = String::from_utf8(output).unwrap();
+        if let Err(error) = result {
+            output.push_str(&format!("Error: {}\n", error));
+        }
+        output
+    }
+
+    #[test]
+    fn test_escapes() {
+        let events = [
+            Event::MapStart,
+            Event::map_key("app.name"),
+            Event::string("Demo"),
+            Event::map_key("key with spaces=:"),
+            Event::string("  leading, trailing  "),
+            Event::map_key("#comment"),
+            Event::string("a\\b\tc\nd\u{1}"),
+            Event::map_key("port"),
+            Event::integer(8080),
+            Event::map_key("empty"),
+            Event::null(),
+            Event::map_key("unicode"),
+            Event::string("grüß"),
+            Event::MapEnd,
+        ];
+        let output = dump(&events);
+        expect![[r#"
+            app.name=Demo
+            key\ with\ spaces\=\:=\  leading, trailing  
+            \#comment=a\\b\tc\nd\u0001
+            port=8080
+            empty=
+            unicode=grüß
+        "#]]
+        .assert_eq(&output);
+        let loaded: Vec<Event> = PropertiesLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        let mut expected = vec![Event::DocumentStart];
+        expected.extend(events.map(|event| match event {
+            Event::Literal(value) => Event::string(value.to_string().replace("null", "")),
+            Event::MapKey(key) => Event::map_key(key.as_str().unwrap()),
+            event => event,
+        }));
+        expected.push(Event::DocumentEnd);
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_nested() {
+        expect![[r#"
+            Error: Properties cannot represent nested values, found one at key 'a'
+        "#]]
+        .assert_eq(&dump(&[
+            Event::MapStart,
+            Event::map_key("a"),
+            Event::MapStart,
+            Event::MapEnd,
+            Event::MapEnd,
+        ]));
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{flat_map_events, insert_last_wins};
+use loadum::error::format_err;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+
+/// Loads Java `.properties` files as a flat map of strings
+///
+/// Follows the format of `java.util.Properties::load`: keys end at the first unescaped `=`, `:`
+/// or whitespace, lines ending in an odd number of backslashes continue on the next line and
+/// later entries replace earlier ones with the same key. The input is read as UTF-8.
+pub struct PropertiesLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> PropertiesLoader<'source> {
+    pub fn new(source: &'source str) -> PropertiesLoader<'source> {
+        PropertiesLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for PropertiesLoader<'source> {}
+
+impl<'source> Iterator for PropertiesLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            match parse(self.source) {
+                Ok(entries) => self.events = Some(flat_map_events(entries).into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+fn parse(source: &str) -> LoadumResult<Vec<(Value<'_>, Value<'_>)>> {
+    let mut entries = vec![];
+    let mut lines = source.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let line = line.trim_start_matches([' ', '\t', '\x0c']);
+        if line.is_empty() || line.starts_with(['#', '!']) {
+            continue;
+        }
+        // Continuation lines are joined with their leading whitespace removed
+        let mut logical_line = Cow::Borrowed(line);
+        while ends_with_continuation(&logical_line) {
+            let logical = logical_line.to_mut();
+            logical.pop();
+            let Some((_, next)) = lines.next() else {
+                break;
+            };
+            logical.push_str(next.trim_start_matches([' ', '\t', '\x0c']));
+        }
+        let line_number = index + 1;
+        let (key, value) = match &logical_line {
+            Cow::Borrowed(line) => parse_entry(line, line_number)?,
+            Cow::Owned(line) => {
+                let (key, value) = parse_entry(line, line_number)?;
+                (key.into_owned(), value.into_owned())
+            }
+        };
+        insert_last_wins(&mut entries, key, value);
+    }
+    Ok(entries)
+}
+
+fn parse_entry(line: &str, line_number: usize) -> LoadumResult<(Value<'_>, Value<'_>)> {
+    let key_end = find_key_end(line);
+    let key = unescape(&line[..key_end], line_number)?;
+    let mut rest = line[key_end..].trim_start_matches([' ', '\t', '\x0c']);
+    if let Some(value) = rest.strip_prefix(['=', ':']) {
+        rest = value.trim_start_matches([' ', '\t', '\x0c']);
+    }
+    Ok((key, unescape(rest, line_number)?))
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    let backslashes = line.bytes().rev().take_while(|c| *c == b'\\').count();
+    backslashes % 2 == 1
+}
+
+fn find_key_end(line: &str) -> usize {
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '=' | ':' | ' ' | '\t' | '\x0c' => return index,
+            _ => {}
+        }
+    }
+    line.len()
+}
+
+/// Replaces escape sequences, borrowing the text if there are none
+fn unescape(text: &str, line_number: usize) -> LoadumResult<Value<'_>> {
+    if !text.contains('\\') {
+        return Ok(Value::BorrowedString(text));
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => output.push('\t'),
+            Some('n') => output.push('\n'),
+            Some('r') => output.push('\r'),
+            Some('f') => output.push('\x0c'),
+            Some('u') => {
+                // Escapes are UTF-16 code units, characters outside the BMP use surrogate pairs
+                let mut units = vec![parse_code_unit(&mut chars, line_number)?];
+                if (0xd800..0xdc00).contains(&units[0]) && chars.as_str().starts_with("\\u") {
+                    chars.nth(1);
+                    units.push(parse_code_unit(&mut chars, line_number)?);
+                }
+                for c in char::decode_utf16(units) {
+                    output.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+            }
+            Some(c) => output.push(c),
+            None => {}
+        }
+    }
+    Ok(Value::String(output.into()))
+}
+
+fn parse_code_unit(chars: &mut std::str::Chars, line_number: usize) -> LoadumResult<u16> {
+    let hex = chars.as_str().get(..4).unwrap_or_default();
+    if hex.len() != 4 || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format_err!(
+            "Malformed \\uxxxx escape at line {}",
+            line_number
+        ));
+    }
+    chars.nth(3);
+    Ok(u16::from_str_radix(hex, 16).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::properties_loader::PropertiesLoader;
+    use expect_test::{Expect, expect};
+    use std::fmt::Write;
+
+    fn test_loader(input: &str, expected: Expect) {
+        let mut output = String::new();
+        for event in PropertiesLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        expected.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_properties() {
+        test_loader(
+            "# comment\n! also a comment\napp.name = Demo\napp.title:Hello\\tWorld\n\
+             key\\ with\\ spaces value\nempty\nfruits = apple, \\\n    banana, \\\n    cherry\n\
+             greeting = gr\\u00fc\\u00DF dich \\ud83d\\ude00\npath = C:\\\\data\\\\\napp.name = Override\n",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("app.name"))
+                Literal(String("Override"))
+                MapKey(String("app.title"))
+                Literal(String("Hello\tWorld"))
+                MapKey(String("key with spaces"))
+                Literal(String("value"))
+                MapKey(String("empty"))
+                Literal(String(""))
+                MapKey(String("fruits"))
+                Literal(String("apple, banana, cherry"))
+                MapKey(String("greeting"))
+                Literal(String("grüß dich 😀"))
+                MapKey(String("path"))
+                Literal(String("C:\\data\\"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            "a = \\u12g4\n",
+            expect![[r#"
+            Error: Malformed \uxxxx escape at line 1
+        "#]],
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Arbitrary precision decimals and integers, which are kept as strings of digits
+
+use loadum::error::bail;
+use loadum::result::LoadumResult;
+use std::fmt::{Display, Formatter};
+
+/// A decimal `coefficient × 10^exponent`, the coefficient keeps trailing zeros as precision
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Decimal {
+    pub negative: bool,
+    /// Digits without leading zeros, `0` for zero
+    pub coefficient: String,
+    pub exponent: i64,
+}
+
+impl Decimal {
+    /// Parses decimals like `-1.50`, `1.5E+3` or the Ion text form `15d2`
+    pub fn parse(text: &str) -> LoadumResult<Decimal> {
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E', 'd', 'D']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()),
+            None => (unsigned, Some(0)),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let is_valid = !integer.is_empty()
+            && integer
+                .bytes()
+                .chain(fraction.bytes())
+                .all(|c| c.is_ascii_digit());
+        let exponent = exponent.and_then(|exponent| exponent.checked_sub(fraction.len() as i64));
+        let (Some(exponent), true) = (exponent, is_valid) else {
+            bail!("Invalid decimal '{}'", text);
+        };
+        let digits = format!("{}{}", integer, fraction);
+        let coefficient = match digits.trim_start_matches('0') {
+            "" => "0",
+            coefficient => coefficient,
+        };
+        Ok(Decimal {
+            negative,
+            coefficient: coefficient.to_string(),
+            exponent,
+        })
+    }
+}
+
+/// Formats like the General Decimal Arithmetic specification, in scientific notation for positive
+/// and small exponents
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.negative { "-" } else { "" };
+        let digits = self.coefficient.as_str();
+        let adjusted_exponent = self.exponent + digits.len() as i64 - 1;
+        if self.exponent > 0 || adjusted_exponent < -6 {
+            let (first, rest) = digits.split_at(1);
+            let point = if rest.is_empty() { "" } else { "." };
+            return write!(
+                f,
+                "{}{}{}{}E{:+}",
+                sign, first, point, rest, adjusted_exponent
+            );
+        }
+        let integer_digits = digits.len() as i64 + self.exponent;
+        if self.exponent == 0 {
+            write!(f, "{}{}", sign, digits)
+        } else if integer_digits > 0 {
+            let (integer, fraction) = digits.split_at(integer_digits as usize);
+            write!(f, "{}{}.{}", sign, integer, fraction)
+        } else {
+            let zeros = "0".repeat(-integer_digits as usize);
+            write!(f, "{}0.{}{}", sign, zeros, digits)
+        }
+    }
+}
+
+/// Converts a big-endian unsigned integer of any size to decimal digits
+pub(crate) fn magnitude_to_digits(magnitude: &[u8]) -> String {
+    let start = magnitude.iter().position(|byte| *byte != 0);
+    let mut magnitude = magnitude[start.unwrap_or(magnitude.len())..].to_vec();
+    let mut digits = vec![];
+    while !magnitude.is_empty() {
+        let mut remainder = 0;
+        for byte in magnitude.iter_mut() {
+            let value = remainder << 8 | *byte as u32;
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+        if magnitude[0] == 0 {
+            magnitude.remove(0);
+        }
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Converts digits in the radix to a big-endian unsigned integer without leading zero bytes
+pub(crate) fn digits_to_magnitude(digits: &str, radix: u32) -> Option<Vec<u8>> {
+    let mut magnitude: Vec<u8> = vec![];
+    for c in digits.chars() {
+        let mut carry = c.to_digit(radix)?;
+        for byte in magnitude.iter_mut().rev() {
+            let value = *byte as u32 * radix + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        while carry > 0 {
+            magnitude.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    Some(magnitude)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decimal::{Decimal, digits_to_magnitude, magnitude_to_digits};
+    use expect_test::expect;
+    use std::fmt::Write;
+
+    #[test]
+    fn test_parse_and_format() {
+        let mut output = String::new();
+        for text in [
+            "0",
+            "-0",
+            "0.00",
+            "1.",
+            "-1.50",
+            "1.5d3",
+            "15e-1",
+            "0.000001",
+            "0.0000001",
+            "1D+2",
+            "007",
+            "1.5e",
+            ".5",
+            "1e99999999999999999999",
+            "1x",
+        ] {
+            match Decimal::parse(text) {
+                Ok(decimal) => writeln!(output, "{} -> {}", text, decimal).unwrap(),
+                Err(error) => writeln!(output, "{} -> Error: {}", text, error).unwrap(),
+            }
+        }
+        expect![[r#"
+            0 -> 0
+            -0 -> -0
+            0.00 -> 0.00
+            1. -> 1
+            -1.50 -> -1.50
+            1.5d3 -> 1.5E+3
+            15e-1 -> 1.5
+            0.000001 -> 0.000001
+            0.0000001 -> 1E-7
+            1D+2 -> 1E+2
+            007 -> 7
+            1.5e -> Error: Invalid decimal '1.5e'
+            .5 -> Error: Invalid decimal '.5'
+            1e99999999999999999999 -> Error: Invalid decimal '1e99999999999999999999'
+            1x -> Error: Invalid decimal '1x'
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_magnitude() {
+        let mut output = String::new();
+        for (digits, radix) in [
+            ("0", 10),
+            ("255", 10),
+            ("256", 10),
+            ("18446744073709551616", 10),
+            ("ff", 16),
+            ("101", 2),
+        ] {
+            let magnitude = digits_to_magnitude(digits, radix).unwrap();
+            let decimal = magnitude_to_digits(&magnitude);
+            writeln!(output, "{} -> {:02x?} -> {}", digits, magnitude, decimal).unwrap();
+        }
+        assert_eq!(digits_to_magnitude("12", 2), None);
+        expect![[r#"
+            0 -> [] -> 0
+            255 -> [ff] -> 255
+            256 -> [01, 00] -> 256
+            18446744073709551616 -> [01, 00, 00, 00, 00, 00, 00, 00, 00] -> 18446744073709551616
+            ff -> [ff] -> 255
+            101 -> [05] -> 5
+        "#]]
+        .assert_eq(&output);
+    }
+}
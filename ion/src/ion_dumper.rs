@@ -0,0 +1,723 @@
+use crate::decimal::{Decimal, digits_to_magnitude, magnitude_to_digits};
+use crate::{
+    BINARY_VERSION_MARKER, CLOB_TAG, DECIMAL_TAG, IonFormat, SEXP_TAG, SYMBOL_TABLE_ANNOTATION,
+    SYMBOL_TAG, SYSTEM_SYMBOLS,
+};
+use loadum::base64;
+use loadum::datetime::DateTime;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Dumps Ion text or binary Ion
+///
+/// In the text format each document is written as a top level value on its own line. In the
+/// binary format each document starts with the version marker, followed by a local symbol table
+/// of its field names, annotations and symbols. The tags of the loader are written as the
+/// corresponding Ion types and other tags as annotations. Local times have no Ion counterpart and
+/// are written as strings.
+pub struct IonDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    format: IonFormat,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> IonDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> IonDumper<'write> {
+        IonDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            format: IonFormat::default(),
+            key_policy: KeyPolicy::default(),
+        }
+    }
+
+    /// Sets the encoding, by default [`IonFormat::Text`]
+    pub fn with_format(mut self, format: IonFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets how keys that are not strings are handled
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for IonDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let output = match self.format {
+            IonFormat::Text => {
+                let mut writer = TextWriter {
+                    output: String::new(),
+                    key_policy: self.key_policy,
+                };
+                writer.write_node(&root)?;
+                writer.output.push('\n');
+                writer.output.into_bytes()
+            }
+            IonFormat::Binary => {
+                let mut writer = BinaryWriter {
+                    symbols: HashMap::new(),
+                    local_symbols: vec![],
+                    key_policy: self.key_policy,
+                };
+                let mut value = vec![];
+                writer.write_node(&mut value, &root)?;
+                let mut output = BINARY_VERSION_MARKER.to_vec();
+                if !writer.local_symbols.is_empty() {
+                    let symbols = writer.local_symbols.drain(..).map(Value::string);
+                    let table = Node::Map(vec![(
+                        Node::Scalar(Value::BorrowedString("symbols")),
+                        Node::List(symbols.map(Node::Scalar).collect()),
+                    )]);
+                    let table = Node::Tagged(SYMBOL_TABLE_ANNOTATION.into(), Box::new(table));
+                    writer.write_node(&mut output, &table)?;
+                }
+                output.extend(value);
+                output
+            }
+        };
+        self.write.write_all(&output)?;
+        Ok(())
+    }
+}
+
+/// A node split into its annotations, the tag of its Ion type and its untagged value
+struct Annotated<'node, 'source> {
+    annotations: Vec<&'node str>,
+    type_tag: Option<&'node str>,
+    value: &'node Node<'source>,
+}
+
+/// Splits the tags of a node, the innermost tag is the Ion type if it matches the value
+fn annotated<'node, 'source>(mut node: &'node Node<'source>) -> Annotated<'node, 'source> {
+    let mut annotations = vec![];
+    while let Node::Tagged(tag, inner) = node {
+        annotations.push(tag.as_str());
+        node = inner;
+    }
+    let type_tag = annotations
+        .last()
+        .copied()
+        .filter(|tag| is_type_of(tag, node));
+    if type_tag.is_some() {
+        annotations.pop();
+    }
+    Annotated {
+        annotations,
+        type_tag,
+        value: node,
+    }
+}
+
+fn is_type_of(tag: &str, node: &Node) -> bool {
+    match (tag, node) {
+        (SYMBOL_TAG, _) => node.as_str().is_some(),
+        (SEXP_TAG, Node::List(_)) => true,
+        (CLOB_TAG, Node::Scalar(Value::Bytes(_))) => true,
+        (DECIMAL_TAG, _) => node
+            .as_str()
+            .is_some_and(|text| Decimal::parse(text).is_ok()),
+        (BIGINT_TAG, _) => node.as_str().is_some_and(|text| {
+            let digits = text.strip_prefix('-').unwrap_or(text);
+            !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+        }),
+        _ => false,
+    }
+}
+
+/// Whether a date-time is an Ion timestamp, which has a date and a year from 1 to 9999
+fn is_timestamp(date_time: &DateTime) -> bool {
+    match date_time {
+        DateTime::OffsetDateTime { date, .. }
+        | DateTime::LocalDateTime { date, .. }
+        | DateTime::LocalDate(date) => (1..=9999).contains(&date.year()),
+        DateTime::LocalTime(_) => false,
+    }
+}
+
+/// Splits an integer string into its sign and big-endian magnitude
+fn big_integer(text: &str) -> (bool, Vec<u8>) {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, text),
+    };
+    let magnitude = digits_to_magnitude(digits, 10).unwrap_or_default();
+    (negative && !magnitude.is_empty(), magnitude)
+}
+
+struct TextWriter {
+    output: String,
+    key_policy: KeyPolicy,
+}
+
+impl TextWriter {
+    fn write_node(&mut self, node: &Node) -> LoadumResult<()> {
+        let Annotated {
+            annotations,
+            type_tag,
+            value: node,
+        } = annotated(node);
+        for annotation in annotations {
+            self.write_symbol(annotation);
+            self.output.push_str("::");
+        }
+        let text = node.as_str().unwrap_or_default();
+        match (type_tag, node) {
+            (Some(SYMBOL_TAG), _) => self.write_symbol(text),
+            (Some(SEXP_TAG), Node::List(items)) => {
+                self.output.push('(');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push(' ');
+                    }
+                    self.write_node(item)?;
+                }
+                self.output.push(')');
+            }
+            (Some(CLOB_TAG), Node::Scalar(Value::Bytes(bytes))) => self.write_clob(bytes),
+            (Some(DECIMAL_TAG), _) => {
+                // The exponent of decimals is marked with `d`, `e` would make them floats
+                let decimal = Decimal::parse(text)?.to_string();
+                match decimal.split_once('E') {
+                    Some((mantissa, exponent)) => {
+                        write!(self.output, "{}d{}", mantissa, exponent).unwrap()
+                    }
+                    None if decimal.contains('.') => self.output.push_str(&decimal),
+                    None => write!(self.output, "{}.", decimal).unwrap(),
+                }
+            }
+            (Some(BIGINT_TAG), _) => {
+                let (negative, magnitude) = big_integer(text);
+                let sign = if negative { "-" } else { "" };
+                write!(self.output, "{}{}", sign, magnitude_to_digits(&magnitude)).unwrap();
+            }
+            (_, Node::Scalar(value)) => self.write_scalar(value),
+            (_, Node::List(items)) => {
+                self.output.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_node(item)?;
+                }
+                self.output.push(']');
+            }
+            (_, Node::Map(entries)) => {
+                self.output.push('{');
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_symbol(&self.key_policy.key_string(key, "Ion keys")?);
+                    self.output.push_str(": ");
+                    self.write_node(value)?;
+                }
+                self.output.push('}');
+            }
+            (_, Node::Tagged(..)) => unreachable!("Tags are split off"),
+        }
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.output.push_str("null"),
+            Value::Boolean(b) => self.output.push_str(if *b { "true" } else { "false" }),
+            Value::Integer(i) => write!(self.output, "{}", i).unwrap(),
+            Value::Number(n) if n.is_nan() => self.output.push_str("nan"),
+            Value::Number(n) if n.is_infinite() => {
+                self.output.push_str(if *n > 0.0 { "+inf" } else { "-inf" })
+            }
+            // Floats need an exponent, without one they would be decimals
+            Value::Number(n) => write!(self.output, "{:e}", n).unwrap(),
+            Value::String(s) => self.write_string(s, '"'),
+            Value::BorrowedString(s) => self.write_string(s, '"'),
+            Value::Bytes(bytes) => {
+                write!(self.output, "{{{{{}}}}}", base64::encode(bytes)).unwrap()
+            }
+            Value::DateTime(date_time) if is_timestamp(date_time) => {
+                write!(self.output, "{}", date_time).unwrap();
+                if matches!(date_time, DateTime::LocalDateTime { .. }) {
+                    self.output.push_str("-00:00");
+                }
+            }
+            Value::DateTime(date_time) => self.write_string(&date_time.to_string(), '"'),
+        }
+    }
+
+    /// Writes symbols as identifiers where possible, otherwise quoted
+    fn write_symbol(&mut self, symbol: &str) {
+        let is_identifier = symbol.starts_with(|c: char| c.is_ascii_alphabetic() || "_$".contains(c))
+            && symbol.chars().all(|c| c.is_ascii_alphanumeric() || "_$".contains(c))
+            && !matches!(symbol, "null" | "true" | "false" | "nan")
+            // Identifiers like `$10` are symbol IDs
+            && !symbol[1..].bytes().all(|c| c.is_ascii_digit());
+        if is_identifier {
+            self.output.push_str(symbol);
+        } else {
+            self.write_string(symbol, '\'');
+        }
+    }
+
+    fn write_string(&mut self, s: &str, quote: char) {
+        self.output.push(quote);
+        for c in s.chars() {
+            match c {
+                '\\' => self.output.push_str("\\\\"),
+                '\t' => self.output.push_str("\\t"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                c if c == quote => write!(self.output, "\\{}", c).unwrap(),
+                c if c.is_control() => {
+                    let escape = if (c as u32) < 0x100 { "x" } else { "u" };
+                    let width = if (c as u32) < 0x100 { 2 } else { 4 };
+                    write!(self.output, "\\{}{:02$x}", escape, c as u32, width).unwrap()
+                }
+                c => self.output.push(c),
+            }
+        }
+        self.output.push(quote);
+    }
+
+    fn write_clob(&mut self, bytes: &[u8]) {
+        self.output.push_str("{{\"");
+        for byte in bytes {
+            match byte {
+                b'"' => self.output.push_str("\\\""),
+                b'\\' => self.output.push_str("\\\\"),
+                b'\t' => self.output.push_str("\\t"),
+                b'\n' => self.output.push_str("\\n"),
+                b'\r' => self.output.push_str("\\r"),
+                b' '..=b'~' => self.output.push(*byte as char),
+                _ => write!(self.output, "\\x{:02x}", byte).unwrap(),
+            }
+        }
+        self.output.push_str("\"}}");
+    }
+}
+
+struct BinaryWriter {
+    /// IDs of the symbols defined in the local symbol table
+    symbols: HashMap<String, u64>,
+    /// Symbols of the local symbol table, which follow the system symbols
+    local_symbols: Vec<String>,
+    key_policy: KeyPolicy,
+}
+
+impl BinaryWriter {
+    fn symbol_id(&mut self, text: &str) -> u64 {
+        if let Some(index) = SYSTEM_SYMBOLS.iter().position(|symbol| *symbol == text) {
+            return index as u64 + 1;
+        }
+        if let Some(id) = self.symbols.get(text) {
+            return *id;
+        }
+        let id = (SYSTEM_SYMBOLS.len() + self.local_symbols.len()) as u64 + 1;
+        self.symbols.insert(text.to_string(), id);
+        self.local_symbols.push(text.to_string());
+        id
+    }
+
+    fn write_node(&mut self, output: &mut Vec<u8>, node: &Node) -> LoadumResult<()> {
+        let Annotated {
+            annotations,
+            type_tag,
+            value: node,
+        } = annotated(node);
+        if annotations.is_empty() {
+            return self.write_value(output, type_tag, node);
+        }
+        let mut ids = vec![];
+        for annotation in annotations {
+            let id = self.symbol_id(annotation);
+            write_var_uint(&mut ids, id);
+        }
+        let mut content = vec![];
+        write_var_uint(&mut content, ids.len() as u64);
+        content.extend(ids);
+        self.write_value(&mut content, type_tag, node)?;
+        write_typed(output, 0xe, &content);
+        Ok(())
+    }
+
+    fn write_value(
+        &mut self,
+        output: &mut Vec<u8>,
+        type_tag: Option<&str>,
+        node: &Node,
+    ) -> LoadumResult<()> {
+        let text = node.as_str().unwrap_or_default();
+        match (type_tag, node) {
+            (Some(SYMBOL_TAG), _) => {
+                let id = self.symbol_id(text);
+                write_typed(output, 0x7, &uint_bytes(id));
+            }
+            (Some(SEXP_TAG), Node::List(items)) => {
+                let mut content = vec![];
+                for item in items {
+                    self.write_node(&mut content, item)?;
+                }
+                write_typed(output, 0xc, &content);
+            }
+            (Some(CLOB_TAG), Node::Scalar(Value::Bytes(bytes))) => write_typed(output, 0x9, bytes),
+            (Some(DECIMAL_TAG), _) => write_decimal(output, &Decimal::parse(text)?),
+            (Some(BIGINT_TAG), _) => {
+                let (negative, magnitude) = big_integer(text);
+                write_typed(output, if negative { 0x3 } else { 0x2 }, &magnitude);
+            }
+            (_, Node::Scalar(value)) => write_scalar(output, value),
+            (_, Node::List(items)) => {
+                let mut content = vec![];
+                for item in items {
+                    self.write_node(&mut content, item)?;
+                }
+                write_typed(output, 0xb, &content);
+            }
+            (_, Node::Map(entries)) => {
+                let mut content = vec![];
+                for (key, value) in entries {
+                    let id = self.symbol_id(&self.key_policy.key_string(key, "Ion keys")?);
+                    write_var_uint(&mut content, id);
+                    self.write_node(&mut content, value)?;
+                }
+                write_typed(output, 0xd, &content);
+            }
+            (_, Node::Tagged(..)) => unreachable!("Tags are split off"),
+        }
+        Ok(())
+    }
+}
+
+fn write_scalar(output: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => output.push(0x0f),
+        Value::Boolean(b) => output.push(if *b { 0x11 } else { 0x10 }),
+        Value::Integer(i) => {
+            let type_code = if *i < 0 { 0x3 } else { 0x2 };
+            write_typed(output, type_code, &uint_bytes(i.unsigned_abs()));
+        }
+        Value::Number(n) => write_typed(output, 0x4, &n.to_be_bytes()),
+        Value::String(s) => write_typed(output, 0x8, s.as_bytes()),
+        Value::BorrowedString(s) => write_typed(output, 0x8, s.as_bytes()),
+        Value::Bytes(bytes) => write_typed(output, 0xa, bytes),
+        Value::DateTime(date_time) if is_timestamp(date_time) => write_timestamp(output, date_time),
+        Value::DateTime(date_time) => write_typed(output, 0x8, date_time.to_string().as_bytes()),
+    }
+}
+
+/// Writes the fields of a timestamp, in UTC if the offset is known
+fn write_timestamp(output: &mut Vec<u8>, date_time: &DateTime) {
+    let (date, time) = match *date_time {
+        DateTime::OffsetDateTime { .. } => {
+            let (seconds, nanosecond) = date_time.unix_timestamp().unwrap_or_default();
+            // Timestamps have years from 1 to 9999, which stay in range in UTC
+            match DateTime::from_unix_timestamp(seconds, nanosecond) {
+                Ok(DateTime::OffsetDateTime { date, time, .. }) => (date, Some(time)),
+                _ => unreachable!("Unix timestamps are offset date-times"),
+            }
+        }
+        DateTime::LocalDateTime { date, time } => (date, Some(time)),
+        DateTime::LocalDate(date) => (date, None),
+        DateTime::LocalTime(_) => unreachable!("Local times are not timestamps"),
+    };
+    let mut content = vec![];
+    match date_time {
+        DateTime::OffsetDateTime { offset_minutes, .. } => write_var_int(
+            &mut content,
+            *offset_minutes < 0,
+            offset_minutes.unsigned_abs() as u64,
+        ),
+        // An offset of `-0` means the offset is unknown
+        _ => write_var_int(&mut content, true, 0),
+    }
+    for field in [date.year() as u64, date.month() as u64, date.day() as u64] {
+        write_var_uint(&mut content, field);
+    }
+    if let Some(time) = time {
+        for field in [time.hour(), time.minute(), time.second()] {
+            write_var_uint(&mut content, field as u64);
+        }
+        if time.nanosecond() != 0 {
+            let digits = format!("{:09}", time.nanosecond());
+            let digits = digits.trim_end_matches('0');
+            write_var_int(&mut content, true, digits.len() as u64);
+            write_int(&mut content, false, &uint_bytes(digits.parse().unwrap()));
+        }
+    }
+    write_typed(output, 0x6, &content);
+}
+
+fn write_decimal(output: &mut Vec<u8>, decimal: &Decimal) {
+    let magnitude = digits_to_magnitude(&decimal.coefficient, 10).unwrap_or_default();
+    if magnitude.is_empty() && !decimal.negative && decimal.exponent == 0 {
+        output.push(0x50);
+        return;
+    }
+    let mut content = vec![];
+    write_var_int(
+        &mut content,
+        decimal.exponent < 0,
+        decimal.exponent.unsigned_abs(),
+    );
+    write_int(&mut content, decimal.negative, &magnitude);
+    write_typed(output, 0x5, &content);
+}
+
+/// Writes a type descriptor with the length of the content, followed by the content
+fn write_typed(output: &mut Vec<u8>, type_code: u8, content: &[u8]) {
+    if content.len() < 14 {
+        output.push(type_code << 4 | content.len() as u8);
+    } else {
+        output.push(type_code << 4 | 14);
+        write_var_uint(output, content.len() as u64);
+    }
+    output.extend_from_slice(content);
+}
+
+/// The big-endian bytes of an unsigned integer, without leading zeros
+fn uint_bytes(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(8);
+    bytes[start..].to_vec()
+}
+
+/// Writes a signed integer whose first bit is the sign, zero is written without bytes
+fn write_int(output: &mut Vec<u8>, negative: bool, magnitude: &[u8]) {
+    let start = output.len();
+    if magnitude.first().is_some_and(|first| first & 0x80 != 0)
+        || (negative && magnitude.is_empty())
+    {
+        output.push(0);
+    }
+    output.extend_from_slice(magnitude);
+    if negative {
+        output[start] |= 0x80;
+    }
+}
+
+/// Writes an unsigned integer in groups of 7 bits, the last group has the high bit set
+fn write_var_uint(output: &mut Vec<u8>, value: u64) {
+    let mut groups = vec![(value & 0x7f) as u8 | 0x80];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8);
+        rest >>= 7;
+    }
+    output.extend(groups.iter().rev());
+}
+
+/// Writes a signed integer in groups of 7 bits, the first group has the sign as second bit
+fn write_var_int(output: &mut Vec<u8>, negative: bool, magnitude: u64) {
+    let mut groups = vec![(magnitude & 0x7f) as u8];
+    let mut rest = magnitude >> 7;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8);
+        rest >>= 7;
+    }
+    if groups.last().is_some_and(|first| first & 0x40 != 0) {
+        groups.push(0);
+    }
+    groups[0] |= 0x80;
+    if negative {
+        *groups.last_mut().unwrap() |= 0x40;
+    }
+    output.extend(groups.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IonFormat;
+    use crate::ion_dumper::IonDumper;
+    use crate::ion_loader::IonLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::testing::{emit_events, format_dump, integer_key_document, invalid_documents};
+
+    fn dump_with(dumper: impl FnOnce(&mut Vec<u8>) -> IonDumper, events: &[Event]) -> String {
+        let mut output = vec![];
+        let result = emit_events(dumper(&mut output), events);
+        format_dump(result, output.escape_ascii())
+    }
+
+    fn dump(events: &[Event]) -> String {
+        dump_with(|output| IonDumper::new(output), events)
+    }
+
+    fn dump_binary(events: &[Event]) -> String {
+        dump_with(
+            |output| IonDumper::new(output).with_format(IonFormat::Binary),
+            events,
+        )
+    }
+
+    fn document() -> Vec<Event<'static>> {
+        vec![
+            Event::DocumentStart,
+            Event::tag("order"),
+            Event::MapStart,
+            Event::map_key("id"),
+            Event::integer(-42),
+            Event::map_key("total"),
+            Event::tag("decimal"),
+            Event::string("12.50"),
+            Event::map_key("tags"),
+            Event::ListStart,
+            Event::tag("symbol"),
+            Event::string("new"),
+            Event::tag("symbol"),
+            Event::string("null"),
+            Event::ListEnd,
+            Event::map_key("at"),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05.5+01:00").unwrap()),
+            Event::map_key("field name"),
+            Event::tag("sexp"),
+            Event::ListStart,
+            Event::tag("symbol"),
+            Event::string("+"),
+            Event::number(1.5),
+            Event::ListEnd,
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ]
+    }
+
+    #[test]
+    fn test_text() {
+        let output = dump(&[
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::null(),
+            Event::bool(true),
+            Event::integer(i64::MIN),
+            Event::number(0.1),
+            Event::number(-0.0),
+            Event::number(f64::NAN),
+            Event::number(f64::NEG_INFINITY),
+            Event::string("quote \" tab \t bell \x07"),
+            Event::bytes(&b"hello"[..]),
+            Event::tag("clob"),
+            Event::bytes(&b"a\"\xff"[..]),
+            Event::tag("decimal"),
+            Event::string("1.5E+3"),
+            Event::tag("decimal"),
+            Event::string("-0"),
+            Event::tag("bigint"),
+            Event::string("-0018446744073709551616"),
+            Event::tag("symbol"),
+            Event::string("$10"),
+            Event::tag("symbol"),
+            Event::string("it's"),
+            Event::tag("keyword"),
+            Event::tag("symbol"),
+            Event::string("a"),
+            Event::tag("decimal"),
+            Event::string("not a decimal"),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05").unwrap()),
+            Event::date_time(DateTime::parse("2024-01-02").unwrap()),
+            Event::date_time(DateTime::parse("03:04:05").unwrap()),
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]);
+        expect![[r#"[null, true, -9223372036854775808, 1e-1, -0e0, nan, -inf, \"quote \\\" tab \\t bell \\x07\", {{aGVsbG8=}}, {{\"a\\\"\\xff\"}}, 1.5d+3, -0., -18446744073709551616, \'$10\', \'it\\\'s\', keyword::\'a\', decimal::\"not a decimal\", 2024-01-02T03:04:05-00:00, 2024-01-02, \"03:04:05\"]\n"#]].assert_eq(&output);
+        expect![[r#"order::{id: -42, total: 12.50, tags: [new, \'null\'], at: 2024-01-02T03:04:05.5+01:00, \'field name\': (\'+\' 1.5e0)}\n"#]].assert_eq(&dump(&document()));
+    }
+
+    #[test]
+    fn test_binary() {
+        expect![[r#"\xe0\x01\x00\xea\xee\xb4\x81\x83\xde\xb0\x87\xbe\xad\x85order\x82id\x85total\x84tags\x83new\x84null\x82at\x8afield name\x81+\xee\xab\x81\x8a\xde\xa7\x8b1*\x8cS\xc2\x04\xe2\x8d\xb4q\x0eq\x0f\x90j\xbc\x0f\xe8\x81\x82\x82\x84\x85\xc1\x05\x91\xcbq\x12H?\xf8\x00\x00\x00\x00\x00\x00"#]].assert_eq(&dump_binary(&document()));
+        let output = dump_binary(&[
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::integer(0),
+            Event::integer(200),
+            Event::tag("decimal"),
+            Event::string("0"),
+            Event::tag("decimal"),
+            Event::string("-1.28"),
+            Event::tag("bigint"),
+            Event::string("-18446744073709551616"),
+            Event::date_time(DateTime::parse("2024-01-02").unwrap()),
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]);
+        expect![[r#"\xe0\x01\x00\xea\xbe\x98 !\xc8PS\xc2\x80\x809\x01\x00\x00\x00\x00\x00\x00\x00\x00e\xc0\x0f\xe8\x81\x82"#]].assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut events = document();
+        events.extend([
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::null(),
+            Event::bool(false),
+            Event::integer(i64::MAX),
+            Event::number(1e300),
+            Event::string("x".repeat(300)),
+            Event::string("é\n\u{1}"),
+            Event::bytes(vec![7u8; 20]),
+            Event::tag("clob"),
+            Event::bytes(&b"\x00\x7f\x80\"\\"[..]),
+            Event::tag("decimal"),
+            Event::string("-0.00"),
+            Event::tag("decimal"),
+            Event::string("1.5E+300"),
+            Event::tag("bigint"),
+            Event::string("-123456789012345678901234567890"),
+            Event::tag("a"),
+            Event::tag("b c"),
+            Event::tag("symbol"),
+            Event::string("$10"),
+            Event::date_time(DateTime::parse("1999-12-31T23:59:59.123456789-05:30").unwrap()),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05").unwrap()),
+            Event::date_time(DateTime::parse("2024-01-02").unwrap()),
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]);
+        for format in [IonFormat::Text, IonFormat::Binary] {
+            let mut output = vec![];
+            let mut dumper = IonDumper::new(&mut output).with_format(format);
+            for event in &events {
+                dumper.emit(event).unwrap();
+            }
+            drop(dumper);
+            let loaded: Vec<Event> = IonLoader::new(&output)
+                .map(|event| event.unwrap().into_owned())
+                .collect();
+            let expected: Vec<Event> = events.iter().cloned().map(Event::into_owned).collect();
+            assert_eq!(loaded, expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = String::new();
+        for events in invalid_documents() {
+            output.push_str(&dump(&events));
+            output.push('\n');
+        }
+        output.push_str(&dump_with(
+            |output| IonDumper::new(output).with_key_policy(KeyPolicy::Reject),
+            &integer_key_document(),
+        ));
+        expect![[r#"
+            Error: Ion keys cannot be composite, but found List([])
+            Error: Document is empty
+            Error: Ion keys must be strings, but found Scalar(Integer(1))"#]]
+        .assert_eq(&output);
+    }
+}
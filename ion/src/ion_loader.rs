@@ -0,0 +1,1633 @@
+use crate::decimal::{Decimal, digits_to_magnitude, magnitude_to_digits};
+use crate::{
+    BINARY_VERSION_MARKER, CLOB_TAG, DECIMAL_TAG, SEXP_TAG, SYMBOL_TABLE_ANNOTATION, SYMBOL_TAG,
+    SYSTEM_SYMBOLS,
+};
+use loadum::LoadumString;
+use loadum::base64;
+use loadum::datetime::{Date, DateTime, Time};
+use loadum::depth::Depth;
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::loader::Loader;
+use loadum::node::Node;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+
+/// Types of typed nulls like `null.string`
+const NULL_TYPES: [&str; 13] = [
+    "null",
+    "bool",
+    "int",
+    "float",
+    "decimal",
+    "timestamp",
+    "symbol",
+    "string",
+    "clob",
+    "blob",
+    "list",
+    "sexp",
+    "struct",
+];
+
+/// Characters of the operator symbols of s-expressions, like `+` or `<=`
+const OPERATOR_CHARS: &[u8] = b"!#%&*+-./;<=>?@^`|~";
+
+/// Loads Ion text, or binary Ion starting with the binary version marker
+///
+/// Each top level value is loaded as a separate document, local symbol tables and version markers
+/// only affect how symbols are resolved. Symbols without text are loaded as `$` followed by their
+/// ID. The input is parsed before the first event is returned.
+pub struct IonLoader<'source> {
+    source: &'source [u8],
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> IonLoader<'source> {
+    pub fn new(source: &'source [u8]) -> IonLoader<'source> {
+        IonLoader {
+            source,
+            events: None,
+        }
+    }
+
+    fn parse(&self) -> LoadumResult<Vec<Event<'source>>> {
+        if self.source.starts_with(BINARY_VERSION_MARKER) {
+            return BinaryParser {
+                bytes: self.source,
+                position: 0,
+                symbols: SymbolTable::default(),
+                depth: Depth::default(),
+            }
+            .parse_documents();
+        }
+        let Ok(source) = std::str::from_utf8(self.source) else {
+            bail!("Ion text must be UTF-8");
+        };
+        TextParser {
+            source,
+            bytes: source.as_bytes(),
+            position: 0,
+            symbols: SymbolTable::default(),
+            depth: Depth::default(),
+        }
+        .parse_documents()
+    }
+}
+
+impl<'source> Loader<'source> for IonLoader<'source> {}
+
+impl<'source> Iterator for IonLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            match self.parse() {
+                Ok(events) => self.events = Some(events.into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+/// Symbols by ID, starting with symbol zero and the system symbols
+struct SymbolTable {
+    /// The text of each symbol, `None` for symbol zero and symbols declared without text
+    symbols: Vec<Option<LoadumString>>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        let system_symbols = SYSTEM_SYMBOLS.iter().map(|text| Some((*text).into()));
+        SymbolTable {
+            symbols: std::iter::once(None).chain(system_symbols).collect(),
+        }
+    }
+}
+
+impl SymbolTable {
+    fn text(&self, id: u64) -> LoadumResult<LoadumString> {
+        match usize::try_from(id).ok().and_then(|id| self.symbols.get(id)) {
+            Some(Some(text)) => Ok(text.clone()),
+            Some(None) => Ok(format!("${}", id).into()),
+            None => bail!("Undefined symbol ID {}", id),
+        }
+    }
+
+    /// Defines the symbols of a local symbol table, which replace or extend the current ones
+    fn load(&mut self, table: &Node) -> LoadumResult<()> {
+        let Node::Map(fields) = table.untagged() else {
+            return Ok(());
+        };
+        let mut append = false;
+        let mut symbols = vec![];
+        for (name, value) in fields {
+            match (name.as_str(), value.untagged()) {
+                (Some("imports"), Node::Scalar(import)) => {
+                    append = import.as_str() == Some(SYMBOL_TABLE_ANNOTATION);
+                }
+                (Some("imports"), Node::List(imports)) if !imports.is_empty() => {
+                    bail!("Imports of shared symbol tables are not supported");
+                }
+                (Some("symbols"), Node::List(items)) => {
+                    symbols.extend(items.iter().map(|item| item.as_str().map(Into::into)));
+                }
+                _ => {}
+            }
+        }
+        if !append {
+            *self = SymbolTable::default();
+        }
+        self.symbols.extend(symbols);
+        Ok(())
+    }
+}
+
+/// Appends a top level value as a document, unless it is a local symbol table
+fn push_document<'source>(
+    node: Node<'source>,
+    symbols: &mut SymbolTable,
+    events: &mut Vec<Event<'source>>,
+) -> LoadumResult<()> {
+    match &node {
+        Node::Tagged(annotation, table)
+            if annotation.as_str() == SYMBOL_TABLE_ANNOTATION
+                && matches!(table.untagged(), Node::Map(_)) =>
+        {
+            symbols.load(table)
+        }
+        _ => {
+            events.push(Event::DocumentStart);
+            node.to_events(events);
+            events.push(Event::DocumentEnd);
+            Ok(())
+        }
+    }
+}
+
+fn tagged<'source>(tag: &str, node: Node<'source>) -> Node<'source> {
+    Node::Tagged(tag.into(), Box::new(node))
+}
+
+fn annotated<'source>(annotations: Vec<LoadumString>, node: Node<'source>) -> Node<'source> {
+    annotations
+        .into_iter()
+        .rev()
+        .fold(node, |node, annotation| {
+            Node::Tagged(annotation, Box::new(node))
+        })
+}
+
+/// An integer from its big-endian magnitude, integers beyond 64 bits are tagged strings
+fn integer_node<'source>(negative: bool, magnitude: &[u8]) -> Node<'source> {
+    let start = magnitude.iter().position(|byte| *byte != 0);
+    let magnitude = &magnitude[start.unwrap_or(magnitude.len())..];
+    if magnitude.len() <= 8 {
+        let mut bytes = [0; 8];
+        bytes[8 - magnitude.len()..].copy_from_slice(magnitude);
+        let unsigned = u64::from_be_bytes(bytes);
+        let integer = if negative {
+            0i64.checked_sub_unsigned(unsigned)
+        } else {
+            i64::try_from(unsigned).ok()
+        };
+        if let Some(integer) = integer {
+            return Node::Scalar(Value::Integer(integer));
+        }
+    }
+    let sign = if negative { "-" } else { "" };
+    let digits = format!("{}{}", sign, magnitude_to_digits(magnitude));
+    tagged(BIGINT_TAG, Node::Scalar(Value::string(digits)))
+}
+
+fn decimal_node<'source>(decimal: &Decimal) -> Node<'source> {
+    tagged(
+        DECIMAL_TAG,
+        Node::Scalar(Value::string(decimal.to_string())),
+    )
+}
+
+/// Combines the fields of a timestamp, the precision decides the kind of date-time
+fn timestamp(date: Date, time: Option<Time>, offset_minutes: Option<i16>) -> DateTime {
+    match (time, offset_minutes) {
+        (None, _) => DateTime::LocalDate(date),
+        // An offset of `-00:00` means the offset is unknown
+        (Some(time), None) => DateTime::LocalDateTime { date, time },
+        (Some(time), Some(offset_minutes)) => DateTime::OffsetDateTime {
+            date,
+            time,
+            offset_minutes,
+        },
+    }
+}
+
+/// Parses a timestamp of the text format, from `2007T` to `2007-02-23T12:14:33.079-08:00`
+fn parse_timestamp(text: &str) -> LoadumResult<DateTime> {
+    let invalid = || format_err!("Invalid timestamp '{}'", text);
+    let digits = |start: usize, count: usize| {
+        text.get(start..start + count)
+            .filter(|digits| digits.bytes().all(|c| c.is_ascii_digit()))
+            .and_then(|digits| digits.parse::<u32>().ok())
+            .ok_or_else(invalid)
+    };
+    let expect = |index: usize, expected: u8| match text.as_bytes().get(index) {
+        Some(c) if *c == expected => Ok(()),
+        _ => Err(invalid()),
+    };
+    let year = digits(0, 4)?;
+    if year == 0 {
+        return Err(invalid());
+    }
+    let date = |month, day| Date::new(year as i32, month as u8, day as u8);
+    if &text[4..] == "T" {
+        return Ok(DateTime::LocalDate(date(1, 1)?));
+    }
+    expect(4, b'-')?;
+    let month = digits(5, 2)?;
+    if text.get(7..) == Some("T") {
+        return Ok(DateTime::LocalDate(date(month, 1)?));
+    }
+    expect(7, b'-')?;
+    let date = date(month, digits(8, 2)?)?;
+    if matches!(text.get(10..), Some("" | "T")) {
+        return Ok(DateTime::LocalDate(date));
+    }
+    expect(10, b'T')?;
+    let hour = digits(11, 2)?;
+    expect(13, b':')?;
+    let minute = digits(14, 2)?;
+    let mut position = 16;
+    let mut second = 0;
+    let mut nanosecond = 0;
+    if text.as_bytes().get(position) == Some(&b':') {
+        second = digits(17, 2)?;
+        position = 19;
+        if text.as_bytes().get(position) == Some(&b'.') {
+            let fraction = &text[position + 1..];
+            let length = fraction
+                .bytes()
+                .position(|c| !c.is_ascii_digit())
+                .unwrap_or(fraction.len());
+            if length == 0 {
+                return Err(invalid());
+            }
+            // Digits beyond nanosecond precision are truncated
+            let digits = format!("{:0<9}", &fraction[..length.min(9)]);
+            nanosecond = digits.parse().map_err(|_| invalid())?;
+            position += 1 + length;
+        }
+    }
+    let time = Time::new(hour as u8, minute as u8, second as u8, nanosecond)?;
+    let offset_minutes = match &text[position..] {
+        "Z" => Some(0),
+        "-00:00" => None,
+        offset => {
+            let sign = match offset.as_bytes().first() {
+                Some(b'+') => 1,
+                Some(b'-') => -1,
+                _ => return Err(invalid()),
+            };
+            let hours = digits(position + 1, 2)?;
+            expect(position + 3, b':')?;
+            let minutes = digits(position + 4, 2)?;
+            if offset.len() != 6 || hours > 23 || minutes > 59 {
+                return Err(invalid());
+            }
+            Some(sign * (hours * 60 + minutes) as i16)
+        }
+    };
+    Ok(timestamp(date, Some(time), offset_minutes))
+}
+
+struct TextParser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    symbols: SymbolTable,
+    depth: Depth,
+}
+
+impl<'source> TextParser<'source> {
+    fn parse_documents(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        let mut events = vec![];
+        loop {
+            self.skip_blank()?;
+            if self.peek().is_none() {
+                return Ok(events);
+            }
+            if self.parse_version_marker()? {
+                continue;
+            }
+            let start = self.position;
+            let node = self.parse_value(false)?;
+            push_document(node, &mut self.symbols, &mut events)
+                .map_err(|error| self.error_at(start, error))?;
+        }
+    }
+
+    /// Parses an unannotated `$ion_1_0` symbol at the top level, which resets the symbols
+    fn parse_version_marker(&mut self) -> LoadumResult<bool> {
+        let start = self.position;
+        let identifier = self.read_identifier();
+        let is_marker = identifier.starts_with("$ion_")
+            && identifier[5..]
+                .split_once('_')
+                .is_some_and(|(major, minor)| {
+                    [major, minor]
+                        .iter()
+                        .all(|part| !part.is_empty() && part.bytes().all(|c| c.is_ascii_digit()))
+                });
+        self.skip_blank()?;
+        if !is_marker || self.rest().starts_with(b"::") {
+            self.position = start;
+            return Ok(false);
+        }
+        if identifier != "$ion_1_0" {
+            return Err(self.error_at(start, format!("Unsupported Ion version {}", identifier)));
+        }
+        self.symbols = SymbolTable::default();
+        Ok(true)
+    }
+
+    fn parse_value(&mut self, in_sexp: bool) -> LoadumResult<Node<'source>> {
+        let mut annotations = vec![];
+        loop {
+            self.skip_blank()?;
+            let start = self.position;
+            let annotation = self.parse_symbol()?;
+            self.skip_blank()?;
+            match annotation {
+                Some(annotation) if self.rest().starts_with(b"::") => {
+                    self.position += 2;
+                    annotations.push(annotation.as_str().unwrap_or_default().into());
+                }
+                _ => {
+                    self.position = start;
+                    break;
+                }
+            }
+        }
+        let node = self.parse_unannotated(in_sexp)?;
+        Ok(annotated(annotations, node))
+    }
+
+    fn parse_unannotated(&mut self, in_sexp: bool) -> LoadumResult<Node<'source>> {
+        let rest = self.rest();
+        let value = match rest {
+            [] => return Err(self.error("Expected value")),
+            [b'[', ..] => return self.parse_container(Self::parse_list),
+            [b'(', ..] => return self.parse_container(Self::parse_sexp),
+            [b'{', b'{', ..] => return self.parse_lob(),
+            [b'{', ..] => return self.parse_container(Self::parse_struct),
+            [b'"', ..] => self.parse_string(b'"')?,
+            [b'\'', b'\'', b'\'', ..] => self.parse_long_strings()?,
+            [b'\'', ..] => {
+                let symbol = self.parse_string(b'\'')?;
+                return Ok(tagged(SYMBOL_TAG, Node::Scalar(symbol)));
+            }
+            [b'0'..=b'9', ..] | [b'-', b'0'..=b'9', ..] => return self.parse_number(),
+            [b'+' | b'-', b'i', b'n', b'f', ..] if is_stop(rest.get(4).copied()) => {
+                return self.parse_number();
+            }
+            [c, ..] if is_identifier_start(*c) => return self.parse_keyword_or_symbol(),
+            [c, ..] if in_sexp && OPERATOR_CHARS.contains(c) => {
+                let start = self.position;
+                while self.peek().is_some_and(|c| OPERATOR_CHARS.contains(&c))
+                    && !self.rest().starts_with(b"//")
+                    && !self.rest().starts_with(b"/*")
+                {
+                    self.position += 1;
+                }
+                let operator = Value::BorrowedString(&self.source[start..self.position]);
+                return Ok(tagged(SYMBOL_TAG, Node::Scalar(operator)));
+            }
+            _ => {
+                let c = self.source[self.position..]
+                    .chars()
+                    .next()
+                    .unwrap_or_default();
+                return Err(self.error(format!("Unexpected '{}'", c.escape_default())));
+            }
+        };
+        Ok(Node::Scalar(value))
+    }
+
+    /// Parses a symbol that may be an annotation: an identifier other than a keyword, a quoted
+    /// symbol or a symbol ID
+    fn parse_symbol(&mut self) -> LoadumResult<Option<Value<'source>>> {
+        match self.rest() {
+            [b'\'', b'\'', b'\'', ..] => Ok(None),
+            [b'\'', ..] => self.parse_string(b'\'').map(Some),
+            [c, ..] if is_identifier_start(*c) => {
+                let start = self.position;
+                let identifier = self.read_identifier();
+                if matches!(identifier, "null" | "true" | "false" | "nan") {
+                    return Ok(None);
+                }
+                self.symbol_text(identifier, start).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_keyword_or_symbol(&mut self) -> LoadumResult<Node<'source>> {
+        let start = self.position;
+        let identifier = self.read_identifier();
+        let value = match identifier {
+            "null" if self.peek() == Some(b'.') => {
+                self.position += 1;
+                let null_type = self.read_identifier();
+                if !NULL_TYPES.contains(&null_type) {
+                    let message = format!("Invalid typed null 'null.{}'", null_type);
+                    return Err(self.error_at(start, message));
+                }
+                Value::Null
+            }
+            "null" => Value::Null,
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            "nan" => Value::Number(f64::NAN),
+            _ => {
+                let symbol = self.symbol_text(identifier, start)?;
+                return Ok(tagged(SYMBOL_TAG, Node::Scalar(symbol)));
+            }
+        };
+        Ok(Node::Scalar(value))
+    }
+
+    /// Resolves symbol IDs like `$10`, other identifiers are their own text
+    fn symbol_text(&self, identifier: &'source str, start: usize) -> LoadumResult<Value<'source>> {
+        let id = identifier
+            .strip_prefix('$')
+            .filter(|id| !id.is_empty() && id.bytes().all(|c| c.is_ascii_digit()));
+        let Some(id) = id else {
+            return Ok(Value::BorrowedString(identifier));
+        };
+        let text = id
+            .parse()
+            .map_err(LoadumError::from)
+            .and_then(|id| self.symbols.text(id))
+            .map_err(|error| self.error_at(start, error))?;
+        Ok(Value::String(text))
+    }
+
+    /// Parses integers, floats, decimals and timestamps
+    fn parse_number(&mut self) -> LoadumResult<Node<'source>> {
+        let start = self.position;
+        let length = self
+            .rest()
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || b"_.+-:".contains(c)))
+            .unwrap_or(self.rest().len());
+        self.position += length;
+        let token = &self.source[start..self.position];
+        let invalid = || self.error_at(start, format!("Invalid number '{}'", token));
+        if !is_stop(self.peek()) {
+            return Err(invalid());
+        }
+        let is_timestamp = token.len() > 4
+            && token[..4].bytes().all(|c| c.is_ascii_digit())
+            && matches!(token.as_bytes()[4], b'-' | b'T');
+        if is_timestamp {
+            let date_time = parse_timestamp(token).map_err(|error| self.error_at(start, error))?;
+            return Ok(Node::Scalar(Value::DateTime(date_time)));
+        }
+        match token {
+            "+inf" => return Ok(Node::Scalar(Value::Number(f64::INFINITY))),
+            "-inf" => return Ok(Node::Scalar(Value::Number(f64::NEG_INFINITY))),
+            _ if token.starts_with('+') => return Err(invalid()),
+            _ => {}
+        }
+        let (negative, unsigned) = match token.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, token),
+        };
+        // Underscores may only separate digits
+        let bytes = unsigned.as_bytes();
+        let separates_digits = |index: usize| {
+            index > 0
+                && bytes[index - 1].is_ascii_hexdigit()
+                && bytes.get(index + 1).is_some_and(u8::is_ascii_hexdigit)
+        };
+        let underscores = bytes.iter().enumerate().filter(|(_, c)| **c == b'_');
+        if !underscores
+            .into_iter()
+            .all(|(index, _)| separates_digits(index))
+        {
+            return Err(invalid());
+        }
+        let digits = unsigned.replace('_', "");
+        let radix_digits = match digits.get(..2) {
+            Some("0x" | "0X") => Some((16, &digits[2..])),
+            Some("0b" | "0B") => Some((2, &digits[2..])),
+            _ => None,
+        };
+        if let Some((radix, digits)) = radix_digits {
+            return match digits_to_magnitude(digits, radix) {
+                Some(magnitude) if !digits.is_empty() => Ok(integer_node(negative, &magnitude)),
+                _ => Err(invalid()),
+            };
+        }
+        let integer_digits = digits.split(['.', 'e', 'E', 'd', 'D']).next().unwrap_or("");
+        if integer_digits.len() > 1 && integer_digits.starts_with('0') {
+            return Err(invalid());
+        }
+        if digits.contains(['e', 'E']) {
+            let decimal = Decimal::parse(&digits).map_err(|_| invalid())?;
+            let text = format!("{}e{}", decimal.coefficient, decimal.exponent);
+            let float: f64 = text.parse().map_err(|_| invalid())?;
+            return Ok(Node::Scalar(Value::Number(if negative {
+                -float
+            } else {
+                float
+            })));
+        }
+        if digits.contains(['.', 'd', 'D']) {
+            let mut decimal = Decimal::parse(&digits).map_err(|_| invalid())?;
+            decimal.negative = negative;
+            return Ok(decimal_node(&decimal));
+        }
+        match digits_to_magnitude(&digits, 10) {
+            Some(magnitude) => Ok(integer_node(negative, &magnitude)),
+            None => Err(invalid()),
+        }
+    }
+
+    /// Parses a string or quoted symbol, which cannot span lines
+    fn parse_string(&mut self, quote: u8) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        let mut index = start + 1;
+        loop {
+            match self.bytes.get(index) {
+                None | Some(b'\n' | b'\r') => {
+                    return Err(self.error_at(start, "Unterminated string"));
+                }
+                Some(b'\\') => index += 2,
+                Some(c) if *c == quote => break,
+                Some(_) => index += 1,
+            }
+        }
+        self.position = index + 1;
+        let content = &self.source[start + 1..index];
+        if !content.contains('\\') {
+            return Ok(Value::BorrowedString(content));
+        }
+        let unescaped = unescape(content).map_err(|error| self.error_at(start, error))?;
+        Ok(Value::string(unescaped))
+    }
+
+    /// Parses adjacent long strings like `'''a''' '''b'''`, which are concatenated
+    fn parse_long_strings(&mut self) -> LoadumResult<Value<'source>> {
+        let mut parts = vec![];
+        loop {
+            let start = self.position;
+            let mut index = start + 3;
+            loop {
+                match &self.bytes[index.min(self.bytes.len())..] {
+                    [] => return Err(self.error_at(start, "Unterminated long string")),
+                    [b'\\', ..] => index += 2,
+                    [b'\'', b'\'', b'\'', ..] => break,
+                    _ => index += 1,
+                }
+            }
+            parts.push((start, &self.source[start + 3..index]));
+            self.position = index + 3;
+            let end = self.position;
+            self.skip_blank()?;
+            if !self.rest().starts_with(b"'''") {
+                self.position = end;
+                break;
+            }
+        }
+        match parts.as_slice() {
+            [(_, content)] if !content.contains('\\') => {
+                return Ok(Value::BorrowedString(content));
+            }
+            _ => {}
+        }
+        let mut output = String::new();
+        for (start, content) in parts {
+            let unescaped = unescape(content).map_err(|error| self.error_at(start, error))?;
+            output.push_str(&unescaped);
+        }
+        Ok(Value::string(output))
+    }
+
+    /// Parses a blob `{{aGVsbG8=}}` or a clob `{{"hello"}}`
+    fn parse_lob(&mut self) -> LoadumResult<Node<'source>> {
+        let start = self.position;
+        self.position += 2;
+        self.skip_whitespace();
+        let node = if matches!(self.peek(), Some(b'"' | b'\'')) {
+            let text_start = self.position;
+            let text = match self.rest() {
+                [b'\'', b'\'', b'\'', ..] => self.parse_long_strings()?,
+                [b'"', ..] => self.parse_string(b'"')?,
+                _ => return Err(self.error("Expected a string in the clob")),
+            };
+            // Clobs contain ASCII text, other bytes must be escaped
+            if !self.source[text_start..self.position].is_ascii() {
+                return Err(self.error_at(text_start, "Clobs may only contain ASCII characters"));
+            }
+            let bytes: Option<Vec<u8>> = text
+                .as_str()
+                .unwrap_or_default()
+                .chars()
+                .map(|c| u8::try_from(c).ok())
+                .collect();
+            let Some(bytes) = bytes else {
+                return Err(self.error_at(start, "Clob escapes may only encode bytes"));
+            };
+            tagged(CLOB_TAG, Node::Scalar(Value::bytes(bytes)))
+        } else {
+            let length = self
+                .rest()
+                .iter()
+                .position(|c| *c == b'}')
+                .unwrap_or(self.rest().len());
+            let text = &self.source[self.position..self.position + length];
+            self.position += length;
+            let bytes = base64::decode(text).map_err(|error| self.error_at(start, error))?;
+            Node::Scalar(Value::bytes(bytes))
+        };
+        self.skip_whitespace();
+        if !self.rest().starts_with(b"}}") {
+            return Err(self.error("Expected '}}'"));
+        }
+        self.position += 2;
+        Ok(node)
+    }
+
+    /// Parses a list, s-expression or struct, limiting their nesting
+    fn parse_container(
+        &mut self,
+        parse: fn(&mut Self) -> LoadumResult<Node<'source>>,
+    ) -> LoadumResult<Node<'source>> {
+        self.depth.enter().map_err(|error| self.error(error))?;
+        let node = parse(self)?;
+        self.depth.exit();
+        Ok(node)
+    }
+
+    fn parse_list(&mut self) -> LoadumResult<Node<'source>> {
+        let start = self.position;
+        self.position += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_blank()?;
+            match self.peek() {
+                Some(b']') => break,
+                None => return Err(self.error_at(start, "Unterminated list")),
+                Some(_) => items.push(self.parse_value(false)?),
+            }
+            self.skip_blank()?;
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {}
+                None => return Err(self.error_at(start, "Unterminated list")),
+                Some(_) => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+        self.position += 1;
+        Ok(Node::List(items))
+    }
+
+    fn parse_sexp(&mut self) -> LoadumResult<Node<'source>> {
+        let start = self.position;
+        self.position += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_blank()?;
+            match self.peek() {
+                Some(b')') => break,
+                None => return Err(self.error_at(start, "Unterminated s-expression")),
+                Some(_) => items.push(self.parse_value(true)?),
+            }
+        }
+        self.position += 1;
+        Ok(tagged(SEXP_TAG, Node::List(items)))
+    }
+
+    fn parse_struct(&mut self) -> LoadumResult<Node<'source>> {
+        let start = self.position;
+        self.position += 1;
+        let mut entries = vec![];
+        loop {
+            self.skip_blank()?;
+            let name = match self.rest() {
+                [b'}', ..] => break,
+                [] => return Err(self.error_at(start, "Unterminated struct")),
+                [b'"', ..] => self.parse_string(b'"')?,
+                [b'\'', b'\'', b'\'', ..] => self.parse_long_strings()?,
+                [b'\'', ..] => self.parse_string(b'\'')?,
+                [c, ..] if is_identifier_start(*c) => {
+                    let start = self.position;
+                    let identifier = self.read_identifier();
+                    self.symbol_text(identifier, start)?
+                }
+                _ => return Err(self.error("Expected a field name")),
+            };
+            self.skip_blank()?;
+            if !self.rest().starts_with(b":") || self.rest().starts_with(b"::") {
+                return Err(self.error("Expected ':' after the field name"));
+            }
+            self.position += 1;
+            entries.push((Node::Scalar(name), self.parse_value(false)?));
+            self.skip_blank()?;
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {}
+                None => return Err(self.error_at(start, "Unterminated struct")),
+                Some(_) => return Err(self.error("Expected ',' or '}'")),
+            }
+        }
+        self.position += 1;
+        Ok(Node::Map(entries))
+    }
+
+    fn read_identifier(&mut self) -> &'source str {
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        let length = rest
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'$')))
+            .unwrap_or(rest.len());
+        self.position += length;
+        &self.source[start..start + length]
+    }
+
+    fn rest(&self) -> &'source [u8] {
+        &self.bytes[self.position..]
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace and comments
+    fn skip_blank(&mut self) -> LoadumResult<()> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with(b"//") {
+                self.position += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+            } else if rest.starts_with(b"/*") {
+                let Some(length) = rest.windows(2).skip(2).position(|end| end == b"*/") else {
+                    return Err(self.error("Unterminated comment"));
+                };
+                self.position += length + 4;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+fn is_whitespace(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'\x0b' | b'\x0c')
+}
+
+fn is_identifier_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, b'_' | b'$')
+}
+
+/// Whether a number may end before the character
+fn is_stop(c: Option<u8>) -> bool {
+    match c {
+        None => true,
+        Some(c) => is_whitespace(c) || b",])}\"'{[(/".contains(&c),
+    }
+}
+
+fn unescape(content: &str) -> LoadumResult<String> {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some('a') => '\u{0007}',
+            Some('b') => '\u{0008}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{000c}',
+            Some('r') => '\r',
+            Some('v') => '\u{000b}',
+            Some('0') => '\0',
+            Some(c @ ('?' | '\'' | '"' | '/' | '\\')) => c,
+            // Escaped line breaks join lines
+            Some('\n') => continue,
+            Some('\r') => {
+                if chars.clone().next() == Some('\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            Some(escape @ ('x' | 'u' | 'U')) => {
+                let length = match escape {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let digits: String = chars.by_ref().take(length).collect();
+                let c = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == length && !digits.starts_with('+'))
+                    .and_then(char::from_u32);
+                let Some(c) = c else {
+                    bail!("Invalid escape sequence '\\{}{}'", escape, digits);
+                };
+                c
+            }
+            Some(c) => bail!("Invalid escape sequence '\\{}'", c.escape_default()),
+            None => bail!("Incomplete escape sequence"),
+        };
+        output.push(c);
+    }
+    Ok(output)
+}
+
+struct BinaryParser<'source> {
+    bytes: &'source [u8],
+    position: usize,
+    symbols: SymbolTable,
+    depth: Depth,
+}
+
+impl<'source> BinaryParser<'source> {
+    fn parse_documents(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        let mut events = vec![];
+        while self.position < self.bytes.len() {
+            let rest = &self.bytes[self.position..];
+            if rest.starts_with(BINARY_VERSION_MARKER) {
+                self.position += BINARY_VERSION_MARKER.len();
+                self.symbols = SymbolTable::default();
+                continue;
+            }
+            if rest[0] == 0xe0 {
+                return Err(self.error("Unsupported Ion version marker"));
+            }
+            let start = self.position;
+            if let Some(node) = self.parse_value(self.bytes.len())? {
+                push_document(node, &mut self.symbols, &mut events)
+                    .map_err(|error| self.error_at(start, error))?;
+            }
+        }
+        Ok(events)
+    }
+
+    /// Parses a value that ends before `end`, returns `None` for padding
+    fn parse_value(&mut self, end: usize) -> LoadumResult<Option<Node<'source>>> {
+        let start = self.position;
+        let descriptor = self.read_u8(end)?;
+        let (type_code, length_code) = (descriptor >> 4, descriptor & 0x0f);
+        match (type_code, length_code) {
+            (0xe, 0..=2 | 0x0f) | (0xf, _) | (0x3, 0) => {
+                let message = format!("Invalid type descriptor 0x{:02x}", descriptor);
+                return Err(self.error_at(start, message));
+            }
+            (_, 0x0f) => return Ok(Some(Node::Scalar(Value::Null))),
+            (0x1, 0) => return Ok(Some(Node::Scalar(Value::Boolean(false)))),
+            (0x1, 1) => return Ok(Some(Node::Scalar(Value::Boolean(true)))),
+            (0x1, _) => {
+                let message = format!("Invalid type descriptor 0x{:02x}", descriptor);
+                return Err(self.error_at(start, message));
+            }
+            _ => {}
+        }
+        let length = match (type_code, length_code) {
+            (_, 0x0e) | (0xd, 1) => self.read_var_uint(end)?,
+            (_, length) => length as u64,
+        };
+        let content_end = usize::try_from(length)
+            .ok()
+            .and_then(|length| self.position.checked_add(length))
+            .filter(|content_end| *content_end <= end);
+        let Some(content_end) = content_end else {
+            return Err(self.error_at(start, "Value is longer than its container"));
+        };
+        let content = &self.bytes[self.position..content_end];
+        let is_container = matches!(type_code, 0xb..=0xd);
+        if is_container {
+            self.depth
+                .enter()
+                .map_err(|error| self.error_at(start, error))?;
+        }
+        let node = match type_code {
+            0x0 => {
+                self.position = content_end;
+                return Ok(None);
+            }
+            0x2 | 0x3 => integer_node(type_code == 0x3, content),
+            0x4 => {
+                let float = match *content {
+                    [] => 0.0,
+                    [a, b, c, d] => f32::from_be_bytes([a, b, c, d]) as f64,
+                    [a, b, c, d, e, f, g, h] => f64::from_be_bytes([a, b, c, d, e, f, g, h]),
+                    _ => {
+                        let message = format!("Invalid float length {}", content.len());
+                        return Err(self.error_at(start, message));
+                    }
+                };
+                Node::Scalar(Value::Number(float))
+            }
+            0x5 => decimal_node(&self.parse_decimal(content_end)?),
+            0x6 => {
+                let timestamp = self
+                    .parse_timestamp(content_end)
+                    .map_err(|error| self.error_at(start, error))?;
+                Node::Scalar(Value::DateTime(timestamp))
+            }
+            0x7 => {
+                let text = match uint(content) {
+                    Some(id) => self.symbols.text(id),
+                    None => Err(format_err!("Symbol ID is too large")),
+                };
+                let text = text.map_err(|error| self.error_at(start, error))?;
+                tagged(SYMBOL_TAG, Node::Scalar(Value::String(text)))
+            }
+            0x8 => match std::str::from_utf8(content) {
+                Ok(text) => Node::Scalar(Value::BorrowedString(text)),
+                Err(_) => return Err(self.error_at(start, "Invalid UTF-8 in string")),
+            },
+            0x9 => tagged(CLOB_TAG, Node::Scalar(Value::bytes(content))),
+            0xa => Node::Scalar(Value::bytes(content)),
+            0xb | 0xc => {
+                let mut items = vec![];
+                while self.position < content_end {
+                    items.extend(self.parse_value(content_end)?);
+                }
+                match type_code {
+                    0xb => Node::List(items),
+                    _ => tagged(SEXP_TAG, Node::List(items)),
+                }
+            }
+            0xd => {
+                let mut entries = vec![];
+                while self.position < content_end {
+                    let field_start = self.position;
+                    let id = self.read_var_uint(content_end)?;
+                    let name = self
+                        .symbols
+                        .text(id)
+                        .map_err(|error| self.error_at(field_start, error))?;
+                    // Padding in structs has a field name, which is ignored
+                    if let Some(value) = self.parse_value(content_end)? {
+                        entries.push((Node::Scalar(Value::String(name)), value));
+                    }
+                }
+                Node::Map(entries)
+            }
+            _ => self.parse_annotation_wrapper(start, content_end)?,
+        };
+        if is_container {
+            self.depth.exit();
+        }
+        self.position = content_end;
+        Ok(Some(node))
+    }
+
+    fn parse_annotation_wrapper(
+        &mut self,
+        start: usize,
+        end: usize,
+    ) -> LoadumResult<Node<'source>> {
+        let length = self.read_var_uint(end)?;
+        let annotations_end = usize::try_from(length)
+            .ok()
+            .and_then(|length| self.position.checked_add(length))
+            .filter(|annotations_end| length > 0 && *annotations_end < end);
+        let Some(annotations_end) = annotations_end else {
+            return Err(self.error_at(start, "Invalid length of annotations"));
+        };
+        let mut annotations = vec![];
+        while self.position < annotations_end {
+            let annotation_start = self.position;
+            let id = self.read_var_uint(annotations_end)?;
+            let annotation = self
+                .symbols
+                .text(id)
+                .map_err(|error| self.error_at(annotation_start, error))?;
+            annotations.push(annotation);
+        }
+        if self.bytes[self.position] >> 4 == 0xe {
+            return Err(self.error("Annotation wrappers cannot be nested"));
+        }
+        let Some(value) = self.parse_value(end)? else {
+            return Err(self.error_at(start, "Padding cannot be annotated"));
+        };
+        if self.position != end {
+            return Err(self.error_at(start, "Annotation wrapper contains more than one value"));
+        }
+        Ok(annotated(annotations, value))
+    }
+
+    fn parse_decimal(&mut self, end: usize) -> LoadumResult<Decimal> {
+        let start = self.position;
+        if start == end {
+            return Decimal::parse("0");
+        }
+        let (negative, exponent) = self.read_var_int(end)?;
+        let Some(exponent) = signed(negative, exponent) else {
+            return Err(self.error_at(start, "Decimal exponent is too large"));
+        };
+        let (negative, magnitude) = sign_and_magnitude(&self.bytes[self.position..end]);
+        Ok(Decimal {
+            negative,
+            coefficient: magnitude_to_digits(&magnitude),
+            exponent,
+        })
+    }
+
+    /// Parses the fields of a timestamp, which are in UTC unless the offset is unknown
+    fn parse_timestamp(&mut self, end: usize) -> LoadumResult<DateTime> {
+        let (negative, offset) = self.read_var_int(end)?;
+        let offset_minutes = match (negative, offset) {
+            (true, 0) => None,
+            (_, 0..1440) => Some(signed(negative, offset).unwrap_or_default() as i16),
+            _ => bail!("Invalid offset of {} minutes", offset),
+        };
+        let year = self.read_var_uint(end)?;
+        let mut fields = [1, 1, 0, 0, 0];
+        let mut precision = 0;
+        while self.position < end && precision < fields.len() {
+            fields[precision] = self.read_var_uint(end)?;
+            precision += 1;
+        }
+        let [month, day, hour, minute, second] = fields.map(|field| field.min(255) as u8);
+        let mut nanosecond = 0;
+        if self.position < end {
+            let (negative, exponent) = self.read_var_int(end)?;
+            let (_, coefficient) = sign_and_magnitude(&self.bytes[self.position..end]);
+            self.position = end;
+            nanosecond = fraction_nanoseconds(negative, exponent, &coefficient)?;
+        }
+        if !(1..=9999).contains(&year) {
+            bail!("Invalid year {}", year);
+        }
+        let date = Date::new(year as i32, month, day)?;
+        if precision == 3 {
+            bail!("Timestamp has an hour without minutes");
+        }
+        if precision < 3 {
+            return Ok(timestamp(date, None, None));
+        }
+        let time = Time::new(hour, minute, second, nanosecond)?;
+        let Some(offset_minutes) = offset_minutes else {
+            return Ok(timestamp(date, Some(time), None));
+        };
+        let utc = DateTime::OffsetDateTime {
+            date,
+            time,
+            offset_minutes: 0,
+        };
+        let (seconds, nanosecond) = utc.unix_timestamp().unwrap_or_default();
+        let local =
+            DateTime::from_unix_timestamp(seconds + offset_minutes as i64 * 60, nanosecond)?;
+        let DateTime::OffsetDateTime { date, time, .. } = local else {
+            unreachable!("Unix timestamps are offset date-times");
+        };
+        Ok(timestamp(date, Some(time), Some(offset_minutes)))
+    }
+
+    fn read_u8(&mut self, end: usize) -> LoadumResult<u8> {
+        if self.position >= end {
+            return Err(self.error(if end == self.bytes.len() {
+                "Unexpected end of input"
+            } else {
+                "Unexpected end of value"
+            }));
+        }
+        self.position += 1;
+        Ok(self.bytes[self.position - 1])
+    }
+
+    /// Reads an unsigned integer in groups of 7 bits, the last group has the high bit set
+    fn read_var_uint(&mut self, end: usize) -> LoadumResult<u64> {
+        let start = self.position;
+        let mut value: u64 = 0;
+        loop {
+            let byte = self.read_u8(end)?;
+            if value >> 57 != 0 {
+                return Err(self.error_at(start, "Variable length integer is too large"));
+            }
+            value = value << 7 | (byte & 0x7f) as u64;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Reads the sign and magnitude of a signed variable length integer, which may be `-0`
+    fn read_var_int(&mut self, end: usize) -> LoadumResult<(bool, u64)> {
+        let start = self.position;
+        let mut byte = self.read_u8(end)?;
+        let negative = byte & 0x40 != 0;
+        let mut magnitude = (byte & 0x3f) as u64;
+        while byte & 0x80 == 0 {
+            byte = self.read_u8(end)?;
+            if magnitude >> 57 != 0 {
+                return Err(self.error_at(start, "Variable length integer is too large"));
+            }
+            magnitude = magnitude << 7 | (byte & 0x7f) as u64;
+        }
+        Ok((negative, magnitude))
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, offset: usize, message: impl Display) -> LoadumError {
+        format_err!("{} at offset {}", message, offset)
+    }
+}
+
+fn uint(bytes: &[u8]) -> Option<u64> {
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    let bytes = &bytes[start..];
+    (bytes.len() <= 8).then(|| {
+        bytes
+            .iter()
+            .fold(0, |value, byte| value << 8 | *byte as u64)
+    })
+}
+
+fn signed(negative: bool, magnitude: u64) -> Option<i64> {
+    let magnitude = i64::try_from(magnitude).ok()?;
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// Splits a signed integer whose first bit is the sign
+fn sign_and_magnitude(bytes: &[u8]) -> (bool, Vec<u8>) {
+    let mut magnitude = bytes.to_vec();
+    match magnitude.first_mut() {
+        Some(first) => {
+            let negative = *first & 0x80 != 0;
+            *first &= 0x7f;
+            (negative, magnitude)
+        }
+        None => (false, magnitude),
+    }
+}
+
+/// Converts the fraction of seconds `coefficient × 10^-exponent` to nanoseconds, truncating it
+fn fraction_nanoseconds(negative: bool, exponent: u64, coefficient: &[u8]) -> LoadumResult<u32> {
+    let Some(coefficient) = uint(coefficient) else {
+        bail!("Fraction of seconds is too large");
+    };
+    if !negative && exponent > 0 && coefficient > 0 {
+        bail!("Fraction of seconds is not less than one");
+    }
+    let scaled = if !negative || exponent <= 9 {
+        let factor = 10u128.pow(9 - exponent.min(9) as u32);
+        coefficient as u128 * factor
+    } else {
+        // Dividing by a factor beyond the range of u128 truncates to zero
+        u32::try_from(exponent - 9)
+            .ok()
+            .and_then(|power| 10u128.checked_pow(power))
+            .map_or(0, |divisor| coefficient as u128 / divisor)
+    };
+    if scaled >= 1_000_000_000 {
+        bail!("Fraction of seconds is not less than one");
+    }
+    Ok(scaled as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IonLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::testing::{collect_events, format_events};
+    use loadum_json::json_dumper::JsonDumper;
+
+    fn load(input: &[u8]) -> String {
+        format_events(&collect_events(
+            IonLoader::new(input).map(|event| event.map(Event::into_owned)),
+        ))
+    }
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_text_scalars() {
+        test_loader(
+            br#"[null, null.string, true, false, 0, -42, 0xf_F, -0b101, 1_000,
+                 18446744073709551616, -9223372036854775808, 1.5e0, -2E3, nan, +inf, -inf,
+                 1.50, 1., -0.0, 1.5d3, 12d-1, "escapes \"\\\t\u00e9\x41", '''long ''' /* c */ '''string''',
+                 sym, 'quoted sym', $4, {{aGVsbG8=}}, {{ "clob\xff" }},
+                 2007T, 2007-02T, 2007-02-23, 2007-02-23T12:14Z, 2007-02-23T12:14:33.079-08:00,
+                 2007-02-23T12:14:33.123456789123+00:00, 2007-02-23T12:14:33-00:00]"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Integer(0))
+                Literal(Integer(-42))
+                Literal(Integer(255))
+                Literal(Integer(-5))
+                Literal(Integer(1000))
+                Tag("bigint")
+                Literal(String("18446744073709551616"))
+                Literal(Integer(-9223372036854775808))
+                Literal(Number(1.5))
+                Literal(Number(-2000.0))
+                Literal(Number(NaN))
+                Literal(Number(inf))
+                Literal(Number(-inf))
+                Tag("decimal")
+                Literal(String("1.50"))
+                Tag("decimal")
+                Literal(String("1"))
+                Tag("decimal")
+                Literal(String("-0.0"))
+                Tag("decimal")
+                Literal(String("1.5E+3"))
+                Tag("decimal")
+                Literal(String("1.2"))
+                Literal(String("escapes \"\\\téA"))
+                Literal(String("long string"))
+                Tag("symbol")
+                Literal(String("sym"))
+                Tag("symbol")
+                Literal(String("quoted sym"))
+                Tag("symbol")
+                Literal(String("name"))
+                Literal(Bytes([104, 101, 108, 108, 111]))
+                Tag("clob")
+                Literal(Bytes([99, 108, 111, 98, 255]))
+                Literal(DateTime(LocalDate(Date { year: 2007, month: 1, day: 1 })))
+                Literal(DateTime(LocalDate(Date { year: 2007, month: 2, day: 1 })))
+                Literal(DateTime(LocalDate(Date { year: 2007, month: 2, day: 23 })))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2007, month: 2, day: 23 }, time: Time { hour: 12, minute: 14, second: 0, nanosecond: 0 }, offset_minutes: 0 }))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2007, month: 2, day: 23 }, time: Time { hour: 12, minute: 14, second: 33, nanosecond: 79000000 }, offset_minutes: -480 }))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2007, month: 2, day: 23 }, time: Time { hour: 12, minute: 14, second: 33, nanosecond: 123456789 }, offset_minutes: 0 }))
+                Literal(DateTime(LocalDateTime { date: Date { year: 2007, month: 2, day: 23 }, time: Time { hour: 12, minute: 14, second: 33, nanosecond: 0 } }))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_text_containers() {
+        test_loader(
+            br#"
+// A struct with annotations
+Person::{
+  name: "Ada", 'quoted field': 1, "string field": [], $5: (+ 1 -2 a<=b),
+  nested: a::b::{x: null.struct,}, sexp: (), list: 'a'::$ion::[1,],
+}
+"#,
+            expect![[r#"
+                DocumentStart
+                Tag("Person")
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Ada"))
+                MapKey(String("quoted field"))
+                Literal(Integer(1))
+                MapKey(String("string field"))
+                ListStart
+                ListEnd
+                MapKey(String("version"))
+                Tag("sexp")
+                ListStart
+                Tag("symbol")
+                Literal(String("+"))
+                Literal(Integer(1))
+                Literal(Integer(-2))
+                Tag("symbol")
+                Literal(String("a"))
+                Tag("symbol")
+                Literal(String("<="))
+                Tag("symbol")
+                Literal(String("b"))
+                ListEnd
+                MapKey(String("nested"))
+                Tag("a")
+                Tag("b")
+                MapStart
+                MapKey(String("x"))
+                Literal(Null)
+                MapEnd
+                MapKey(String("sexp"))
+                Tag("sexp")
+                ListStart
+                ListEnd
+                MapKey(String("list"))
+                Tag("a")
+                Tag("$ion")
+                ListStart
+                Literal(Integer(1))
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        test_loader(
+            b"1 a::2\n[] $ion_1_0 3 // end",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                Tag("a")
+                Literal(Integer(2))
+                DocumentEnd
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+                DocumentStart
+                Literal(Integer(3))
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b"/* only a comment */ $ion_1_0", expect![""]);
+    }
+
+    #[test]
+    fn test_symbol_tables() {
+        test_loader(
+            br#"
+$ion_symbol_table::{symbols: ["first", "second", null]}
+[$10, $11, $12]
+$ion_symbol_table::{imports: $ion_symbol_table, symbols: ["third"]}
+$13::{$10: $13}
+$ion_1_0
+$4
+"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Tag("symbol")
+                Literal(String("first"))
+                Tag("symbol")
+                Literal(String("second"))
+                Tag("symbol")
+                Literal(String("$12"))
+                ListEnd
+                DocumentEnd
+                DocumentStart
+                Tag("third")
+                MapStart
+                MapKey(String("first"))
+                Tag("symbol")
+                Literal(String("third"))
+                MapEnd
+                DocumentEnd
+                DocumentStart
+                Tag("symbol")
+                Literal(String("name"))
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        test_loader(
+            &[
+                0xe0, 0x01, 0x00, 0xea, // Version marker
+                0xee, 0x93, 0x81, 0x83, // $ion_symbol_table::
+                0xde, 0x8f, 0x87, 0xba, 0x84, b'n', b'a', b'm', b'e', 0x84, b'l', b'i', b's', b't',
+                0x80, 0x01, b'x', // {symbols: ["name", "list"]} with padding
+                0xdf, // null.struct
+                0xbe, 0xcc, // List of 76 bytes
+                0x0f, 0x11, 0x20, 0x21, 0x2a, 0x31, 0x01, // null, true, 0, 42, -1
+                0x48, 0x3f, 0xf8, 0, 0, 0, 0, 0, 0, // 1.5
+                0x53, 0xc2, 0x00, 0x96, // 1.50
+                0x71, 0x0a, // Symbol 10
+                0x83, b'a', b'b', b'c', // "abc"
+                0xa2, 0x00, 0xff, // Blob
+                0x01, 0x00, // Padding
+                0xc0, // Empty s-expression
+                0xe4, 0x81, 0x8b, 0x21, 0x01, // list::1
+                0xd3, 0x8a, 0x21, 0x07, // {name: 7}
+                0x68, 0x80, 0x0f, 0xd7, 0x82, 0x97, 0x83, 0x84, 0x85, // 2007-02-23T03:04:05Z
+                0x63, 0xc0, 0x0f, 0xd7, // 2007T
+                0x6a, 0xfc, 0x0f, 0xd7, 0x82, 0x97, 0x83, 0x84, 0x85, 0xc3,
+                0x01, // -60 minutes
+                0x2a, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 2^72
+            ],
+            expect![[r#"
+                DocumentStart
+                Literal(Null)
+                DocumentEnd
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Integer(0))
+                Literal(Integer(42))
+                Literal(Integer(-1))
+                Literal(Number(1.5))
+                Tag("decimal")
+                Literal(String("1.50"))
+                Tag("symbol")
+                Literal(String("name"))
+                Literal(String("abc"))
+                Literal(Bytes([0, 255]))
+                Tag("sexp")
+                ListStart
+                ListEnd
+                Tag("list")
+                Literal(Integer(1))
+                MapStart
+                MapKey(String("name"))
+                Literal(Integer(7))
+                MapEnd
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2007, month: 2, day: 23 }, time: Time { hour: 3, minute: 4, second: 5, nanosecond: 0 }, offset_minutes: 0 }))
+                Literal(DateTime(LocalDate(Date { year: 2007, month: 1, day: 1 })))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2007, month: 2, day: 23 }, time: Time { hour: 2, minute: 4, second: 5, nanosecond: 1000000 }, offset_minutes: -60 }))
+                Tag("bigint")
+                Literal(String("4722366482869645213696"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    /// Binary lists each containing the next list, ending with `0`
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut value = vec![0x20];
+        for _ in 0..depth {
+            let mut list = vec![];
+            if value.len() < 14 {
+                list.push(0xb0 | value.len() as u8);
+            } else {
+                list.push(0xbe);
+                let mut length = vec![value.len() as u8 & 0x7f | 0x80];
+                let mut rest = value.len() >> 7;
+                while rest > 0 {
+                    length.insert(0, rest as u8 & 0x7f);
+                    rest >>= 7;
+                }
+                list.extend(length);
+            }
+            list.extend(value);
+            value = list;
+        }
+        [&b"\xe0\x01\x00\xea"[..], &value].concat()
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(IonLoader::new(&nested_lists(MAX_DEPTH)).all(|event| event.is_ok()));
+        let output: String = [
+            &b"[1, 2"[..],
+            b"[1 2]",
+            b"{a 1}",
+            b"{a: 1",
+            b"(a",
+            b")",
+            b"007",
+            b"1_",
+            b"1.5x",
+            b"0x",
+            b"+1",
+            b"2007-02-30",
+            b"2007-02-23T12:14",
+            b"null.foo",
+            b"\"unterminated",
+            b"\"\\q\"",
+            b"'''long",
+            b"$99",
+            b"{{\"\\u0100\"}}",
+            b"{{\"\xc3\xa9\"}}",
+            b"{{ not base64 }}",
+            b"/* unterminated",
+            b"$ion_2_0",
+            b"$ion_symbol_table::{imports: [{name: \"shared\"}]}",
+            b"\xff",
+            b"\xe0\x01\x00\xea\xe0\x02",
+            b"\xe0\x01\x00\xea\x21",
+            b"\xe0\x01\x00\xea\xb3\x21\x01",
+            b"\xe0\x01\x00\xea\x30",
+            b"\xe0\x01\x00\xea\x43\x00\x00\x00",
+            b"\xe0\x01\x00\xea\x71\x63",
+            b"\xe0\x01\x00\xea\x82\xc3\x28",
+            b"\xe0\x01\x00\xea\xe4\x81\x84\x01\x00",
+            b"\xe0\x01\x00\xea\xe4\x81\x84\x20\x20",
+            b"\xe0\x01\x00\xea\x66\x80\x0f\xd7\x82\x97\x83",
+            b"\xe0\x01\x00\xea\x6a\x80\x0f\xd7\x82\x97\x83\x84\x85\xc1\x0a",
+            "[".repeat(MAX_DEPTH + 1).as_bytes(),
+            "{a:".repeat(MAX_DEPTH + 1).as_bytes(),
+            &nested_lists(MAX_DEPTH + 1),
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Unterminated list at line 1, column 1
+            Error: Expected ',' or ']' at line 1, column 4
+            Error: Expected ':' after the field name at line 1, column 4
+            Error: Unterminated struct at line 1, column 1
+            Error: Unterminated s-expression at line 1, column 1
+            Error: Unexpected ')' at line 1, column 1
+            Error: Invalid number '007' at line 1, column 1
+            Error: Invalid number '1_' at line 1, column 1
+            Error: Invalid number '1.5x' at line 1, column 1
+            Error: Invalid number '0x' at line 1, column 1
+            Error: Unexpected '+' at line 1, column 1
+            Error: Invalid date 2007-02-30 at line 1, column 1
+            Error: Invalid timestamp '2007-02-23T12:14' at line 1, column 1
+            Error: Invalid typed null 'null.foo' at line 1, column 1
+            Error: Unterminated string at line 1, column 1
+            Error: Invalid escape sequence '\q' at line 1, column 1
+            Error: Unterminated long string at line 1, column 1
+            Error: Undefined symbol ID 99 at line 1, column 1
+            Error: Clob escapes may only encode bytes at line 1, column 1
+            Error: Clobs may only contain ASCII characters at line 1, column 3
+            Error: Invalid base64: truncated input at line 1, column 1
+            Error: Unterminated comment at line 1, column 1
+            Error: Unsupported Ion version $ion_2_0 at line 1, column 1
+            Error: Imports of shared symbol tables are not supported at line 1, column 1
+            Error: Ion text must be UTF-8
+            Error: Unsupported Ion version marker at offset 4
+            Error: Value is longer than its container at offset 4
+            Error: Value is longer than its container at offset 4
+            Error: Invalid type descriptor 0x30 at offset 4
+            Error: Invalid float length 3 at offset 4
+            Error: Undefined symbol ID 99 at offset 4
+            Error: Invalid UTF-8 in string at offset 4
+            Error: Padding cannot be annotated at offset 4
+            Error: Annotation wrapper contains more than one value at offset 4
+            Error: Timestamp has an hour without minutes at offset 4
+            Error: Fraction of seconds is not less than one at offset 4
+            Error: Document is nested deeper than 128 levels at line 1, column 129
+            Error: Document is nested deeper than 128 levels at line 1, column 385
+            Error: Document is nested deeper than 128 levels at offset 307
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        let input = br#"order::{id: 42, total: 12.50, tags: [new], at: 2024-01-02T03:04:05Z}"#;
+        for event in IonLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"id": 42,
+            	"total": "12.50",
+            	"tags": [
+            		"new"
+            	],
+            	"at": "2024-01-02T03:04:05Z"
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,48 @@
+//! Amazon Ion, in the text and binary formats
+//!
+//! Structs are maps, lists are lists, blobs are bytes and timestamps are date-times. Timestamps
+//! with year or month precision are loaded as the first day of the period, timestamps with an
+//! unknown offset (`-00:00`) as local date-times. Annotations are loaded as tags, followed by a
+//! tag for Ion types without a counterpart in loadum values: symbols are tagged strings,
+//! s-expressions tagged lists and clobs tagged bytes. Decimals and integers beyond 64 bits are
+//! tagged strings, so they are not rounded. Typed nulls like `null.string` are loaded as null.
+
+mod decimal;
+pub mod ion_dumper;
+pub mod ion_loader;
+
+/// Encoding of Ion written by the dumper, the loader detects it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IonFormat {
+    /// The text format
+    #[default]
+    Text,
+    /// The binary format starting with the binary version marker
+    Binary,
+}
+
+/// Tag of symbols, loaded as strings
+pub(crate) const SYMBOL_TAG: &str = "symbol";
+/// Tag of s-expressions, loaded as lists
+pub(crate) const SEXP_TAG: &str = "sexp";
+/// Tag of clobs, loaded as bytes
+pub(crate) const CLOB_TAG: &str = "clob";
+/// Tag of decimals, loaded as strings like `1.50` or `1.5E+3` that keep their precision
+pub(crate) const DECIMAL_TAG: &str = "decimal";
+
+/// Start of binary Ion, for version 1.0
+pub(crate) const BINARY_VERSION_MARKER: &[u8; 4] = b"\xe0\x01\x00\xea";
+/// Annotation of structs defining local symbols, which are not loaded as documents
+pub(crate) const SYMBOL_TABLE_ANNOTATION: &str = "$ion_symbol_table";
+/// Symbols with the IDs 1 to 9, which every symbol table starts with
+pub(crate) const SYSTEM_SYMBOLS: [&str; 9] = [
+    "$ion",
+    "$ion_1_0",
+    "$ion_symbol_table",
+    "name",
+    "version",
+    "imports",
+    "symbols",
+    "max_id",
+    "$ion_shared_symbol_table",
+];
@@ -0,0 +1,65 @@
+use crate::JsonSyntax;
+use crate::json_dumper::JsonFormatter;
+use loadum::dumper::{AsyncDumper, Dumper, KeyPolicy};
+use loadum::event::Event;
+use loadum::result::LoadumResult;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Output is buffered up to this size before it is written
+const WRITE_THRESHOLD: usize = 8 * 1024;
+
+/// Dumps JSON to an async writer, output is only guaranteed to be written after [`AsyncDumper::flush`]
+pub struct AsyncJsonDumper<'write> {
+    formatter: JsonFormatter<Vec<u8>>,
+    write: Box<dyn AsyncWrite + Unpin + Send + 'write>,
+}
+
+impl<'write> AsyncJsonDumper<'write> {
+    pub fn new(write: impl AsyncWrite + Unpin + Send + 'write) -> AsyncJsonDumper<'write> {
+        AsyncJsonDumper {
+            formatter: JsonFormatter::new(vec![]),
+            write: Box::new(write),
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.formatter = self.formatter.with_key_policy(key_policy);
+        self
+    }
+
+    /// Sets the dialect to write, by default strict JSON
+    pub fn with_syntax(mut self, syntax: JsonSyntax) -> Self {
+        self.formatter = self.formatter.with_syntax(syntax);
+        self
+    }
+
+    /// Sets whether to write JSON Lines, with each document compactly on its own line
+    pub fn with_json_lines(mut self, json_lines: bool) -> Self {
+        self.formatter = self.formatter.with_json_lines(json_lines);
+        self
+    }
+
+    async fn write_buffered(&mut self) -> LoadumResult<()> {
+        let buffer = self.formatter.output_mut();
+        self.write.write_all(buffer).await?;
+        buffer.clear();
+        Ok(())
+    }
+}
+
+impl AsyncDumper for AsyncJsonDumper<'_> {
+    async fn emit(&mut self, event: &Event<'_>) -> LoadumResult<()> {
+        self.formatter.emit(event)?;
+        if self.formatter.output_mut().len() >= WRITE_THRESHOLD {
+            self.write_buffered().await?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> LoadumResult<()> {
+        self.write_buffered().await?;
+        self.write.flush().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+use crate::JsonSyntax;
+use crate::json_loader::{LoaderState, Step, initial_state, parse_buffered};
+use loadum::event::Event;
+use loadum::loader::AsyncLoader;
+use loadum::read_buffer::ReadBuffer;
+use loadum::result::LoadumResult;
+use tokio::io::AsyncRead;
+
+/// Loads JSON incrementally from an async reader
+pub struct AsyncJsonLoader<'source> {
+    buffer: ReadBuffer<Box<dyn AsyncRead + Unpin + Send + 'source>>,
+    /// Scanned length of an incomplete string, see [`parse_buffered`]
+    scanned: usize,
+    state: Vec<LoaderState>,
+    syntax: JsonSyntax,
+}
+
+impl<'source> AsyncJsonLoader<'source> {
+    pub fn new(read: impl AsyncRead + Unpin + Send + 'source) -> AsyncJsonLoader<'source> {
+        AsyncJsonLoader {
+            buffer: ReadBuffer::new(Box::new(read)),
+            scanned: 0,
+            state: vec![LoaderState::Initial],
+            syntax: JsonSyntax::Json,
+        }
+    }
+
+    /// Sets the accepted dialect, by default only strict JSON is accepted
+    pub fn with_syntax(mut self, syntax: JsonSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Sets whether the input is JSON Lines, with one document per line
+    pub fn with_json_lines(mut self, json_lines: bool) -> Self {
+        self.state = vec![initial_state(json_lines)];
+        self
+    }
+
+    async fn load_event(&mut self) -> LoadumResult<Option<Event<'static>>> {
+        loop {
+            match parse_buffered(
+                &mut self.state,
+                &mut self.buffer,
+                &mut self.scanned,
+                self.syntax,
+            )? {
+                Step::Event(event) => return Ok(Some(event)),
+                Step::NeedMoreInput => {
+                    self.buffer.fill_async().await?;
+                }
+                Step::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<'source> AsyncLoader<'source> for AsyncJsonLoader<'source> {
+    async fn next_event(&mut self) -> Option<LoadumResult<Event<'source>>> {
+        match self.load_event().await {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = vec![LoaderState::Done];
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncJsonLoader;
+    use crate::async_json_dumper::AsyncJsonDumper;
+    use expect_test::expect;
+    use loadum::dumper::AsyncDumper;
+    use loadum::loader::AsyncLoader;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn test_roundtrip() {
+        let (client, mut server) = tokio::io::duplex(4);
+        let input = r#"{"list": [1, "two", {"three": [true, null]}]}"#;
+        let writer = tokio::spawn(async move {
+            let mut dumper = AsyncJsonDumper::new(client);
+            let mut loader = AsyncJsonLoader::new(input.as_bytes());
+            while let Some(event) = loader.next_event().await {
+                dumper.emit(&event.unwrap()).await.unwrap();
+            }
+            dumper.flush().await.unwrap();
+        });
+        let mut output = vec![];
+        server.read_to_end(&mut output).await.unwrap();
+        writer.await.unwrap();
+        expect![[r#"
+            {
+            	"list": [
+            		1,
+            		"two",
+            		{
+            			"three": [
+            				true,
+            				null
+            			]
+            		}
+            	]
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_error() {
+        let mut loader = AsyncJsonLoader::new(&b"[1 2]"[..]);
+        let mut output = vec![];
+        while let Some(event) = loader.next_event().await {
+            match event {
+                Ok(event) => output.push(format!("{:?}", event)),
+                Err(error) => output.push(format!("Error: {}", error)),
+            }
+        }
+        expect![[r#"
+            [
+                "DocumentStart",
+                "ListStart",
+                "Literal(Integer(1))",
+                "Error: Expected ',' or ']', but found '2' at offset 3",
+            ]
+        "#]]
+        .assert_debug_eq(&output);
+    }
+}
@@ -1,15 +1,35 @@
-use loadum::LoadumString;
-use loadum::dumper::Dumper;
+use crate::JsonSyntax;
+use loadum::base64;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
 use loadum::event::Event;
 use loadum::result::LoadumResult;
 use loadum::value::Value;
+use std::borrow::Cow;
 use std::io::Write;
 
 pub struct JsonDumper<'write> {
+    formatter: JsonFormatter<Box<dyn Write + 'write>>,
+}
+
+/// Formatting state of a JSON dumper, independent of where the output goes
+pub(crate) struct JsonFormatter<W> {
     indentation_level: u32,
     indentation: &'static str,
-    write: Box<dyn Write + 'write>,
+    newline: &'static str,
+    key_separator: &'static str,
+    write: W,
     state: Vec<DumperState>,
+    key_policy: KeyPolicy,
+    syntax: JsonSyntax,
+    json_lines: bool,
+    complex_key: Option<ComplexKey>,
+}
+
+/// Events of a composite map key, collected until the key is complete so it can be stringified
+struct ComplexKey {
+    events: Vec<Event<'static>>,
+    depth: u32,
 }
 
 #[derive(Debug, PartialEq)]
@@ -23,14 +43,148 @@ enum DumperState {
     ListHasValue,
 }
 
+macro_rules! assert_state {
+    ($self:ident, $expected_state:pat) => {
+        let Some(state) = $self.state.last() else {
+            panic!("State stack is empty");
+        };
+        if !matches!(state, $expected_state) {
+            panic!(
+                "Invalid state: expected {:?}, but was: {:?}",
+                stringify!($expected_state),
+                state
+            );
+        }
+    };
+}
+
 impl<'write> JsonDumper<'write> {
     pub fn new(write: impl Write + 'write) -> JsonDumper<'write> {
         JsonDumper {
-            write: Box::new(write),
+            formatter: JsonFormatter::new(Box::new(write)),
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    ///
+    /// Composite keys are stringified as compact JSON.
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.formatter.key_policy = key_policy;
+        self
+    }
+
+    /// Sets the dialect to write, by default strict JSON
+    ///
+    /// JSON5 output leaves keys that are identifiers unquoted and writes `Infinity` and `NaN`.
+    /// JSONC output is plain JSON.
+    pub fn with_syntax(mut self, syntax: JsonSyntax) -> Self {
+        self.formatter.syntax = syntax;
+        self
+    }
+
+    /// Sets whether to write JSON Lines, with each document compactly on its own line
+    pub fn with_json_lines(mut self, json_lines: bool) -> Self {
+        self.formatter = self.formatter.with_json_lines(json_lines);
+        self
+    }
+}
+
+impl Dumper for JsonDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        self.formatter.emit(event)
+    }
+}
+
+impl<W: Write> JsonFormatter<W> {
+    pub(crate) fn new(write: W) -> JsonFormatter<W> {
+        JsonFormatter {
+            write,
             indentation_level: 0,
             indentation: "\t",
+            newline: "\n",
+            key_separator: ": ",
             state: vec![DumperState::Initial],
+            key_policy: KeyPolicy::Stringify,
+            syntax: JsonSyntax::Json,
+            json_lines: false,
+            complex_key: None,
+        }
+    }
+
+    pub(crate) fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn with_syntax(mut self, syntax: JsonSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// The output written so far
+    #[cfg(feature = "async")]
+    pub(crate) fn output_mut(&mut self) -> &mut W {
+        &mut self.write
+    }
+
+    pub(crate) fn with_json_lines(mut self, json_lines: bool) -> Self {
+        self.json_lines = json_lines;
+        self.with_compact(json_lines)
+    }
+
+    fn with_compact(mut self, compact: bool) -> Self {
+        (self.indentation, self.newline, self.key_separator) = if compact {
+            ("", "", ":")
+        } else {
+            ("\t", "\n", ": ")
+        };
+        self
+    }
+
+    fn emit_key(&mut self, key: &str) -> LoadumResult<()> {
+        assert_state!(self, DumperState::MapInitial | DumperState::MapHasValue);
+        self.emit_comma_if_needed()?;
+        *self.state.last_mut().unwrap() = DumperState::MapHasKey;
+        self.indent()?;
+        if self.syntax == JsonSyntax::Json5 && is_identifier(key) {
+            self.write.write_all(key.as_bytes())?;
+        } else {
+            self.emit_string(key)?;
+        }
+        self.write.write_all(self.key_separator.as_bytes())?;
+        Ok(())
+    }
+
+    /// Collects the events of a composite key, returns true if the event was consumed
+    fn collect_complex_key(&mut self, event: &Event) -> LoadumResult<bool> {
+        let Some(complex_key) = &mut self.complex_key else {
+            return Ok(false);
+        };
+        match event {
+            Event::MapStart | Event::ListStart => complex_key.depth += 1,
+            Event::MapEnd | Event::ListEnd if complex_key.depth > 0 => complex_key.depth -= 1,
+            Event::Literal(_) | Event::Tag(_) => {}
+            _ if complex_key.depth > 0 => {}
+            // Only a value can start the key
+            event => bail!("Expected a complex key, but found {:?}", event),
         }
+        complex_key.events.push(event.clone().into_owned());
+        if complex_key.depth > 0 || matches!(event, Event::Tag(_)) {
+            return Ok(true);
+        }
+        let events = std::mem::take(&mut complex_key.events);
+        self.complex_key = None;
+        let mut key_formatter = JsonFormatter::new(vec![])
+            .with_key_policy(self.key_policy)
+            .with_compact(true);
+        key_formatter.emit(&Event::DocumentStart)?;
+        for event in &events {
+            key_formatter.emit(event)?;
+        }
+        key_formatter.emit(&Event::DocumentEnd)?;
+        self.emit_key(&String::from_utf8(key_formatter.write)?)?;
+        Ok(true)
     }
 
     fn indent(&mut self) -> LoadumResult<()> {
@@ -43,10 +197,17 @@ impl<'write> JsonDumper<'write> {
     fn emit_value(&mut self, value: &Value) -> LoadumResult<()> {
         match value {
             Value::String(s) => {
-                self.write.write_all(b"\"")?;
-                let escaped_string = escape_string(s);
-                self.write.write_all(escaped_string.as_bytes())?;
-                self.write.write_all(b"\"")?;
+                self.emit_string(s)?;
+            }
+            Value::BorrowedString(s) => {
+                self.emit_string(s)?;
+            }
+            Value::Integer(i) => {
+                self.write.write_all(i.to_string().as_bytes())?;
+            }
+            Value::Number(n) if self.syntax == JsonSyntax::Json5 && n.is_infinite() => {
+                let infinity: &[u8] = if *n > 0.0 { b"Infinity" } else { b"-Infinity" };
+                self.write.write_all(infinity)?;
             }
             Value::Number(i) => {
                 self.write.write_all(i.to_string().as_bytes())?;
@@ -57,10 +218,25 @@ impl<'write> JsonDumper<'write> {
             Value::Null => {
                 self.write.write_all(b"null")?;
             }
+            Value::Bytes(bytes) => {
+                // JSON has no binary type, so bytes are written as a base64 string
+                self.emit_string(&base64::encode(bytes))?;
+            }
+            Value::DateTime(date_time) => {
+                self.emit_string(&date_time.to_string())?;
+            }
         }
         Ok(())
     }
 
+    fn emit_string(&mut self, s: &str) -> LoadumResult<()> {
+        self.write.write_all(b"\"")?;
+        let escaped_string = escape_string(s);
+        self.write.write_all(escaped_string.as_bytes())?;
+        self.write.write_all(b"\"")?;
+        Ok(())
+    }
+
     fn emit_comma_if_needed(&mut self) -> LoadumResult<()> {
         let last_value = self.state.last_mut().unwrap();
         let mut write_comma = false;
@@ -87,7 +263,8 @@ impl<'write> JsonDumper<'write> {
             }
         };
         if write_comma {
-            self.write.write_all(b",\n")?;
+            self.write.write_all(b",")?;
+            self.write.write_all(self.newline.as_bytes())?;
         }
         if indent {
             self.indent()?;
@@ -105,30 +282,18 @@ impl<'write> JsonDumper<'write> {
             _ => {}
         }
         if newline {
-            self.write.write_all(b"\n")?;
+            self.write.write_all(self.newline.as_bytes())?;
             self.indent()?;
         }
         Ok(())
     }
 }
 
-macro_rules! assert_state {
-    ($self:ident, $expected_state:pat) => {
-        let Some(state) = $self.state.last() else {
-            panic!("State stack is empty");
-        };
-        if !matches!(state, $expected_state) {
-            panic!(
-                "Invalid state: expected {:?}, but was: {:?}",
-                stringify!($expected_state),
-                state
-            );
-        }
-    };
-}
-
-impl Dumper for JsonDumper<'_> {
+impl<W: Write> Dumper for JsonFormatter<W> {
     fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        if self.collect_complex_key(event)? {
+            return Ok(());
+        }
         match event {
             Event::DocumentStart => {
                 assert_state!(self, DumperState::Initial);
@@ -137,6 +302,9 @@ impl Dumper for JsonDumper<'_> {
             Event::DocumentEnd => {
                 self.state.pop();
                 assert_state!(self, DumperState::Initial);
+                if self.json_lines {
+                    self.write.write_all(b"\n")?;
+                }
             }
             Event::MapStart => {
                 assert_state!(
@@ -148,33 +316,44 @@ impl Dumper for JsonDumper<'_> {
                 );
                 self.emit_comma_if_needed()?;
                 self.state.push(DumperState::MapInitial);
-                self.write.write_all(b"{\n")?;
+                self.write.write_all(b"{")?;
+                self.write.write_all(self.newline.as_bytes())?;
                 self.indentation_level += 1;
             }
             Event::MapEnd => {
                 assert_state!(self, DumperState::MapInitial | DumperState::MapHasValue);
                 self.state.pop();
                 self.indentation_level -= 1;
-                self.write.write_all(b"\n")?;
+                self.write.write_all(self.newline.as_bytes())?;
                 self.indent()?;
                 self.write.write_all(b"}")?;
             }
             Event::MapKey(value) => {
+                let key = self.key_policy.scalar_key_string(value, "JSON map keys")?;
+                self.emit_key(&key)?;
+            }
+            Event::ComplexKey => {
                 assert_state!(self, DumperState::MapInitial | DumperState::MapHasValue);
-                self.emit_comma_if_needed()?;
-                *self.state.last_mut().unwrap() = DumperState::MapHasKey;
-                self.indent()?;
-                self.emit_value(value)?;
-                self.write.write_all(b": ")?;
+                if self.key_policy == KeyPolicy::Reject {
+                    bail!("JSON map keys must be strings, but found a composite key");
+                }
+                self.complex_key = Some(ComplexKey {
+                    events: vec![],
+                    depth: 0,
+                });
             }
             Event::ListStart => {
                 assert_state!(
                     self,
-                    DumperState::MapHasKey | DumperState::ListInitial | DumperState::ListHasValue
+                    DumperState::WantMapping
+                        | DumperState::MapHasKey
+                        | DumperState::ListInitial
+                        | DumperState::ListHasValue
                 );
                 self.emit_comma_if_needed()?;
 
-                self.write.write_all(b"[\n")?;
+                self.write.write_all(b"[")?;
+                self.write.write_all(self.newline.as_bytes())?;
                 self.indentation_level += 1;
                 self.state.push(DumperState::ListInitial);
             }
@@ -188,17 +367,30 @@ impl Dumper for JsonDumper<'_> {
             Event::Literal(value) => {
                 assert_state!(
                     self,
-                    DumperState::MapHasKey | DumperState::ListHasValue | DumperState::ListInitial
+                    DumperState::WantMapping
+                        | DumperState::MapHasKey
+                        | DumperState::ListHasValue
+                        | DumperState::ListInitial
                 );
                 self.emit_comma_if_needed()?;
                 self.emit_value(value)?;
             }
+            // JSON has no tags, the tagged value is written as is
+            Event::Tag(_) => {}
         }
         Ok(())
     }
 }
 
-fn escape_string(string: &LoadumString) -> LoadumString {
+/// Checks whether a key can be written unquoted in JSON5
+fn is_identifier(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+fn escape_string(string: &str) -> Cow<'_, str> {
     let mut must_escape = false;
     for c in string.chars() {
         match c {
@@ -210,9 +402,9 @@ fn escape_string(string: &LoadumString) -> LoadumString {
         }
     }
     if !must_escape {
-        return string.clone();
+        return Cow::Borrowed(string);
     }
-    let mut new_string = LoadumString::with_capacity(string.len() + 1);
+    let mut new_string = String::with_capacity(string.len() + 1);
     for c in string.chars() {
         match c {
             '"' | '\\' => {
@@ -236,16 +428,21 @@ fn escape_string(string: &LoadumString) -> LoadumString {
             _ => new_string.push(c),
         }
     }
-    LoadumString::from(new_string)
+    Cow::Owned(new_string)
 }
 
 #[cfg(test)]
 mod tests {
     use super::JsonDumper;
+    use crate::JsonSyntax;
+    use crate::json_loader::JsonLoader;
     use expect_test::expect;
-    use loadum::dumper::Dumper;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
     use loadum::event::Event;
     use loadum::event::Event::{DocumentEnd, DocumentStart, MapEnd, MapStart};
+    use loadum::result::LoadumResult;
+    use loadum::value::Value;
     use std::io::Cursor;
 
     fn run_test(events: &[Event], expected: expect_test::Expect) {
@@ -391,6 +588,219 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bytes_value() {
+        run_test(
+            &[
+                MapStart,
+                Event::map_key("empty"),
+                Event::bytes(&b""[..]),
+                Event::map_key("data"),
+                Event::bytes(&b"\x00\x01loadum\xff"[..]),
+                MapEnd,
+            ],
+            expect![[r#"
+                {
+                	"empty": "",
+                	"data": "AAFsb2FkdW3/"
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_date_time_value() {
+        run_test(
+            &[
+                MapStart,
+                Event::map_key("offset"),
+                Event::date_time(DateTime::parse("1979-05-27T00:32:00.5-07:00").unwrap()),
+                Event::map_key("local"),
+                Event::date_time(DateTime::parse("1979-05-27 07:32:00").unwrap()),
+                Event::map_key("date"),
+                Event::date_time(DateTime::parse("1979-05-27").unwrap()),
+                Event::map_key("time"),
+                Event::date_time(DateTime::parse("07:32:00").unwrap()),
+                MapEnd,
+            ],
+            expect![[r#"
+                {
+                	"offset": "1979-05-27T00:32:00.5-07:00",
+                	"local": "1979-05-27T07:32:00",
+                	"date": "1979-05-27",
+                	"time": "07:32:00"
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_non_string_keys() {
+        run_test(
+            &[
+                MapStart,
+                Event::MapKey(Value::number(1)),
+                Event::null(),
+                Event::MapKey(Value::Boolean(true)),
+                Event::null(),
+                Event::MapKey(Value::Null),
+                Event::null(),
+                MapEnd,
+            ],
+            expect![[r#"
+                {
+                	"1": null,
+                	"true": null,
+                	"null": null
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_complex_keys() {
+        run_test(
+            &[
+                MapStart,
+                Event::ComplexKey,
+                Event::ListStart,
+                Event::number(1),
+                Event::string("two"),
+                Event::ListEnd,
+                Event::string("list"),
+                Event::ComplexKey,
+                MapStart,
+                Event::map_key("a"),
+                Event::ListStart,
+                Event::ListEnd,
+                Event::ComplexKey,
+                MapStart,
+                MapEnd,
+                Event::null(),
+                MapEnd,
+                MapStart,
+                MapEnd,
+                MapEnd,
+            ],
+            expect![[r#"
+                {
+                	"[1,\"two\"]": "list",
+                	"{\"a\":[],\"{}\":null}": {
+
+                	}
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_tags_ignored() {
+        run_test(
+            &[
+                Event::tag("outer"),
+                MapStart,
+                Event::tag("key"),
+                Event::map_key("a"),
+                Event::tag("ext:5"),
+                Event::bytes(&b"\x01"[..]),
+                Event::ComplexKey,
+                Event::tag("list"),
+                Event::ListStart,
+                Event::ListEnd,
+                Event::null(),
+                MapEnd,
+            ],
+            expect![[r#"
+                {
+                	"a": "AQ==",
+                	"[]": null
+                }"#]],
+        );
+    }
+
+    #[test]
+    fn test_json5() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output).with_syntax(JsonSyntax::Json5);
+        for event in [
+            DocumentStart,
+            MapStart,
+            Event::map_key("identifier_$1"),
+            Event::number(f64::INFINITY),
+            Event::map_key("not an identifier"),
+            Event::number(f64::NEG_INFINITY),
+            Event::map_key("1st"),
+            Event::number(f64::NAN),
+            MapEnd,
+            DocumentEnd,
+        ] {
+            dumper.emit(&event).unwrap();
+        }
+        drop(dumper);
+        let output = String::from_utf8(output).unwrap();
+        expect![[r#"
+            {
+            	identifier_$1: Infinity,
+            	"not an identifier": -Infinity,
+            	"1st": NaN
+            }"#]]
+        .assert_eq(&output);
+        let loaded = JsonLoader::new(&output)
+            .with_syntax(JsonSyntax::Json5)
+            .collect::<LoadumResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            loaded[2].clone().into_owned(),
+            Event::map_key("identifier_$1")
+        );
+        assert_eq!(loaded[5], Event::number(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let input = "{\"level\": \"info\", \"tags\": [\"a\", \"b\"]}\n\n[]\n  {\"nested\": {}}\n";
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output).with_json_lines(true);
+        for event in JsonLoader::from_reader(input.as_bytes()).with_json_lines(true) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {"level":"info","tags":["a","b"]}
+            []
+            {"nested":{}}
+        "#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_reject_non_string_keys() {
+        let mut dumper = JsonDumper::new(std::io::sink()).with_key_policy(KeyPolicy::Reject);
+        dumper.emit(&DocumentStart).unwrap();
+        dumper.emit(&MapStart).unwrap();
+        let error = dumper.emit(&Event::MapKey(Value::number(1))).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "JSON map keys must be strings, but found Number(1.0)"
+        );
+        let error = dumper.emit(&Event::ComplexKey).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "JSON map keys must be strings, but found a composite key"
+        );
+    }
+
+    #[test]
+    fn test_empty_complex_key() {
+        for end in [MapEnd, Event::ListEnd] {
+            let mut dumper = JsonDumper::new(std::io::sink());
+            dumper.emit(&DocumentStart).unwrap();
+            dumper.emit(&MapStart).unwrap();
+            dumper.emit(&Event::ComplexKey).unwrap();
+            let error = dumper.emit(&end).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("Expected a complex key, but found {:?}", end)
+            );
+        }
+    }
+
     #[test]
     fn test_empty_sequence() {
         run_test(
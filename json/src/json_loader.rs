@@ -0,0 +1,1372 @@
+use crate::JsonSyntax;
+use loadum::LoadumString;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::read_buffer::ReadBuffer;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Read;
+
+pub struct JsonLoader<'source> {
+    input: Input<'source>,
+    state: Vec<LoaderState>,
+    syntax: JsonSyntax,
+}
+
+enum Input<'source> {
+    Str {
+        source: &'source [u8],
+        position: usize,
+    },
+    Read {
+        buffer: ReadBuffer<Box<dyn Read + 'source>>,
+        /// Scanned length of an incomplete string, see [`parse_buffered`]
+        scanned: usize,
+    },
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub(crate) enum LoaderState {
+    Initial,
+    WantValue,
+    MapInitial,
+    MapWantKey,
+    MapHasValue,
+    ListInitial,
+    ListHasValue,
+    /// After a comma, where a trailing comma may end the list
+    ListWantValue,
+    DocumentEnd,
+    /// Before a document of JSON Lines input, or its end
+    Lines,
+    /// After a document of JSON Lines input, which has to end its line
+    LineEnd,
+    Done,
+}
+
+pub(crate) enum Step<'data> {
+    Event(Event<'data>),
+    NeedMoreInput,
+    Done,
+}
+
+impl Step<'_> {
+    fn into_owned(self) -> Step<'static> {
+        match self {
+            Step::Event(event) => Step::Event(event.into_owned()),
+            Step::NeedMoreInput => Step::NeedMoreInput,
+            Step::Done => Step::Done,
+        }
+    }
+}
+
+impl<'source> JsonLoader<'source> {
+    pub fn new(source: &'source str) -> JsonLoader<'source> {
+        JsonLoader {
+            input: Input::Str {
+                source: source.as_bytes(),
+                position: 0,
+            },
+            state: vec![LoaderState::Initial],
+            syntax: JsonSyntax::Json,
+        }
+    }
+
+    /// Loads JSON incrementally from a reader, only buffering the input needed for the next event
+    pub fn from_reader(read: impl Read + 'source) -> JsonLoader<'source> {
+        JsonLoader {
+            input: Input::Read {
+                buffer: ReadBuffer::new(Box::new(read)),
+                scanned: 0,
+            },
+            state: vec![LoaderState::Initial],
+            syntax: JsonSyntax::Json,
+        }
+    }
+
+    /// Sets the accepted dialect, by default only strict JSON is accepted
+    pub fn with_syntax(mut self, syntax: JsonSyntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Sets whether the input is JSON Lines, with one document per line
+    ///
+    /// Each line is loaded as a separate document, blank lines are skipped.
+    pub fn with_json_lines(mut self, json_lines: bool) -> Self {
+        self.state = vec![initial_state(json_lines)];
+        self
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        let step = match &mut self.input {
+            Input::Str { source, position } => {
+                let source: &'source [u8] = source;
+                let mut cursor = Cursor {
+                    data: &source[*position..],
+                    position: 0,
+                    offset: *position as u64,
+                    eof: true,
+                    syntax: self.syntax,
+                    scanned: 0,
+                };
+                let step = parse_event(&mut self.state, &mut cursor)?;
+                *position += cursor.position;
+                step
+            }
+            Input::Read { buffer, scanned } => loop {
+                match parse_buffered(&mut self.state, buffer, scanned, self.syntax)? {
+                    Step::NeedMoreInput => {
+                        buffer.fill()?;
+                    }
+                    step => break step,
+                }
+            },
+        };
+        match step {
+            Step::Event(event) => Ok(Some(event)),
+            Step::NeedMoreInput => bail!("Unexpected end of input"),
+            Step::Done => Ok(None),
+        }
+    }
+}
+
+impl<'source> Loader<'source> for JsonLoader<'source> {}
+
+impl<'source> Iterator for JsonLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = vec![LoaderState::Done];
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// The state a loader starts in
+pub(crate) fn initial_state(json_lines: bool) -> LoaderState {
+    if json_lines {
+        LoaderState::Lines
+    } else {
+        LoaderState::Initial
+    }
+}
+
+/// Parses the next event from buffered input, consuming the parsed bytes
+///
+/// If a string needs more input, its scanned length is kept in `scanned`, so that long strings
+/// arriving in small chunks are not scanned again from their start after every refill.
+pub(crate) fn parse_buffered<R>(
+    state: &mut Vec<LoaderState>,
+    buffer: &mut ReadBuffer<R>,
+    scanned: &mut usize,
+    syntax: JsonSyntax,
+) -> LoadumResult<Step<'static>> {
+    let mut cursor = Cursor {
+        data: buffer.data(),
+        position: 0,
+        offset: buffer.offset(),
+        eof: buffer.is_eof(),
+        syntax,
+        scanned: std::mem::take(scanned),
+    };
+    // Events must not borrow from the buffer, since it is refilled
+    let step = parse_event(state, &mut cursor).map(Step::into_owned);
+    if matches!(step, Ok(Step::NeedMoreInput)) {
+        *scanned = cursor.scanned;
+    }
+    let consumed = cursor.position;
+    buffer.consume(consumed);
+    step
+}
+
+struct Cursor<'data> {
+    data: &'data [u8],
+    position: usize,
+    offset: u64,
+    eof: bool,
+    syntax: JsonSyntax,
+    /// Scanned length of the string at the start of the data, before the data was refilled
+    ///
+    /// Strings that need more input set it to the length scanned so far and everything else resets
+    /// it, the incomplete string starts the data once the input before it is consumed.
+    scanned: usize,
+}
+
+/// Whitespace characters JSON5 accepts in addition to those of JSON
+const JSON5_WHITESPACE: [&[u8]; 6] = [
+    b"\x0b",
+    b"\x0c",
+    "\u{a0}".as_bytes(),
+    "\u{feff}".as_bytes(),
+    "\u{2028}".as_bytes(),
+    "\u{2029}".as_bytes(),
+];
+
+impl<'data> Cursor<'data> {
+    /// Skips whitespace and, unless the syntax is strict JSON, comments
+    ///
+    /// Returns false if more input is needed to know where a comment or whitespace ends.
+    fn skip_whitespace(&mut self) -> LoadumResult<bool> {
+        loop {
+            let rest = self.rest();
+            let length = match rest {
+                [b' ' | b'\t' | b'\n' | b'\r', ..] => 1,
+                _ if self.syntax == JsonSyntax::Json => return Ok(true),
+                [b'/', b'/', ..] => match rest.iter().position(|c| matches!(c, b'\n' | b'\r')) {
+                    Some(index) => index + 1,
+                    None if self.eof => rest.len(),
+                    None => return Ok(false),
+                },
+                [b'/', b'*', ..] => match rest[2..].windows(2).position(|end| end == b"*/") {
+                    Some(index) => index + 4,
+                    None if self.eof => {
+                        bail!("Unterminated comment at offset {}", self.offset())
+                    }
+                    None => return Ok(false),
+                },
+                [b'/'] if !self.eof => return Ok(false),
+                _ if self.syntax == JsonSyntax::Jsonc => return Ok(true),
+                _ => match JSON5_WHITESPACE.iter().find(|c| rest.starts_with(c)) {
+                    Some(c) => c.len(),
+                    None if !self.eof
+                        && !rest.is_empty()
+                        && JSON5_WHITESPACE.iter().any(|c| c.starts_with(rest)) =>
+                    {
+                        return Ok(false);
+                    }
+                    None => return Ok(true),
+                },
+            };
+            self.position += length;
+        }
+    }
+
+    /// Skips blanks up to and including the end of the line
+    ///
+    /// Returns false if more input is needed to find the end of the line.
+    fn skip_line_end(&mut self) -> LoadumResult<bool> {
+        while let Some(c) = self.peek() {
+            match c {
+                b' ' | b'\t' | b'\r' => self.position += 1,
+                b'\n' => {
+                    self.position += 1;
+                    return Ok(true);
+                }
+                c => bail!(
+                    "Expected end of line after document, but found {} at offset {}",
+                    describe(c),
+                    self.offset()
+                ),
+            }
+        }
+        Ok(self.eof)
+    }
+
+    fn allows_trailing_commas(&self) -> bool {
+        self.syntax != JsonSyntax::Json
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.position).copied()
+    }
+
+    fn rest(&self) -> &'data [u8] {
+        &self.data[self.position..]
+    }
+
+    fn offset(&self) -> u64 {
+        self.offset + self.position as u64
+    }
+}
+
+fn parse_event<'data>(
+    state: &mut Vec<LoaderState>,
+    cursor: &mut Cursor<'data>,
+) -> LoadumResult<Step<'data>> {
+    loop {
+        let Some(top) = state.last_mut() else {
+            return Ok(Step::Done);
+        };
+        match *top {
+            LoaderState::Initial => {
+                *top = LoaderState::DocumentEnd;
+                state.push(LoaderState::WantValue);
+                return Ok(Step::Event(Event::DocumentStart));
+            }
+            LoaderState::Lines => {
+                if !cursor.skip_whitespace()? {
+                    return Ok(Step::NeedMoreInput);
+                }
+                if cursor.peek().is_none() {
+                    if !cursor.eof {
+                        return Ok(Step::NeedMoreInput);
+                    }
+                    state.pop();
+                    return Ok(Step::Done);
+                }
+                state.push(LoaderState::LineEnd);
+                state.push(LoaderState::WantValue);
+                return Ok(Step::Event(Event::DocumentStart));
+            }
+            LoaderState::LineEnd => {
+                if !cursor.skip_line_end()? {
+                    return Ok(Step::NeedMoreInput);
+                }
+                state.pop();
+                return Ok(Step::Event(Event::DocumentEnd));
+            }
+            LoaderState::Done => return Ok(Step::Done),
+            _ => {}
+        }
+        if !cursor.skip_whitespace()? {
+            return Ok(Step::NeedMoreInput);
+        }
+        let Some(c) = cursor.peek() else {
+            if !cursor.eof {
+                return Ok(Step::NeedMoreInput);
+            }
+            if *top == LoaderState::DocumentEnd {
+                *top = LoaderState::Done;
+                return Ok(Step::Event(Event::DocumentEnd));
+            }
+            bail!("Unexpected end of input at offset {}", cursor.offset());
+        };
+        match *top {
+            LoaderState::WantValue => {
+                let Some(event) = parse_value(cursor)? else {
+                    return Ok(Step::NeedMoreInput);
+                };
+                state.pop();
+                match event {
+                    Event::MapStart => state.push(LoaderState::MapInitial),
+                    Event::ListStart => state.push(LoaderState::ListInitial),
+                    _ => {}
+                }
+                return Ok(Step::Event(event));
+            }
+            LoaderState::MapInitial | LoaderState::MapHasValue if c == b'}' => {
+                cursor.position += 1;
+                state.pop();
+                return Ok(Step::Event(Event::MapEnd));
+            }
+            LoaderState::MapInitial => {
+                *top = LoaderState::MapWantKey;
+            }
+            LoaderState::MapHasValue => {
+                expect_character(cursor, c, b',', "',' or '}'")?;
+                *top = LoaderState::MapWantKey;
+            }
+            LoaderState::MapWantKey if c == b'}' && cursor.allows_trailing_commas() => {
+                cursor.position += 1;
+                state.pop();
+                return Ok(Step::Event(Event::MapEnd));
+            }
+            LoaderState::MapWantKey => {
+                let start = cursor.position;
+                let key = match c {
+                    b'"' => parse_string(cursor)?,
+                    b'\'' if cursor.syntax == JsonSyntax::Json5 => parse_string(cursor)?,
+                    c if cursor.syntax == JsonSyntax::Json5 && is_identifier_start(c) => {
+                        parse_identifier(cursor)?
+                    }
+                    _ => bail!(
+                        "Expected string as map key, but found {} at offset {}",
+                        describe(c),
+                        cursor.offset()
+                    ),
+                };
+                let Some(key) = key else {
+                    return Ok(Step::NeedMoreInput);
+                };
+                if !cursor.skip_whitespace()? {
+                    cursor.position = start;
+                    return Ok(Step::NeedMoreInput);
+                }
+                let Some(c) = cursor.peek() else {
+                    if cursor.eof {
+                        bail!("Unexpected end of input at offset {}", cursor.offset());
+                    }
+                    cursor.position = start;
+                    return Ok(Step::NeedMoreInput);
+                };
+                expect_character(cursor, c, b':', "':'")?;
+                *top = LoaderState::MapHasValue;
+                state.push(LoaderState::WantValue);
+                return Ok(Step::Event(Event::MapKey(key)));
+            }
+            LoaderState::ListInitial | LoaderState::ListHasValue if c == b']' => {
+                cursor.position += 1;
+                state.pop();
+                return Ok(Step::Event(Event::ListEnd));
+            }
+            LoaderState::ListWantValue if c == b']' && cursor.allows_trailing_commas() => {
+                cursor.position += 1;
+                state.pop();
+                return Ok(Step::Event(Event::ListEnd));
+            }
+            LoaderState::ListInitial | LoaderState::ListWantValue => {
+                *top = LoaderState::ListHasValue;
+                state.push(LoaderState::WantValue);
+            }
+            LoaderState::ListHasValue => {
+                expect_character(cursor, c, b',', "',' or ']'")?;
+                *top = LoaderState::ListWantValue;
+            }
+            LoaderState::DocumentEnd => {
+                bail!(
+                    "Unexpected trailing {} at offset {}",
+                    describe(c),
+                    cursor.offset()
+                );
+            }
+            LoaderState::Initial
+            | LoaderState::Lines
+            | LoaderState::LineEnd
+            | LoaderState::Done => unreachable!(),
+        }
+    }
+}
+
+fn expect_character(
+    cursor: &mut Cursor,
+    actual: u8,
+    expected: u8,
+    description: &str,
+) -> LoadumResult<()> {
+    if actual != expected {
+        bail!(
+            "Expected {}, but found {} at offset {}",
+            description,
+            describe(actual),
+            cursor.offset()
+        );
+    }
+    cursor.position += 1;
+    Ok(())
+}
+
+/// Parses a value or the start of a container, returns None if more input is needed
+fn parse_value<'data>(cursor: &mut Cursor<'data>) -> LoadumResult<Option<Event<'data>>> {
+    let Some(c) = cursor.peek() else {
+        return Ok(None);
+    };
+    let event = match c {
+        b'{' => {
+            cursor.position += 1;
+            Event::MapStart
+        }
+        b'[' => {
+            cursor.position += 1;
+            Event::ListStart
+        }
+        b'"' => match parse_string(cursor)? {
+            Some(string) => Event::Literal(string),
+            None => return Ok(None),
+        },
+        b'\'' if cursor.syntax == JsonSyntax::Json5 => match parse_string(cursor)? {
+            Some(string) => Event::Literal(string),
+            None => return Ok(None),
+        },
+        b't' => return parse_keyword(cursor, "true", Event::bool(true)),
+        b'f' => return parse_keyword(cursor, "false", Event::bool(false)),
+        b'n' => return parse_keyword(cursor, "null", Event::null()),
+        b'-' | b'0'..=b'9' => return parse_number(cursor),
+        b'+' | b'.' | b'I' | b'N' if cursor.syntax == JsonSyntax::Json5 => {
+            return parse_number(cursor);
+        }
+        _ => bail!("Unexpected {} at offset {}", describe(c), cursor.offset()),
+    };
+    Ok(Some(event))
+}
+
+fn parse_keyword<'data>(
+    cursor: &mut Cursor,
+    keyword: &str,
+    event: Event<'data>,
+) -> LoadumResult<Option<Event<'data>>> {
+    let rest = cursor.rest();
+    if rest.starts_with(keyword.as_bytes()) {
+        cursor.position += keyword.len();
+        return Ok(Some(event));
+    }
+    if !cursor.eof && keyword.as_bytes().starts_with(rest) {
+        return Ok(None);
+    }
+    bail!(
+        "Invalid literal at offset {}, expected '{}'",
+        cursor.offset(),
+        keyword
+    );
+}
+
+fn parse_number<'data>(cursor: &mut Cursor) -> LoadumResult<Option<Event<'data>>> {
+    let rest = cursor.rest();
+    let is_json5 = cursor.syntax == JsonSyntax::Json5;
+    // JSON5 numbers may be hexadecimal, Infinity or NaN
+    let length = rest
+        .iter()
+        .position(|c| {
+            let is_number_byte = matches!(c, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E');
+            !(is_number_byte || is_json5 && c.is_ascii_alphabetic())
+        })
+        .unwrap_or(rest.len());
+    if length == rest.len() && !cursor.eof {
+        return Ok(None);
+    }
+    let text = &rest[..length];
+    if is_json5 {
+        let Some(event) = parse_json5_number(text) else {
+            bail!(
+                "Invalid number '{}' at offset {}",
+                text.escape_ascii(),
+                cursor.offset()
+            );
+        };
+        cursor.position += length;
+        return Ok(Some(event));
+    }
+    if !is_valid_number(text) {
+        bail!(
+            "Invalid number '{}' at offset {}",
+            text.escape_ascii(),
+            cursor.offset()
+        );
+    }
+    // Only ASCII characters were matched above
+    let text = std::str::from_utf8(text)?;
+    let event = match text.parse::<i64>() {
+        // Negative zero keeps its sign as a float
+        Ok(integer) if integer != 0 || !text.starts_with('-') => Event::integer(integer),
+        // Fractions, exponents and integers that do not fit
+        _ => Event::number(text.parse::<f64>()?),
+    };
+    cursor.position += length;
+    Ok(Some(event))
+}
+
+/// Parses a JSON5 number, returns None if the text is not one
+fn parse_json5_number<'data>(text: &[u8]) -> Option<Event<'data>> {
+    let (negative, unsigned) = match text {
+        [b'-', rest @ ..] => (true, rest),
+        [b'+', rest @ ..] => (false, rest),
+        _ => (false, text),
+    };
+    let sign = if negative { -1.0 } else { 1.0 };
+    match unsigned {
+        b"Infinity" => return Some(Event::number(sign * f64::INFINITY)),
+        b"NaN" => return Some(Event::number(f64::NAN)),
+        [b'0', b'x' | b'X', digits @ ..] => {
+            let digits = std::str::from_utf8(digits).ok()?;
+            if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let event = match u64::from_str_radix(digits, 16) {
+                Ok(value) => {
+                    let value = if negative {
+                        -(value as i128)
+                    } else {
+                        value as i128
+                    };
+                    match i64::try_from(value) {
+                        Ok(integer) => Event::integer(integer),
+                        Err(_) => Event::number(value as f64),
+                    }
+                }
+                // More than 64 bits, the value is approximated
+                Err(_) => Event::number(
+                    sign * digits.bytes().fold(0.0, |value, digit| {
+                        value * 16.0 + (digit as char).to_digit(16).unwrap() as f64
+                    }),
+                ),
+            };
+            return Some(event);
+        }
+        _ => {}
+    }
+    // Leading and trailing decimal points are completed to the JSON number grammar
+    let mut normalized = Vec::with_capacity(text.len() + 3);
+    if negative {
+        normalized.push(b'-');
+    }
+    if unsigned.starts_with(b".") {
+        normalized.push(b'0');
+    }
+    for (index, c) in unsigned.iter().enumerate() {
+        normalized.push(*c);
+        let next = unsigned.get(index + 1);
+        if *c == b'.' && !next.is_some_and(u8::is_ascii_digit) {
+            normalized.push(b'0');
+        }
+    }
+    if !is_valid_number(&normalized) {
+        return None;
+    }
+    let text = std::str::from_utf8(&normalized).ok()?;
+    Some(match text.parse::<i64>() {
+        Ok(integer) if integer != 0 || !negative => Event::integer(integer),
+        _ => Event::number(text.parse::<f64>().ok()?),
+    })
+}
+
+/// Checks the number grammar from RFC 8259: `-?(0|[1-9][0-9]*)(\.[0-9]+)?([eE][+-]?[0-9]+)?`
+fn is_valid_number(text: &[u8]) -> bool {
+    fn digits(text: &[u8], index: &mut usize) -> usize {
+        let start = *index;
+        while let Some(b'0'..=b'9') = text.get(*index) {
+            *index += 1;
+        }
+        *index - start
+    }
+    let mut index = 0;
+    if text.get(index) == Some(&b'-') {
+        index += 1;
+    }
+    match text.get(index) {
+        Some(b'0') => index += 1,
+        Some(b'1'..=b'9') => {
+            digits(text, &mut index);
+        }
+        _ => return false,
+    }
+    if text.get(index) == Some(&b'.') {
+        index += 1;
+        if digits(text, &mut index) == 0 {
+            return false;
+        }
+    }
+    if let Some(b'e' | b'E') = text.get(index) {
+        index += 1;
+        if let Some(b'+' | b'-') = text.get(index) {
+            index += 1;
+        }
+        if digits(text, &mut index) == 0 {
+            return false;
+        }
+    }
+    index == text.len()
+}
+
+/// Parses a quoted string, returns None if more input is needed
+///
+/// Strings without escape sequences are borrowed from the input
+fn parse_string<'data>(cursor: &mut Cursor<'data>) -> LoadumResult<Option<Value<'data>>> {
+    let rest = cursor.rest();
+    let quote = rest[0];
+    let is_json5 = cursor.syntax == JsonSyntax::Json5;
+    let mut index = std::mem::take(&mut cursor.scanned).max(1);
+    // Escapes before the resumed position are not known, so resumed strings are unescaped
+    let mut has_escapes = index > 1;
+    let mut last_escape = None;
+    loop {
+        match rest.get(index) {
+            None => {
+                if cursor.eof {
+                    bail!("Unterminated string at offset {}", cursor.offset());
+                }
+                // An escape at the end may be incomplete, it is scanned again after the refill
+                cursor.scanned = resume_index(index, last_escape, rest.len());
+                return Ok(None);
+            }
+            Some(&c) if c == quote => break,
+            Some(b'\\') => {
+                has_escapes = true;
+                last_escape = Some(index);
+                index += 2;
+                // Escaped CRLF line breaks continue the string in JSON5
+                if is_json5 && rest.get(index - 1..index + 1) == Some(b"\r\n") {
+                    index += 1;
+                }
+            }
+            // JSON5 strings only exclude line breaks, like JavaScript strings
+            Some(b'\n' | b'\r') if is_json5 => {
+                bail!(
+                    "Unescaped line break in string at offset {}",
+                    cursor.offset() + index as u64
+                );
+            }
+            // U+2028 and U+2029 are line separators
+            Some(0xe2) if is_json5 => match rest.get(index + 1..index + 3) {
+                Some([0x80, 0xa8 | 0xa9]) => {
+                    bail!(
+                        "Unescaped line break in string at offset {}",
+                        cursor.offset() + index as u64
+                    );
+                }
+                None if !cursor.eof => {
+                    cursor.scanned = resume_index(index, last_escape, rest.len());
+                    return Ok(None);
+                }
+                _ => index += 1,
+            },
+            Some(&c) if c < 0x20 && !is_json5 => {
+                bail!(
+                    "Unescaped control character in string at offset {}",
+                    cursor.offset() + index as u64
+                );
+            }
+            Some(_) => index += 1,
+        }
+    }
+    let content = &rest[1..index];
+    let string = if has_escapes {
+        Value::String(unescape_string(content, cursor.offset() + 1, is_json5)?)
+    } else {
+        Value::BorrowedString(utf8(content, cursor.offset() + 1)?)
+    };
+    cursor.position += index + 1;
+    Ok(Some(string))
+}
+
+/// Index to resume a string scan at after a refill, before an escape that may be incomplete
+fn resume_index(index: usize, last_escape: Option<usize>, length: usize) -> usize {
+    match last_escape {
+        Some(escape) if escape + 3 > length => escape,
+        _ => index,
+    }
+}
+
+/// Parses an unquoted JSON5 map key, returns None if more input is needed
+fn parse_identifier<'data>(cursor: &mut Cursor<'data>) -> LoadumResult<Option<Value<'data>>> {
+    let rest = cursor.rest();
+    let Some(length) = rest
+        .iter()
+        .position(|c| !is_identifier_start(*c) && !c.is_ascii_digit())
+    else {
+        if !cursor.eof {
+            return Ok(None);
+        }
+        return Ok(Some(Value::BorrowedString(utf8(rest, cursor.offset())?)));
+    };
+    let identifier = utf8(&rest[..length], cursor.offset())?;
+    cursor.position += length;
+    Ok(Some(Value::BorrowedString(identifier)))
+}
+
+/// Checks for the start of an ECMAScript identifier, any non-ASCII character is accepted
+fn is_identifier_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c == b'$' || c >= 0x80
+}
+
+fn unescape_string(content: &[u8], offset: u64, is_json5: bool) -> LoadumResult<LoadumString> {
+    let mut string = LoadumString::with_capacity(content.len());
+    let mut index = 0;
+    while index < content.len() {
+        let Some(next_escape) = content[index..].iter().position(|c| *c == b'\\') else {
+            string.push_str(utf8(&content[index..], offset + index as u64)?);
+            break;
+        };
+        string.push_str(utf8(
+            &content[index..index + next_escape],
+            offset + index as u64,
+        )?);
+        index += next_escape;
+        let escape_offset = offset + index as u64;
+        let Some(&escaped) = content.get(index + 1) else {
+            bail!("Incomplete escape sequence at offset {}", escape_offset);
+        };
+        index += 2;
+        let c = match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{0008}',
+            b'f' => '\u{000c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = parse_hex4(content, &mut index, escape_offset)?;
+                let code_point = if (0xD800..=0xDBFF).contains(&high) {
+                    if content.get(index..index + 2) != Some(b"\\u") {
+                        bail!("Unpaired surrogate in escape at offset {}", escape_offset);
+                    }
+                    index += 2;
+                    let low = parse_hex4(content, &mut index, escape_offset)?;
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        bail!("Unpaired surrogate in escape at offset {}", escape_offset);
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                let Some(c) = char::from_u32(code_point) else {
+                    bail!("Unpaired surrogate in escape at offset {}", escape_offset);
+                };
+                c
+            }
+            b'\'' if is_json5 => '\'',
+            b'v' if is_json5 => '\u{000b}',
+            b'0' if is_json5 && !content.get(index).is_some_and(u8::is_ascii_digit) => '\0',
+            b'x' if is_json5 => {
+                let Some(digits) = content
+                    .get(index..index + 2)
+                    .filter(|digits| digits.iter().all(u8::is_ascii_hexdigit))
+                else {
+                    bail!("Invalid hexadecimal escape at offset {}", escape_offset);
+                };
+                index += 2;
+                // Two hexadecimal digits were checked above
+                u8::from_str_radix(std::str::from_utf8(digits)?, 16)? as char
+            }
+            // Escaped line breaks continue the string without adding a character
+            b'\n' if is_json5 => continue,
+            b'\r' if is_json5 => {
+                if content.get(index) == Some(&b'\n') {
+                    index += 1;
+                }
+                continue;
+            }
+            // Other characters except digits are escaped to themselves
+            c if is_json5 && c.is_ascii() && !c.is_ascii_digit() => c as char,
+            c if is_json5 && !c.is_ascii() => {
+                let bytes = &content[index - 1..(index + 3).min(content.len())];
+                let valid = match std::str::from_utf8(bytes) {
+                    Ok(valid) => valid,
+                    Err(error) => std::str::from_utf8(&bytes[..error.valid_up_to()])?,
+                };
+                let Some(c) = valid.chars().next() else {
+                    bail!("Invalid UTF-8 in string at offset {}", escape_offset + 1);
+                };
+                index += c.len_utf8() - 1;
+                if matches!(c, '\u{2028}' | '\u{2029}') {
+                    continue;
+                }
+                c
+            }
+            _ => bail!(
+                "Invalid escape sequence '\\{}' at offset {}",
+                escaped.escape_ascii(),
+                escape_offset
+            ),
+        };
+        string.push(c);
+    }
+    Ok(string)
+}
+
+fn parse_hex4(content: &[u8], index: &mut usize, offset: u64) -> LoadumResult<u32> {
+    let Some(digits) = content.get(*index..*index + 4) else {
+        bail!("Incomplete unicode escape at offset {}", offset);
+    };
+    let mut value = 0;
+    for digit in digits {
+        let Some(digit) = (*digit as char).to_digit(16) else {
+            bail!("Invalid unicode escape at offset {}", offset);
+        };
+        value = value * 16 + digit;
+    }
+    *index += 4;
+    Ok(value)
+}
+
+fn utf8(bytes: &[u8], offset: u64) -> LoadumResult<&str> {
+    match std::str::from_utf8(bytes) {
+        Ok(string) => Ok(string),
+        Err(error) => bail!(
+            "Invalid UTF-8 in string at offset {}",
+            offset + error.valid_up_to() as u64
+        ),
+    }
+}
+
+fn describe(c: u8) -> String {
+    format!("'{}'", c.escape_ascii())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonLoader;
+    use crate::JsonSyntax;
+    use crate::json_dumper::JsonDumper;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::result::LoadumResult;
+    use loadum::value::Value;
+    use std::fmt::Write;
+    use std::io::Read;
+
+    /// Reader returning a single byte per read call, to exercise buffer refills
+    struct TrickleReader<'a>(&'a [u8]);
+
+    impl Read for TrickleReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn format_events(loader: JsonLoader) -> String {
+        let mut output = String::new();
+        for event in loader {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        test_syntax(input, JsonSyntax::Json, expected);
+    }
+
+    fn test_syntax(input: &str, syntax: JsonSyntax, expected: Expect) {
+        let output = format_events(JsonLoader::new(input).with_syntax(syntax));
+        let streamed = format_events(
+            JsonLoader::from_reader(TrickleReader(input.as_bytes())).with_syntax(syntax),
+        );
+        assert_eq!(output, streamed, "Streaming output differs");
+        expected.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_empty_map() {
+        test_loader(
+            " { } ",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            r#"[null, true, false, 0, -0, -1.5, 2e3, "foo"]"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Integer(0))
+                Literal(Number(-0.0))
+                Literal(Number(-1.5))
+                Literal(Number(2000.0))
+                Literal(String("foo"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_top_level_scalar() {
+        test_loader(
+            "42",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(42))
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_nested() {
+        test_loader(
+            r#"{"a": {"b": [1, {"c": []}]}, "d": null}"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("a"))
+                MapStart
+                MapKey(String("b"))
+                ListStart
+                Literal(Integer(1))
+                MapStart
+                MapKey(String("c"))
+                ListStart
+                ListEnd
+                MapEnd
+                ListEnd
+                MapEnd
+                MapKey(String("d"))
+                Literal(Null)
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        test_loader(
+            r#"["\"\\\/\b\f\n\r\t", "ä€😀", "👨‍👩‍👦‍👦"]"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(String("\"\\/\u{8}\u{c}\n\r\t"))
+                Literal(String("ä€😀"))
+                Literal(String("👨\u{200d}👩\u{200d}👦\u{200d}👦"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            "",
+            expect![[r#"
+                DocumentStart
+                Error: Unexpected end of input at offset 0
+            "#]],
+        );
+        test_loader(
+            "[1,]",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(1))
+                Error: Unexpected ']' at offset 3
+            "#]],
+        );
+        test_loader(
+            "{1: 2}",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Expected string as map key, but found '1' at offset 1
+            "#]],
+        );
+        test_loader(
+            r#"{"a" 2}"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Expected ':', but found '2' at offset 5
+            "#]],
+        );
+        test_loader(
+            r#"["abc"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Unterminated string at offset 1
+            "#]],
+        );
+        test_loader(
+            "[01]",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Invalid number '01' at offset 1
+            "#]],
+        );
+        test_loader(
+            "[nul]",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Invalid literal at offset 1, expected 'null'
+            "#]],
+        );
+        test_loader(
+            r#"["\x"]"#,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Invalid escape sequence '\x' at offset 2
+            "#]],
+        );
+        test_loader(
+            "{} {}",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapEnd
+                Error: Unexpected trailing '{' at offset 3
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_jsonc() {
+        test_syntax(
+            "// settings\n{\n  \"a\": [1, 2, /* two */],\n  \"b\": {\"c\": null,},\n} // end",
+            JsonSyntax::Jsonc,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("a"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                MapKey(String("b"))
+                MapStart
+                MapKey(String("c"))
+                Literal(Null)
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+        test_syntax(
+            "{\"a\": 1 /* open",
+            JsonSyntax::Jsonc,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("a"))
+                Literal(Integer(1))
+                Error: Unterminated comment at offset 8
+            "#]],
+        );
+        test_syntax(
+            "[,]",
+            JsonSyntax::Jsonc,
+            expect![[r#"
+            DocumentStart
+            ListStart
+            Error: Unexpected ',' at offset 1
+        "#]],
+        );
+        test_syntax(
+            "{a: 1}",
+            JsonSyntax::Jsonc,
+            expect![[r#"
+            DocumentStart
+            MapStart
+            Error: Expected string as map key, but found 'a' at offset 1
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_json5() {
+        test_syntax(
+            r#"{
+  // comments
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  negativeHex: -0X10,
+  leadingDecimalPoint: .8675309, andTrailing: +8675309.,
+  positiveSign: +1, negativeZero: -0,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+  infinities: [Infinity, -Infinity],
+  escapes: '\x41\v\0\q\'',
+  $_ident1: NaN,
+}"#,
+            JsonSyntax::Json5,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("unquoted"))
+                Literal(String("and you can quote me on that"))
+                MapKey(String("singleQuotes"))
+                Literal(String("I can use \"double quotes\" here"))
+                MapKey(String("lineBreaks"))
+                Literal(String("Look, Mom! No \\n's!"))
+                MapKey(String("hexadecimal"))
+                Literal(Integer(912559))
+                MapKey(String("negativeHex"))
+                Literal(Integer(-16))
+                MapKey(String("leadingDecimalPoint"))
+                Literal(Number(0.8675309))
+                MapKey(String("andTrailing"))
+                Literal(Number(8675309.0))
+                MapKey(String("positiveSign"))
+                Literal(Integer(1))
+                MapKey(String("negativeZero"))
+                Literal(Number(-0.0))
+                MapKey(String("trailingComma"))
+                Literal(String("in objects"))
+                MapKey(String("andIn"))
+                ListStart
+                Literal(String("arrays"))
+                ListEnd
+                MapKey(String("backwardsCompatible"))
+                Literal(String("with JSON"))
+                MapKey(String("infinities"))
+                ListStart
+                Literal(Number(inf))
+                Literal(Number(-inf))
+                ListEnd
+                MapKey(String("escapes"))
+                Literal(String("A\u{b}\0q'"))
+                MapKey(String("$_ident1"))
+                Literal(Number(NaN))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+        test_syntax(
+            "[0x]",
+            JsonSyntax::Json5,
+            expect![[r#"
+            DocumentStart
+            ListStart
+            Error: Invalid number '0x' at offset 1
+        "#]],
+        );
+        test_syntax(
+            "[Infinit]",
+            JsonSyntax::Json5,
+            expect![[r#"
+            DocumentStart
+            ListStart
+            Error: Invalid number 'Infinit' at offset 1
+        "#]],
+        );
+        test_syntax(
+            "['a\u{1}\u{7f}\u{85}\tb']",
+            JsonSyntax::Json5,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(String("a\u{1}\u{7f}\u{85}\tb"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_syntax(
+            "['a\nb']",
+            JsonSyntax::Json5,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Unescaped line break in string at offset 3
+            "#]],
+        );
+        test_syntax(
+            "['a\u{2029}b']",
+            JsonSyntax::Json5,
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Error: Unescaped line break in string at offset 3
+            "#]],
+        );
+        test_syntax(
+            r"['a\1']",
+            JsonSyntax::Json5,
+            expect![[r#"
+            DocumentStart
+            ListStart
+            Error: Invalid escape sequence '\1' at offset 3
+        "#]],
+        );
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let input = "{\"level\": \"info\"}\r\n\n  [1, 2]  \n\"last\"";
+        let output = format_events(JsonLoader::new(input).with_json_lines(true));
+        let streamed = format_events(
+            JsonLoader::from_reader(TrickleReader(input.as_bytes())).with_json_lines(true),
+        );
+        assert_eq!(output, streamed);
+        expect![[r#"
+            DocumentStart
+            MapStart
+            MapKey(String("level"))
+            Literal(String("info"))
+            MapEnd
+            DocumentEnd
+            DocumentStart
+            ListStart
+            Literal(Integer(1))
+            Literal(Integer(2))
+            ListEnd
+            DocumentEnd
+            DocumentStart
+            Literal(String("last"))
+            DocumentEnd
+        "#]]
+        .assert_eq(&output);
+        let output: String = ["", " \n\n", "1 2\n", "{\"a\": 1\n"]
+            .into_iter()
+            .map(|input| format_events(JsonLoader::new(input).with_json_lines(true)))
+            .collect();
+        expect![[r#"
+            DocumentStart
+            Literal(Integer(1))
+            Error: Expected end of line after document, but found '2' at offset 2
+            DocumentStart
+            MapStart
+            MapKey(String("a"))
+            Literal(Integer(1))
+            Error: Unexpected end of input at offset 8
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_borrowed_strings() {
+        let input = r#"{"plain": "foo", "escaped": "a\nb"}"#;
+        let events = JsonLoader::new(input)
+            .collect::<LoadumResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(events[2], Event::MapKey(Value::BorrowedString("plain")));
+        assert_eq!(events[3], Event::Literal(Value::BorrowedString("foo")));
+        assert_eq!(events[4], Event::MapKey(Value::BorrowedString("escaped")));
+        assert_eq!(events[5], Event::string("a\nb"));
+        let streamed = JsonLoader::from_reader(input.as_bytes())
+            .collect::<LoadumResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(streamed[3], Event::string("foo"));
+    }
+
+    #[test]
+    fn test_long_string_in_small_reads() {
+        // Scanned from its start after every read, this string would take minutes
+        let string = "a\\\"".repeat(1 << 18);
+        let input = format!(r#"["{}"]"#, string);
+        let events = JsonLoader::from_reader(TrickleReader(input.as_bytes()))
+            .collect::<LoadumResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(events[2], Event::string("a\"".repeat(1 << 18)));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let loader = JsonLoader::from_reader(&b"[\"\xff\"]"[..]);
+        let error = loader.collect::<LoadumResult<Vec<_>>>().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid UTF-8 in string at offset 2");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = r#"{"list": [1, "two", {"three": [true, null]}]}"#;
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        for event in JsonLoader::from_reader(input.as_bytes()) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"list": [
+            		1,
+            		"two",
+            		{
+            			"three": [
+            				true,
+            				null
+            			]
+            		}
+            	]
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -1 +1,19 @@
-pub mod json_dumper;
+#[cfg(feature = "async")]
+pub mod async_json_dumper;
+#[cfg(feature = "async")]
+pub mod async_json_loader;
+pub mod json_dumper;
+pub mod json_loader;
+
+/// The JSON dialect read by a loader or written by a dumper
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonSyntax {
+    /// Strict JSON as specified by RFC 8259
+    #[default]
+    Json,
+    /// JSON with `//` and `/* */` comments and trailing commas, as used by VS Code and tsconfig
+    Jsonc,
+    /// JSON5, which adds unquoted keys, single quoted strings, hexadecimal numbers, `Infinity`
+    /// and `NaN` to JSONC
+    Json5,
+}
@@ -0,0 +1,434 @@
+use crate::is_identifier;
+use loadum::base64;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Dumps KDL 2 documents, following the convention described in the crate documentation
+///
+/// Maps are written as children blocks, lists of several scalars as arguments and other lists
+/// as children named `-`. Only the innermost tag of a value is written as its type annotation.
+/// Empty lists are written as empty children blocks, which load as empty maps.
+pub struct KdlDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> KdlDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> KdlDumper<'write> {
+        KdlDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for KdlDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut formatter = KdlFormatter {
+            output: String::new(),
+            key_policy: self.key_policy,
+        };
+        match root.untagged() {
+            Node::Map(entries) => formatter.write_entries(entries, 0)?,
+            Node::List(items) => formatter.write_items(items, 0)?,
+            Node::Scalar(_) => bail!("KDL documents must be maps or lists, but found {:?}", root),
+            Node::Tagged(..) => unreachable!(),
+        }
+        self.write.write_all(formatter.output.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct KdlFormatter {
+    output: String,
+    key_policy: KeyPolicy,
+}
+
+impl KdlFormatter {
+    fn write_entries(
+        &mut self,
+        entries: &[(Node, Node)],
+        indentation_level: usize,
+    ) -> LoadumResult<()> {
+        for (key, value) in entries {
+            let name = self.key_policy.key_string(key, "KDL node names")?;
+            self.write_node(&name, value, indentation_level)?;
+        }
+        Ok(())
+    }
+
+    fn write_items(&mut self, items: &[Node], indentation_level: usize) -> LoadumResult<()> {
+        for item in items {
+            self.write_node("-", item, indentation_level)?;
+        }
+        Ok(())
+    }
+
+    fn write_node(
+        &mut self,
+        name: &str,
+        value: &Node,
+        indentation_level: usize,
+    ) -> LoadumResult<()> {
+        for _ in 0..indentation_level {
+            self.output.push_str("    ");
+        }
+        let (tag, value) = innermost_tag(value);
+        if let Node::Scalar(value) = value {
+            self.write_string(name);
+            self.output.push(' ');
+            self.write_annotation(tag);
+            self.write_scalar(value);
+            self.output.push('\n');
+            return Ok(());
+        }
+        self.write_annotation(tag);
+        self.write_string(name);
+        match value {
+            // A single argument would load as a scalar instead of a list
+            Node::List(items)
+                if items.len() > 1
+                    && items
+                        .iter()
+                        .all(|item| matches!(item.untagged(), Node::Scalar(_))) =>
+            {
+                for item in items {
+                    let (tag, item) = innermost_tag(item);
+                    let Node::Scalar(item) = item else {
+                        unreachable!()
+                    };
+                    self.output.push(' ');
+                    self.write_annotation(tag);
+                    self.write_scalar(item);
+                }
+                self.output.push('\n');
+            }
+            Node::List(items) if items.is_empty() => self.output.push_str(" {}\n"),
+            Node::Map(entries) if entries.is_empty() => self.output.push_str(" {}\n"),
+            Node::List(items) => {
+                self.output.push_str(" {\n");
+                self.write_items(items, indentation_level + 1)?;
+                self.close_block(indentation_level);
+            }
+            Node::Map(entries) => {
+                self.output.push_str(" {\n");
+                self.write_entries(entries, indentation_level + 1)?;
+                self.close_block(indentation_level);
+            }
+            Node::Scalar(_) | Node::Tagged(..) => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn close_block(&mut self, indentation_level: usize) {
+        for _ in 0..indentation_level {
+            self.output.push_str("    ");
+        }
+        self.output.push_str("}\n");
+    }
+
+    fn write_annotation(&mut self, tag: Option<&str>) {
+        if let Some(tag) = tag {
+            self.output.push('(');
+            self.write_string(tag);
+            self.output.push(')');
+        }
+    }
+
+    fn write_scalar(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.output.push_str("#null"),
+            Value::Boolean(b) => self.output.push_str(if *b { "#true" } else { "#false" }),
+            Value::Integer(i) => write!(self.output, "{}", i).unwrap(),
+            Value::Number(n) if n.is_nan() => self.output.push_str("#nan"),
+            Value::Number(n) if n.is_infinite() => {
+                self.output
+                    .push_str(if *n > 0.0 { "#inf" } else { "#-inf" })
+            }
+            // Debug formatting always includes a fraction or exponent, keeping floats distinct
+            Value::Number(n) => write!(self.output, "{:?}", n).unwrap(),
+            // String values are always quoted, so they cannot be mistaken for names
+            Value::String(s) => self.write_quoted(s),
+            Value::BorrowedString(s) => self.write_quoted(s),
+            // KDL has no binary type, so bytes are written as a base64 string
+            Value::Bytes(bytes) => self.write_quoted(&base64::encode(bytes)),
+            Value::DateTime(date_time) => self.write_quoted(&date_time.to_string()),
+        }
+    }
+
+    /// Writes a name or type as an identifier if possible, otherwise quoted
+    fn write_string(&mut self, s: &str) {
+        if is_identifier(s) {
+            self.output.push_str(s);
+        } else {
+            self.write_quoted(s);
+        }
+    }
+
+    fn write_quoted(&mut self, s: &str) {
+        self.output.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\u{0008}' => self.output.push_str("\\b"),
+                '\t' => self.output.push_str("\\t"),
+                '\n' => self.output.push_str("\\n"),
+                '\u{000c}' => self.output.push_str("\\f"),
+                '\r' => self.output.push_str("\\r"),
+                c if c.is_control() => write!(self.output, "\\u{{{:x}}}", c as u32).unwrap(),
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+}
+
+/// Returns the innermost tag of a node and the untagged node
+fn innermost_tag<'node, 'value>(
+    node: &'node Node<'value>,
+) -> (Option<&'node str>, &'node Node<'value>) {
+    match node {
+        Node::Tagged(tag, inner) => match innermost_tag(inner) {
+            (None, inner) => (Some(tag), inner),
+            tagged => tagged,
+        },
+        node => (None, node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kdl_dumper::KdlDumper;
+    use crate::kdl_loader::KdlLoader;
+    use expect_test::{Expect, expect};
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump(events: &[Event], key_policy: KeyPolicy) -> String {
+        let mut output = vec![];
+        let mut dumper = KdlDumper::new(&mut output).with_key_policy(key_policy);
+        let result = [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+            .try_for_each(|event| dumper.emit(event));
+        drop(dumper);
+        match result {
+            Ok(()) => String::from_utf8(output).unwrap(),
+            Err(error) => format!("Error: {}", error),
+        }
+    }
+
+    fn test_dump(events: &[Event], expected: Expect) {
+        expected.assert_eq(&dump(events, KeyPolicy::Stringify));
+    }
+
+    #[test]
+    fn test_values() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("string"),
+                Event::string("quote \" backslash \\ newline \n bell \u{7}"),
+                Event::map_key("integer"),
+                Event::integer(-42),
+                Event::map_key("floats"),
+                Event::ListStart,
+                Event::number(1),
+                Event::number(6.626e-34),
+                Event::number(f64::NEG_INFINITY),
+                Event::number(f64::NAN),
+                Event::ListEnd,
+                Event::map_key("null"),
+                Event::null(),
+                Event::map_key("bool"),
+                Event::bool(false),
+                Event::map_key("bytes"),
+                Event::bytes(&b"foo"[..]),
+                Event::map_key("date_time"),
+                Event::date_time(DateTime::parse("1979-05-27T07:32:00Z").unwrap()),
+                Event::MapKey(Value::integer(1)),
+                Event::string("number key"),
+                Event::map_key("needs quotes"),
+                Event::tag("u8"),
+                Event::integer(5),
+                Event::MapEnd,
+            ],
+            expect![[r#"
+                string "quote \" backslash \\ newline \n bell \u{7}"
+                integer -42
+                floats 1.0 6.626e-34 #-inf #nan
+                "null" #null
+                bool #false
+                bytes "Zm9v"
+                date_time "1979-05-27T07:32:00Z"
+                "1" "number key"
+                "needs quotes" (u8)5
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_nesting() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("server"),
+                Event::tag("config"),
+                Event::MapStart,
+                Event::map_key("host"),
+                Event::string("localhost"),
+                Event::map_key("ports"),
+                Event::ListStart,
+                Event::integer(80),
+                Event::tag("u16"),
+                Event::integer(443),
+                Event::ListEnd,
+                Event::map_key("single"),
+                Event::ListStart,
+                Event::integer(1),
+                Event::ListEnd,
+                Event::map_key("users"),
+                Event::ListStart,
+                Event::MapStart,
+                Event::map_key("name"),
+                Event::string("alice"),
+                Event::MapEnd,
+                Event::ListStart,
+                Event::ListEnd,
+                Event::ListEnd,
+                Event::map_key("empty"),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            expect![[r#"
+                (config)server {
+                    host "localhost"
+                    ports 80 (u16)443
+                    single {
+                        - 1
+                    }
+                    users {
+                        - {
+                            name "alice"
+                        }
+                        - {}
+                    }
+                    empty {}
+                }
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output = [
+            dump(&[Event::integer(1)], KeyPolicy::Stringify),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::MapKey(Value::integer(1)),
+                    Event::null(),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Reject,
+            ),
+            dump(
+                &[
+                    Event::MapStart,
+                    Event::ComplexKey,
+                    Event::ListStart,
+                    Event::ListEnd,
+                    Event::null(),
+                    Event::MapEnd,
+                ],
+                KeyPolicy::Stringify,
+            ),
+        ]
+        .join("\n");
+        expect![[r#"
+            Error: KDL documents must be maps or lists, but found Scalar(Integer(1))
+            Error: KDL node names must be strings, but found Scalar(Integer(1))
+            Error: KDL node names cannot be composite, but found List([])"#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_empty_lists() {
+        let events = [
+            Event::ListStart,
+            Event::ListStart,
+            Event::ListEnd,
+            Event::ListEnd,
+        ];
+        let output = dump(&events, KeyPolicy::Stringify);
+        expect![[r#"
+            - {}
+        "#]]
+        .assert_eq(&output);
+        // Empty lists load back as empty maps
+        let loaded: Vec<Event> = KdlLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        assert_eq!(
+            loaded,
+            [
+                Event::DocumentStart,
+                Event::ListStart,
+                Event::MapStart,
+                Event::MapEnd,
+                Event::ListEnd,
+                Event::DocumentEnd,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = r#"server {
+    host "localhost"
+    port (u16)8080
+    users "alice" "bob"
+    matrix {
+        - 1 2
+        - {
+            - 3
+        }
+    }
+    options {
+        "needs quotes" #null
+        retries (u8)3
+    }
+}
+"#;
+        let mut output = vec![];
+        let mut dumper = KdlDumper::new(&mut output);
+        for event in KdlLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+}
@@ -0,0 +1,881 @@
+use crate::{is_identifier_char, is_newline};
+use loadum::LoadumString;
+use loadum::depth::Depth;
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::node::Node;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+
+/// Loads KDL documents, following the convention described in the crate documentation
+///
+/// Both KDL 2 and KDL 1 syntax are accepted, e.g. `#true` as well as `true` and `#"raw"#` as
+/// well as `r#"raw"#`.
+pub struct KdlLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> KdlLoader<'source> {
+    pub fn new(source: &'source str) -> KdlLoader<'source> {
+        KdlLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for KdlLoader<'source> {}
+
+impl<'source> Iterator for KdlLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                bytes: self.source.as_bytes(),
+                position: 0,
+                depth: Depth::default(),
+            };
+            match parser.parse_document() {
+                Ok(nodes) => {
+                    let mut events = vec![Event::DocumentStart];
+                    block_value(nodes).to_events(&mut events);
+                    events.push(Event::DocumentEnd);
+                    self.events = Some(events.into_iter());
+                }
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+struct KdlNode<'source> {
+    name: Value<'source>,
+    annotation: Option<LoadumString>,
+    arguments: Vec<Node<'source>>,
+    properties: Vec<(Value<'source>, Node<'source>)>,
+    children: Option<Vec<KdlNode<'source>>>,
+}
+
+/// Map entries whose values are collected in a list if their key is repeated
+#[derive(Default)]
+struct Entries<'source> {
+    entries: Vec<(Value<'source>, Vec<Node<'source>>)>,
+}
+
+impl<'source> Entries<'source> {
+    fn insert(&mut self, key: Value<'source>, value: Node<'source>) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|(existing, _)| existing.as_str() == key.as_str())
+        {
+            Some((_, values)) => values.push(value),
+            None => self.entries.push((key, vec![value])),
+        }
+    }
+
+    fn into_node(self) -> Node<'source> {
+        let entries = self.entries.into_iter().map(|(key, mut values)| {
+            let value = if values.len() == 1 {
+                values.pop().unwrap()
+            } else {
+                Node::List(values)
+            };
+            (Node::Scalar(key), value)
+        });
+        Node::Map(entries.collect())
+    }
+}
+
+/// The value of the document or a children block
+fn block_value(nodes: Vec<KdlNode>) -> Node {
+    if !nodes.is_empty() && nodes.iter().all(|node| node.name.as_str() == Some("-")) {
+        return Node::List(nodes.into_iter().map(node_value).collect());
+    }
+    let mut entries = Entries::default();
+    for node in nodes {
+        let name = node.name.clone();
+        entries.insert(name, node_value(node));
+    }
+    entries.into_node()
+}
+
+fn node_value(node: KdlNode) -> Node {
+    let value = if node.properties.is_empty() && node.children.is_none() {
+        arguments_value(node.arguments)
+    } else if node.properties.is_empty() && node.arguments.is_empty() {
+        block_value(node.children.unwrap_or_default())
+    } else {
+        let mut entries = Entries::default();
+        if !node.arguments.is_empty() {
+            entries.insert(Value::BorrowedString("-"), arguments_value(node.arguments));
+        }
+        for (key, value) in node.properties {
+            entries.insert(key, value);
+        }
+        for child in node.children.into_iter().flatten() {
+            let name = child.name.clone();
+            entries.insert(name, node_value(child));
+        }
+        entries.into_node()
+    };
+    tagged(value, node.annotation)
+}
+
+fn arguments_value(mut arguments: Vec<Node>) -> Node {
+    match arguments.len() {
+        0 => Node::Scalar(Value::Null),
+        1 => arguments.pop().unwrap(),
+        _ => Node::List(arguments),
+    }
+}
+
+fn tagged(node: Node, annotation: Option<LoadumString>) -> Node {
+    match annotation {
+        Some(tag) => Node::Tagged(tag, Box::new(node)),
+        None => node,
+    }
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Vec<KdlNode<'source>>> {
+        let nodes = self.parse_nodes()?;
+        if self.peek().is_some() {
+            return Err(self.error("Unexpected '}'"));
+        }
+        Ok(nodes)
+    }
+
+    /// Parses nodes up to the end of the input or a closing brace
+    fn parse_nodes(&mut self) -> LoadumResult<Vec<KdlNode<'source>>> {
+        let mut nodes = vec![];
+        loop {
+            self.skip_line_space()?;
+            if matches!(self.peek(), None | Some('}')) {
+                return Ok(nodes);
+            }
+            let is_commented = self.skip_slashdash()?;
+            let node = self.parse_node()?;
+            if !is_commented {
+                nodes.push(node);
+            }
+        }
+    }
+
+    fn parse_node(&mut self) -> LoadumResult<KdlNode<'source>> {
+        let annotation = self.parse_annotation()?;
+        let start = self.position;
+        let Some(name) = self.parse_value()?.filter(|name| name.as_str().is_some()) else {
+            return Err(self.error_at(start, "Expected node name"));
+        };
+        let mut node = KdlNode {
+            name,
+            annotation,
+            arguments: vec![],
+            properties: vec![],
+            children: None,
+        };
+        loop {
+            let has_space = self.skip_node_space()?;
+            let is_commented = self.skip_slashdash()?;
+            match self.peek() {
+                None | Some('}') => return Ok(node),
+                Some(';') => {
+                    self.position += 1;
+                    return Ok(node);
+                }
+                Some(c) if is_newline(c) || self.rest().starts_with(b"//") => return Ok(node),
+                Some('{') => {
+                    let children = self.parse_children()?;
+                    if is_commented {
+                        continue;
+                    }
+                    if node.children.is_some() {
+                        return Err(self.error("Node has more than one children block"));
+                    }
+                    node.children = Some(children);
+                }
+                Some(_) if node.children.is_some() => {
+                    return Err(self.error("Expected end of node after children block"));
+                }
+                Some(_) if !has_space && !is_commented => {
+                    return Err(self.error("Expected whitespace before argument or property"));
+                }
+                Some(_) => self.parse_entry(&mut node, !is_commented)?,
+            }
+        }
+    }
+
+    fn parse_children(&mut self) -> LoadumResult<Vec<KdlNode<'source>>> {
+        let start = self.position;
+        self.depth.enter().map_err(|error| self.error(error))?;
+        self.position += 1;
+        let children = self.parse_nodes()?;
+        if self.peek() != Some('}') {
+            return Err(self.error_at(start, "Unterminated children block"));
+        }
+        self.position += 1;
+        self.depth.exit();
+        Ok(children)
+    }
+
+    /// Parses an argument or property, which is only added to the node if it is kept
+    fn parse_entry(&mut self, node: &mut KdlNode<'source>, keep: bool) -> LoadumResult<()> {
+        let start = self.position;
+        let annotation = self.parse_annotation()?;
+        let Some(value) = self.parse_value()? else {
+            return Err(self.error("Expected argument or property"));
+        };
+        let before_space = self.position;
+        self.skip_node_space()?;
+        if self.peek() != Some('=') {
+            self.position = before_space;
+            if keep {
+                node.arguments.push(tagged(Node::Scalar(value), annotation));
+            }
+            return Ok(());
+        }
+        if annotation.is_some() || value.as_str().is_none() {
+            return Err(self.error_at(start, "Expected property name"));
+        }
+        self.position += 1;
+        self.skip_node_space()?;
+        let annotation = self.parse_annotation()?;
+        let Some(property) = self.parse_value()? else {
+            return Err(self.error("Expected property value"));
+        };
+        if keep {
+            let property = tagged(Node::Scalar(property), annotation);
+            // The rightmost property with a name wins
+            match node
+                .properties
+                .iter_mut()
+                .find(|(key, _)| key.as_str() == value.as_str())
+            {
+                Some((_, existing)) => *existing = property,
+                None => node.properties.push((value, property)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses a type annotation like `(u8)`
+    fn parse_annotation(&mut self) -> LoadumResult<Option<LoadumString>> {
+        if self.peek() != Some('(') {
+            return Ok(None);
+        }
+        self.position += 1;
+        self.skip_node_space()?;
+        let start = self.position;
+        let name = self.parse_value()?;
+        let Some(name) = name.as_ref().and_then(Value::as_str) else {
+            return Err(self.error_at(start, "Expected type name"));
+        };
+        let name = LoadumString::from(name);
+        self.skip_node_space()?;
+        if self.peek() != Some(')') {
+            return Err(self.error("Expected ')' after type name"));
+        }
+        self.position += 1;
+        self.skip_node_space()?;
+        Ok(Some(name))
+    }
+
+    /// Parses a string, number or keyword, returns None if there is no value at the position
+    fn parse_value(&mut self) -> LoadumResult<Option<Value<'source>>> {
+        let rest = self.rest();
+        let value = match self.peek() {
+            Some('"') => self.parse_quoted_string()?,
+            Some('#') if raw_string_hashes(rest).is_some() => self.parse_raw_string()?,
+            Some('r') if raw_string_hashes(&rest[1..]).is_some() => {
+                self.position += 1;
+                self.parse_raw_string()?
+            }
+            Some('#') => {
+                let start = self.position;
+                self.position += 1;
+                let keyword = self.parse_identifier();
+                match keyword {
+                    "true" => Value::Boolean(true),
+                    "false" => Value::Boolean(false),
+                    "null" => Value::Null,
+                    "inf" => Value::Number(f64::INFINITY),
+                    "-inf" => Value::Number(f64::NEG_INFINITY),
+                    "nan" => Value::Number(f64::NAN),
+                    keyword => {
+                        return Err(self.error_at(start, format!("Invalid keyword '#{}'", keyword)));
+                    }
+                }
+            }
+            Some(c) if is_identifier_char(c) => {
+                let start = self.position;
+                let identifier = self.parse_identifier();
+                let unsigned = identifier.strip_prefix(['+', '-']).unwrap_or(identifier);
+                let unsigned = unsigned.strip_prefix('.').unwrap_or(unsigned);
+                if unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+                    let Some(number) = parse_number(identifier) else {
+                        return Err(
+                            self.error_at(start, format!("Invalid number '{}'", identifier))
+                        );
+                    };
+                    number
+                } else {
+                    // Keywords of KDL 1
+                    match identifier {
+                        "true" => Value::Boolean(true),
+                        "false" => Value::Boolean(false),
+                        "null" => Value::Null,
+                        identifier => Value::BorrowedString(identifier),
+                    }
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+
+    fn parse_identifier(&mut self) -> &'source str {
+        let rest = &self.source[self.position..];
+        let length = rest
+            .find(|c: char| !is_identifier_char(c))
+            .unwrap_or(rest.len());
+        self.position += length;
+        &rest[..length]
+    }
+
+    fn parse_quoted_string(&mut self) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        let is_multi_line = self.rest().starts_with(b"\"\"\"");
+        let quotes = if is_multi_line { 3 } else { 1 };
+        let mut index = start + quotes;
+        loop {
+            match self.bytes.get(index) {
+                None => return Err(self.error("Unterminated string")),
+                Some(b'\\') => index += 2,
+                Some(b'"') if !is_multi_line || self.bytes[index..].starts_with(b"\"\"\"") => break,
+                Some(_) => index += 1,
+            }
+        }
+        let content = &self.source[start + quotes..index];
+        self.position = index + quotes;
+        let string = if is_multi_line {
+            dedent(content).and_then(|content| unescape(&content))
+        } else if !content.contains('\\') {
+            return Ok(Value::BorrowedString(content));
+        } else {
+            unescape(content)
+        };
+        Ok(Value::String(
+            string.map_err(|error| self.error_at(start, error))?,
+        ))
+    }
+
+    /// Parses raw strings like `#"a "quoted" word"#`, which have no escapes
+    fn parse_raw_string(&mut self) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        let hashes = raw_string_hashes(self.rest()).unwrap();
+        let is_multi_line = self.rest()[hashes..].starts_with(b"\"\"\"");
+        let quotes = if is_multi_line { "\"\"\"" } else { "\"" };
+        let content_start = start + hashes + quotes.len();
+        let terminator = format!("{}{}", quotes, "#".repeat(hashes));
+        let Some(length) = self.source[content_start..].find(&terminator) else {
+            return Err(self.error("Unterminated string"));
+        };
+        let content = &self.source[content_start..content_start + length];
+        self.position = content_start + length + terminator.len();
+        if is_multi_line {
+            let content = dedent(content).map_err(|error| self.error_at(start, error))?;
+            return Ok(Value::String(content));
+        }
+        Ok(Value::BorrowedString(content))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    fn rest(&self) -> &'source [u8] {
+        &self.bytes[self.position..]
+    }
+
+    /// Skips a `/-` comment marker, returns whether the following item is commented out
+    fn skip_slashdash(&mut self) -> LoadumResult<bool> {
+        if !self.rest().starts_with(b"/-") {
+            return Ok(false);
+        }
+        self.position += 2;
+        self.skip_line_space()?;
+        Ok(true)
+    }
+
+    /// Skips whitespace, newlines and comments between nodes
+    fn skip_line_space(&mut self) -> LoadumResult<()> {
+        loop {
+            self.skip_node_space()?;
+            match self.peek() {
+                Some(c) if is_newline(c) => self.position += c.len_utf8(),
+                Some('/') if self.rest().starts_with(b"//") => self.skip_line_comment(),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips whitespace, block comments and line continuations within a node
+    ///
+    /// Returns whether anything was skipped.
+    fn skip_node_space(&mut self) -> LoadumResult<bool> {
+        let start = self.position;
+        loop {
+            match self.peek() {
+                Some(c) if (c.is_whitespace() && !is_newline(c)) || c == '\u{feff}' => {
+                    self.position += c.len_utf8();
+                }
+                Some('/') if self.rest().starts_with(b"/*") => self.skip_block_comment()?,
+                Some('\\') => {
+                    self.position += 1;
+                    while let Some(c) = self.peek().filter(|c| c.is_whitespace() && !is_newline(*c))
+                    {
+                        self.position += c.len_utf8();
+                    }
+                    if self.rest().starts_with(b"//") {
+                        self.skip_line_comment();
+                    }
+                    match self.peek() {
+                        None => {}
+                        Some(c) if is_newline(c) => self.position += c.len_utf8(),
+                        Some(_) => return Err(self.error("Expected newline after '\\'")),
+                    }
+                }
+                _ => return Ok(self.position > start),
+            }
+        }
+    }
+
+    fn skip_line_comment(&mut self) {
+        let rest = &self.source[self.position..];
+        self.position += rest.find(is_newline).unwrap_or(rest.len());
+    }
+
+    /// Skips a block comment, which may contain nested block comments
+    fn skip_block_comment(&mut self) -> LoadumResult<()> {
+        let start = self.position;
+        let mut depth = 0;
+        loop {
+            let rest = self.rest();
+            if rest.starts_with(b"/*") {
+                depth += 1;
+                self.position += 2;
+            } else if rest.starts_with(b"*/") {
+                depth -= 1;
+                self.position += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if rest.is_empty() {
+                return Err(self.error_at(start, "Unterminated comment"));
+            } else {
+                self.position += 1;
+            }
+        }
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+/// Returns the number of `#` of a raw string starting at the first `#`, or None if it is none
+///
+/// Raw strings without `#` only exist in KDL 1, as `r"raw"`.
+fn raw_string_hashes(rest: &[u8]) -> Option<usize> {
+    let hashes = rest.iter().take_while(|c| **c == b'#').count();
+    (rest.get(hashes) == Some(&b'"')).then_some(hashes)
+}
+
+/// Removes the indentation of the closing quotes from the lines of a multi-line string
+fn dedent(content: &str) -> LoadumResult<LoadumString> {
+    let Some(content) = content
+        .strip_prefix('\n')
+        .or_else(|| content.strip_prefix("\r\n"))
+    else {
+        bail!("Multi-line strings have to start with a newline");
+    };
+    let (body, indentation) = match content.rfind('\n') {
+        Some(index) => (&content[..index], &content[index + 1..]),
+        None => ("", content),
+    };
+    if !indentation.chars().all(|c| c == ' ' || c == '\t') {
+        bail!("The closing quotes of multi-line strings have to be on their own line");
+    }
+    let mut lines = vec![];
+    for line in body.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.chars().all(|c| c == ' ' || c == '\t') {
+            lines.push("");
+            continue;
+        }
+        let Some(line) = line.strip_prefix(indentation) else {
+            bail!(
+                "Lines of multi-line strings have to start with the indentation of the closing quotes"
+            );
+        };
+        lines.push(line);
+    }
+    Ok(lines.join("\n").into())
+}
+
+fn unescape(content: &str) -> LoadumResult<LoadumString> {
+    let mut string = LoadumString::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('b') => '\u{0008}',
+            Some('f') => '\u{000c}',
+            Some('s') => ' ',
+            Some(c @ ('\\' | '"' | '/')) => c,
+            Some('u') => {
+                let digits: String = if chars.next_if_eq(&'{').is_some() {
+                    chars.by_ref().take_while(|c| *c != '}').collect()
+                } else {
+                    String::new()
+                };
+                let code_point = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32);
+                let Some(c) = code_point else {
+                    bail!("Invalid unicode escape '\\u{{{}}}'", digits);
+                };
+                c
+            }
+            // Escaped whitespace is removed together with the backslash
+            Some(c) if c.is_whitespace() => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                continue;
+            }
+            Some(c) => bail!("Invalid escape sequence '\\{}'", c.escape_default()),
+            None => bail!("Incomplete escape sequence"),
+        };
+        string.push(c);
+    }
+    Ok(string)
+}
+
+fn parse_number(text: &str) -> Option<Value<'static>> {
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let sign = if text.starts_with('-') { "-" } else { "" };
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = unsigned.strip_prefix(prefix) {
+            if !valid_digits(digits, radix) {
+                return None;
+            }
+            let digits = format!("{}{}", sign, digits.replace('_', ""));
+            return i64::from_str_radix(&digits, radix).ok().map(Value::Integer);
+        }
+    }
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    if !valid_digits(integer, 10) || fraction.is_some_and(|fraction| !valid_digits(fraction, 10)) {
+        return None;
+    }
+    let exponent_digits =
+        exponent.map(|exponent| exponent.strip_prefix(['+', '-']).unwrap_or(exponent));
+    if exponent_digits.is_some_and(|digits| !valid_digits(digits, 10)) {
+        return None;
+    }
+    let number = text.replace('_', "");
+    if fraction.is_none() && exponent.is_none() {
+        number.parse().ok().map(Value::Integer)
+    } else {
+        number.parse().ok().map(Value::Number)
+    }
+}
+
+/// Digits with underscores allowed after the first digit
+fn valid_digits(text: &str, radix: u32) -> bool {
+    text.starts_with(|c: char| c.is_digit(radix))
+        && text.chars().all(|c| c == '_' || c.is_digit(radix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KdlLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use std::fmt::Write;
+
+    fn load(input: &str) -> String {
+        let mut output = String::new();
+        for event in KdlLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_convention() {
+        test_loader(
+            r#"
+// Server settings
+server port=8080 {
+    host "localhost"
+    users "alice" "bob"
+    enabled
+}
+route "/a"; route "/b"
+matrix {
+    - 1 2
+    - 3 4
+}
+mixed 1 2 key=#true {
+    child #null
+}
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("server"))
+                MapStart
+                MapKey(String("port"))
+                Literal(Integer(8080))
+                MapKey(String("host"))
+                Literal(String("localhost"))
+                MapKey(String("users"))
+                ListStart
+                Literal(String("alice"))
+                Literal(String("bob"))
+                ListEnd
+                MapKey(String("enabled"))
+                Literal(Null)
+                MapEnd
+                MapKey(String("route"))
+                ListStart
+                Literal(String("/a"))
+                Literal(String("/b"))
+                ListEnd
+                MapKey(String("matrix"))
+                ListStart
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                ListStart
+                Literal(Integer(3))
+                Literal(Integer(4))
+                ListEnd
+                ListEnd
+                MapKey(String("mixed"))
+                MapStart
+                MapKey(String("-"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                MapKey(String("key"))
+                Literal(Boolean(true))
+                MapKey(String("child"))
+                Literal(Null)
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        test_loader(
+            r####"
+numbers 1 -2 +3 1_000 0xff -0o17 0b101 1.5 -2e3 1.0E-2 #inf #-inf #nan
+keywords #true #false #null true false null
+strings "quoted \"\t\u{1F600}\s" bare #"raw "quoted""# r"raw v1" ##"hash "# inside"##
+multi """
+    first
+      indented
+
+    last \
+        continued
+    """
+"####,
+            expect![[r##"
+                DocumentStart
+                MapStart
+                MapKey(String("numbers"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(-2))
+                Literal(Integer(3))
+                Literal(Integer(1000))
+                Literal(Integer(255))
+                Literal(Integer(-15))
+                Literal(Integer(5))
+                Literal(Number(1.5))
+                Literal(Number(-2000.0))
+                Literal(Number(0.01))
+                Literal(Number(inf))
+                Literal(Number(-inf))
+                Literal(Number(NaN))
+                ListEnd
+                MapKey(String("keywords"))
+                ListStart
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Null)
+                ListEnd
+                MapKey(String("strings"))
+                ListStart
+                Literal(String("quoted \"\t😀 "))
+                Literal(String("bare"))
+                Literal(String("raw \"quoted\""))
+                Literal(String("raw v1"))
+                Literal(String("hash \"# inside"))
+                ListEnd
+                MapKey(String("multi"))
+                Literal(String("first\n  indented\n\nlast continued"))
+                MapEnd
+                DocumentEnd
+            "##]],
+        );
+    }
+
+    #[test]
+    fn test_annotations_and_comments() {
+        test_loader(
+            r#"
+/* block /* nested */ comment */ (person)author name=(string)"Alice" age=(u8)30 \ // continuation comment
+    email="alice@example.com"
+/-ignored node
+list /-1 /-key=1 {
+    /-
+    - 4
+    - (u8)5
+}
+"quoted name" 1 name=2 name=3
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("author"))
+                Tag("person")
+                MapStart
+                MapKey(String("name"))
+                Tag("string")
+                Literal(String("Alice"))
+                MapKey(String("age"))
+                Tag("u8")
+                Literal(Integer(30))
+                MapKey(String("email"))
+                Literal(String("alice@example.com"))
+                MapEnd
+                MapKey(String("list"))
+                ListStart
+                Tag("u8")
+                Literal(Integer(5))
+                ListEnd
+                MapKey(String("quoted name"))
+                MapStart
+                MapKey(String("-"))
+                Literal(Integer(1))
+                MapKey(String("name"))
+                Literal(Integer(3))
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "node \"unterminated",
+            "node {",
+            "}",
+            "node 1abc",
+            "node #maybe",
+            "node(u8)1",
+            "node \"a\"\"b\"",
+            "node {} 1",
+            "(u8 node",
+            "node 1=2",
+            "node \"\\q\"",
+            "node \"\"\"\n  text\n not indented\n  \"\"\"",
+            "node \\ 1",
+            "/* open",
+            &"a {".repeat(MAX_DEPTH + 1),
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Unterminated string at line 1, column 6
+            Error: Unterminated children block at line 1, column 6
+            Error: Unexpected '}' at line 1, column 1
+            Error: Invalid number '1abc' at line 1, column 6
+            Error: Invalid keyword '#maybe' at line 1, column 6
+            Error: Expected whitespace before argument or property at line 1, column 5
+            Error: Expected whitespace before argument or property at line 1, column 9
+            Error: Expected end of node after children block at line 1, column 9
+            Error: Expected ')' after type name at line 1, column 5
+            Error: Expected property name at line 1, column 6
+            Error: Invalid escape sequence '\q' at line 1, column 6
+            Error: Lines of multi-line strings have to start with the indentation of the closing quotes at line 1, column 6
+            Error: Expected newline after '\' at line 1, column 8
+            Error: Unterminated comment at line 1, column 1
+            Error: Document is nested deeper than 128 levels at line 1, column 387
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,47 @@
+//! KDL documents mapped to maps and lists
+//!
+//! The document and each children block are maps from node names to node values, and repeated
+//! names are collected in a list under the shared name. A block consisting only of nodes named
+//! `-` is a list of their values instead.
+//!
+//! A node without properties and children has null, its argument or a list of its arguments as
+//! its value, and a node with only children has the value of its children block. Other nodes are
+//! maps of their properties and children, with their arguments under the key `-`. Type
+//! annotations of nodes and values are tags.
+//!
+//! Empty lists cannot be told apart from empty maps, both are written as empty children blocks
+//! and loaded as empty maps.
+//!
+//! ```kdl
+//! server port=8080 {
+//!     host "localhost"
+//!     users "alice" "bob"
+//! }
+//! ```
+//!
+//! is loaded as `{"server": {"port": 8080, "host": "localhost", "users": ["alice", "bob"]}}`.
+
+pub mod kdl_dumper;
+pub mod kdl_loader;
+
+pub(crate) fn is_newline(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0085}' | '\u{000c}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Characters allowed in identifiers, i.e. bare node names, property names and strings
+pub(crate) fn is_identifier_char(c: char) -> bool {
+    !(c.is_whitespace() || c.is_control() || "\\/(){}[];\"#=\u{feff}".contains(c))
+}
+
+/// Whether a string can be written as an identifier without being read as a number or keyword
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let unsigned = name.strip_prefix(['+', '-']).unwrap_or(name);
+    let unsigned = unsigned.strip_prefix('.').unwrap_or(unsigned);
+    !name.is_empty()
+        && name.chars().all(is_identifier_char)
+        && !unsigned.starts_with(|c: char| c.is_ascii_digit())
+        && !matches!(name, "true" | "false" | "null" | "inf" | "-inf" | "nan")
+}
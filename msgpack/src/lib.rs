@@ -0,0 +1,2 @@
+pub mod msgpack_dumper;
+pub mod msgpack_loader;
@@ -0,0 +1,342 @@
+use crate::msgpack_loader::TIMESTAMP_EXT_TYPE;
+use loadum::dumper::Dumper;
+use loadum::error::bail;
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Write;
+
+/// Dumps MessagePack, each document is written as a top level value
+///
+/// Lengths of maps and lists precede their content, so each document is collected and written
+/// once it is complete. Bytes tagged with `ext:<type>` are written as ext types, digits tagged with
+/// `bigint` as unsigned 64 bit integers if they fit.
+pub struct MsgpackDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+}
+
+impl<'write> MsgpackDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> MsgpackDumper<'write> {
+        MsgpackDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+        }
+    }
+}
+
+impl Dumper for MsgpackDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut output = vec![];
+        write_node(&mut output, &root)?;
+        self.write.write_all(&output)?;
+        Ok(())
+    }
+}
+
+fn write_node(output: &mut Vec<u8>, node: &Node) -> LoadumResult<()> {
+    match node {
+        Node::Scalar(value) => write_value(output, value)?,
+        Node::List(items) => {
+            write_header(output, items.len(), 0x90, 0xdc)?;
+            for item in items {
+                write_node(output, item)?;
+            }
+        }
+        Node::Map(entries) => {
+            write_header(output, entries.len(), 0x80, 0xde)?;
+            for (key, value) in entries {
+                write_node(output, key)?;
+                write_node(output, value)?;
+            }
+        }
+        Node::Tagged(tag, node) => match (ext_type(tag), node.untagged()) {
+            (Some(ext_type), Node::Scalar(Value::Bytes(data))) => {
+                write_ext(output, ext_type, data)?
+            }
+            (None, node) if tag == BIGINT_TAG => match node.as_str().map(str::parse::<u64>) {
+                Some(Ok(value)) => {
+                    output.push(0xcf);
+                    output.extend_from_slice(&value.to_be_bytes());
+                }
+                _ => write_node(output, node)?,
+            },
+            // Other tags have no MessagePack representation, the tagged value is written as is
+            _ => write_node(output, node)?,
+        },
+    }
+    Ok(())
+}
+
+fn ext_type(tag: &str) -> Option<i8> {
+    tag.strip_prefix("ext:")?.parse().ok()
+}
+
+fn write_value(output: &mut Vec<u8>, value: &Value) -> LoadumResult<()> {
+    match value {
+        Value::Null => output.push(0xc0),
+        Value::Boolean(b) => output.push(if *b { 0xc3 } else { 0xc2 }),
+        Value::Integer(i) => write_integer(output, *i),
+        Value::Number(n) => {
+            output.push(0xcb);
+            output.extend_from_slice(&n.to_be_bytes());
+        }
+        Value::String(s) => write_string(output, s)?,
+        Value::BorrowedString(s) => write_string(output, s)?,
+        Value::Bytes(bytes) => {
+            match u8::try_from(bytes.len()) {
+                Ok(length) => output.extend_from_slice(&[0xc4, length]),
+                Err(_) => write_length(output, bytes.len(), 0xc5)?,
+            }
+            output.extend_from_slice(bytes);
+        }
+        Value::DateTime(date_time) => match date_time.unix_timestamp() {
+            Some((seconds, nanosecond)) => {
+                let data = if nanosecond == 0 && u32::try_from(seconds).is_ok() {
+                    (seconds as u32).to_be_bytes().to_vec()
+                } else if (0..1 << 34).contains(&seconds) {
+                    ((nanosecond as u64) << 34 | seconds as u64)
+                        .to_be_bytes()
+                        .to_vec()
+                } else {
+                    let mut data = nanosecond.to_be_bytes().to_vec();
+                    data.extend_from_slice(&seconds.to_be_bytes());
+                    data
+                };
+                write_ext(output, TIMESTAMP_EXT_TYPE, &data)?;
+            }
+            // Timestamps are absolute, local dates and times are written as strings
+            None => write_string(output, &date_time.to_string())?,
+        },
+    }
+    Ok(())
+}
+
+fn write_integer(output: &mut Vec<u8>, i: i64) {
+    match i {
+        0..=0x7f => output.push(i as u8),
+        -32..0 => output.push(i as i8 as u8),
+        0x80..=0xff => output.extend_from_slice(&[0xcc, i as u8]),
+        0x100..=0xffff => {
+            output.push(0xcd);
+            output.extend_from_slice(&(i as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            output.push(0xce);
+            output.extend_from_slice(&(i as u32).to_be_bytes());
+        }
+        -0x80..-32 => output.extend_from_slice(&[0xd0, i as i8 as u8]),
+        -0x8000..-0x80 => {
+            output.push(0xd1);
+            output.extend_from_slice(&(i as i16).to_be_bytes());
+        }
+        -0x8000_0000..-0x8000 => {
+            output.push(0xd2);
+            output.extend_from_slice(&(i as i32).to_be_bytes());
+        }
+        _ if i > 0 => {
+            output.push(0xcf);
+            output.extend_from_slice(&(i as u64).to_be_bytes());
+        }
+        _ => {
+            output.push(0xd3);
+            output.extend_from_slice(&i.to_be_bytes());
+        }
+    }
+}
+
+fn write_string(output: &mut Vec<u8>, s: &str) -> LoadumResult<()> {
+    match s.len() {
+        0..=31 => output.push(0xa0 | s.len() as u8),
+        32..=0xff => output.extend_from_slice(&[0xd9, s.len() as u8]),
+        _ => write_length(output, s.len(), 0xda)?,
+    }
+    output.extend_from_slice(s.as_bytes());
+    Ok(())
+}
+
+/// Writes the marker and length of a list or map
+fn write_header(
+    output: &mut Vec<u8>,
+    length: usize,
+    fix_marker: u8,
+    marker: u8,
+) -> LoadumResult<()> {
+    if length < 16 {
+        output.push(fix_marker | length as u8);
+        return Ok(());
+    }
+    write_length(output, length, marker)
+}
+
+/// Writes a 16 or 32 bit length, with the marker for 16 bit lengths, followed by the one for 32 bit
+fn write_length(output: &mut Vec<u8>, length: usize, marker: u8) -> LoadumResult<()> {
+    if let Ok(length) = u16::try_from(length) {
+        output.push(marker);
+        output.extend_from_slice(&length.to_be_bytes());
+    } else if let Ok(length) = u32::try_from(length) {
+        output.push(marker + 1);
+        output.extend_from_slice(&length.to_be_bytes());
+    } else {
+        bail!("Length {} exceeds the MessagePack limit", length);
+    }
+    Ok(())
+}
+
+fn write_ext(output: &mut Vec<u8>, ext_type: i8, data: &[u8]) -> LoadumResult<()> {
+    match data.len() {
+        1 => output.push(0xd4),
+        2 => output.push(0xd5),
+        4 => output.push(0xd6),
+        8 => output.push(0xd7),
+        16 => output.push(0xd8),
+        length => match u8::try_from(length) {
+            Ok(length) => output.extend_from_slice(&[0xc7, length]),
+            Err(_) => write_length(output, length, 0xc8)?,
+        },
+    }
+    output.push(ext_type as u8);
+    output.extend_from_slice(data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msgpack_dumper::MsgpackDumper;
+    use crate::msgpack_loader::MsgpackLoader;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump(events: &[Event]) -> Vec<u8> {
+        let mut output = vec![];
+        let mut dumper = MsgpackDumper::new(&mut output);
+        for event in events {
+            dumper.emit(event).unwrap();
+        }
+        drop(dumper);
+        output
+    }
+
+    fn assert_roundtrip(events: &[Event]) {
+        let output = dump(events);
+        let loaded: Vec<Event> = MsgpackLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        let expected: Vec<Event> = events
+            .iter()
+            .map(|event| event.clone().into_owned())
+            .collect();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(
+            dump(&[
+                Event::DocumentStart,
+                Event::ListStart,
+                Event::integer(1),
+                Event::integer(-1),
+                Event::integer(200),
+                Event::integer(-200),
+                Event::number(1.5),
+                Event::string("abc"),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::ListEnd,
+                Event::DocumentEnd,
+            ]),
+            b"\x97\x01\xff\xcc\xc8\xd1\xff\x38\xcb\x3f\xf8\x00\x00\x00\x00\x00\x00\xa3abc\x80"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let integers = [
+            0,
+            127,
+            128,
+            255,
+            256,
+            65535,
+            65536,
+            u32::MAX as i64,
+            u32::MAX as i64 + 1,
+            i64::MAX,
+            -1,
+            -32,
+            -33,
+            -128,
+            -129,
+            -32768,
+            -32769,
+            i32::MIN as i64,
+            i32::MIN as i64 - 1,
+            i64::MIN,
+        ];
+        let long_string = "x".repeat(300);
+        let long_bytes = vec![7u8; 70000];
+        let mut events = vec![Event::DocumentStart, Event::MapStart];
+        events.push(Event::map_key("integers"));
+        events.push(Event::ListStart);
+        events.extend(integers.map(Event::integer));
+        events.push(Event::ListEnd);
+        events.extend([
+            Event::map_key("values"),
+            Event::ListStart,
+            Event::null(),
+            Event::bool(true),
+            Event::number(0.1),
+            Event::string(""),
+            Event::string("x".repeat(31)),
+            Event::string("x".repeat(32)),
+            Event::string(long_string.as_str()),
+            Event::bytes(&b""[..]),
+            Event::bytes(long_bytes.as_slice()),
+            Event::tag("ext:5"),
+            Event::bytes(&b"\x01\x02\x03"[..]),
+            Event::tag("ext:-2"),
+            Event::bytes(vec![0u8; 16]),
+            Event::tag("bigint"),
+            Event::string("9223372036854775808"),
+            Event::tag("bigint"),
+            Event::string(u64::MAX.to_string()),
+            Event::date_time(DateTime::from_unix_timestamp(1, 0).unwrap()),
+            Event::date_time(DateTime::from_unix_timestamp(2, 1).unwrap()),
+            Event::date_time(DateTime::from_unix_timestamp(-1, 5).unwrap()),
+            Event::date_time(DateTime::from_unix_timestamp(1 << 40, 0).unwrap()),
+            Event::ListEnd,
+            Event::MapKey(Value::Integer(1)),
+            Event::null(),
+            Event::ComplexKey,
+            Event::ListStart,
+            Event::ListEnd,
+            Event::null(),
+            Event::MapEnd,
+            Event::DocumentEnd,
+            Event::DocumentStart,
+            Event::ListStart,
+        ]);
+        events.extend((0..20).map(Event::integer));
+        events.extend([Event::ListEnd, Event::DocumentEnd]);
+        assert_roundtrip(&events);
+    }
+
+    #[test]
+    fn test_local_date_time() {
+        assert_eq!(
+            dump(&[
+                Event::DocumentStart,
+                Event::date_time(DateTime::parse("1979-05-27").unwrap()),
+                Event::DocumentEnd,
+            ]),
+            b"\xaa1979-05-27"
+        );
+    }
+}
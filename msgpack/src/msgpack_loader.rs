@@ -0,0 +1,429 @@
+use loadum::LoadumString;
+use loadum::byte_input::ByteInput;
+use loadum::datetime::DateTime;
+use loadum::error::bail;
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Read;
+
+/// Ext type of the MessagePack timestamp extension, loaded as a date-time
+pub(crate) const TIMESTAMP_EXT_TYPE: i8 = -1;
+
+/// Loads MessagePack, each top level value in the input is loaded as a separate document
+///
+/// Ext types other than timestamps are loaded as bytes, tagged with `ext:<type>`. Unsigned
+/// integers beyond the signed 64 bit range are loaded as strings of their digits, tagged with
+/// `bigint`.
+pub struct MsgpackLoader<'source> {
+    input: ByteInput<'source>,
+    state: LoaderState,
+    stack: Vec<Container>,
+    /// Event to return before reading more input
+    pending: Option<Event<'source>>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    BetweenDocuments,
+    InDocument,
+    DocumentEnd,
+    Done,
+}
+
+struct Container {
+    is_map: bool,
+    /// Remaining values, map entries count as two values
+    remaining: u64,
+}
+
+enum Item<'source> {
+    Scalar(Value<'source>),
+    Tagged(LoadumString, Value<'source>),
+    List(u64),
+    Map(u64),
+}
+
+impl<'source> MsgpackLoader<'source> {
+    pub fn new(source: &'source [u8]) -> MsgpackLoader<'source> {
+        Self::with_input(ByteInput::from_slice(source))
+    }
+
+    /// Loads MessagePack incrementally from a reader
+    pub fn from_reader(read: impl Read + 'source) -> MsgpackLoader<'source> {
+        Self::with_input(ByteInput::from_reader(read))
+    }
+
+    fn with_input(input: ByteInput<'source>) -> MsgpackLoader<'source> {
+        MsgpackLoader {
+            input,
+            state: LoaderState::BetweenDocuments,
+            stack: vec![],
+            pending: None,
+        }
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        if let Some(event) = self.pending.take() {
+            return Ok(Some(event));
+        }
+        match self.state {
+            LoaderState::BetweenDocuments => {
+                if self.input.is_at_end()? {
+                    self.state = LoaderState::Done;
+                    return Ok(None);
+                }
+                self.state = LoaderState::InDocument;
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::DocumentEnd => {
+                self.state = LoaderState::BetweenDocuments;
+                return Ok(Some(Event::DocumentEnd));
+            }
+            LoaderState::Done => return Ok(None),
+            LoaderState::InDocument => {}
+        }
+        let is_key = match self.stack.last_mut() {
+            Some(container) if container.remaining == 0 => {
+                let event = if container.is_map {
+                    Event::MapEnd
+                } else {
+                    Event::ListEnd
+                };
+                self.stack.pop();
+                self.end_value();
+                return Ok(Some(event));
+            }
+            Some(container) => {
+                container.remaining -= 1;
+                container.is_map && container.remaining % 2 == 1
+            }
+            None => false,
+        };
+        let event = match self.read_item()? {
+            Item::Scalar(value) => {
+                self.end_value();
+                if is_key {
+                    Event::MapKey(value)
+                } else {
+                    Event::Literal(value)
+                }
+            }
+            Item::Tagged(tag, value) => {
+                self.end_value();
+                self.pending = Some(if is_key {
+                    Event::MapKey(value)
+                } else {
+                    Event::Literal(value)
+                });
+                Event::Tag(tag)
+            }
+            Item::List(length) => self.start_container(false, length, is_key),
+            Item::Map(length) => self.start_container(true, length * 2, is_key),
+        };
+        Ok(Some(event))
+    }
+
+    fn start_container(&mut self, is_map: bool, remaining: u64, is_key: bool) -> Event<'source> {
+        self.stack.push(Container { is_map, remaining });
+        let event = if is_map {
+            Event::MapStart
+        } else {
+            Event::ListStart
+        };
+        if is_key {
+            self.pending = Some(event);
+            return Event::ComplexKey;
+        }
+        event
+    }
+
+    /// Ends the document once the top level value is complete
+    fn end_value(&mut self) {
+        if self.stack.is_empty() {
+            self.state = LoaderState::DocumentEnd;
+        }
+    }
+
+    fn read_item(&mut self) -> LoadumResult<Item<'source>> {
+        let offset = self.input.offset();
+        let marker = self.input.read_u8()?;
+        let value = match marker {
+            0x00..=0x7f => Value::Integer(marker as i64),
+            0x80..=0x8f => return Ok(Item::Map((marker & 0x0f) as u64)),
+            0x90..=0x9f => return Ok(Item::List((marker & 0x0f) as u64)),
+            0xa0..=0xbf => self.input.read_string((marker & 0x1f) as usize)?,
+            0xc0 => Value::Null,
+            0xc2 => Value::Boolean(false),
+            0xc3 => Value::Boolean(true),
+            0xc4..=0xc6 => {
+                let length = self.read_length(marker - 0xc4)?;
+                Value::bytes(&*self.input.read_bytes(length)?)
+            }
+            0xc7..=0xc9 => {
+                let length = self.read_length(marker - 0xc7)?;
+                return self.read_ext(length);
+            }
+            0xca => Value::Number(f32::from_be_bytes(self.input.read_array()?) as f64),
+            0xcb => Value::Number(f64::from_be_bytes(self.input.read_array()?)),
+            0xcc => Value::Integer(self.input.read_u8()? as i64),
+            0xcd => Value::Integer(u16::from_be_bytes(self.input.read_array()?) as i64),
+            0xce => Value::Integer(u32::from_be_bytes(self.input.read_array()?) as i64),
+            0xcf => {
+                let value = u64::from_be_bytes(self.input.read_array()?);
+                match i64::try_from(value) {
+                    Ok(value) => Value::Integer(value),
+                    Err(_) => {
+                        let digits = Value::string(value.to_string());
+                        return Ok(Item::Tagged(BIGINT_TAG.into(), digits));
+                    }
+                }
+            }
+            0xd0 => Value::Integer(i8::from_be_bytes(self.input.read_array()?) as i64),
+            0xd1 => Value::Integer(i16::from_be_bytes(self.input.read_array()?) as i64),
+            0xd2 => Value::Integer(i32::from_be_bytes(self.input.read_array()?) as i64),
+            0xd3 => Value::Integer(i64::from_be_bytes(self.input.read_array()?)),
+            0xd4..=0xd8 => return self.read_ext(1 << (marker - 0xd4)),
+            0xd9..=0xdb => {
+                let length = self.read_length(marker - 0xd9)?;
+                self.input.read_string(length)?
+            }
+            0xdc | 0xdd => return Ok(Item::List(self.read_length(marker - 0xdc + 1)? as u64)),
+            0xde | 0xdf => return Ok(Item::Map(self.read_length(marker - 0xde + 1)? as u64)),
+            0xe0..=0xff => Value::Integer(marker as i8 as i64),
+            0xc1 => bail!("Invalid MessagePack marker 0xc1 at offset {}", offset),
+        };
+        Ok(Item::Scalar(value))
+    }
+
+    /// Reads a big endian length of 1, 2 or 4 bytes, given by the size class 0, 1 or 2
+    fn read_length(&mut self, size_class: u8) -> LoadumResult<usize> {
+        let length = match size_class {
+            0 => self.input.read_u8()? as u32,
+            1 => u16::from_be_bytes(self.input.read_array()?) as u32,
+            _ => u32::from_be_bytes(self.input.read_array()?),
+        };
+        Ok(length as usize)
+    }
+
+    fn read_ext(&mut self, length: usize) -> LoadumResult<Item<'source>> {
+        let ext_type = self.input.read_u8()? as i8;
+        let offset = self.input.offset();
+        let data = self.input.read_bytes(length)?;
+        if ext_type != TIMESTAMP_EXT_TYPE {
+            let tag = format!("ext:{}", ext_type).into();
+            return Ok(Item::Tagged(tag, Value::bytes(&*data)));
+        }
+        let (seconds, nanosecond) = match data.len() {
+            4 => (u32::from_be_bytes(data[..].try_into()?) as i64, 0),
+            8 => {
+                let value = u64::from_be_bytes(data[..].try_into()?);
+                ((value & 0x3_ffff_ffff) as i64, (value >> 34) as u32)
+            }
+            12 => (
+                i64::from_be_bytes(data[4..].try_into()?),
+                u32::from_be_bytes(data[..4].try_into()?),
+            ),
+            length => bail!("Invalid timestamp length {} at offset {}", length, offset),
+        };
+        if nanosecond >= 1_000_000_000 {
+            bail!(
+                "Invalid timestamp nanoseconds {} at offset {}",
+                nanosecond,
+                offset
+            );
+        }
+        Ok(Item::Scalar(Value::DateTime(
+            DateTime::from_unix_timestamp(seconds, nanosecond)?,
+        )))
+    }
+}
+
+impl<'source> Loader<'source> for MsgpackLoader<'source> {}
+
+impl<'source> Iterator for MsgpackLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                self.pending = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::msgpack_loader::MsgpackLoader;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::Dumper;
+    use loadum::testing::{collect_events, format_events};
+    use loadum_json::json_dumper::JsonDumper;
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let events = collect_events(MsgpackLoader::new(input));
+        expected.assert_eq(&format_events(&events));
+        assert_eq!(events, collect_events(MsgpackLoader::from_reader(input)));
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            b"\x95\xc0\xc2\xc3\xa3abc\xc4\x02\x00\xff",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(false))
+                Literal(Boolean(true))
+                Literal(BorrowedString("abc"))
+                Literal(Bytes([0, 255]))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        test_loader(
+            b"\x9c\x00\x7f\xff\xe0\xcc\xff\xcd\x01\x00\xd0\x80\xd1\x80\x00\
+              \xd3\x80\x00\x00\x00\x00\x00\x00\x00\xcf\xff\xff\xff\xff\xff\xff\xff\xff\
+              \xca\x3f\xc0\x00\x00\xcb\x40\x09\x21\xfb\x54\x44\x2d\x18",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(0))
+                Literal(Integer(127))
+                Literal(Integer(-1))
+                Literal(Integer(-32))
+                Literal(Integer(255))
+                Literal(Integer(256))
+                Literal(Integer(-128))
+                Literal(Integer(-32768))
+                Literal(Integer(-9223372036854775808))
+                Tag("bigint")
+                Literal(String("18446744073709551615"))
+                Literal(Number(1.5))
+                Literal(Number(3.141592653589793))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_maps() {
+        test_loader(
+            b"\x83\xa1a\x80\x01\x90\x91\x01\xa1b",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("a"))
+                MapStart
+                MapEnd
+                MapKey(Integer(1))
+                ListStart
+                ListEnd
+                ComplexKey
+                ListStart
+                Literal(Integer(1))
+                ListEnd
+                Literal(BorrowedString("b"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_ext() {
+        test_loader(
+            b"\x94\xd4\x05\x01\xc7\x03\xfe\x01\x02\x03\
+              \xd6\xff\x00\x00\x00\x01\xd7\xff\x00\x00\x00\x04\x00\x00\x00\x02",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Tag("ext:5")
+                Literal(Bytes([1]))
+                Tag("ext:-2")
+                Literal(Bytes([1, 2, 3]))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1970, month: 1, day: 1 }, time: Time { hour: 0, minute: 0, second: 1, nanosecond: 0 }, offset_minutes: 0 }))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1970, month: 1, day: 1 }, time: Time { hour: 0, minute: 0, second: 2, nanosecond: 1 }, offset_minutes: 0 }))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        test_loader(
+            b"\x01\x80",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                MapStart
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b"", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            b"\x92\x01",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(1))
+                Error: Unexpected end of input at offset 2
+            "#]],
+        );
+        test_loader(
+            b"\xc1",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid MessagePack marker 0xc1 at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\xa2\xff\xfe",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid UTF-8 in string at offset 1
+            "#]],
+        );
+        test_loader(
+            b"\xd5\xff\x00\x00",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid timestamp length 2 at offset 2
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        for event in MsgpackLoader::new(b"\x82\xa2id\x2a\xa4data\xd4\x05\x01") {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"id": 42,
+            	"data": "AQ=="
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
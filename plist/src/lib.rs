@@ -0,0 +1,27 @@
+//! Apple property lists, in the XML and binary formats
+//!
+//! Dictionaries are maps, arrays are lists, `<date>` values are date-times and `<data>` values
+//! are bytes. UIDs of keyed archives are tagged integers. Property lists have no null value, it
+//! only occurs in binary property lists.
+
+pub mod plist_dumper;
+pub mod plist_loader;
+
+/// Encoding of property lists written by the dumper, the loader detects it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlistFormat {
+    /// The XML format of `Info.plist` files
+    #[default]
+    Xml,
+    /// The binary format starting with `bplist00`
+    Binary,
+}
+
+/// Tag of UIDs, which are loaded as integers
+pub(crate) const UID_TAG: &str = "uid";
+/// Key of the dictionaries representing UIDs in the XML format
+pub(crate) const UID_KEY: &str = "CF$UID";
+/// Magic number and version of binary property lists
+pub(crate) const BINARY_HEADER: &[u8] = b"bplist00";
+/// Seconds from the unix epoch to 2001-01-01, the reference date of property list dates
+pub(crate) const REFERENCE_DATE: i64 = 978_307_200;
@@ -0,0 +1,564 @@
+use crate::{BINARY_HEADER, PlistFormat, REFERENCE_DATE, UID_KEY, UID_TAG};
+use loadum::base64;
+use loadum::datetime::DateTime;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Dumps a property list in the XML or binary format
+///
+/// Property lists have no null value, so documents containing null cannot be dumped. Integers
+/// tagged with `uid` are written as UIDs, local date-times are written as strings and dates in
+/// the XML format have a precision of seconds.
+pub struct PlistDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    format: PlistFormat,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> PlistDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> PlistDumper<'write> {
+        PlistDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            format: PlistFormat::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets the encoding, by default [`PlistFormat::Xml`]
+    pub fn with_format(mut self, format: PlistFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for PlistDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let output = match self.format {
+            PlistFormat::Xml => {
+                let mut writer = XmlWriter {
+                    output: String::new(),
+                    key_policy: self.key_policy,
+                };
+                writer.output.push_str(concat!(
+                    "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                    "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" ",
+                    "\"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+                    "<plist version=\"1.0\">\n",
+                ));
+                writer.write_node(&root, 0)?;
+                writer.output.push_str("</plist>\n");
+                writer.output.into_bytes()
+            }
+            PlistFormat::Binary => {
+                let mut writer = BinaryWriter {
+                    objects: vec![],
+                    reference_size: integer_size(count_objects(&root) as u64 - 1),
+                    key_policy: self.key_policy,
+                };
+                writer.add_object(&root)?;
+                writer.finish()
+            }
+        };
+        self.write.write_all(&output)?;
+        Ok(())
+    }
+}
+
+/// Returns the UID of an integer tagged with `uid`
+fn uid(node: &Node) -> Option<u64> {
+    match node {
+        Node::Tagged(tag, node) if tag.as_str() == UID_TAG => match node.untagged() {
+            Node::Scalar(Value::Integer(uid)) => u64::try_from(*uid).ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+struct XmlWriter {
+    output: String,
+    key_policy: KeyPolicy,
+}
+
+impl XmlWriter {
+    fn write_node(&mut self, node: &Node, indentation_level: usize) -> LoadumResult<()> {
+        if let Some(uid) = uid(node) {
+            let entries = [(
+                Node::Scalar(Value::BorrowedString(UID_KEY)),
+                Node::Scalar(Value::Integer(uid as i64)),
+            )];
+            return self.write_dict(&entries, indentation_level);
+        }
+        match node {
+            // Other tags have no property list representation
+            Node::Tagged(_, node) => return self.write_node(node, indentation_level),
+            Node::Map(entries) => return self.write_dict(entries, indentation_level),
+            _ => {}
+        }
+        self.indent(indentation_level);
+        match node {
+            Node::Scalar(value) => self.write_scalar(value)?,
+            Node::List(items) if items.is_empty() => self.output.push_str("<array/>"),
+            Node::List(items) => {
+                self.output.push_str("<array>\n");
+                for item in items {
+                    self.write_node(item, indentation_level + 1)?;
+                }
+                self.indent(indentation_level);
+                self.output.push_str("</array>");
+            }
+            Node::Map(_) | Node::Tagged(..) => unreachable!(),
+        }
+        self.output.push('\n');
+        Ok(())
+    }
+
+    fn write_dict(
+        &mut self,
+        entries: &[(Node, Node)],
+        indentation_level: usize,
+    ) -> LoadumResult<()> {
+        self.indent(indentation_level);
+        if entries.is_empty() {
+            self.output.push_str("<dict/>\n");
+            return Ok(());
+        }
+        self.output.push_str("<dict>\n");
+        for (key, value) in entries {
+            let key = self.key_policy.key_string(key, "Property list keys")?;
+            self.indent(indentation_level + 1);
+            self.write_element("key", &key);
+            self.output.push('\n');
+            self.write_node(value, indentation_level + 1)?;
+        }
+        self.indent(indentation_level);
+        self.output.push_str("</dict>\n");
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, value: &Value) -> LoadumResult<()> {
+        match value {
+            Value::Null => bail!("Property lists cannot contain null"),
+            Value::Boolean(b) => self
+                .output
+                .push_str(if *b { "<true/>" } else { "<false/>" }),
+            Value::Integer(i) => self.write_element("integer", &i.to_string()),
+            Value::Number(n) if n.is_nan() => self.write_element("real", "nan"),
+            Value::Number(n) if n.is_infinite() => {
+                self.write_element("real", if *n > 0.0 { "+infinity" } else { "-infinity" })
+            }
+            Value::Number(n) => self.write_element("real", &format!("{:?}", n)),
+            Value::String(s) => self.write_element("string", s),
+            Value::BorrowedString(s) => self.write_element("string", s),
+            Value::Bytes(bytes) => self.write_element("data", &base64::encode(bytes)),
+            Value::DateTime(date_time) => match date_time.unix_timestamp() {
+                Some((seconds, _)) => {
+                    let date = DateTime::from_unix_timestamp(seconds, 0)?.to_string();
+                    self.write_element("date", &date)
+                }
+                // Property list dates are absolute, local dates and times are written as strings
+                None => self.write_element("string", &date_time.to_string()),
+            },
+        }
+        Ok(())
+    }
+
+    fn write_element(&mut self, name: &str, text: &str) {
+        write!(self.output, "<{}>", name).unwrap();
+        for c in text.chars() {
+            match c {
+                '&' => self.output.push_str("&amp;"),
+                '<' => self.output.push_str("&lt;"),
+                '>' => self.output.push_str("&gt;"),
+                c => self.output.push(c),
+            }
+        }
+        write!(self.output, "</{}>", name).unwrap();
+    }
+
+    fn indent(&mut self, indentation_level: usize) {
+        for _ in 0..indentation_level {
+            self.output.push('\t');
+        }
+    }
+}
+
+/// Counts the objects of a binary property list, keys are objects as well
+fn count_objects(node: &Node) -> usize {
+    match node {
+        node if uid(node).is_some() => 1,
+        Node::Tagged(_, node) => count_objects(node),
+        Node::Scalar(_) => 1,
+        Node::List(items) => 1 + items.iter().map(count_objects).sum::<usize>(),
+        Node::Map(entries) => {
+            let values = entries.iter().map(|(_, value)| count_objects(value));
+            1 + entries.len() + values.sum::<usize>()
+        }
+    }
+}
+
+/// Size in bytes of the smallest unsigned integer of 1, 2, 4 or 8 bytes that holds the value
+fn integer_size(value: u64) -> usize {
+    match value {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        0x1_0000..=0xffff_ffff => 4,
+        _ => 8,
+    }
+}
+
+struct BinaryWriter {
+    /// Encoded objects, in the order of their references
+    objects: Vec<Vec<u8>>,
+    reference_size: usize,
+    key_policy: KeyPolicy,
+}
+
+impl BinaryWriter {
+    /// Adds a node and its children, returns the reference to the node
+    fn add_object(&mut self, node: &Node) -> LoadumResult<u64> {
+        let reference = self.objects.len() as u64;
+        self.objects.push(vec![]);
+        let mut object = vec![];
+        if let Some(uid) = uid(node) {
+            let size = integer_size(uid);
+            object.push(0x80 | (size - 1) as u8);
+            object.extend_from_slice(&uid.to_be_bytes()[8 - size..]);
+            self.objects[reference as usize] = object;
+            return Ok(reference);
+        }
+        match node {
+            Node::Tagged(_, node) => {
+                // The tagged node takes the place reserved for this node
+                self.objects.pop();
+                return self.add_object(node);
+            }
+            Node::Scalar(value) => write_scalar(&mut object, value)?,
+            Node::List(items) => {
+                let references = items
+                    .iter()
+                    .map(|item| self.add_object(item))
+                    .collect::<LoadumResult<Vec<_>>>()?;
+                write_header(&mut object, 0xa, references.len());
+                self.write_references(&mut object, &references);
+            }
+            Node::Map(entries) => {
+                let mut references = vec![];
+                for (key, _) in entries {
+                    let key = self.key_policy.key_string(key, "Property list keys")?;
+                    let mut key_object = vec![];
+                    write_string(&mut key_object, &key);
+                    references.push(self.objects.len() as u64);
+                    self.objects.push(key_object);
+                }
+                for (_, value) in entries {
+                    references.push(self.add_object(value)?);
+                }
+                write_header(&mut object, 0xd, entries.len());
+                self.write_references(&mut object, &references);
+            }
+        }
+        self.objects[reference as usize] = object;
+        Ok(reference)
+    }
+
+    fn write_references(&self, object: &mut Vec<u8>, references: &[u64]) {
+        for reference in references {
+            object.extend_from_slice(&reference.to_be_bytes()[8 - self.reference_size..]);
+        }
+    }
+
+    /// Concatenates the objects and appends the offset table and the trailer
+    fn finish(self) -> Vec<u8> {
+        let mut output = BINARY_HEADER.to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for object in &self.objects {
+            offsets.push(output.len() as u64);
+            output.extend_from_slice(object);
+        }
+        let offset_table = output.len() as u64;
+        let offset_size = integer_size(offset_table);
+        for offset in offsets {
+            output.extend_from_slice(&offset.to_be_bytes()[8 - offset_size..]);
+        }
+        output.extend_from_slice(&[0; 6]);
+        output.extend_from_slice(&[offset_size as u8, self.reference_size as u8]);
+        output.extend_from_slice(&(self.objects.len() as u64).to_be_bytes());
+        output.extend_from_slice(&0u64.to_be_bytes());
+        output.extend_from_slice(&offset_table.to_be_bytes());
+        output
+    }
+}
+
+fn write_scalar(object: &mut Vec<u8>, value: &Value) -> LoadumResult<()> {
+    match value {
+        Value::Null => bail!("Property lists cannot contain null"),
+        Value::Boolean(b) => object.push(if *b { 0x09 } else { 0x08 }),
+        Value::Integer(i) => write_integer(object, *i),
+        Value::Number(n) => {
+            object.push(0x23);
+            object.extend_from_slice(&n.to_be_bytes());
+        }
+        Value::String(s) => write_string(object, s),
+        Value::BorrowedString(s) => write_string(object, s),
+        Value::Bytes(bytes) => {
+            write_header(object, 0x4, bytes.len());
+            object.extend_from_slice(bytes);
+        }
+        Value::DateTime(date_time) => match date_time.unix_timestamp() {
+            Some((seconds, nanosecond)) => {
+                let seconds = (seconds - REFERENCE_DATE) as f64 + nanosecond as f64 / 1e9;
+                object.push(0x33);
+                object.extend_from_slice(&seconds.to_be_bytes());
+            }
+            None => write_string(object, &date_time.to_string()),
+        },
+    }
+    Ok(())
+}
+
+/// Writes an integer, negative integers always take 8 bytes
+fn write_integer(object: &mut Vec<u8>, i: i64) {
+    let size = if i < 0 { 8 } else { integer_size(i as u64) };
+    object.push(0x10 | size.trailing_zeros() as u8);
+    object.extend_from_slice(&i.to_be_bytes()[8 - size..]);
+}
+
+/// Writes ASCII strings as bytes and other strings as UTF-16
+fn write_string(object: &mut Vec<u8>, s: &str) {
+    if s.is_ascii() {
+        write_header(object, 0x5, s.len());
+        object.extend_from_slice(s.as_bytes());
+        return;
+    }
+    let units: Vec<u16> = s.encode_utf16().collect();
+    write_header(object, 0x6, units.len());
+    for unit in units {
+        object.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+/// Writes the marker of a kind of object, with the length as an integer object if it is large
+fn write_header(object: &mut Vec<u8>, kind: u8, length: usize) {
+    if length < 0x0f {
+        object.push(kind << 4 | length as u8);
+        return;
+    }
+    object.push(kind << 4 | 0x0f);
+    write_integer(object, length as i64);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::PlistFormat;
+    use crate::plist_dumper::PlistDumper;
+    use crate::plist_loader::PlistLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump(
+        events: &[Event],
+        format: PlistFormat,
+        key_policy: KeyPolicy,
+    ) -> Result<Vec<u8>, String> {
+        let mut output = vec![];
+        let mut dumper = PlistDumper::new(&mut output)
+            .with_format(format)
+            .with_key_policy(key_policy);
+        let result = [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+            .try_for_each(|event| dumper.emit(event));
+        drop(dumper);
+        match result {
+            Ok(()) => Ok(output),
+            Err(error) => Err(format!("Error: {}", error)),
+        }
+    }
+
+    fn events() -> Vec<Event<'static>> {
+        let long_list = (0..20).map(Event::integer);
+        let mut events = vec![
+            Event::MapStart,
+            Event::map_key("CFBundleName"),
+            Event::string("Tom & Jerry"),
+            Event::map_key("values"),
+            Event::ListStart,
+            Event::integer(-42),
+            Event::integer(300),
+            Event::integer(i64::MAX),
+            Event::number(1.5),
+            Event::number(f64::NEG_INFINITY),
+            Event::bool(true),
+            Event::bool(false),
+            Event::string(""),
+            Event::string("ünïcode 🎉"),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05Z").unwrap()),
+            Event::bytes(&b"\x01\x02\x03"[..]),
+            Event::MapStart,
+            Event::MapEnd,
+            Event::ListStart,
+            Event::ListEnd,
+            Event::ListEnd,
+            Event::map_key("uid"),
+            Event::tag("uid"),
+            Event::integer(7),
+            Event::map_key("long"),
+            Event::ListStart,
+        ];
+        events.extend(long_list);
+        events.extend([Event::ListEnd, Event::MapEnd]);
+        events
+    }
+
+    #[test]
+    fn test_xml() {
+        let output = dump(&events(), PlistFormat::Xml, KeyPolicy::Stringify).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        expect![[r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+            <plist version="1.0">
+            <dict>
+            	<key>CFBundleName</key>
+            	<string>Tom &amp; Jerry</string>
+            	<key>values</key>
+            	<array>
+            		<integer>-42</integer>
+            		<integer>300</integer>
+            		<integer>9223372036854775807</integer>
+            		<real>1.5</real>
+            		<real>-infinity</real>
+            		<true/>
+            		<false/>
+            		<string></string>
+            		<string>ünïcode 🎉</string>
+            		<date>2024-01-02T03:04:05Z</date>
+            		<data>AQID</data>
+            		<dict/>
+            		<array/>
+            	</array>
+            	<key>uid</key>
+            	<dict>
+            		<key>CF$UID</key>
+            		<integer>7</integer>
+            	</dict>
+            	<key>long</key>
+            	<array>
+            		<integer>0</integer>
+            		<integer>1</integer>
+            		<integer>2</integer>
+            		<integer>3</integer>
+            		<integer>4</integer>
+            		<integer>5</integer>
+            		<integer>6</integer>
+            		<integer>7</integer>
+            		<integer>8</integer>
+            		<integer>9</integer>
+            		<integer>10</integer>
+            		<integer>11</integer>
+            		<integer>12</integer>
+            		<integer>13</integer>
+            		<integer>14</integer>
+            		<integer>15</integer>
+            		<integer>16</integer>
+            		<integer>17</integer>
+            		<integer>18</integer>
+            		<integer>19</integer>
+            	</array>
+            </dict>
+            </plist>
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut expected = vec![Event::DocumentStart];
+        expected.extend(events());
+        expected.push(Event::DocumentEnd);
+        for format in [PlistFormat::Xml, PlistFormat::Binary] {
+            let output = dump(&events(), format, KeyPolicy::Stringify).unwrap();
+            let loaded: Vec<Event> = PlistLoader::new(&output)
+                .map(|event| event.unwrap().into_owned())
+                .collect();
+            assert_eq!(loaded, expected, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_binary_date() {
+        let date_time = DateTime::parse("2024-01-02T03:04:05.25Z").unwrap();
+        let output = dump(
+            &[Event::date_time(date_time)],
+            PlistFormat::Binary,
+            KeyPolicy::Stringify,
+        )
+        .unwrap();
+        let loaded: Vec<Event> = PlistLoader::new(&output).map(Result::unwrap).collect();
+        assert_eq!(loaded[1], Event::date_time(date_time));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = String::new();
+        for format in [PlistFormat::Xml, PlistFormat::Binary] {
+            for (events, key_policy) in [
+                (&[Event::null()][..], KeyPolicy::Stringify),
+                (
+                    &[
+                        Event::MapStart,
+                        Event::MapKey(Value::integer(1)),
+                        Event::bool(true),
+                        Event::MapEnd,
+                    ][..],
+                    KeyPolicy::Reject,
+                ),
+                (
+                    &[
+                        Event::MapStart,
+                        Event::ComplexKey,
+                        Event::ListStart,
+                        Event::ListEnd,
+                        Event::bool(true),
+                        Event::MapEnd,
+                    ][..],
+                    KeyPolicy::Stringify,
+                ),
+            ] {
+                output.push_str(&dump(events, format, key_policy).unwrap_err());
+                output.push('\n');
+            }
+        }
+        expect![[r#"
+            Error: Property lists cannot contain null
+            Error: Property list keys must be strings, but found Scalar(Integer(1))
+            Error: Property list keys cannot be composite, but found List([])
+            Error: Property lists cannot contain null
+            Error: Property list keys must be strings, but found Scalar(Integer(1))
+            Error: Property list keys cannot be composite, but found List([])
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,929 @@
+use crate::{BINARY_HEADER, REFERENCE_DATE, UID_KEY, UID_TAG};
+use loadum::LoadumString;
+use loadum::base64;
+use loadum::datetime::DateTime;
+use loadum::depth::{Depth, check_depth};
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::node::Node;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::fmt::Display;
+
+/// Loads a property list, in the XML format or the binary format starting with `bplist00`
+///
+/// Binary property lists are read by following object references, so the document is parsed
+/// before the first event is returned.
+pub struct PlistLoader<'source> {
+    source: &'source [u8],
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> PlistLoader<'source> {
+    pub fn new(source: &'source [u8]) -> PlistLoader<'source> {
+        PlistLoader {
+            source,
+            events: None,
+        }
+    }
+
+    fn parse(&self) -> LoadumResult<Node<'source>> {
+        if self.source.starts_with(BINARY_HEADER) {
+            return BinaryParser::new(self.source)?.parse_document();
+        }
+        let Ok(source) = std::str::from_utf8(self.source) else {
+            bail!("XML property lists must be UTF-8");
+        };
+        XmlParser {
+            source,
+            bytes: source.as_bytes(),
+            position: 0,
+            depth: Depth::default(),
+        }
+        .parse_document()
+    }
+}
+
+impl<'source> Loader<'source> for PlistLoader<'source> {}
+
+impl<'source> Iterator for PlistLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            match self.parse() {
+                Ok(root) => {
+                    let mut events = vec![Event::DocumentStart];
+                    root.to_events(&mut events);
+                    events.push(Event::DocumentEnd);
+                    self.events = Some(events.into_iter());
+                }
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+fn uid_node<'source>(uid: u64) -> Node<'source> {
+    let value = match i64::try_from(uid) {
+        Ok(uid) => Value::Integer(uid),
+        Err(_) => Value::Number(uid as f64),
+    };
+    Node::Tagged(UID_TAG.into(), Box::new(Node::Scalar(value)))
+}
+
+fn date_value<'source>(seconds_since_reference: f64) -> LoadumResult<Value<'source>> {
+    let seconds = seconds_since_reference.floor();
+    if !seconds.is_finite() || seconds.abs() > 1e15 {
+        bail!("Invalid date {}", seconds_since_reference);
+    }
+    let nanosecond = ((seconds_since_reference - seconds) * 1e9).round() as u32;
+    // Rounding may carry into the next second
+    let (seconds, nanosecond) = match nanosecond {
+        1_000_000_000 => (seconds as i64 + 1, 0),
+        nanosecond => (seconds as i64, nanosecond),
+    };
+    Ok(Value::DateTime(DateTime::from_unix_timestamp(
+        REFERENCE_DATE + seconds,
+        nanosecond,
+    )?))
+}
+
+struct XmlParser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+struct StartTag<'source> {
+    name: &'source str,
+    is_empty: bool,
+    position: usize,
+}
+
+impl<'source> XmlParser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Node<'source>> {
+        if self.source.starts_with('\u{feff}') {
+            self.position = '\u{feff}'.len_utf8();
+        }
+        self.skip_misc(true)?;
+        let tag = self.parse_start_tag()?;
+        if tag.name != "plist" || tag.is_empty {
+            return Err(self.error_at(tag.position, "Expected <plist> element"));
+        }
+        let root = self.parse_value()?;
+        self.skip_misc(false)?;
+        self.expect_end_tag("plist")?;
+        self.skip_misc(false)?;
+        if self.position < self.bytes.len() {
+            return Err(self.error("Unexpected content after the </plist> element"));
+        }
+        Ok(root)
+    }
+
+    fn parse_value(&mut self) -> LoadumResult<Node<'source>> {
+        self.skip_misc(false)?;
+        let tag = self.parse_start_tag()?;
+        let value = match tag.name {
+            "dict" | "array" => {
+                self.depth
+                    .enter()
+                    .map_err(|error| self.error_at(tag.position, error))?;
+                let node = if tag.name == "dict" {
+                    self.parse_dict(tag)
+                } else {
+                    self.parse_array(tag)
+                };
+                self.depth.exit();
+                return node;
+            }
+            "true" | "false" => {
+                if !tag.is_empty {
+                    self.expect_end_tag(tag.name)?;
+                }
+                Value::Boolean(tag.name == "true")
+            }
+            "string" => match self.parse_text(&tag)? {
+                Cow::Borrowed(text) => Value::BorrowedString(text),
+                Cow::Owned(text) => Value::String(text.into()),
+            },
+            "integer" => {
+                let text = self.parse_text(&tag)?;
+                let text = text.trim();
+                let integer = match text.strip_prefix("0x") {
+                    Some(hex) => i64::from_str_radix(hex, 16).map(Value::Integer).ok(),
+                    None => text.parse().map(Value::Integer).ok(),
+                };
+                // Integers are signed 64 bit, larger unsigned values are approximated
+                let integer =
+                    integer.or_else(|| text.parse::<u64>().ok().map(|i| Value::Number(i as f64)));
+                let Some(integer) = integer else {
+                    return Err(
+                        self.error_at(tag.position, format_args!("Invalid integer '{}'", text))
+                    );
+                };
+                integer
+            }
+            "real" => {
+                let text = self.parse_text(&tag)?;
+                let text = text.trim();
+                let real = match text.to_ascii_lowercase().as_str() {
+                    "nan" => Some(f64::NAN),
+                    "inf" | "infinity" | "+inf" | "+infinity" => Some(f64::INFINITY),
+                    "-inf" | "-infinity" => Some(f64::NEG_INFINITY),
+                    _ => text.parse().ok(),
+                };
+                let Some(real) = real else {
+                    return Err(
+                        self.error_at(tag.position, format_args!("Invalid real '{}'", text))
+                    );
+                };
+                Value::Number(real)
+            }
+            "date" => {
+                let text = self.parse_text(&tag)?;
+                match DateTime::parse(text.trim()) {
+                    Ok(date_time) => Value::DateTime(date_time),
+                    Err(error) => return Err(self.error_at(tag.position, error)),
+                }
+            }
+            "data" => {
+                let text = self.parse_text(&tag)?;
+                match base64::decode(&text) {
+                    Ok(bytes) => Value::bytes(bytes),
+                    Err(error) => return Err(self.error_at(tag.position, error)),
+                }
+            }
+            name => {
+                return Err(
+                    self.error_at(tag.position, format_args!("Unexpected element <{}>", name))
+                );
+            }
+        };
+        Ok(Node::Scalar(value))
+    }
+
+    fn parse_array(&mut self, tag: StartTag) -> LoadumResult<Node<'source>> {
+        let mut items = vec![];
+        if !tag.is_empty {
+            while !self.at_end_tag()? {
+                items.push(self.parse_value()?);
+            }
+            self.expect_end_tag("array")?;
+        }
+        Ok(Node::List(items))
+    }
+
+    fn parse_dict(&mut self, tag: StartTag) -> LoadumResult<Node<'source>> {
+        let mut entries = vec![];
+        if !tag.is_empty {
+            while !self.at_end_tag()? {
+                let key_tag = self.parse_start_tag()?;
+                if key_tag.name != "key" {
+                    return Err(self.error_at(key_tag.position, "Expected <key> element"));
+                }
+                let key = match self.parse_text(&key_tag)? {
+                    Cow::Borrowed(key) => Value::BorrowedString(key),
+                    Cow::Owned(key) => Value::String(key.into()),
+                };
+                entries.push((Node::Scalar(key), self.parse_value()?));
+            }
+            self.expect_end_tag("dict")?;
+        }
+        // Keyed archives write UIDs as dictionaries with a single key
+        match entries.as_slice() {
+            [(key, Node::Scalar(Value::Integer(uid)))]
+                if key.as_str() == Some(UID_KEY) && *uid >= 0 =>
+            {
+                Ok(uid_node(*uid as u64))
+            }
+            _ => Ok(Node::Map(entries)),
+        }
+    }
+
+    /// Skips whitespace and comments, returns true if an end tag follows
+    fn at_end_tag(&mut self) -> LoadumResult<bool> {
+        self.skip_misc(false)?;
+        if self.position >= self.bytes.len() {
+            return Err(self.error("Unexpected end of input"));
+        }
+        Ok(self.starts_with("</"))
+    }
+
+    fn parse_start_tag(&mut self) -> LoadumResult<StartTag<'source>> {
+        let position = self.position;
+        if !self.starts_with("<") || self.starts_with("</") {
+            return Err(self.error("Expected element"));
+        }
+        self.position += 1;
+        let name = self.parse_name()?;
+        // Attributes, such as the version of <plist>, are ignored
+        let Some(length) = self.source[self.position..].find('>') else {
+            return Err(self.error_at(position, "Unterminated tag"));
+        };
+        self.position += length + 1;
+        Ok(StartTag {
+            name,
+            is_empty: self.bytes[self.position - 2] == b'/',
+            position,
+        })
+    }
+
+    fn expect_end_tag(&mut self, name: &str) -> LoadumResult<()> {
+        let position = self.position;
+        let is_end_tag = self.starts_with("</") && {
+            self.position += 2;
+            self.parse_name().ok() == Some(name)
+        };
+        self.skip_whitespace();
+        if !is_end_tag || !self.starts_with(">") {
+            return Err(self.error_at(position, format_args!("Expected </{}>", name)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Parses the text content of an element and its end tag
+    fn parse_text(&mut self, tag: &StartTag) -> LoadumResult<Cow<'source, str>> {
+        if tag.is_empty {
+            return Ok(Cow::Borrowed(""));
+        }
+        let start = self.position;
+        let Some(length) = self.source[start..].find('<') else {
+            return Err(self.error_at(
+                tag.position,
+                format_args!("Unclosed element <{}>", tag.name),
+            ));
+        };
+        self.position += length;
+        let text = self.unescape(start, self.position)?;
+        self.expect_end_tag(tag.name)?;
+        Ok(text)
+    }
+
+    fn parse_name(&mut self) -> LoadumResult<&'source str> {
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b':' | b'.'))
+        {
+            self.position += 1;
+        }
+        if self.position == start {
+            return Err(self.error("Expected element name"));
+        }
+        Ok(&self.source[start..self.position])
+    }
+
+    /// Skips whitespace, comments, processing instructions and, in the prolog, the doctype
+    fn skip_misc(&mut self, in_prolog: bool) -> LoadumResult<()> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<?") {
+                self.skip_past("?>", "Unterminated processing instruction")?;
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->", "Unterminated comment")?;
+            } else if in_prolog && self.starts_with("<!DOCTYPE") {
+                self.skip_past(">", "Unterminated document type declaration")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Replaces entity and character references, borrowing the text if there are none
+    fn unescape(&self, start: usize, end: usize) -> LoadumResult<Cow<'source, str>> {
+        let text = &self.source[start..end];
+        if !text.contains('&') {
+            return Ok(Cow::Borrowed(text));
+        }
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(index) = rest.find('&') {
+            output.push_str(&rest[..index]);
+            let reference_start = end - rest.len() + index;
+            let Some(length) = rest[index..].find(';') else {
+                return Err(self.error_at(reference_start, "Unterminated entity reference"));
+            };
+            let reference = &rest[index + 1..index + length];
+            let c = match reference {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => {
+                    let code = if let Some(hex) = reference.strip_prefix("#x") {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else if let Some(decimal) = reference.strip_prefix('#') {
+                        decimal.parse().ok()
+                    } else {
+                        None
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            let Some(c) = c else {
+                return Err(self.error_at(
+                    reference_start,
+                    format_args!("Unknown entity '&{};'", reference),
+                ));
+            };
+            output.push(c);
+            rest = &rest[index + length + 1..];
+        }
+        output.push_str(rest);
+        Ok(Cow::Owned(output))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|c| matches!(c, b' ' | b'\t' | b'\r' | b'\n'))
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Skips to just after the terminator
+    fn skip_past(&mut self, terminator: &str, message: &str) -> LoadumResult<()> {
+        match self.source[self.position..].find(terminator) {
+            Some(index) => {
+                self.position += index + terminator.len();
+                Ok(())
+            }
+            None => Err(self.error(message)),
+        }
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.bytes[self.position..].starts_with(prefix.as_bytes())
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+/// Reads the objects of a binary property list, starting at the top object given by the trailer
+struct BinaryParser<'source> {
+    source: &'source [u8],
+    offset_size: usize,
+    reference_size: usize,
+    object_count: u64,
+    top_object: u64,
+    offset_table: usize,
+    /// Objects being parsed, to detect cycles
+    stack: Vec<u64>,
+    /// Number of objects that may still be loaded, see [`MAX_EXPANSION`]
+    remaining_objects: u64,
+}
+
+/// Limit of loaded objects per byte of object data
+///
+/// Objects can be referenced more than once, so nested shared references would otherwise expand a
+/// small file exponentially. Without shared arrays and dictionaries every loaded object takes a
+/// reference of at least one byte, the factor leaves room for sharing them.
+const MAX_EXPANSION: u64 = 16;
+
+impl<'source> BinaryParser<'source> {
+    fn new(source: &'source [u8]) -> LoadumResult<BinaryParser<'source>> {
+        let Some(trailer_start) = source.len().checked_sub(32) else {
+            bail!("Binary property list is too short for its trailer");
+        };
+        let trailer = &source[trailer_start..];
+        let parser = BinaryParser {
+            source: &source[..trailer_start],
+            offset_size: trailer[6] as usize,
+            reference_size: trailer[7] as usize,
+            object_count: read_unsigned(&trailer[8..16]),
+            top_object: read_unsigned(&trailer[16..24]),
+            offset_table: read_unsigned(&trailer[24..32]) as usize,
+            stack: vec![],
+            remaining_objects: (trailer_start as u64).saturating_mul(MAX_EXPANSION),
+        };
+        let table_end = parser
+            .object_count
+            .checked_mul(parser.offset_size as u64)
+            .and_then(|size| size.checked_add(parser.offset_table as u64));
+        if !(1..=8).contains(&parser.offset_size)
+            || !(1..=8).contains(&parser.reference_size)
+            || parser.top_object >= parser.object_count
+            || parser.offset_table < BINARY_HEADER.len()
+            || table_end.is_none_or(|end| end > trailer_start as u64)
+        {
+            bail!("Invalid binary property list trailer");
+        }
+        Ok(parser)
+    }
+
+    fn parse_document(&mut self) -> LoadumResult<Node<'source>> {
+        self.parse_object(self.top_object)
+    }
+
+    fn parse_object(&mut self, index: u64) -> LoadumResult<Node<'source>> {
+        if index >= self.object_count {
+            bail!("Invalid object reference {}", index);
+        }
+        if self.stack.contains(&index) {
+            bail!("Cyclic reference to object {}", index);
+        }
+        check_depth(self.stack.len())?;
+        let Some(remaining_objects) = self.remaining_objects.checked_sub(1) else {
+            bail!("Binary property list expands to too many objects");
+        };
+        self.remaining_objects = remaining_objects;
+        let entry = self.offset_table + index as usize * self.offset_size;
+        let offset = read_unsigned(&self.source[entry..entry + self.offset_size]) as usize;
+        if offset < BINARY_HEADER.len() || offset >= self.offset_table {
+            bail!("Invalid offset {} of object {}", offset, index);
+        }
+        self.stack.push(index);
+        let node = self.parse_object_at(offset, index)?;
+        self.stack.pop();
+        Ok(node)
+    }
+
+    fn parse_object_at(&mut self, offset: usize, index: u64) -> LoadumResult<Node<'source>> {
+        let marker = self.source[offset];
+        let size = marker & 0x0f;
+        let mut position = offset + 1;
+        let value = match marker >> 4 {
+            0x0 => match size {
+                0x0 => Value::Null,
+                0x8 => Value::Boolean(false),
+                0x9 => Value::Boolean(true),
+                _ => bail!("Invalid marker 0x{:02x} of object {}", marker, index),
+            },
+            0x1 => self.read_integer(size, &mut position, index)?,
+            0x2 => match size {
+                2 => {
+                    Value::Number(f32::from_be_bytes(self.read_array(&mut position, index)?) as f64)
+                }
+                3 => Value::Number(f64::from_be_bytes(self.read_array(&mut position, index)?)),
+                _ => bail!("Invalid marker 0x{:02x} of object {}", marker, index),
+            },
+            0x3 if size == 3 => {
+                date_value(f64::from_be_bytes(self.read_array(&mut position, index)?))?
+            }
+            0x4 => {
+                let length = self.read_length(size, &mut position, index)?;
+                Value::bytes(self.read_bytes(&mut position, length, index)?)
+            }
+            // ASCII strings, and UTF-8 strings of newer versions
+            0x5 | 0x7 => {
+                let length = self.read_length(size, &mut position, index)?;
+                let bytes = self.read_bytes(&mut position, length, index)?;
+                let Ok(string) = std::str::from_utf8(bytes) else {
+                    bail!("Invalid string of object {}", index);
+                };
+                Value::BorrowedString(string)
+            }
+            0x6 => {
+                let length = self.read_length(size, &mut position, index)?;
+                let Some(byte_length) = length.checked_mul(2) else {
+                    bail!("Invalid length of object {}", index);
+                };
+                let bytes = self.read_bytes(&mut position, byte_length, index)?;
+                let units = bytes
+                    .chunks(2)
+                    .map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+                let Ok(string) = char::decode_utf16(units).collect::<Result<String, _>>() else {
+                    bail!("Invalid UTF-16 string of object {}", index);
+                };
+                Value::String(LoadumString::from(string))
+            }
+            0x8 => {
+                let bytes = self.read_bytes(&mut position, size as usize + 1, index)?;
+                if bytes.len() > 8 {
+                    bail!("Invalid UID size of object {}", index);
+                }
+                return Ok(uid_node(read_unsigned(bytes)));
+            }
+            // Arrays, and sets which have no other representation
+            0xa | 0xc => {
+                let length = self.read_length(size, &mut position, index)?;
+                let references = self.read_references(&mut position, length, index)?;
+                let items = references
+                    .into_iter()
+                    .map(|reference| self.parse_object(reference))
+                    .collect::<LoadumResult<_>>()?;
+                return Ok(Node::List(items));
+            }
+            0xd => {
+                let length = self.read_length(size, &mut position, index)?;
+                let Some(reference_count) = length.checked_mul(2) else {
+                    bail!("Invalid length of object {}", index);
+                };
+                let references = self.read_references(&mut position, reference_count, index)?;
+                let (keys, values) = references.split_at(length);
+                let mut entries = Vec::with_capacity(length);
+                for (key, value) in keys.iter().zip(values) {
+                    entries.push((self.parse_object(*key)?, self.parse_object(*value)?));
+                }
+                return Ok(Node::Map(entries));
+            }
+            _ => bail!("Invalid marker 0x{:02x} of object {}", marker, index),
+        };
+        Ok(Node::Scalar(value))
+    }
+
+    /// Reads a big endian integer of `1 << size` bytes, 8 and 16 byte integers are signed
+    fn read_integer(
+        &self,
+        size: u8,
+        position: &mut usize,
+        index: u64,
+    ) -> LoadumResult<Value<'source>> {
+        if size > 4 {
+            bail!("Invalid integer size of object {}", index);
+        }
+        let bytes = self.read_bytes(position, 1 << size, index)?;
+        let value = match size {
+            0..=2 => Value::Integer(read_unsigned(bytes) as i64),
+            3 => Value::Integer(i64::from_be_bytes(bytes.try_into()?)),
+            _ => {
+                let value = i128::from_be_bytes(bytes.try_into()?);
+                match i64::try_from(value) {
+                    Ok(value) => Value::Integer(value),
+                    // Integers are signed 64 bit, larger values are approximated
+                    Err(_) => Value::Number(value as f64),
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    /// Reads the length in the low bits of a marker, or the integer object following it
+    fn read_length(&self, size: u8, position: &mut usize, index: u64) -> LoadumResult<usize> {
+        if size != 0x0f {
+            return Ok(size as usize);
+        }
+        let marker = self.read_bytes(position, 1, index)?[0];
+        if marker >> 4 != 0x1 {
+            bail!("Invalid length of object {}", index);
+        }
+        match self.read_integer(marker & 0x0f, position, index)? {
+            Value::Integer(length) if length >= 0 => Ok(length as usize),
+            _ => bail!("Invalid length of object {}", index),
+        }
+    }
+
+    fn read_references(
+        &self,
+        position: &mut usize,
+        count: usize,
+        index: u64,
+    ) -> LoadumResult<Vec<u64>> {
+        let Some(length) = count.checked_mul(self.reference_size) else {
+            bail!("Invalid length of object {}", index);
+        };
+        let bytes = self.read_bytes(position, length, index)?;
+        Ok(bytes
+            .chunks(self.reference_size)
+            .map(read_unsigned)
+            .collect())
+    }
+
+    fn read_array<const N: usize>(
+        &self,
+        position: &mut usize,
+        index: u64,
+    ) -> LoadumResult<[u8; N]> {
+        Ok(self.read_bytes(position, N, index)?.try_into()?)
+    }
+
+    fn read_bytes(
+        &self,
+        position: &mut usize,
+        length: usize,
+        index: u64,
+    ) -> LoadumResult<&'source [u8]> {
+        let start = *position;
+        match start.checked_add(length) {
+            Some(end) if end <= self.offset_table => {
+                *position = end;
+                Ok(&self.source[start..end])
+            }
+            _ => bail!("Object {} exceeds the object data", index),
+        }
+    }
+}
+
+/// Reads a big endian unsigned integer of up to 8 bytes
+fn read_unsigned(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(0, |value, byte| value << 8 | *byte as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plist_loader::PlistLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use std::fmt::Write;
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let mut output = String::new();
+        for event in PlistLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        expected.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_xml() {
+        test_loader(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>CFBundleName</key>
+	<string>Tom &amp; Jerry</string>
+	<!-- Comments are skipped -->
+	<key>values</key>
+	<array>
+		<integer>-42</integer>
+		<integer>0x10</integer>
+		<real>1.5</real>
+		<real>+infinity</real>
+		<true/>
+		<false></false>
+		<string/>
+		<date>2024-01-02T03:04:05Z</date>
+		<data>
+		AQID
+		</data>
+		<dict/>
+		<array/>
+	</array>
+	<key>uid</key>
+	<dict>
+		<key>CF$UID</key>
+		<integer>7</integer>
+	</dict>
+</dict>
+</plist>
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("CFBundleName"))
+                Literal(String("Tom & Jerry"))
+                MapKey(BorrowedString("values"))
+                ListStart
+                Literal(Integer(-42))
+                Literal(Integer(16))
+                Literal(Number(1.5))
+                Literal(Number(inf))
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(BorrowedString(""))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2024, month: 1, day: 2 }, time: Time { hour: 3, minute: 4, second: 5, nanosecond: 0 }, offset_minutes: 0 }))
+                Literal(Bytes([1, 2, 3]))
+                MapStart
+                MapEnd
+                ListStart
+                ListEnd
+                ListEnd
+                MapKey(BorrowedString("uid"))
+                Tag("uid")
+                Literal(Integer(7))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        // {"a": [1, -1, 1.5, "é", <00ff>, 2001-01-01T00:00:01Z], "b": true, "c": UID 3}
+        let mut input = b"bplist00".to_vec();
+        let objects: [&[u8]; 12] = [
+            b"\xd3\x01\x02\x03\x04\x05\x06",
+            b"\x51a",
+            b"\x51b",
+            b"\x51c",
+            b"\xa6\x07\x08\x09\x0a\x0b\x0c",
+            b"\x09",
+            b"\x80\x03",
+            b"\x10\x01",
+            b"\x13\xff\xff\xff\xff\xff\xff\xff\xff",
+            b"\x23\x3f\xf8\x00\x00\x00\x00\x00\x00",
+            b"\x61\x00\xe9",
+            b"\x42\x00\xff",
+        ];
+        let mut offsets = vec![];
+        for object in objects {
+            offsets.push(input.len() as u8);
+            input.extend_from_slice(object);
+        }
+        offsets.push(input.len() as u8);
+        input.extend_from_slice(b"\x33\x3f\xf0\x00\x00\x00\x00\x00\x00");
+        let offset_table = input.len() as u8;
+        input.extend_from_slice(&offsets);
+        input.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        input.extend_from_slice(&13u64.to_be_bytes());
+        input.extend_from_slice(&0u64.to_be_bytes());
+        input.extend_from_slice(&(offset_table as u64).to_be_bytes());
+        test_loader(
+            &input,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("a"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(-1))
+                Literal(Number(1.5))
+                Literal(String("é"))
+                Literal(Bytes([0, 255]))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 2001, month: 1, day: 1 }, time: Time { hour: 0, minute: 0, second: 1, nanosecond: 0 }, offset_minutes: 0 }))
+                ListEnd
+                MapKey(BorrowedString("b"))
+                Literal(Boolean(true))
+                MapKey(BorrowedString("c"))
+                Tag("uid")
+                Literal(Integer(3))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    /// Arrays each referencing the next array twice, ending with `true`
+    fn shared_references(depth: u8) -> Vec<u8> {
+        let mut input = b"bplist00".to_vec();
+        let mut offsets = vec![];
+        for index in 1..=depth {
+            offsets.push(input.len() as u8);
+            input.extend_from_slice(&[0xa2, index, index]);
+        }
+        offsets.push(input.len() as u8);
+        input.push(0x09);
+        let offset_table = input.len() as u64;
+        input.extend_from_slice(&offsets);
+        input.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 1]);
+        input.extend_from_slice(&(depth as u64 + 1).to_be_bytes());
+        input.extend_from_slice(&0u64.to_be_bytes());
+        input.extend_from_slice(&offset_table.to_be_bytes());
+        input
+    }
+
+    #[test]
+    fn test_shared_references() {
+        test_loader(
+            &shared_references(2),
+            expect![[r#"
+                DocumentStart
+                ListStart
+                ListStart
+                Literal(Boolean(true))
+                Literal(Boolean(true))
+                ListEnd
+                ListStart
+                Literal(Boolean(true))
+                Literal(Boolean(true))
+                ListEnd
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(
+            &shared_references(64),
+            expect![[r#"
+                Error: Binary property list expands to too many objects
+            "#]],
+        );
+    }
+
+    /// Arrays each containing the next array, ending with `true`
+    fn nested_arrays(depth: u16) -> Vec<u8> {
+        let mut input = b"bplist00".to_vec();
+        let mut offsets = vec![];
+        for index in 1..=depth {
+            offsets.extend_from_slice(&(input.len() as u16).to_be_bytes());
+            input.push(0xa1);
+            input.extend_from_slice(&index.to_be_bytes());
+        }
+        offsets.extend_from_slice(&(input.len() as u16).to_be_bytes());
+        input.push(0x09);
+        let offset_table = input.len() as u64;
+        input.extend_from_slice(&offsets);
+        input.extend_from_slice(&[0, 0, 0, 0, 0, 0, 2, 2]);
+        input.extend_from_slice(&(depth as u64 + 1).to_be_bytes());
+        input.extend_from_slice(&0u64.to_be_bytes());
+        input.extend_from_slice(&offset_table.to_be_bytes());
+        input
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = String::new();
+        let deep_xml = format!("<plist>{}", "<array>".repeat(MAX_DEPTH + 1));
+        let deep_binary = nested_arrays(MAX_DEPTH as u16 + 1);
+        for input in [
+            &b"<dict/>"[..],
+            b"<plist><dict><string>a</string></dict></plist>",
+            b"<plist><integer>x</integer></plist>",
+            b"<plist><string>a</plist>",
+            b"<plist><foo/></plist>",
+            b"<plist><data>!</data></plist>",
+            b"<plist><true/><true/></plist>",
+            b"<plist><array>",
+            b"\xff",
+            b"bplist00",
+            b"bplist00\xa1\x00\x08\0\0\0\0\0\0\x01\x01\0\0\0\0\0\0\0\x01\0\0\0\0\0\0\0\0\0\0\0\0\0\0\0\x0a",
+            deep_xml.as_bytes(),
+            &deep_binary,
+        ] {
+            for event in PlistLoader::new(input) {
+                if let Err(error) = event {
+                    writeln!(output, "{}", error).unwrap();
+                }
+            }
+        }
+        expect![[r#"
+            Expected <plist> element at line 1, column 1
+            Expected <key> element at line 1, column 14
+            Invalid integer 'x' at line 1, column 8
+            Expected </string> at line 1, column 17
+            Unexpected element <foo> at line 1, column 8
+            Invalid base64 character '!' at line 1, column 8
+            Expected </plist> at line 1, column 15
+            Unexpected end of input at line 1, column 15
+            XML property lists must be UTF-8
+            Binary property list is too short for its trailer
+            Cyclic reference to object 0
+            Document is nested deeper than 128 levels at line 1, column 904
+            Document is nested deeper than 128 levels
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,73 @@
+//! Loader and dumper for URL query strings and `application/x-www-form-urlencoded` bodies
+//!
+//! A query string is loaded as a single document containing a map. Nested maps and lists are
+//! encoded in the keys, either with brackets like `user[roles][0]=admin` or with dots like
+//! `user.roles.0=admin`, see [`Nesting`]. All values are loaded as strings.
+
+use std::borrow::Cow;
+
+pub mod query_dumper;
+pub mod query_loader;
+
+/// How nested maps and lists are encoded in the keys of a query string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Nesting {
+    /// Keys like `a[b][0]`, where `a[]` appends to a list, as used by PHP, Rails and `qs`
+    #[default]
+    Brackets,
+    /// Keys like `a.b.0`
+    Dots,
+}
+
+/// Decodes a key or value, with `+` as a space
+///
+/// Invalid percent escapes are kept as is and invalid UTF-8 is replaced, like browsers do.
+pub(crate) fn decode(text: &str) -> Cow<'_, str> {
+    if !text.contains(['%', '+']) {
+        return Cow::Borrowed(text);
+    }
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes
+            .get(index + 1..index + 3)
+            .filter(|hex| bytes[index] == b'%' && hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+            (b'+', None) => decoded.push(b' '),
+            (byte, None) => decoded.push(byte),
+        }
+        index += 1;
+    }
+    Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+/// Encodes a value, keeping only alphanumerics and `*-._` and writing spaces as `+`
+pub(crate) fn encode(output: &mut String, text: &str) {
+    encode_except(output, text, false);
+}
+
+/// Encodes a key segment like a value, with dots escaped if they separate the segments
+pub(crate) fn encode_key(output: &mut String, key: &str, nesting: Nesting) {
+    encode_except(output, key, nesting == Nesting::Dots);
+}
+
+fn encode_except(output: &mut String, text: &str, escape_dots: bool) {
+    for byte in text.bytes() {
+        match byte {
+            b'.' if escape_dots => output.push_str("%2E"),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                output.push(byte as char)
+            }
+            b' ' => output.push('+'),
+            _ => output.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{Nesting, encode, encode_key};
+use loadum::base64;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Dumps maps as URL query strings, each document is written on its own line
+///
+/// Nested maps and lists are flattened into keys following the nesting convention, with list
+/// items written with their indices. Null values are written as a key without `=`, bytes as
+/// base64 and tags are ignored. Empty maps and lists have no representation and are omitted.
+pub struct QueryDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    nesting: Nesting,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> QueryDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> QueryDumper<'write> {
+        QueryDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            nesting: Nesting::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how nested maps and lists are encoded in the keys, by default with brackets
+    pub fn with_nesting(mut self, nesting: Nesting) -> Self {
+        self.nesting = nesting;
+        self
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    fn write_node(&self, output: &mut String, path: &str, node: &Node) -> LoadumResult<()> {
+        match node {
+            Node::Tagged(_, node) => self.write_node(output, path, node)?,
+            Node::Scalar(value) => {
+                if !output.is_empty() {
+                    output.push('&');
+                }
+                output.push_str(path);
+                let text = match value {
+                    Value::Null => return Ok(()),
+                    Value::Bytes(bytes) => Cow::Owned(base64::encode(bytes)),
+                    value => value
+                        .as_str()
+                        .map_or_else(|| Cow::Owned(value.to_string()), Cow::Borrowed),
+                };
+                output.push('=');
+                encode(output, &text);
+            }
+            Node::List(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    let path = self.child_path(path, &index.to_string());
+                    self.write_node(output, &path, item)?;
+                }
+            }
+            Node::Map(entries) => {
+                for (key, value) in entries {
+                    let path = self
+                        .child_path(path, &self.key_policy.key_string(key, "Query string keys")?);
+                    self.write_node(output, &path, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the encoded key of an entry nested in the entry with the given key
+    fn child_path(&self, path: &str, key: &str) -> String {
+        let mut child = path.to_string();
+        match self.nesting {
+            Nesting::Brackets => {
+                child.push('[');
+                encode_key(&mut child, key, self.nesting);
+                child.push(']');
+            }
+            Nesting::Dots => {
+                child.push('.');
+                encode_key(&mut child, key, self.nesting);
+            }
+        }
+        child
+    }
+}
+
+impl Dumper for QueryDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let Node::Map(entries) = root.untagged() else {
+            bail!("Query string documents must be maps, but found {:?}", root);
+        };
+        let mut output = String::new();
+        for (key, value) in entries {
+            let mut path = String::new();
+            encode_key(
+                &mut path,
+                &self.key_policy.key_string(key, "Query string keys")?,
+                self.nesting,
+            );
+            self.write_node(&mut output, &path, value)?;
+        }
+        output.push('\n');
+        self.write.write_all(output.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Nesting;
+    use crate::query_dumper::QueryDumper;
+    use crate::query_loader::QueryLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump_with(dumper: impl FnOnce(&mut Vec<u8>) -> QueryDumper, events: &[Event]) -> String {
+        let mut output = vec![];
+        let mut dumper = dumper(&mut output);
+        let result = [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+            .try_for_each(|event| dumper.emit(event));
+        drop(dumper);
+        match result {
+            Ok(()) => String::from_utf8(output).unwrap(),
+            Err(error) => format!("Error: {}\n", error),
+        }
+    }
+
+    fn dump(events: &[Event]) -> String {
+        dump_with(|output| QueryDumper::new(output), events)
+    }
+
+    fn nested() -> Vec<Event<'static>> {
+        vec![
+            Event::MapStart,
+            Event::map_key("user"),
+            Event::MapStart,
+            Event::map_key("name"),
+            Event::string("Ada Lovelace"),
+            Event::map_key("roles"),
+            Event::ListStart,
+            Event::string("admin"),
+            Event::string("dev&ops"),
+            Event::ListEnd,
+            Event::map_key("tags"),
+            Event::ListStart,
+            Event::ListEnd,
+            Event::MapEnd,
+            Event::map_key("page"),
+            Event::integer(2),
+            Event::map_key("a[b]"),
+            Event::string("key=value"),
+            Event::MapEnd,
+        ]
+    }
+
+    #[test]
+    fn test_nesting() {
+        let mut output = dump(&nested());
+        output.push_str(&dump_with(
+            |output| QueryDumper::new(output).with_nesting(Nesting::Dots),
+            &nested(),
+        ));
+        expect![[r#"
+            user[name]=Ada+Lovelace&user[roles][0]=admin&user[roles][1]=dev%26ops&page=2&a%5Bb%5D=key%3Dvalue
+            user.name=Ada+Lovelace&user.roles.0=admin&user.roles.1=dev%26ops&page=2&a%5Bb%5D=key%3Dvalue
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_values() {
+        let output = dump(&[
+            Event::MapStart,
+            Event::map_key("null"),
+            Event::null(),
+            Event::map_key("bool"),
+            Event::bool(true),
+            Event::map_key("number"),
+            Event::number(1.5),
+            Event::map_key("bytes"),
+            Event::bytes(&b"\xff\xfe"[..]),
+            Event::map_key("date"),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05+01:00").unwrap()),
+            Event::map_key("unicode"),
+            Event::tag("ignored"),
+            Event::string("café ~*"),
+            Event::MapKey(Value::Integer(1)),
+            Event::string("stringified"),
+            Event::MapEnd,
+        ]);
+        expect![[r#"
+            null&bool=true&number=1.5&bytes=%2F%2F4%3D&date=2024-01-02T03%3A04%3A05%2B01%3A00&unicode=caf%C3%A9+%7E*&1=stringified
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for (input, nesting) in [
+            (
+                "user[name]=Ada+Lovelace&user[roles][0]=admin&user[roles][1]=dev%26ops&page=2",
+                Nesting::Brackets,
+            ),
+            (
+                "filter.status=open&filter.labels.0=bug&sort=-created",
+                Nesting::Dots,
+            ),
+        ] {
+            let mut output = vec![];
+            let mut dumper = QueryDumper::new(&mut output).with_nesting(nesting);
+            for event in QueryLoader::new(input).with_nesting(nesting) {
+                dumper.emit(&event.unwrap()).unwrap();
+            }
+            drop(dumper);
+            assert_eq!(String::from_utf8(output).unwrap(), format!("{}\n", input));
+        }
+    }
+
+    #[test]
+    fn test_special_keys_roundtrip() {
+        let events = [
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::map_key("a.b"),
+            Event::MapStart,
+            Event::map_key("c[d]"),
+            Event::string("1"),
+            Event::map_key("[]"),
+            Event::string("2"),
+            Event::MapEnd,
+            Event::map_key("e]"),
+            Event::string("3"),
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ];
+        for nesting in [Nesting::Brackets, Nesting::Dots] {
+            let mut output = vec![];
+            let mut dumper = QueryDumper::new(&mut output).with_nesting(nesting);
+            for event in &events {
+                dumper.emit(event).unwrap();
+            }
+            drop(dumper);
+            let output = String::from_utf8(output).unwrap();
+            let loaded: Vec<Event> = QueryLoader::new(output.trim_end())
+                .with_nesting(nesting)
+                .map(|event| event.unwrap().into_owned())
+                .collect();
+            assert_eq!(loaded, events, "{}", output);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = String::new();
+        for events in [
+            vec![Event::ListStart, Event::ListEnd],
+            vec![
+                Event::MapStart,
+                Event::ComplexKey,
+                Event::ListStart,
+                Event::ListEnd,
+                Event::integer(1),
+                Event::MapEnd,
+            ],
+        ] {
+            output.push_str(&dump(&events));
+        }
+        output.push_str(&dump_with(
+            |output| QueryDumper::new(output).with_key_policy(KeyPolicy::Reject),
+            &[
+                Event::MapStart,
+                Event::MapKey(Value::Integer(1)),
+                Event::integer(1),
+                Event::MapEnd,
+            ],
+        ));
+        expect![[r#"
+            Error: Query string documents must be maps, but found List([])
+            Error: Query string keys cannot be composite, but found List([])
+            Error: Query string keys must be strings, but found Scalar(Integer(1))
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,376 @@
+use crate::{Nesting, decode};
+use loadum::depth::check_depth;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+
+/// Loads a URL query string or form body as a single document containing a map of strings
+///
+/// Pairs are separated by `&`, a leading `?` is skipped and a pair without `=` has an empty value.
+/// The nesting of keys is parsed before they are decoded, so percent-encoded brackets and dots are
+/// part of the key, keys that don't follow the nesting convention are used as is. A key repeated
+/// without nesting collects its values in a list. List indices have to be consecutive, otherwise
+/// the list is loaded as a map with the indices as keys. Keys nested deeper than
+/// [`MAX_DEPTH`](loadum::depth::MAX_DEPTH) levels are rejected.
+pub struct QueryLoader<'source> {
+    source: &'source str,
+    nesting: Nesting,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> QueryLoader<'source> {
+    pub fn new(source: &'source str) -> QueryLoader<'source> {
+        QueryLoader {
+            source,
+            nesting: Nesting::default(),
+            events: None,
+        }
+    }
+
+    /// Sets how nested maps and lists are encoded in the keys, by default with brackets
+    pub fn with_nesting(mut self, nesting: Nesting) -> Self {
+        self.nesting = nesting;
+        self
+    }
+
+    fn parse(&self) -> LoadumResult<Tree<'source>> {
+        let source = self.source.strip_prefix('?').unwrap_or(self.source);
+        let mut root = Tree::Map(vec![]);
+        for pair in source.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let segments: Vec<Value<'source>> = path(key, self.nesting)
+                .into_iter()
+                .map(decoded_value)
+                .collect();
+            // Inserting is recursive, one level per segment
+            check_depth(segments.len())?;
+            root.insert(&segments, decoded_value(value), &decode(key))?;
+        }
+        Ok(root)
+    }
+}
+
+impl<'source> Loader<'source> for QueryLoader<'source> {}
+
+impl<'source> Iterator for QueryLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            match self.parse() {
+                Ok(root) => {
+                    let mut events = vec![Event::DocumentStart];
+                    root.push_events(&mut events);
+                    events.push(Event::DocumentEnd);
+                    self.events = Some(events.into_iter());
+                }
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+fn decoded_value(text: &str) -> Value<'_> {
+    match decode(text) {
+        Cow::Borrowed(text) => Value::BorrowedString(text),
+        Cow::Owned(text) => Value::string(text),
+    }
+}
+
+/// Splits a key into its path segments, or returns the key itself if it isn't nested
+fn path(key: &str, nesting: Nesting) -> Vec<&str> {
+    let segments = match nesting {
+        Nesting::Brackets => bracket_path(key),
+        Nesting::Dots => Some(key.split('.').collect::<Vec<_>>())
+            .filter(|segments| segments.iter().all(|segment| !segment.is_empty())),
+    };
+    segments.unwrap_or_else(|| vec![key])
+}
+
+fn bracket_path(key: &str) -> Option<Vec<&str>> {
+    let open = key.find('[').filter(|open| *open > 0)?;
+    let mut segments = vec![&key[..open]];
+    let mut rest = &key[open..];
+    while !rest.is_empty() {
+        rest = rest.strip_prefix('[')?;
+        let close = rest.find(']')?;
+        segments.push(&rest[..close]);
+        rest = &rest[close + 1..];
+    }
+    Some(segments)
+}
+
+/// Returns the index of a list segment, an empty segment appends to the list
+fn list_index(segment: &Value, len: usize) -> Option<usize> {
+    match segment.as_str()? {
+        "" => Some(len),
+        "0" => Some(0),
+        digits if !digits.starts_with('0') && digits.bytes().all(|b| b.is_ascii_digit()) => {
+            digits.parse().ok()
+        }
+        _ => None,
+    }
+}
+
+enum Tree<'source> {
+    Value(Value<'source>),
+    List(Vec<Tree<'source>>),
+    Map(Vec<(Value<'source>, Tree<'source>)>),
+}
+
+impl<'source> Tree<'source> {
+    /// Returns an empty container for the given segment, a list if it starts one
+    fn container(segment: &Value) -> Tree<'source> {
+        match list_index(segment, 0) {
+            Some(0) => Tree::List(vec![]),
+            _ => Tree::Map(vec![]),
+        }
+    }
+
+    fn insert(
+        &mut self,
+        segments: &[Value<'source>],
+        value: Value<'source>,
+        key: &str,
+    ) -> LoadumResult<()> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return self.push_value(value, key);
+        };
+        let child = match self {
+            Tree::Value(_) => bail!("Key '{}' has both a value and nested keys", key),
+            Tree::Map(entries) => {
+                match entries
+                    .iter()
+                    .position(|(existing, _)| existing.as_str() == segment.as_str())
+                {
+                    Some(index) => &mut entries[index].1,
+                    None => {
+                        entries.push((segment.clone(), Tree::new(rest, value, key)?));
+                        return Ok(());
+                    }
+                }
+            }
+            Tree::List(items) => match list_index(segment, items.len()) {
+                Some(index) if index < items.len() => &mut items[index],
+                Some(index) if index == items.len() => {
+                    items.push(Tree::new(rest, value, key)?);
+                    return Ok(());
+                }
+                _ => {
+                    let entries = std::mem::take(items)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, item)| (Value::string(index.to_string()), item))
+                        .collect();
+                    *self = Tree::Map(entries);
+                    return self.insert(segments, value, key);
+                }
+            },
+        };
+        child.insert(rest, value, key)
+    }
+
+    /// Returns a new tree containing the value at the given path
+    fn new(segments: &[Value<'source>], value: Value<'source>, key: &str) -> LoadumResult<Self> {
+        let Some(segment) = segments.first() else {
+            return Ok(Tree::Value(value));
+        };
+        let mut tree = Tree::container(segment);
+        tree.insert(segments, value, key)?;
+        Ok(tree)
+    }
+
+    /// Adds a value for a key that already has one, collecting the values in a list
+    fn push_value(&mut self, value: Value<'source>, key: &str) -> LoadumResult<()> {
+        match self {
+            Tree::Value(existing) => {
+                let existing = std::mem::replace(existing, Value::Null);
+                *self = Tree::List(vec![Tree::Value(existing), Tree::Value(value)]);
+            }
+            Tree::List(items) => items.push(Tree::Value(value)),
+            Tree::Map(_) => bail!("Key '{}' has both a value and nested keys", key),
+        }
+        Ok(())
+    }
+
+    fn push_events(self, events: &mut Vec<Event<'source>>) {
+        match self {
+            Tree::Value(value) => events.push(Event::Literal(value)),
+            Tree::List(items) => {
+                events.push(Event::ListStart);
+                for item in items {
+                    item.push_events(events);
+                }
+                events.push(Event::ListEnd);
+            }
+            Tree::Map(entries) => {
+                events.push(Event::MapStart);
+                for (key, value) in entries {
+                    events.push(Event::MapKey(key));
+                    value.push_events(events);
+                }
+                events.push(Event::MapEnd);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Nesting;
+    use crate::query_loader::QueryLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use std::fmt::Write;
+
+    fn load_with(input: &str, nesting: Nesting) -> String {
+        let mut output = String::new();
+        for event in QueryLoader::new(input).with_nesting(nesting) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, nesting: Nesting, expected: Expect) {
+        expected.assert_eq(&load_with(input, nesting));
+    }
+
+    #[test]
+    fn test_brackets() {
+        test_loader(
+            "?user[name]=Ada&user[roles][]=admin&user[roles][]=dev&items[0][id]=1&items[0][qty]=2\
+             &items[1][id]=3&sparse[0]=a&sparse[5]=b&tag=x&tag=y&flag&a.b=1&odd[=1&[]=2&&",
+            Nesting::Brackets,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("user"))
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Ada"))
+                MapKey(String("roles"))
+                ListStart
+                Literal(String("admin"))
+                Literal(String("dev"))
+                ListEnd
+                MapEnd
+                MapKey(String("items"))
+                ListStart
+                MapStart
+                MapKey(String("id"))
+                Literal(String("1"))
+                MapKey(String("qty"))
+                Literal(String("2"))
+                MapEnd
+                MapStart
+                MapKey(String("id"))
+                Literal(String("3"))
+                MapEnd
+                ListEnd
+                MapKey(String("sparse"))
+                MapStart
+                MapKey(String("0"))
+                Literal(String("a"))
+                MapKey(String("5"))
+                Literal(String("b"))
+                MapEnd
+                MapKey(String("tag"))
+                ListStart
+                Literal(String("x"))
+                Literal(String("y"))
+                ListEnd
+                MapKey(String("flag"))
+                Literal(String(""))
+                MapKey(String("a.b"))
+                Literal(String("1"))
+                MapKey(String("odd["))
+                Literal(String("1"))
+                MapKey(String("[]"))
+                Literal(String("2"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_dots() {
+        test_loader(
+            "user.name=Ada&user.roles.0=admin&user.roles.1=dev&a[b]=1&trailing.=1&.leading=2",
+            Nesting::Dots,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("user"))
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Ada"))
+                MapKey(String("roles"))
+                ListStart
+                Literal(String("admin"))
+                Literal(String("dev"))
+                ListEnd
+                MapEnd
+                MapKey(String("a[b]"))
+                Literal(String("1"))
+                MapKey(String("trailing."))
+                Literal(String("1"))
+                MapKey(String(".leading"))
+                Literal(String("2"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_decoding() {
+        test_loader(
+            "q=caf%C3%A9+au+lait&%61%5Bb%5D=encoded&percent=100%25&invalid=%zz%4&lossy=%FF&empty=",
+            Nesting::Brackets,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("q"))
+                Literal(String("café au lait"))
+                MapKey(String("a[b]"))
+                Literal(String("encoded"))
+                MapKey(String("percent"))
+                Literal(String("100%"))
+                MapKey(String("invalid"))
+                Literal(String("%zz%4"))
+                MapKey(String("lossy"))
+                Literal(String("�"))
+                MapKey(String("empty"))
+                Literal(String(""))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let deep = format!("a{}=1", "[a]".repeat(MAX_DEPTH));
+        let output: String = ["a=1&a[b]=2", "a[b]=1&a=2", "a[]=1&a[0][x]=2", &deep]
+            .into_iter()
+            .map(|input| load_with(input, Nesting::Brackets))
+            .collect();
+        expect![[r#"
+            Error: Key 'a[b]' has both a value and nested keys
+            Error: Key 'a' has both a value and nested keys
+            Error: Key 'a[0][x]' has both a value and nested keys
+            Error: Document is nested deeper than 128 levels
+        "#]]
+        .assert_eq(&output);
+    }
+}
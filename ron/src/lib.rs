@@ -0,0 +1,13 @@
+pub mod ron_dumper;
+pub mod ron_loader;
+
+/// Whether a name can be written as a RON identifier, e.g. as a struct name or field
+pub(crate) fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether digits fit the 128 bit integers of RON
+pub(crate) fn is_big_integer(digits: &str) -> bool {
+    digits.parse::<i128>().is_ok() || digits.parse::<u128>().is_ok()
+}
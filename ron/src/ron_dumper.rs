@@ -0,0 +1,361 @@
+use crate::{is_big_integer, is_identifier};
+use loadum::dumper::Dumper;
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Dumps RON (Rusty Object Notation) documents
+///
+/// Values tagged with an identifier are written as named structs, tuple structs, newtypes or
+/// unit variants, other tags are ignored. Maps are written as structs if they are tagged and all
+/// keys are identifiers. Digits tagged with `bigint` are written as integers, null as `None` and
+/// bytes as byte strings.
+pub struct RonDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+}
+
+impl<'write> RonDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> RonDumper<'write> {
+        RonDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+        }
+    }
+}
+
+impl Dumper for RonDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut formatter = RonFormatter {
+            output: String::new(),
+        };
+        formatter.write_node(&root, 0);
+        formatter.output.push('\n');
+        self.write.write_all(formatter.output.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct RonFormatter {
+    output: String,
+}
+
+impl RonFormatter {
+    fn write_node(&mut self, node: &Node, indentation_level: usize) {
+        match node {
+            Node::Tagged(tag, node) if tag == BIGINT_TAG => match node.as_str() {
+                Some(digits) if is_big_integer(digits) => self.output.push_str(digits),
+                _ => self.write_named(tag, node, indentation_level),
+            },
+            Node::Tagged(name, node) if is_identifier(name) => {
+                self.write_named(name, node, indentation_level)
+            }
+            // Tags of other formats have no RON representation
+            Node::Tagged(_, node) => self.write_node(node, indentation_level),
+            Node::Scalar(value) => self.write_scalar(value),
+            Node::List(items) if items.is_empty() => self.output.push_str("[]"),
+            Node::List(items) => {
+                self.output.push_str("[\n");
+                for item in items {
+                    self.indent(indentation_level + 1);
+                    self.write_node(item, indentation_level + 1);
+                    self.output.push_str(",\n");
+                }
+                self.indent(indentation_level);
+                self.output.push(']');
+            }
+            Node::Map(entries) => self.write_entries("{", '}', entries, false, indentation_level),
+        }
+    }
+
+    /// Writes a value tagged with a struct or enum variant name
+    fn write_named(&mut self, name: &str, node: &Node, indentation_level: usize) {
+        match node {
+            Node::Scalar(Value::Null) => self.output.push_str(name),
+            Node::Map(entries)
+                if !entries.is_empty()
+                    && entries
+                        .iter()
+                        .all(|(key, _)| key.as_str().is_some_and(is_identifier)) =>
+            {
+                self.write_entries(&format!("{}(", name), ')', entries, true, indentation_level);
+            }
+            // Tuple structs are written on one line like tuples, a single item would be a newtype
+            Node::List(items) if items.len() != 1 => {
+                self.output.push_str(name);
+                self.output.push('(');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_node(item, indentation_level);
+                }
+                self.output.push(')');
+            }
+            node => {
+                self.output.push_str(name);
+                self.output.push('(');
+                self.write_node(node, indentation_level);
+                self.output.push(')');
+            }
+        }
+    }
+
+    /// Writes the entries of a map or the fields of a struct, one per line
+    fn write_entries(
+        &mut self,
+        open: &str,
+        close: char,
+        entries: &[(Node, Node)],
+        is_struct: bool,
+        indentation_level: usize,
+    ) {
+        self.output.push_str(open);
+        if entries.is_empty() {
+            self.output.push(close);
+            return;
+        }
+        self.output.push('\n');
+        for (key, value) in entries {
+            self.indent(indentation_level + 1);
+            match key.as_str() {
+                Some(field) if is_struct => self.output.push_str(field),
+                _ => self.write_node(key, indentation_level + 1),
+            }
+            self.output.push_str(": ");
+            self.write_node(value, indentation_level + 1);
+            self.output.push_str(",\n");
+        }
+        self.indent(indentation_level);
+        self.output.push(close);
+    }
+
+    fn indent(&mut self, indentation_level: usize) {
+        for _ in 0..indentation_level {
+            self.output.push_str("    ");
+        }
+    }
+
+    fn write_scalar(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.output.push_str("None"),
+            Value::Boolean(b) => self.output.push_str(if *b { "true" } else { "false" }),
+            Value::Integer(i) => write!(self.output, "{}", i).unwrap(),
+            Value::Number(n) if n.is_nan() => self.output.push_str("NaN"),
+            Value::Number(n) if n.is_infinite() => {
+                self.output.push_str(if *n > 0.0 { "inf" } else { "-inf" })
+            }
+            // Debug formatting always includes a fraction or exponent, keeping floats distinct
+            Value::Number(n) => write!(self.output, "{:?}", n).unwrap(),
+            Value::String(s) => self.write_string(s),
+            Value::BorrowedString(s) => self.write_string(s),
+            Value::Bytes(bytes) => {
+                self.output.push_str("b\"");
+                for byte in bytes {
+                    match byte {
+                        b'"' => self.output.push_str("\\\""),
+                        b'\\' => self.output.push_str("\\\\"),
+                        b' '..=b'~' => self.output.push(*byte as char),
+                        byte => write!(self.output, "\\x{:02x}", byte).unwrap(),
+                    }
+                }
+                self.output.push('"');
+            }
+            Value::DateTime(date_time) => self.write_string(&date_time.to_string()),
+        }
+    }
+
+    fn write_string(&mut self, s: &str) {
+        self.output.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\t' => self.output.push_str("\\t"),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                c if c.is_control() => write!(self.output, "\\u{{{:x}}}", c as u32).unwrap(),
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ron_dumper::RonDumper;
+    use crate::ron_loader::RonLoader;
+    use expect_test::{Expect, expect};
+    use loadum::datetime::DateTime;
+    use loadum::dumper::Dumper;
+    use loadum::event::Event;
+    use loadum::testing::{emit_events, format_dump};
+
+    fn dump(events: &[Event]) -> String {
+        let mut output = vec![];
+        let result = emit_events(
+            RonDumper::new(&mut output),
+            [&Event::DocumentStart]
+                .into_iter()
+                .chain(events)
+                .chain([&Event::DocumentEnd]),
+        );
+        format_dump(result, String::from_utf8(output).unwrap())
+    }
+
+    fn test_dump(events: &[Event], expected: Expect) {
+        expected.assert_eq(&dump(events));
+    }
+
+    #[test]
+    fn test_values() {
+        test_dump(
+            &[
+                Event::ListStart,
+                Event::null(),
+                Event::bool(true),
+                Event::integer(-42),
+                Event::tag("bigint"),
+                Event::string("-9223372036854775809"),
+                Event::tag("bigint"),
+                Event::string("not digits"),
+                Event::number(1),
+                Event::number(6.626e-34),
+                Event::number(f64::NEG_INFINITY),
+                Event::number(f64::NAN),
+                Event::string("quote \" backslash \\ newline \n bell \u{7}"),
+                Event::bytes(&b"a\"\x00\xff"[..]),
+                Event::date_time(DateTime::parse("1979-05-27T07:32:00Z").unwrap()),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::ListStart,
+                Event::ListEnd,
+                Event::ListEnd,
+            ],
+            expect![[r#"
+                [
+                    None,
+                    true,
+                    -42,
+                    -9223372036854775809,
+                    bigint("not digits"),
+                    1.0,
+                    6.626e-34,
+                    -inf,
+                    NaN,
+                    "quote \" backslash \\ newline \n bell \u{7}",
+                    b"a\"\x00\xff",
+                    "1979-05-27T07:32:00Z",
+                    {},
+                    [],
+                ]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_named() {
+        test_dump(
+            &[
+                Event::tag("Scene"),
+                Event::MapStart,
+                Event::map_key("kind"),
+                Event::tag("Player"),
+                Event::null(),
+                Event::map_key("health"),
+                Event::tag("Health"),
+                Event::integer(100),
+                Event::map_key("bounds"),
+                Event::tag("Rect"),
+                Event::ListStart,
+                Event::integer(0),
+                Event::integer(640),
+                Event::ListEnd,
+                Event::map_key("single"),
+                Event::tag("Single"),
+                Event::ListStart,
+                Event::integer(1),
+                Event::ListEnd,
+                Event::map_key("map"),
+                Event::tag("Map"),
+                Event::MapStart,
+                Event::map_key("not a field"),
+                Event::tag("tag:yaml.org,2002:str"),
+                Event::string("ignored tag"),
+                Event::ComplexKey,
+                Event::ListStart,
+                Event::integer(1),
+                Event::ListEnd,
+                Event::null(),
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            expect![[r#"
+                Scene(
+                    kind: Player,
+                    health: Health(100),
+                    bounds: Rect(0, 640),
+                    single: Single([
+                        1,
+                    ]),
+                    map: Map({
+                        "not a field": "ignored tag",
+                        [
+                            1,
+                        ]: None,
+                    }),
+                )
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = r#"Scene(
+    name: "Level 1",
+    entities: [
+        Entity(
+            position: [
+                1.0,
+                2.5,
+            ],
+            kind: Player,
+            health: Health(100),
+            inventory: {
+                "sword": 1,
+                Potion(2): 3,
+            },
+        ),
+        Entity(
+            position: [
+                0.0,
+                0.0,
+            ],
+            kind: Enemy(
+                speed: 2,
+            ),
+            tags: [],
+        ),
+    ],
+    bounds: Rect(0, 0, 640, 480),
+    empty: Empty(),
+    icon: b"\x89PNG",
+    parent: None,
+)
+"#;
+        let mut output = vec![];
+        let mut dumper = RonDumper::new(&mut output);
+        for event in RonLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+}
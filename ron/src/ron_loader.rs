@@ -0,0 +1,805 @@
+use loadum::depth::Depth;
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::{BIGINT_TAG, Event};
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+
+/// Loads RON (Rusty Object Notation) documents
+///
+/// Structs load as maps and tuples as lists, the names of structs and enum variants are loaded
+/// as tags. Unit structs and variants like `Red` load as a tagged null, newtypes like
+/// `Meters(5.0)` as their tagged content. `()` and `None` load as null, `Some(value)` as its value
+/// and characters as strings. Integers beyond the signed 64 bit range are loaded as their digits
+/// tagged with `bigint`.
+pub struct RonLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> RonLoader<'source> {
+    pub fn new(source: &'source str) -> RonLoader<'source> {
+        RonLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for RonLoader<'source> {}
+
+impl<'source> Iterator for RonLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                bytes: self.source.as_bytes(),
+                position: 0,
+                depth: Depth::default(),
+            };
+            match parser.parse_document() {
+                Ok(events) => self.events = Some(events.into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        self.skip_blank()?;
+        // Attributes like `#![enable(implicit_some)]` only affect deserialization into Rust types
+        while self.bytes[self.position..].starts_with(b"#!") {
+            self.skip_attribute()?;
+            self.skip_blank()?;
+        }
+        let mut events = vec![Event::DocumentStart];
+        self.parse_value(&mut events)?;
+        self.skip_blank()?;
+        if self.peek().is_some() {
+            return Err(self.error("Expected end of document"));
+        }
+        events.push(Event::DocumentEnd);
+        Ok(events)
+    }
+
+    fn skip_attribute(&mut self) -> LoadumResult<()> {
+        let start = self.position;
+        self.position += 2;
+        self.expect(b'[')?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                None => return Err(self.error_at(start, "Unterminated attribute")),
+                Some(b'[') => depth += 1,
+                Some(b']') => depth -= 1,
+                Some(_) => {}
+            }
+            self.position += 1;
+        }
+        Ok(())
+    }
+
+    fn parse_value(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        self.skip_blank()?;
+        let rest = &self.bytes[self.position..];
+        let value = match self.peek() {
+            None => return Err(self.error("Expected value")),
+            Some(b'[') => return self.parse_list(events),
+            Some(b'{') => return self.parse_map(events),
+            Some(b'(') => return self.parse_parenthesized(None, events),
+            Some(quote @ (b'"' | b'\'')) => self.parse_string(quote)?,
+            Some(b'r') if raw_string_hashes(rest).is_some() => {
+                Value::BorrowedString(self.parse_raw_string()?)
+            }
+            Some(b'b') if rest.starts_with(b"b\"") => {
+                self.position += 1;
+                let start = self.position;
+                let content = self.quoted(b'"')?;
+                let bytes = unescape(content, true).map_err(|error| self.error_at(start, error))?;
+                Value::bytes(bytes.as_slice())
+            }
+            Some(b'b') if raw_string_hashes(&rest[1..]).is_some() => {
+                self.position += 1;
+                Value::bytes(self.parse_raw_string()?.as_bytes())
+            }
+            Some(c) if c.is_ascii_digit() || matches!(c, b'+' | b'-' | b'.') => {
+                let (tag, value) = self.parse_number()?;
+                if let Some(tag) = tag {
+                    events.push(Event::tag(tag));
+                }
+                value
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => return self.parse_named(events),
+            Some(c) => {
+                return Err(self.error(format!("Expected value, but found '{}'", c.escape_ascii())));
+            }
+        };
+        events.push(Event::Literal(value));
+        Ok(())
+    }
+
+    /// Parses values starting with an identifier: keywords, named structs and enum variants
+    fn parse_named(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let value = match self.parse_identifier()? {
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            "inf" => Value::Number(f64::INFINITY),
+            "NaN" => Value::Number(f64::NAN),
+            "None" => Value::Null,
+            "Some" => {
+                self.expect(b'(')?;
+                self.depth.enter().map_err(|error| self.error(error))?;
+                self.parse_value(events)?;
+                self.depth.exit();
+                self.skip_blank()?;
+                if self.peek() == Some(b',') {
+                    self.position += 1;
+                }
+                return self.expect(b')');
+            }
+            name => {
+                self.skip_blank()?;
+                if self.peek() == Some(b'(') {
+                    return self.parse_parenthesized(Some(name), events);
+                }
+                events.push(Event::Tag(name.into()));
+                Value::Null
+            }
+        };
+        events.push(Event::Literal(value));
+        Ok(())
+    }
+
+    /// Parses a struct, a tuple or the unit value `()`, optionally preceded by a name
+    fn parse_parenthesized(
+        &mut self,
+        name: Option<&str>,
+        events: &mut Vec<Event<'source>>,
+    ) -> LoadumResult<()> {
+        self.position += 1;
+        if let Some(name) = name {
+            events.push(Event::Tag(name.into()));
+        }
+        self.skip_blank()?;
+        if self.is_field()? {
+            events.push(Event::MapStart);
+            self.parse_items(b')', "struct", |parser| {
+                let field = parser.parse_identifier()?;
+                events.push(Event::MapKey(Value::BorrowedString(field)));
+                parser.expect(b':')?;
+                parser.parse_value(events)
+            })?;
+            events.push(Event::MapEnd);
+            return Ok(());
+        }
+        let mut items = vec![];
+        let count = self.parse_items(b')', "tuple", |parser| parser.parse_value(&mut items))?;
+        match (name, count) {
+            (None, 0) => events.push(Event::Literal(Value::Null)),
+            // Newtypes are transparent, as in most serde formats
+            (Some(_), 1) => events.extend(items),
+            _ => {
+                events.push(Event::ListStart);
+                events.extend(items);
+                events.push(Event::ListEnd);
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the next item is a struct field, `name: value`
+    fn is_field(&mut self) -> LoadumResult<bool> {
+        let start = self.position;
+        let is_field = self.parse_identifier().is_ok() && {
+            self.skip_blank()?;
+            self.peek() == Some(b':')
+        };
+        self.position = start;
+        Ok(is_field)
+    }
+
+    fn parse_list(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        self.position += 1;
+        events.push(Event::ListStart);
+        self.parse_items(b']', "list", |parser| parser.parse_value(events))?;
+        events.push(Event::ListEnd);
+        Ok(())
+    }
+
+    fn parse_map(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        self.position += 1;
+        events.push(Event::MapStart);
+        self.parse_items(b'}', "map", |parser| {
+            parser.parse_key(events)?;
+            parser.expect(b':')?;
+            parser.parse_value(events)
+        })?;
+        events.push(Event::MapEnd);
+        Ok(())
+    }
+
+    /// Parses a map key, which may be any value
+    fn parse_key(&mut self, events: &mut Vec<Event<'source>>) -> LoadumResult<()> {
+        let mut key = vec![];
+        self.parse_value(&mut key)?;
+        match key.pop() {
+            Some(Event::Literal(value))
+                if key.iter().all(|event| matches!(event, Event::Tag(_))) =>
+            {
+                events.extend(key);
+                events.push(Event::MapKey(value));
+            }
+            last => {
+                events.push(Event::ComplexKey);
+                events.extend(key);
+                events.extend(last);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses comma separated items up to the closing delimiter, returning their number
+    ///
+    /// A trailing comma is allowed.
+    fn parse_items(
+        &mut self,
+        close: u8,
+        container: &str,
+        mut parse_item: impl FnMut(&mut Self) -> LoadumResult<()>,
+    ) -> LoadumResult<usize> {
+        self.depth.enter().map_err(|error| self.error(error))?;
+        let mut count = 0;
+        loop {
+            self.skip_blank()?;
+            if self.peek() == Some(close) {
+                self.position += 1;
+                self.depth.exit();
+                return Ok(count);
+            }
+            parse_item(self)?;
+            count += 1;
+            self.skip_blank()?;
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(c) if c == close => {
+                    self.position += 1;
+                    self.depth.exit();
+                    return Ok(count);
+                }
+                _ => {
+                    return Err(self.error(format!(
+                        "Expected ',' or '{}' in {}",
+                        close as char, container
+                    )));
+                }
+            }
+        }
+    }
+
+    fn parse_identifier(&mut self) -> LoadumResult<&'source str> {
+        // Raw identifiers like `r#type` allow keywords as names
+        if self.bytes[self.position..].starts_with(b"r#") {
+            self.position += 2;
+        }
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        let length = rest
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || *c == b'_'))
+            .unwrap_or(rest.len());
+        if length == 0 || rest[0].is_ascii_digit() {
+            return Err(self.error("Expected identifier"));
+        }
+        self.position += length;
+        Ok(&self.source[start..start + length])
+    }
+
+    fn parse_number(&mut self) -> LoadumResult<(Option<&'static str>, Value<'source>)> {
+        let rest = &self.bytes[self.position..];
+        let unsigned = rest.strip_prefix(b"-").unwrap_or(rest);
+        let is_hex = unsigned.starts_with(b"0x");
+        // The first byte is a digit, sign or dot
+        let mut length = 1;
+        while let Some(&c) = rest.get(length) {
+            let is_exponent_sign =
+                matches!(c, b'+' | b'-') && matches!(rest[length - 1], b'e' | b'E') && !is_hex;
+            if !(c.is_ascii_alphanumeric() || c == b'_' || c == b'.' || is_exponent_sign) {
+                break;
+            }
+            length += 1;
+        }
+        let text = &self.source[self.position..self.position + length];
+        let Some(value) = parse_number(text) else {
+            return Err(self.error(format!("Invalid number '{}'", text)));
+        };
+        self.position += length;
+        Ok(value)
+    }
+
+    /// Parses a string or character, characters are loaded as strings
+    fn parse_string(&mut self, quote: u8) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        let content = self.quoted(quote)?;
+        let value = if content.contains('\\') {
+            let bytes = unescape(content, false).map_err(|error| self.error_at(start, error))?;
+            Value::String(String::from_utf8(bytes)?.into())
+        } else {
+            Value::BorrowedString(content)
+        };
+        if quote == b'\'' && value.as_str().is_some_and(|c| c.chars().count() != 1) {
+            return Err(self.error_at(start, "Expected a single character"));
+        }
+        Ok(value)
+    }
+
+    /// Returns the content between the quote at the current position and its closing quote
+    fn quoted(&mut self, quote: u8) -> LoadumResult<&'source str> {
+        let start = self.position + 1;
+        let mut index = start;
+        loop {
+            match self.bytes.get(index) {
+                None => return Err(self.error("Unterminated string")),
+                Some(b'\\') => index += 2,
+                Some(&c) if c == quote => break,
+                Some(_) => index += 1,
+            }
+        }
+        self.position = index + 1;
+        Ok(&self.source[start..index])
+    }
+
+    /// Parses raw strings like `r#"a "quoted" word"#`, which have no escapes
+    fn parse_raw_string(&mut self) -> LoadumResult<&'source str> {
+        let hashes = raw_string_hashes(&self.bytes[self.position..]).unwrap();
+        let start = self.position + hashes + 2;
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let Some(length) = self.source[start..].find(&terminator) else {
+            return Err(self.error("Unterminated string"));
+        };
+        self.position = start + length + terminator.len();
+        Ok(&self.source[start..start + length])
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: u8) -> LoadumResult<()> {
+        self.skip_blank()?;
+        if self.peek() != Some(expected) {
+            return Err(self.error(format!("Expected '{}'", expected as char)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    /// Skips whitespace and comments
+    fn skip_blank(&mut self) -> LoadumResult<()> {
+        loop {
+            let rest = &self.bytes[self.position..];
+            match rest {
+                [b' ' | b'\t' | b'\n' | b'\r', ..] => self.position += 1,
+                [b'/', b'/', ..] => {
+                    self.position += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                }
+                [b'/', b'*', ..] => self.skip_block_comment()?,
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Skips a block comment, which may contain nested block comments
+    fn skip_block_comment(&mut self) -> LoadumResult<()> {
+        let start = self.position;
+        let mut depth = 0;
+        loop {
+            let rest = &self.bytes[self.position..];
+            if rest.starts_with(b"/*") {
+                depth += 1;
+                self.position += 2;
+            } else if rest.starts_with(b"*/") {
+                depth -= 1;
+                self.position += 2;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if rest.is_empty() {
+                return Err(self.error_at(start, "Unterminated comment"));
+            } else {
+                self.position += 1;
+            }
+        }
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+/// Returns the number of `#` of a raw string starting at the `r`, or None if it is no raw string
+fn raw_string_hashes(rest: &[u8]) -> Option<usize> {
+    let hashes = rest
+        .strip_prefix(b"r")?
+        .iter()
+        .take_while(|c| **c == b'#')
+        .count();
+    (rest.get(hashes + 1) == Some(&b'"')).then_some(hashes)
+}
+
+/// Parses a number, integers beyond 64 bits are returned as their tagged digits
+fn parse_number(text: &str) -> Option<(Option<&'static str>, Value<'static>)> {
+    let negative = text.starts_with('-');
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    match unsigned {
+        "inf" if negative => return Some((None, Value::Number(f64::NEG_INFINITY))),
+        "inf" => return Some((None, Value::Number(f64::INFINITY))),
+        "NaN" => return Some((None, Value::Number(f64::NAN))),
+        _ => {}
+    }
+    if unsigned.starts_with('_') {
+        return None;
+    }
+    let digits = unsigned.replace('_', "");
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = digits.strip_prefix(prefix) {
+            if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+                return None;
+            }
+            return parse_integer(digits, radix, negative);
+        }
+    }
+    let is_float = digits.contains(['.', 'e', 'E']);
+    if !digits
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return None;
+    }
+    if is_float {
+        let sign = if negative { "-" } else { "" };
+        format!("{}{}", sign, digits)
+            .parse()
+            .ok()
+            .map(|number| (None, Value::Number(number)))
+    } else {
+        parse_integer(&digits, 10, negative)
+    }
+}
+
+/// Parses the digits of an integer up to the 128 bits of RON
+fn parse_integer(
+    digits: &str,
+    radix: u32,
+    negative: bool,
+) -> Option<(Option<&'static str>, Value<'static>)> {
+    let magnitude = u128::from_str_radix(digits, radix).ok()?;
+    let integer = if negative {
+        0i128.checked_sub_unsigned(magnitude)?
+    } else {
+        match i128::try_from(magnitude) {
+            Ok(integer) => integer,
+            Err(_) => return Some((Some(BIGINT_TAG), Value::string(magnitude.to_string()))),
+        }
+    };
+    match i64::try_from(integer) {
+        Ok(integer) => Some((None, Value::Integer(integer))),
+        Err(_) => Some((Some(BIGINT_TAG), Value::string(integer.to_string()))),
+    }
+}
+
+/// Resolves escape sequences, outside of byte strings `\x` escapes are limited to ASCII
+fn unescape(content: &str, is_bytes: bool) -> LoadumResult<Vec<u8>> {
+    let mut output = Vec::with_capacity(content.len());
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+            continue;
+        }
+        let c = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('b') => '\u{0008}',
+            Some('f') => '\u{000c}',
+            Some(c @ ('\\' | '"' | '\'' | '/')) => c,
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|byte| digits.len() == 2 && (is_bytes || byte.is_ascii()));
+                let Some(byte) = byte else {
+                    bail!("Invalid escape sequence '\\x{}'", digits);
+                };
+                output.push(byte);
+                continue;
+            }
+            // Both `\u{1F600}` and `\u00E9` are accepted
+            Some('u') if !is_bytes => {
+                let braced = chars.as_str().starts_with('{');
+                let digits: String = if braced {
+                    chars.next();
+                    chars.by_ref().take_while(|c| *c != '}').collect()
+                } else {
+                    chars.by_ref().take(4).collect()
+                };
+                let code_point = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| braced || digits.len() == 4)
+                    .and_then(char::from_u32);
+                let Some(c) = code_point else {
+                    bail!("Invalid unicode escape '\\u{}'", digits);
+                };
+                c
+            }
+            Some(c) => bail!("Invalid escape sequence '\\{}'", c.escape_default()),
+            None => bail!("Incomplete escape sequence"),
+        };
+        output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RonLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use loadum::event::Event;
+    use loadum::testing::{collect_events, format_events};
+
+    fn load(input: &str) -> String {
+        format_events(&collect_events(
+            RonLoader::new(input).map(|event| event.map(Event::into_owned)),
+        ))
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            "[true, false, -42, 0xff, -0b101, 1_000, 1.5, -2e3, .5, inf, -inf, NaN, (), None, \
+             Some(7), 'c', '\\n', 18446744073709551615, -0x8000000000000001, \
+             -170141183460469231731687303715884105728]",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Integer(-42))
+                Literal(Integer(255))
+                Literal(Integer(-5))
+                Literal(Integer(1000))
+                Literal(Number(1.5))
+                Literal(Number(-2000.0))
+                Literal(Number(0.5))
+                Literal(Number(inf))
+                Literal(Number(-inf))
+                Literal(Number(NaN))
+                Literal(Null)
+                Literal(Null)
+                Literal(Integer(7))
+                Literal(String("c"))
+                Literal(String("\n"))
+                Tag("bigint")
+                Literal(String("18446744073709551615"))
+                Tag("bigint")
+                Literal(String("-9223372036854775809"))
+                Tag("bigint")
+                Literal(String("-170141183460469231731687303715884105728"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_structs() {
+        test_loader(
+            r#"
+#![enable(implicit_some)]
+// A scene with its entities
+Scene( /* nested /* block */ comment */
+    name: "Level 1",
+    entities: [
+        Entity(position: (1.0, 2.5), kind: Player, health: Health(100)),
+        Entity(position: (0.0, 0.0), kind: Enemy(speed: 2), tags: [],),
+    ],
+    bounds: Rect(0, 0, 640, 480),
+    empty: Empty(),
+    anonymous: (r#type: "struct"),
+)
+"#,
+            expect![[r#"
+                DocumentStart
+                Tag("Scene")
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Level 1"))
+                MapKey(String("entities"))
+                ListStart
+                Tag("Entity")
+                MapStart
+                MapKey(String("position"))
+                ListStart
+                Literal(Number(1.0))
+                Literal(Number(2.5))
+                ListEnd
+                MapKey(String("kind"))
+                Tag("Player")
+                Literal(Null)
+                MapKey(String("health"))
+                Tag("Health")
+                Literal(Integer(100))
+                MapEnd
+                Tag("Entity")
+                MapStart
+                MapKey(String("position"))
+                ListStart
+                Literal(Number(0.0))
+                Literal(Number(0.0))
+                ListEnd
+                MapKey(String("kind"))
+                Tag("Enemy")
+                MapStart
+                MapKey(String("speed"))
+                Literal(Integer(2))
+                MapEnd
+                MapKey(String("tags"))
+                ListStart
+                ListEnd
+                MapEnd
+                ListEnd
+                MapKey(String("bounds"))
+                Tag("Rect")
+                ListStart
+                Literal(Integer(0))
+                Literal(Integer(0))
+                Literal(Integer(640))
+                Literal(Integer(480))
+                ListEnd
+                MapKey(String("empty"))
+                Tag("Empty")
+                ListStart
+                ListEnd
+                MapKey(String("anonymous"))
+                MapStart
+                MapKey(String("type"))
+                Literal(String("struct"))
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_maps() {
+        test_loader(
+            r#"{"a": 1, 2: "two", Red: [], (1, 2): {}, Some("b"): None}"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("a"))
+                Literal(Integer(1))
+                MapKey(Integer(2))
+                Literal(String("two"))
+                Tag("Red")
+                MapKey(Null)
+                ListStart
+                ListEnd
+                ComplexKey
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                MapStart
+                MapEnd
+                MapKey(String("b"))
+                Literal(Null)
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        test_loader(
+            r####"["plain", "escapes \"\\\t\u{1F600}\u00e9\x41", r"raw \n", r##"with "# inside"##,
+            "multi
+line", b"bytes\x00\xff", br#"raw "bytes""#]"####,
+            expect![[r##"
+                DocumentStart
+                ListStart
+                Literal(String("plain"))
+                Literal(String("escapes \"\\\t😀éA"))
+                Literal(String("raw \\n"))
+                Literal(String("with \"# inside"))
+                Literal(String("multi\nline"))
+                Literal(Bytes([98, 121, 116, 101, 115, 0, 255]))
+                Literal(Bytes([114, 97, 119, 32, 34, 98, 121, 116, 101, 115, 34]))
+                ListEnd
+                DocumentEnd
+            "##]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "",
+            "[1, 2",
+            "[1 2]",
+            "{1 2}",
+            "Point(x: 1, 2)",
+            "12abc",
+            "340282366920938463463374607431768211456",
+            "\"unterminated",
+            "\"\\q\"",
+            "\"\\xff\"",
+            "'ab'",
+            "/* open",
+            "1 2",
+            "#![enable(",
+            "@",
+            &"[".repeat(MAX_DEPTH + 1),
+            &"Some(".repeat(MAX_DEPTH + 1),
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Expected value at line 1, column 1
+            Error: Expected ',' or ']' in list at line 1, column 6
+            Error: Expected ',' or ']' in list at line 1, column 4
+            Error: Expected ':' at line 1, column 4
+            Error: Expected identifier at line 1, column 13
+            Error: Invalid number '12abc' at line 1, column 1
+            Error: Invalid number '340282366920938463463374607431768211456' at line 1, column 1
+            Error: Unterminated string at line 1, column 1
+            Error: Invalid escape sequence '\q' at line 1, column 1
+            Error: Invalid escape sequence '\xff' at line 1, column 1
+            Error: Expected a single character at line 1, column 1
+            Error: Unterminated comment at line 1, column 1
+            Error: Expected end of document at line 1, column 3
+            Error: Unterminated attribute at line 1, column 1
+            Error: Expected value, but found '@' at line 1, column 1
+            Error: Document is nested deeper than 128 levels at line 1, column 130
+            Error: Document is nested deeper than 128 levels at line 1, column 646
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,19 @@
+//! Smile, the binary JSON format of Jackson, as used by Elasticsearch
+//!
+//! Smile maps one to one onto JSON, with bytes as an extra type. Big integers and big decimals are
+//! loaded as integers if they fit and are approximated by floats otherwise.
+
+pub mod smile_dumper;
+pub mod smile_loader;
+
+/// Start of the four byte header, the fourth byte holds the version and flags
+pub(crate) const HEADER: &[u8; 3] = b":)\n";
+
+/// Header flag for back references to earlier key names
+pub(crate) const SHARED_KEYS_FLAG: u8 = 0x01;
+
+/// Header flag for back references to earlier short string values
+pub(crate) const SHARED_VALUES_FLAG: u8 = 0x02;
+
+/// Size of the tables of shared keys and values, which are cleared once they are full
+pub(crate) const MAX_SHARED: usize = 1024;
@@ -0,0 +1,399 @@
+use crate::{HEADER, MAX_SHARED, SHARED_KEYS_FLAG};
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Dumps Smile, each document is written as a top level value with its own header
+///
+/// Keys are shared by default, repeated keys are written as references to their first occurrence
+/// in the document. Shared string values are not used. Floats are written as 64 bit floats, bytes
+/// in the 7 bit encoding, date-times as strings and tags are ignored.
+pub struct SmileDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+    shared_keys: bool,
+}
+
+impl<'write> SmileDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> SmileDumper<'write> {
+        SmileDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::default(),
+            shared_keys: true,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    /// Sets whether repeated keys are written as references, which is enabled by default
+    pub fn with_shared_keys(mut self, shared_keys: bool) -> Self {
+        self.shared_keys = shared_keys;
+        self
+    }
+}
+
+impl Dumper for SmileDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let flags = if self.shared_keys {
+            SHARED_KEYS_FLAG
+        } else {
+            0
+        };
+        let mut writer = SmileWriter {
+            output: [&HEADER[..], &[flags]].concat(),
+            key_policy: self.key_policy,
+            shared_keys: self.shared_keys.then(HashMap::new),
+        };
+        writer.write_node(&root)?;
+        self.write.write_all(&writer.output)?;
+        Ok(())
+    }
+}
+
+struct SmileWriter {
+    output: Vec<u8>,
+    key_policy: KeyPolicy,
+    /// Indices of the keys written so far, if keys are shared
+    shared_keys: Option<HashMap<String, usize>>,
+}
+
+impl SmileWriter {
+    fn write_node(&mut self, node: &Node) -> LoadumResult<()> {
+        match node {
+            Node::Scalar(value) => self.write_value(value),
+            Node::List(items) => {
+                self.output.push(0xf8);
+                for item in items {
+                    self.write_node(item)?;
+                }
+                self.output.push(0xf9);
+            }
+            Node::Map(entries) => {
+                self.output.push(0xfa);
+                for (key, value) in entries {
+                    let key = self.key_policy.key_string(key, "Smile keys")?;
+                    self.write_key(&key);
+                    self.write_node(value)?;
+                }
+                self.output.push(0xfb);
+            }
+            Node::Tagged(_, node) => self.write_node(node)?,
+        }
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &str) {
+        if key.is_empty() {
+            self.output.push(0x20);
+            return;
+        }
+        if let Some(shared_keys) = &mut self.shared_keys {
+            match shared_keys.get(key) {
+                Some(index @ 0..64) => {
+                    self.output.push(0x40 | *index as u8);
+                    return;
+                }
+                Some(index) => {
+                    self.output
+                        .extend_from_slice(&[0x30 | (index >> 8) as u8, *index as u8]);
+                    return;
+                }
+                None => {
+                    if shared_keys.len() == MAX_SHARED {
+                        shared_keys.clear();
+                    }
+                    shared_keys.insert(key.to_string(), shared_keys.len());
+                }
+            }
+        }
+        let length = key.len();
+        match (key.is_ascii(), length) {
+            (true, 1..=64) => self.output.push(0x80 | (length - 1) as u8),
+            (false, 2..=57) => self.output.push(0xc0 | (length - 2) as u8),
+            _ => {
+                self.output.push(0x34);
+                self.output.extend_from_slice(key.as_bytes());
+                self.output.push(0xfc);
+                return;
+            }
+        }
+        self.output.extend_from_slice(key.as_bytes());
+    }
+
+    fn write_value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.output.push(0x21),
+            Value::Boolean(b) => self.output.push(if *b { 0x23 } else { 0x22 }),
+            Value::Integer(i) => match i {
+                -16..=15 => self.output.push(0xc0 | zigzag_encode(*i) as u8),
+                _ if i32::try_from(*i).is_ok() => {
+                    self.output.push(0x24);
+                    write_vint(&mut self.output, zigzag_encode(*i));
+                }
+                _ => {
+                    self.output.push(0x25);
+                    write_vint(&mut self.output, zigzag_encode(*i));
+                }
+            },
+            Value::Number(n) => {
+                let bits = n.to_bits();
+                self.output.push(0x29);
+                self.output.extend(
+                    (0..10)
+                        .rev()
+                        .map(|group| (bits >> (group * 7)) as u8 & 0x7f),
+                );
+            }
+            Value::String(s) => self.write_string(s),
+            Value::BorrowedString(s) => self.write_string(s),
+            Value::Bytes(bytes) => {
+                self.output.push(0xe8);
+                write_vint(&mut self.output, bytes.len() as u64);
+                write_7bit_bytes(&mut self.output, bytes);
+            }
+            Value::DateTime(date_time) => self.write_string(&date_time.to_string()),
+        }
+    }
+
+    fn write_string(&mut self, s: &str) {
+        let length = s.len();
+        let token = match (s.is_ascii(), length) {
+            (_, 0) => {
+                self.output.push(0x20);
+                return;
+            }
+            (true, 1..=32) => 0x40 | (length - 1) as u8,
+            (true, 33..=64) => 0x60 | (length - 33) as u8,
+            (false, 2..=33) => 0x80 | (length - 2) as u8,
+            (false, 34..=64) => 0xa0 | (length - 34) as u8,
+            (is_ascii, _) => {
+                self.output.push(if is_ascii { 0xe0 } else { 0xe4 });
+                self.output.extend_from_slice(s.as_bytes());
+                self.output.push(0xfc);
+                return;
+            }
+        };
+        self.output.push(token);
+        self.output.extend_from_slice(s.as_bytes());
+    }
+}
+
+fn zigzag_encode(i: i64) -> u64 {
+    ((i << 1) ^ (i >> 63)) as u64
+}
+
+/// Writes an unsigned variable length integer, whose last byte has the high bit set
+fn write_vint(output: &mut Vec<u8>, value: u64) {
+    let mut bytes = vec![0x80 | (value & 0x3f) as u8];
+    let mut rest = value >> 6;
+    while rest > 0 {
+        bytes.push((rest & 0x7f) as u8);
+        rest >>= 7;
+    }
+    output.extend(bytes.iter().rev());
+}
+
+/// Writes binary data in groups of 7 bits, the last group holds the remaining bits
+fn write_7bit_bytes(output: &mut Vec<u8>, bytes: &[u8]) {
+    for chunk in bytes.chunks(7) {
+        let count = chunk.len();
+        let value = chunk
+            .iter()
+            .fold(0u64, |value, byte| value << 8 | *byte as u64);
+        let last_bits = if count == 7 { 7 } else { count };
+        for group in 0..count {
+            output.push((value >> (8 * count - 7 * (group + 1))) as u8 & 0x7f);
+        }
+        output.push(value as u8 & ((1 << last_bits) - 1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::smile_dumper::SmileDumper;
+    use crate::smile_loader::SmileLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::testing::{emit_events, format_dump, integer_key_document, invalid_documents};
+    use loadum::value::Value;
+
+    fn dump_with(dumper: impl FnOnce(&mut Vec<u8>) -> SmileDumper, events: &[Event]) -> String {
+        let mut output = vec![];
+        let result = emit_events(dumper(&mut output), events);
+        format_dump(result, output.escape_ascii())
+    }
+
+    fn dump(events: &[Event]) -> String {
+        dump_with(|output| SmileDumper::new(output), events)
+    }
+
+    fn objects() -> Vec<Event<'static>> {
+        vec![
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::MapStart,
+            Event::map_key("a"),
+            Event::integer(1),
+            Event::MapEnd,
+            Event::MapStart,
+            Event::map_key("a"),
+            Event::integer(2),
+            Event::MapEnd,
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]
+    }
+
+    #[test]
+    fn test_encoding() {
+        let mut output = dump(&[
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::null(),
+            Event::bool(true),
+            Event::integer(-16),
+            Event::integer(100),
+            Event::integer(1i64 << 40),
+            Event::number(1.5),
+            Event::string("abc"),
+            Event::string("é"),
+            Event::string(""),
+            Event::bytes(&b"\x00\xff"[..]),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05Z").unwrap()),
+            Event::tag("ignored"),
+            Event::MapStart,
+            Event::map_key("é"),
+            Event::null(),
+            Event::map_key(""),
+            Event::null(),
+            Event::MapKey(Value::Integer(1)),
+            Event::null(),
+            Event::MapEnd,
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]);
+        output.push('\n');
+        output.push_str(&dump(&objects()));
+        output.push('\n');
+        output.push_str(&dump_with(
+            |output| SmileDumper::new(output).with_shared_keys(false),
+            &objects(),
+        ));
+        expect![[r#"
+            :)\n\x01\xf8!#\xdf$\x03\x88%\x01\x00\x00\x00\x00\x00\x80)\x00?|\x00\x00\x00\x00\x00\x00\x00Babc\x80\xc3\xa9 \xe8\x82\x00?\x03S2024-01-02T03:04:05Z\xfa\xc0\xc3\xa9! !\x801!\xfb\xf9
+            :)\n\x01\xf8\xfa\x80a\xc2\xfb\xfa@\xc4\xfb\xf9
+            :)\n\x00\xf8\xfa\x80a\xc2\xfb\xfa\x80a\xc4\xfb\xf9"#]].assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let keys: Vec<String> = (0..1100).map(|index| format!("key{}", index)).collect();
+        let mut events = vec![Event::DocumentStart, Event::MapStart];
+        events.push(Event::map_key("integers"));
+        events.push(Event::ListStart);
+        events.extend(
+            [
+                0,
+                15,
+                -16,
+                16,
+                -17,
+                i32::MAX as i64,
+                i32::MIN as i64,
+                i64::MAX,
+                i64::MIN,
+            ]
+            .map(Event::integer),
+        );
+        events.push(Event::ListEnd);
+        events.extend([
+            Event::map_key("values"),
+            Event::ListStart,
+            Event::null(),
+            Event::bool(false),
+            Event::number(0.1),
+            Event::number(f64::NEG_INFINITY),
+            Event::string("x".repeat(32)),
+            Event::string("x".repeat(33)),
+            Event::string("x".repeat(64)),
+            Event::string("x".repeat(65)),
+            Event::string("é".repeat(16)),
+            Event::string("é".repeat(17)),
+            Event::string("é".repeat(32)),
+            Event::string("é".repeat(33)),
+            Event::bytes((0..=255).collect::<Vec<u8>>()),
+            Event::ListEnd,
+            Event::map_key("x".repeat(64)),
+            Event::null(),
+            Event::map_key("x".repeat(65)),
+            Event::null(),
+            Event::map_key("é".repeat(28)),
+            Event::null(),
+            Event::map_key("é".repeat(29)),
+            Event::null(),
+            Event::map_key("shared"),
+            Event::ListStart,
+        ]);
+        // More keys than fit in the shared key table, each used twice
+        for key in keys.iter().chain(&keys) {
+            events.extend([
+                Event::MapStart,
+                Event::map_key(key.as_str()),
+                Event::null(),
+                Event::MapEnd,
+            ]);
+        }
+        events.extend([
+            Event::ListEnd,
+            Event::MapEnd,
+            Event::DocumentEnd,
+            Event::DocumentStart,
+            Event::integer(1),
+            Event::DocumentEnd,
+        ]);
+        let mut output = vec![];
+        let mut dumper = SmileDumper::new(&mut output);
+        for event in &events {
+            dumper.emit(event).unwrap();
+        }
+        drop(dumper);
+        let loaded: Vec<Event> = SmileLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        let expected: Vec<Event> = events.into_iter().map(Event::into_owned).collect();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = String::new();
+        for events in invalid_documents() {
+            output.push_str(&dump(&events));
+            output.push('\n');
+        }
+        output.push_str(&dump_with(
+            |output| SmileDumper::new(output).with_key_policy(KeyPolicy::Reject),
+            &integer_key_document(),
+        ));
+        expect![[r#"
+            Error: Smile keys cannot be composite, but found List([])
+            Error: Document is empty
+            Error: Smile keys must be strings, but found Scalar(Integer(1))"#]]
+        .assert_eq(&output);
+    }
+}
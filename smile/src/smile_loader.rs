@@ -0,0 +1,520 @@
+use crate::{HEADER, MAX_SHARED, SHARED_KEYS_FLAG, SHARED_VALUES_FLAG};
+use loadum::byte_input::ByteInput;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Read;
+
+/// Loads Smile, each top level value in the input is loaded as a separate document
+///
+/// The header is optional, without one shared keys and values are disabled. A header may precede
+/// any document and resets the shared keys and values, end markers between documents are skipped.
+pub struct SmileLoader<'source> {
+    input: ByteInput<'source>,
+    state: LoaderState,
+    stack: Vec<Container>,
+    /// Keys that can be referenced, if enabled by the header
+    shared_keys: Option<Vec<Value<'source>>>,
+    /// Short string values that can be referenced, if enabled by the header
+    shared_values: Option<Vec<Value<'source>>>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    BetweenDocuments,
+    InDocument,
+    DocumentEnd,
+    Done,
+}
+
+struct Container {
+    is_map: bool,
+    /// True if an object expects a key or its end, rather than a value
+    expects_key: bool,
+}
+
+impl<'source> SmileLoader<'source> {
+    pub fn new(source: &'source [u8]) -> SmileLoader<'source> {
+        Self::with_input(ByteInput::from_slice(source))
+    }
+
+    /// Loads Smile incrementally from a reader
+    pub fn from_reader(read: impl Read + 'source) -> SmileLoader<'source> {
+        Self::with_input(ByteInput::from_reader(read))
+    }
+
+    fn with_input(input: ByteInput<'source>) -> SmileLoader<'source> {
+        SmileLoader {
+            input,
+            state: LoaderState::BetweenDocuments,
+            stack: vec![],
+            shared_keys: None,
+            shared_values: None,
+        }
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        match self.state {
+            LoaderState::BetweenDocuments => {
+                loop {
+                    match self.input.peek_u8()? {
+                        None => {
+                            self.state = LoaderState::Done;
+                            return Ok(None);
+                        }
+                        Some(0xff) => {
+                            self.input.read_u8()?;
+                        }
+                        Some(b':') => self.read_header()?,
+                        Some(_) => break,
+                    }
+                }
+                self.state = LoaderState::InDocument;
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::DocumentEnd => {
+                self.state = LoaderState::BetweenDocuments;
+                return Ok(Some(Event::DocumentEnd));
+            }
+            LoaderState::Done => return Ok(None),
+            LoaderState::InDocument => {}
+        }
+        let offset = self.input.offset();
+        let token = self.input.read_u8()?;
+        match self.stack.last_mut() {
+            Some(container) if container.expects_key && token == 0xfb => {
+                self.stack.pop();
+                self.end_value();
+                return Ok(Some(Event::MapEnd));
+            }
+            Some(container) if container.expects_key => {
+                container.expects_key = false;
+                return Ok(Some(Event::MapKey(self.read_key(token, offset)?)));
+            }
+            Some(container) if container.is_map => container.expects_key = true,
+            Some(_) if token == 0xf9 => {
+                self.stack.pop();
+                self.end_value();
+                return Ok(Some(Event::ListEnd));
+            }
+            _ => {}
+        }
+        let event = match token {
+            0xf8 | 0xfa => {
+                let is_map = token == 0xfa;
+                self.stack.push(Container {
+                    is_map,
+                    expects_key: is_map,
+                });
+                if is_map {
+                    Event::MapStart
+                } else {
+                    Event::ListStart
+                }
+            }
+            _ => {
+                let value = self.read_value(token, offset)?;
+                self.end_value();
+                Event::Literal(value)
+            }
+        };
+        Ok(Some(event))
+    }
+
+    /// Ends the document once the top level value is complete
+    fn end_value(&mut self) {
+        if self.stack.is_empty() {
+            self.state = LoaderState::DocumentEnd;
+        }
+    }
+
+    fn read_header(&mut self) -> LoadumResult<()> {
+        let offset = self.input.offset();
+        let header: [u8; 4] = self.input.read_array()?;
+        if header[..3] != HEADER[..] {
+            bail!("Invalid Smile header at offset {}", offset);
+        }
+        let flags = header[3];
+        if flags >> 4 != 0 {
+            bail!(
+                "Unsupported Smile version {} at offset {}",
+                flags >> 4,
+                offset
+            );
+        }
+        self.shared_keys = (flags & SHARED_KEYS_FLAG != 0).then(Vec::new);
+        self.shared_values = (flags & SHARED_VALUES_FLAG != 0).then(Vec::new);
+        Ok(())
+    }
+
+    fn read_key(&mut self, token: u8, offset: u64) -> LoadumResult<Value<'source>> {
+        let key = match token {
+            0x20 => return Ok(Value::BorrowedString("")),
+            0x30..=0x33 => {
+                let index = ((token & 0x03) as usize) << 8 | self.input.read_u8()? as usize;
+                return shared(&self.shared_keys, index, "key", offset);
+            }
+            0x40..=0x7f => {
+                return shared(&self.shared_keys, (token & 0x3f) as usize, "key", offset);
+            }
+            0x34 => self.read_terminated_string(offset)?,
+            0x80..=0xbf => self.input.read_string((token & 0x3f) as usize + 1)?,
+            0xc0..=0xf7 => self.input.read_string((token & 0x3f) as usize + 2)?,
+            _ => bail!("Invalid key token 0x{:02x} at offset {}", token, offset),
+        };
+        if let Some(shared_keys) = &mut self.shared_keys {
+            add_shared(shared_keys, key.clone());
+        }
+        Ok(key)
+    }
+
+    fn read_value(&mut self, token: u8, offset: u64) -> LoadumResult<Value<'source>> {
+        let value = match token {
+            0x01..=0x1f => {
+                return shared(&self.shared_values, token as usize - 1, "value", offset);
+            }
+            0x20 => Value::BorrowedString(""),
+            0x21 => Value::Null,
+            0x22 => Value::Boolean(false),
+            0x23 => Value::Boolean(true),
+            0x24 | 0x25 => Value::Integer(zigzag_decode(self.read_vint()?)),
+            0x26 => {
+                let length = self.read_vint()? as usize;
+                match big_integer(&self.read_7bit_bytes(length)?) {
+                    Ok(i) => Value::Integer(i),
+                    Err(n) => Value::Number(n),
+                }
+            }
+            0x28 => {
+                let bits = self.read_7bit_bits(5)?;
+                Value::Number(f32::from_bits(bits as u32) as f64)
+            }
+            0x29 => Value::Number(f64::from_bits(self.read_7bit_bits(10)?)),
+            0x2a => {
+                let scale = zigzag_decode(self.read_vint()?);
+                let length = self.read_vint()? as usize;
+                let unscaled =
+                    big_integer(&self.read_7bit_bytes(length)?).map_or_else(|n| n, |i| i as f64);
+                let Ok(scale) = i32::try_from(scale) else {
+                    bail!(
+                        "Big decimal scale {} at offset {} is out of range",
+                        scale,
+                        offset
+                    );
+                };
+                Value::Number(unscaled / 10f64.powi(scale))
+            }
+            0x40..=0xbf => {
+                let length = match token {
+                    0x40..=0x5f => (token & 0x1f) as usize + 1,
+                    0x60..=0x7f => (token & 0x1f) as usize + 33,
+                    0x80..=0x9f => (token & 0x1f) as usize + 2,
+                    _ => (token & 0x1f) as usize + 34,
+                };
+                let value = self.input.read_string(length)?;
+                if let Some(shared_values) = &mut self.shared_values {
+                    add_shared(shared_values, value.clone());
+                }
+                value
+            }
+            0xc0..=0xdf => Value::Integer(zigzag_decode((token & 0x1f) as u64)),
+            0xe0 | 0xe4 => self.read_terminated_string(offset)?,
+            0xe8 => {
+                let length = self.read_vint()? as usize;
+                Value::bytes(self.read_7bit_bytes(length)?)
+            }
+            0xec..=0xef => {
+                let index = ((token & 0x03) as usize) << 8 | self.input.read_u8()? as usize;
+                return shared(&self.shared_values, index, "value", offset);
+            }
+            0xfd => {
+                let length = self.read_vint()? as usize;
+                Value::bytes(&*self.input.read_bytes(length)?)
+            }
+            _ => bail!("Invalid value token 0x{:02x} at offset {}", token, offset),
+        };
+        Ok(value)
+    }
+
+    /// Reads an unsigned variable length integer, whose last byte has the high bit set
+    fn read_vint(&mut self) -> LoadumResult<u64> {
+        let offset = self.input.offset();
+        let mut value = 0u64;
+        for _ in 0..10 {
+            let byte = self.input.read_u8()?;
+            if byte & 0x80 != 0 {
+                return Ok(value << 6 | (byte & 0x3f) as u64);
+            }
+            value = value << 7 | byte as u64;
+        }
+        bail!("Invalid variable length integer at offset {}", offset);
+    }
+
+    /// Reads the bits of a float, which are stored in groups of 7 bits
+    fn read_7bit_bits(&mut self, length: usize) -> LoadumResult<u64> {
+        let bytes = self.input.read_bytes(length)?;
+        Ok(bytes
+            .iter()
+            .fold(0, |bits, byte| bits << 7 | (byte & 0x7f) as u64))
+    }
+
+    /// Reads binary data stored in groups of 7 bits, the last group holds the remaining bits
+    fn read_7bit_bytes(&mut self, length: usize) -> LoadumResult<Vec<u8>> {
+        let remainder = length % 7;
+        let encoded_length = length / 7 * 8 + if remainder > 0 { remainder + 1 } else { 0 };
+        let encoded = self.input.read_bytes(encoded_length)?;
+        let mut bytes = Vec::with_capacity(length);
+        for chunk in encoded.chunks(8) {
+            let count = chunk.len() - 1;
+            let last_bits = if chunk.len() == 8 { 7 } else { count };
+            let value = chunk[..count]
+                .iter()
+                .fold(0u64, |value, byte| value << 7 | (byte & 0x7f) as u64);
+            let value = value << last_bits | (chunk[count] & ((1 << last_bits) - 1)) as u64;
+            bytes.extend_from_slice(&value.to_be_bytes()[8 - count..]);
+        }
+        Ok(bytes)
+    }
+
+    /// Reads a string terminated by an end of string marker
+    fn read_terminated_string(&mut self, offset: u64) -> LoadumResult<Value<'source>> {
+        let mut bytes = vec![];
+        loop {
+            match self.input.read_u8()? {
+                0xfc => break,
+                byte => bytes.push(byte),
+            }
+        }
+        match String::from_utf8(bytes) {
+            Ok(string) => Ok(Value::string(string)),
+            Err(_) => bail!("Invalid UTF-8 in string at offset {}", offset),
+        }
+    }
+}
+
+impl<'source> Loader<'source> for SmileLoader<'source> {}
+
+impl<'source> Iterator for SmileLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+fn shared<'source>(
+    table: &Option<Vec<Value<'source>>>,
+    index: usize,
+    kind: &str,
+    offset: u64,
+) -> LoadumResult<Value<'source>> {
+    match table.as_ref().and_then(|table| table.get(index)) {
+        Some(value) => Ok(value.clone()),
+        None => bail!(
+            "Invalid shared {} reference {} at offset {}",
+            kind,
+            index,
+            offset
+        ),
+    }
+}
+
+fn add_shared<'source>(table: &mut Vec<Value<'source>>, value: Value<'source>) {
+    if table.len() == MAX_SHARED {
+        table.clear();
+    }
+    table.push(value);
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Returns a big endian two's complement integer if it fits, or its approximation as an error
+fn big_integer(bytes: &[u8]) -> Result<i64, f64> {
+    let is_negative = bytes.first().is_some_and(|byte| byte & 0x80 != 0);
+    let fill = if is_negative { 0xff } else { 0x00 };
+    let (head, tail) = bytes.split_at(bytes.len().saturating_sub(8));
+    let mut array = [fill; 8];
+    array[8 - tail.len()..].copy_from_slice(tail);
+    let i = i64::from_be_bytes(array);
+    if head.iter().all(|byte| *byte == fill) && (i < 0) == is_negative {
+        return Ok(i);
+    }
+    let magnitude = bytes.iter().fold(0.0, |n, byte| n * 256.0 + *byte as f64);
+    if is_negative {
+        Err(magnitude - 256f64.powi(bytes.len() as i32))
+    } else {
+        Err(magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::smile_loader::SmileLoader;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::Dumper;
+    use loadum::testing::{collect_events, format_events};
+    use loadum_json::json_dumper::JsonDumper;
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let events = collect_events(SmileLoader::new(input));
+        expected.assert_eq(&format_events(&events));
+        assert_eq!(events, collect_events(SmileLoader::from_reader(input)));
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            b":)\n\x00\xf8\x21\x22\x23\x20\xc0\xc1\xdf\x24\x03\x88\x28\x03\x7e\x00\x00\x00\
+              \x42abc\x80\xc3\xa9\xe0long\xfc\xe8\x82\x00\x3f\x03\xfd\x81\x07\
+              \x26\x82\x00\x40\x00\x2a\x84\x81\x3d\x01\xf9",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(false))
+                Literal(Boolean(true))
+                Literal(BorrowedString(""))
+                Literal(Integer(0))
+                Literal(Integer(-1))
+                Literal(Integer(-16))
+                Literal(Integer(100))
+                Literal(Number(1.5))
+                Literal(BorrowedString("abc"))
+                Literal(BorrowedString("é"))
+                Literal(String("long"))
+                Literal(Bytes([0, 255]))
+                Literal(Bytes([7]))
+                Literal(Integer(256))
+                Literal(Number(1.23))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_shared() {
+        test_loader(
+            b":)\n\x03\xf8\x42abc\x01\xec\x00\xfa\x80a\xc2\x34long key\xfc\xc0\xfb\
+              \xfa\x40\xc4\x41\xc6\xfb\xf9",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(BorrowedString("abc"))
+                Literal(BorrowedString("abc"))
+                Literal(BorrowedString("abc"))
+                MapStart
+                MapKey(BorrowedString("a"))
+                Literal(Integer(1))
+                MapKey(String("long key"))
+                Literal(Integer(0))
+                MapEnd
+                MapStart
+                MapKey(BorrowedString("a"))
+                Literal(Integer(2))
+                MapKey(String("long key"))
+                Literal(Integer(3))
+                MapEnd
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        test_loader(
+            b"\xc2\xff:)\n\x01\xfa\xfb\xff",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                MapStart
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b":)\n\x00", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            b"\xf8\xc2",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(1))
+                Error: Unexpected end of input at offset 2
+            "#]],
+        );
+        test_loader(
+            b"\x2b",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid value token 0x2b at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\x01",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid shared value reference 0 at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\xfa\xf9",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Invalid key token 0xf9 at offset 1
+            "#]],
+        );
+        test_loader(
+            b":)x\x00",
+            expect![[r#"
+                Error: Invalid Smile header at offset 0
+            "#]],
+        );
+        test_loader(
+            b":)\n\x10",
+            expect![[r#"
+                Error: Unsupported Smile version 1 at offset 0
+            "#]],
+        );
+        test_loader(
+            b"\x41\xff\xfe",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid UTF-8 in string at offset 1
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        for event in SmileLoader::new(b":)\n\x01\xfa\x81id\x24\x01\x94\x83data\xe8\x81\x00\x01\xfb")
+        {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"id": 42,
+            	"data": "AQ=="
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,8 @@
+//! Protobuf text format, as used for gRPC test fixtures and configuration files
+//!
+//! Without a schema, messages are loaded as maps and values close to the protobuf JSON mapping:
+//! repeated fields become lists, enum values are loaded as strings of their identifiers and
+//! extensions are keyed by their bracketed name, like `[com.example.ext]`. Expanded `Any` messages
+//! are loaded as maps with their type URL in an `@type` entry.
+
+pub mod textproto_loader;
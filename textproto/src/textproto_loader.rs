@@ -0,0 +1,755 @@
+use loadum::depth::Depth;
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::node::Node;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+
+/// Key of the type URL of expanded `Any` messages, as in the JSON mapping
+const ANY_TYPE_KEY: &str = "@type";
+
+/// Loads a message in the protobuf text format as a map, without a schema
+///
+/// A field that occurs more than once or is written with the list syntax is loaded as a list.
+/// Identifiers other than `true`, `True`, `false`, `False` and the float values `inf` and `nan`
+/// are loaded as strings, since enum values can't be told apart without a schema. Strings that are
+/// not valid UTF-8 are loaded as bytes and integers beyond the range of `i64` as decimal strings,
+/// like the JSON mapping does for 64-bit integers.
+pub struct TextprotoLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> TextprotoLoader<'source> {
+    pub fn new(source: &'source str) -> TextprotoLoader<'source> {
+        TextprotoLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for TextprotoLoader<'source> {}
+
+impl<'source> Iterator for TextprotoLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                bytes: self.source.as_bytes(),
+                position: 0,
+                depth: Depth::default(),
+            };
+            match parser.parse_document() {
+                Ok(events) => self.events = Some(events.into_iter()),
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+/// Fields of a message, in the order of their first occurrence
+type Message<'source> = Vec<(Value<'source>, Field<'source>)>;
+
+struct Field<'source> {
+    values: Vec<Node<'source>>,
+    /// True if the field was written with the list syntax, like `ids: [1, 2]`
+    is_list: bool,
+}
+
+enum FieldName<'source> {
+    Field(Value<'source>),
+    /// Type URL of an expanded `Any` message, like `[type.googleapis.com/com.example.Type]`
+    AnyType(&'source str),
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Vec<Event<'source>>> {
+        let message = self.parse_message(None)?;
+        let mut events = vec![Event::DocumentStart];
+        message_node(message).to_events(&mut events);
+        events.push(Event::DocumentEnd);
+        Ok(events)
+    }
+
+    /// Parses fields, up to the closing delimiter of the message starting at the given position
+    fn parse_message(&mut self, start: Option<(usize, u8)>) -> LoadumResult<Message<'source>> {
+        if let Some((start, _)) = start {
+            self.depth
+                .enter()
+                .map_err(|error| self.error_at(start, error))?;
+        }
+        let mut message = vec![];
+        loop {
+            self.skip_blank();
+            match (self.peek(), start) {
+                (None, None) => return Ok(message),
+                (None, Some((start, _))) => {
+                    return Err(self.error_at(start, "Unterminated message"));
+                }
+                (Some(c), Some((_, close))) if c == close => {
+                    self.position += 1;
+                    self.depth.exit();
+                    return Ok(message);
+                }
+                _ => {}
+            }
+            match self.parse_field_name()? {
+                FieldName::Field(name) => {
+                    self.skip_blank();
+                    let has_colon = self.peek() == Some(b':');
+                    if has_colon {
+                        self.position += 1;
+                        self.skip_blank();
+                    }
+                    if self.peek() == Some(b'[') {
+                        let values = self.parse_list(has_colon)?;
+                        add_field(&mut message, name, values, true);
+                    } else {
+                        let value = self.parse_value(has_colon)?;
+                        add_field(&mut message, name, vec![value], false);
+                    }
+                }
+                FieldName::AnyType(url) => {
+                    self.skip_blank();
+                    let Some(close) = self.message_close() else {
+                        return Err(self.error("Expected a message after an Any type URL"));
+                    };
+                    let start = self.position;
+                    self.position += 1;
+                    let fields = self.parse_message(Some((start, close)))?;
+                    let url = Node::Scalar(Value::BorrowedString(url));
+                    add_field(
+                        &mut message,
+                        Value::BorrowedString(ANY_TYPE_KEY),
+                        vec![url],
+                        false,
+                    );
+                    for (name, field) in fields {
+                        add_field(&mut message, name, field.values, field.is_list);
+                    }
+                }
+            }
+            self.skip_blank();
+            if matches!(self.peek(), Some(b',' | b';')) {
+                self.position += 1;
+            }
+        }
+    }
+
+    /// Parses a field name, an extension name in brackets or an `Any` type URL in brackets
+    fn parse_field_name(&mut self) -> LoadumResult<FieldName<'source>> {
+        if self.peek() != Some(b'[') {
+            return match self.parse_identifier() {
+                Some(name) => Ok(FieldName::Field(Value::BorrowedString(name))),
+                None => Err(self.error("Expected field name")),
+            };
+        }
+        self.position += 1;
+        self.skip_blank();
+        let start = self.position;
+        let length = self.bytes[start..]
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b'/')))
+            .unwrap_or(self.bytes.len() - start);
+        let name = &self.source[start..start + length];
+        self.position += length;
+        self.skip_blank();
+        if name.is_empty() || self.peek() != Some(b']') {
+            return Err(self.error_at(start, "Invalid extension name"));
+        }
+        self.position += 1;
+        if name.contains('/') {
+            return Ok(FieldName::AnyType(name));
+        }
+        Ok(FieldName::Field(Value::string(format!("[{}]", name))))
+    }
+
+    /// Parses the values of a list, which can only contain messages if there is no colon
+    fn parse_list(&mut self, has_colon: bool) -> LoadumResult<Vec<Node<'source>>> {
+        let start = self.position;
+        self.position += 1;
+        let mut values = vec![];
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                Some(b']') => break,
+                None => return Err(self.error_at(start, "Unterminated list")),
+                _ => {}
+            }
+            values.push(self.parse_value(has_colon)?);
+            self.skip_blank();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => break,
+                _ => return Err(self.error("Expected ',' or ']' in list")),
+            }
+        }
+        self.position += 1;
+        Ok(values)
+    }
+
+    /// Parses a message or, after a colon, a scalar value
+    fn parse_value(&mut self, has_colon: bool) -> LoadumResult<Node<'source>> {
+        if let Some(close) = self.message_close() {
+            let start = self.position;
+            self.position += 1;
+            let message = self.parse_message(Some((start, close)))?;
+            return Ok(message_node(message));
+        }
+        if !has_colon {
+            return Err(self.error("Expected ':' or a message"));
+        }
+        let value = match self.peek() {
+            Some(b'"' | b'\'') => self.parse_strings()?,
+            Some(b'-') => {
+                self.position += 1;
+                self.skip_blank();
+                self.parse_number(true)?
+            }
+            Some(b'0'..=b'9' | b'.') => self.parse_number(false)?,
+            _ => match self.parse_identifier() {
+                Some("true" | "True") => Value::Boolean(true),
+                Some("false" | "False") => Value::Boolean(false),
+                Some(identifier) => match float_keyword(identifier) {
+                    Some(n) => Value::Number(n),
+                    None => Value::BorrowedString(identifier),
+                },
+                None => return Err(self.error("Expected a value")),
+            },
+        };
+        Ok(Node::Scalar(value))
+    }
+
+    /// Returns the closing delimiter if a message starts at the current position
+    fn message_close(&self) -> Option<u8> {
+        match self.peek()? {
+            b'{' => Some(b'}'),
+            b'<' => Some(b'>'),
+            _ => None,
+        }
+    }
+
+    /// Parses one or more adjacent strings, which are concatenated
+    fn parse_strings(&mut self) -> LoadumResult<Value<'source>> {
+        let mut contents = vec![];
+        let mut has_escapes = false;
+        while let Some(quote @ (b'"' | b'\'')) = self.peek() {
+            let start = self.position;
+            let mut end = start + 1;
+            loop {
+                match self.bytes.get(end) {
+                    Some(c) if *c == quote => break,
+                    Some(b'\\') => {
+                        has_escapes = true;
+                        end += 2;
+                    }
+                    Some(b'\n') | None => return Err(self.error_at(start, "Unterminated string")),
+                    Some(_) => end += 1,
+                }
+            }
+            contents.push((start, &self.source[start + 1..end]));
+            self.position = end + 1;
+            self.skip_blank();
+        }
+        if let ([(_, content)], false) = (contents.as_slice(), has_escapes) {
+            return Ok(Value::BorrowedString(content));
+        }
+        let mut bytes = vec![];
+        for (start, content) in contents {
+            unescape(&mut bytes, content).map_err(|error| self.error_at(start, error))?;
+        }
+        match String::from_utf8(bytes) {
+            Ok(string) => Ok(Value::string(string)),
+            Err(error) => Ok(Value::bytes(error.into_bytes())),
+        }
+    }
+
+    /// Parses a number, after its sign if it has one
+    fn parse_number(&mut self, is_negative: bool) -> LoadumResult<Value<'source>> {
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        let is_hex = rest.len() > 1 && rest[0] == b'0' && matches!(rest[1], b'x' | b'X');
+        let mut length = 0;
+        while let Some(c) = rest.get(length) {
+            let is_exponent_sign = matches!(c, b'+' | b'-')
+                && !is_hex
+                && length > 0
+                && matches!(rest[length - 1], b'e' | b'E');
+            if !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.') || is_exponent_sign) {
+                break;
+            }
+            length += 1;
+        }
+        self.position += length;
+        let text = &self.source[start..start + length];
+        let invalid = || self.error_at(start, format!("Invalid number '{}'", text));
+        let magnitude = if is_hex {
+            u64::from_str_radix(&text[2..], 16).map_err(|_| invalid())?
+        } else if text.len() > 1 && text.starts_with('0') && !text.contains(['.', 'e', 'E']) {
+            u64::from_str_radix(&text[1..], 8).map_err(|_| invalid())?
+        } else if !text.is_empty() && text.bytes().all(|c| c.is_ascii_digit()) {
+            match text.parse::<u64>() {
+                Ok(magnitude) => magnitude,
+                Err(_) => {
+                    return Err(self.error_at(start, format!("Integer '{}' is out of range", text)));
+                }
+            }
+        } else {
+            let float = text.strip_suffix(['f', 'F']).unwrap_or(text);
+            let n = float_keyword(text)
+                .or_else(|| {
+                    float
+                        .parse()
+                        .ok()
+                        .filter(|_| float.contains(|c: char| c.is_ascii_digit()))
+                })
+                .ok_or_else(invalid)?;
+            return Ok(Value::Number(if is_negative { -n } else { n }));
+        };
+        match (is_negative, i64::try_from(magnitude)) {
+            (false, Ok(i)) => Ok(Value::Integer(i)),
+            (true, _) if magnitude <= 1 << 63 => {
+                Ok(Value::Integer((magnitude as i64).wrapping_neg()))
+            }
+            (false, Err(_)) => Ok(Value::string(magnitude.to_string())),
+            (true, _) => Err(self.error_at(start, format!("Integer '-{}' is out of range", text))),
+        }
+    }
+
+    fn parse_identifier(&mut self) -> Option<&'source str> {
+        let start = self.position;
+        let rest = &self.bytes[start..];
+        if !rest
+            .first()
+            .is_some_and(|c| c.is_ascii_alphabetic() || *c == b'_')
+        {
+            return None;
+        }
+        let length = rest
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || *c == b'_'))
+            .unwrap_or(rest.len());
+        self.position += length;
+        Some(&self.source[start..start + length])
+    }
+
+    /// Skips whitespace and comments
+    fn skip_blank(&mut self) {
+        loop {
+            let rest = &self.bytes[self.position..];
+            match rest {
+                [b' ' | b'\t' | b'\r' | b'\n' | b'\x0b' | b'\x0c', ..] => self.position += 1,
+                [b'#', ..] => {
+                    self.position += rest.iter().position(|c| *c == b'\n').unwrap_or(rest.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+/// Returns the float value of the identifiers `inf`, `infinity` and `nan`, in any case
+fn float_keyword(identifier: &str) -> Option<f64> {
+    match identifier.to_ascii_lowercase().as_str() {
+        "inf" | "infinity" => Some(f64::INFINITY),
+        "nan" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+/// Adds values to a field, appending them to the values of an earlier occurrence
+fn add_field<'source>(
+    message: &mut Message<'source>,
+    name: Value<'source>,
+    values: Vec<Node<'source>>,
+    is_list: bool,
+) {
+    match message
+        .iter_mut()
+        .find(|(existing, _)| existing.as_str() == name.as_str())
+    {
+        Some((_, field)) => {
+            field.values.extend(values);
+            field.is_list |= is_list;
+        }
+        None => message.push((name, Field { values, is_list })),
+    }
+}
+
+fn message_node(message: Message) -> Node {
+    let entries = message
+        .into_iter()
+        .map(|(name, field)| {
+            let value = match (field.is_list, <[Node; 1]>::try_from(field.values)) {
+                (false, Ok([value])) => value,
+                (true, Ok(values)) => Node::List(values.into()),
+                (_, Err(values)) => Node::List(values),
+            };
+            (Node::Scalar(name), value)
+        })
+        .collect();
+    Node::Map(entries)
+}
+
+/// Resolves the escape sequences of a string, which can produce bytes that are not UTF-8
+fn unescape(output: &mut Vec<u8>, content: &str) -> LoadumResult<()> {
+    let bytes = content.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'\\' {
+            output.push(bytes[index]);
+            index += 1;
+            continue;
+        }
+        let escape = bytes[index + 1];
+        index += 2;
+        let digits = |radix: u32, max: usize| {
+            bytes[index..]
+                .iter()
+                .take(max)
+                .take_while(|c| (**c as char).is_digit(radix))
+                .count()
+        };
+        let byte = match escape {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'v' => 0x0b,
+            b'\\' | b'\'' | b'"' | b'?' => escape,
+            b'0'..=b'7' => {
+                let length = digits(8, 2);
+                let code = u32::from_str_radix(&content[index - 1..index + length], 8).unwrap();
+                let Ok(byte) = u8::try_from(code) else {
+                    bail!(
+                        "Invalid octal escape '\\{}'",
+                        &content[index - 1..index + length]
+                    );
+                };
+                index += length;
+                byte
+            }
+            b'x' | b'X' => {
+                let length = digits(16, 2);
+                if length == 0 {
+                    bail!("Invalid hex escape '\\{}'", escape as char);
+                }
+                let byte = u8::from_str_radix(&content[index..index + length], 16).unwrap();
+                index += length;
+                byte
+            }
+            b'u' | b'U' => {
+                let length = if escape == b'u' { 4 } else { 8 };
+                let hex = content
+                    .get(index..index + length)
+                    .unwrap_or(&content[index..]);
+                let c = u32::from_str_radix(hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == length)
+                    .and_then(char::from_u32);
+                let Some(c) = c else {
+                    bail!("Invalid unicode escape '\\{}{}'", escape as char, hex);
+                };
+                output.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+                index += length;
+                continue;
+            }
+            _ => bail!(
+                "Invalid escape sequence '\\{}'",
+                (escape as char).escape_default()
+            ),
+        };
+        output.push(byte);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextprotoLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use loadum::dumper::Dumper;
+    use loadum_json::json_dumper::JsonDumper;
+    use std::fmt::Write;
+
+    fn load(input: &str) -> String {
+        let mut output = String::new();
+        for event in TextprotoLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, expected: Expect) {
+        expected.assert_eq(&load(input));
+    }
+
+    #[test]
+    fn test_messages() {
+        test_loader(
+            r#"
+# proto-file: example/user.proto
+# proto-message: User
+name: "Ada"
+id: 42
+status: ACTIVE
+address {
+  city: "London"
+  zip: 'N1'
+}
+roles: "admin" roles: "dev";
+tags: []
+ids: [1, 2, 3],
+friends: [{ name: "Bob" }, < name: "Eve" >]
+settings: < dark_mode: true >
+[com.example.ext]: 7
+details {
+  [type.googleapis.com/com.example.Location] { lat: 51.5 lng: -0.12 }
+}
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Ada"))
+                MapKey(String("id"))
+                Literal(Integer(42))
+                MapKey(String("status"))
+                Literal(String("ACTIVE"))
+                MapKey(String("address"))
+                MapStart
+                MapKey(String("city"))
+                Literal(String("London"))
+                MapKey(String("zip"))
+                Literal(String("N1"))
+                MapEnd
+                MapKey(String("roles"))
+                ListStart
+                Literal(String("admin"))
+                Literal(String("dev"))
+                ListEnd
+                MapKey(String("tags"))
+                ListStart
+                ListEnd
+                MapKey(String("ids"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                Literal(Integer(3))
+                ListEnd
+                MapKey(String("friends"))
+                ListStart
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Bob"))
+                MapEnd
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Eve"))
+                MapEnd
+                ListEnd
+                MapKey(String("settings"))
+                MapStart
+                MapKey(String("dark_mode"))
+                Literal(Boolean(true))
+                MapEnd
+                MapKey(String("[com.example.ext]"))
+                Literal(Integer(7))
+                MapKey(String("details"))
+                MapStart
+                MapKey(String("@type"))
+                Literal(String("type.googleapis.com/com.example.Location"))
+                MapKey(String("lat"))
+                Literal(Number(51.5))
+                MapKey(String("lng"))
+                Literal(Number(-0.12))
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            r#"
+integers: [0, -7, 0x1F, 017, 9223372036854775807, -9223372036854775808]
+uint64: 18446744073709551615
+floats: [1.5, -2.5e-3, .5, 1f, 2.5F, 1E3]
+special: [inf, -inf, Infinity, nan, -nan]
+bools: [true, True, false, False, t, f]
+strings: "concatenated " 'strings'
+escapes: "\a\t\n\\\'\"\?\101\x42\u00e9\U0001F600"
+bytes: "\377\xfe"
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("integers"))
+                ListStart
+                Literal(Integer(0))
+                Literal(Integer(-7))
+                Literal(Integer(31))
+                Literal(Integer(15))
+                Literal(Integer(9223372036854775807))
+                Literal(Integer(-9223372036854775808))
+                ListEnd
+                MapKey(String("uint64"))
+                Literal(String("18446744073709551615"))
+                MapKey(String("floats"))
+                ListStart
+                Literal(Number(1.5))
+                Literal(Number(-0.0025))
+                Literal(Number(0.5))
+                Literal(Number(1.0))
+                Literal(Number(2.5))
+                Literal(Number(1000.0))
+                ListEnd
+                MapKey(String("special"))
+                ListStart
+                Literal(Number(inf))
+                Literal(Number(-inf))
+                Literal(Number(inf))
+                Literal(Number(NaN))
+                Literal(Number(NaN))
+                ListEnd
+                MapKey(String("bools"))
+                ListStart
+                Literal(Boolean(true))
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Boolean(false))
+                Literal(String("t"))
+                Literal(String("f"))
+                ListEnd
+                MapKey(String("strings"))
+                Literal(String("concatenated strings"))
+                MapKey(String("escapes"))
+                Literal(String("\u{7}\t\n\\'\"?ABé😀"))
+                MapKey(String("bytes"))
+                Literal(Bytes([255, 254]))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let output: String = [
+            "a 1",
+            "a: ",
+            "a { b: 1",
+            "a: [1 2]",
+            "a: [1,",
+            "a: \"unterminated",
+            "a: \"\\q\"",
+            "a: \"\\400\"",
+            "a: 09",
+            "a: 18446744073709551616",
+            "a: -9223372036854775809",
+            "a: -foo",
+            "[com.example ext]: 1",
+            "[type.googleapis.com/T]: 1",
+            "1: 2",
+            &"a {".repeat(MAX_DEPTH + 1),
+        ]
+        .into_iter()
+        .map(load)
+        .collect();
+        expect![[r#"
+            Error: Expected ':' or a message at line 1, column 3
+            Error: Expected a value at line 1, column 4
+            Error: Unterminated message at line 1, column 3
+            Error: Expected ',' or ']' in list at line 1, column 7
+            Error: Unterminated list at line 1, column 4
+            Error: Unterminated string at line 1, column 4
+            Error: Invalid escape sequence '\q' at line 1, column 4
+            Error: Invalid octal escape '\400' at line 1, column 4
+            Error: Invalid number '09' at line 1, column 4
+            Error: Integer '18446744073709551616' is out of range at line 1, column 4
+            Error: Integer '-9223372036854775809' is out of range at line 1, column 5
+            Error: Invalid number 'foo' at line 1, column 5
+            Error: Invalid extension name at line 1, column 2
+            Error: Expected a message after an Any type URL at line 1, column 24
+            Error: Expected field name at line 1, column 1
+            Error: Document is nested deeper than 128 levels at line 1, column 387
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        let input = r#"
+request { user_id: 1 fields: "name" fields: "email" }
+expected_response { user { id: 1 name: "Ada" } }
+"#;
+        for event in TextprotoLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"request": {
+            		"user_id": 1,
+            		"fields": [
+            			"name",
+            			"email"
+            		]
+            	},
+            	"expected_response": {
+            		"user": {
+            			"id": 1,
+            			"name": "Ada"
+            		}
+            	}
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,2 @@
+pub mod toml_dumper;
+pub mod toml_loader;
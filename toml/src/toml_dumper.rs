@@ -0,0 +1,440 @@
+use loadum::base64;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Dumps TOML documents
+///
+/// Sub-tables have to be written after all other entries of a table, so the document is
+/// collected and written once it is complete.
+pub struct TomlDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> TomlDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> TomlDumper<'write> {
+        TomlDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    ///
+    /// Composite keys are stringified as inline TOML values.
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for TomlDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let Node::Map(entries) = &root else {
+            bail!("TOML documents must be tables, but found {:?}", root);
+        };
+        let mut formatter = TomlFormatter {
+            output: String::new(),
+            key_policy: self.key_policy,
+        };
+        formatter.write_table(&mut vec![], entries, false)?;
+        self.write.write_all(formatter.output.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct TomlFormatter {
+    output: String,
+    key_policy: KeyPolicy,
+}
+
+/// Whether a value is written as a `[table]` or `[[array of tables]]` section
+fn is_section(node: &Node) -> bool {
+    match node.untagged() {
+        Node::Map(_) => true,
+        Node::List(items) => {
+            !items.is_empty()
+                && items
+                    .iter()
+                    .all(|item| matches!(item.untagged(), Node::Map(_)))
+        }
+        _ => false,
+    }
+}
+
+impl TomlFormatter {
+    fn write_table(
+        &mut self,
+        path: &mut Vec<String>,
+        entries: &[(Node, Node)],
+        is_array_item: bool,
+    ) -> LoadumResult<()> {
+        let has_values = entries.iter().any(|(_, value)| !is_section(value));
+        let has_sections = entries.iter().any(|(_, value)| is_section(value));
+        // Tables only containing sub-tables are implicitly defined by their sub-table headers
+        if is_array_item || (!path.is_empty() && (has_values || !has_sections)) {
+            if !self.output.is_empty() {
+                self.output.push('\n');
+            }
+            let (open, close) = if is_array_item {
+                ("[[", "]]")
+            } else {
+                ("[", "]")
+            };
+            writeln!(self.output, "{}{}{}", open, path.join("."), close)?;
+        }
+        for (key, value) in entries {
+            if !is_section(value) {
+                self.write_key(key)?;
+                self.output.push_str(" = ");
+                self.write_value(value)?;
+                self.output.push('\n');
+            }
+        }
+        for (key, value) in entries {
+            if !is_section(value) {
+                continue;
+            }
+            let start = self.output.len();
+            self.write_key(key)?;
+            path.push(self.output.split_off(start));
+            match value.untagged() {
+                Node::Map(entries) => self.write_table(path, entries, false)?,
+                Node::List(items) => {
+                    for item in items {
+                        let Node::Map(entries) = item.untagged() else {
+                            unreachable!("Arrays of tables only contain tables");
+                        };
+                        self.write_table(path, entries, true)?;
+                    }
+                }
+                _ => unreachable!("Only maps and lists are sections"),
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &Node) -> LoadumResult<()> {
+        let key = key.untagged();
+        if let Some(key) = key.as_str() {
+            let is_bare = !key.is_empty()
+                && key
+                    .bytes()
+                    .all(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'-');
+            if is_bare {
+                self.output.push_str(key);
+            } else {
+                self.write_string(key);
+            }
+            return Ok(());
+        }
+        if self.key_policy == KeyPolicy::Reject {
+            bail!("TOML keys must be strings, but found {:?}", key);
+        }
+        let key = match key {
+            Node::Scalar(value) => value.to_string(),
+            _ => {
+                let start = self.output.len();
+                self.write_value(key)?;
+                self.output.split_off(start)
+            }
+        };
+        self.write_string(&key);
+        Ok(())
+    }
+
+    fn write_value(&mut self, node: &Node) -> LoadumResult<()> {
+        match node {
+            Node::Scalar(value) => self.write_scalar(value)?,
+            Node::List(items) => {
+                self.output.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_value(item)?;
+                }
+                self.output.push(']');
+            }
+            // TOML has no tags, the tagged value is written as is
+            Node::Tagged(_, node) => self.write_value(node)?,
+            Node::Map(entries) if entries.is_empty() => self.output.push_str("{}"),
+            Node::Map(entries) => {
+                self.output.push_str("{ ");
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_key(key)?;
+                    self.output.push_str(" = ");
+                    self.write_value(value)?;
+                }
+                self.output.push_str(" }");
+            }
+        }
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, value: &Value) -> LoadumResult<()> {
+        match value {
+            Value::Null => bail!("TOML has no null value"),
+            Value::Boolean(b) => self.output.push_str(if *b { "true" } else { "false" }),
+            Value::Integer(i) => write!(self.output, "{}", i)?,
+            Value::Number(n) if n.is_nan() => self.output.push_str("nan"),
+            Value::Number(n) if n.is_infinite() => {
+                self.output.push_str(if *n > 0.0 { "inf" } else { "-inf" })
+            }
+            // Debug formatting always includes a fraction or exponent, keeping floats distinct
+            Value::Number(n) => write!(self.output, "{:?}", n)?,
+            Value::String(s) => self.write_string(s),
+            Value::BorrowedString(s) => self.write_string(s),
+            // TOML has no binary type, so bytes are written as a base64 string
+            Value::Bytes(bytes) => self.write_string(&base64::encode(bytes)),
+            Value::DateTime(date_time) => write!(self.output, "{}", date_time)?,
+        }
+        Ok(())
+    }
+
+    fn write_string(&mut self, s: &str) {
+        self.output.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => self.output.push_str("\\\""),
+                '\\' => self.output.push_str("\\\\"),
+                '\u{0008}' => self.output.push_str("\\b"),
+                '\t' => self.output.push_str("\\t"),
+                '\n' => self.output.push_str("\\n"),
+                '\u{000c}' => self.output.push_str("\\f"),
+                '\r' => self.output.push_str("\\r"),
+                c if c.is_ascii_control() => {
+                    write!(self.output, "\\u{:04X}", c as u32).unwrap();
+                }
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::toml_dumper::TomlDumper;
+    use crate::toml_loader::TomlLoader;
+    use expect_test::{Expect, expect};
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::value::Value;
+
+    fn dump(events: &[Event], key_policy: KeyPolicy) -> String {
+        let mut output = vec![];
+        let mut dumper = TomlDumper::new(&mut output).with_key_policy(key_policy);
+        let result = [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+            .try_for_each(|event| dumper.emit(event));
+        drop(dumper);
+        match result {
+            Ok(()) => String::from_utf8(output).unwrap(),
+            Err(error) => format!("Error: {}", error),
+        }
+    }
+
+    fn test_dump(events: &[Event], expected: Expect) {
+        expected.assert_eq(&dump(events, KeyPolicy::Stringify));
+    }
+
+    #[test]
+    fn test_values() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("string"),
+                Event::string("quote \" backslash \\ newline \n bell \u{7}"),
+                Event::map_key("integer"),
+                Event::integer(-42),
+                Event::map_key("float"),
+                Event::number(1),
+                Event::map_key("exponent"),
+                Event::number(6.626e-34),
+                Event::map_key("special"),
+                Event::ListStart,
+                Event::number(f64::INFINITY),
+                Event::number(f64::NEG_INFINITY),
+                Event::number(f64::NAN),
+                Event::ListEnd,
+                Event::map_key("bool"),
+                Event::bool(true),
+                Event::map_key("bytes"),
+                Event::bytes(&b"foo"[..]),
+                Event::map_key("date_time"),
+                Event::date_time(DateTime::parse("1979-05-27T07:32:00-07:00").unwrap()),
+                Event::map_key("needs quotes"),
+                Event::MapStart,
+                Event::map_key("a.b"),
+                Event::ListStart,
+                Event::integer(1),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::ListEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            expect![[r#"
+                string = "quote \" backslash \\ newline \n bell \u0007"
+                integer = -42
+                float = 1.0
+                exponent = 6.626e-34
+                special = [inf, -inf, nan]
+                bool = true
+                bytes = "Zm9v"
+                date_time = 1979-05-27T07:32:00-07:00
+
+                ["needs quotes"]
+                "a.b" = [1, {}]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("title"),
+                Event::string("example"),
+                Event::map_key("x"),
+                Event::MapStart,
+                Event::map_key("y"),
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::integer(1),
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::map_key("products"),
+                Event::ListStart,
+                Event::MapStart,
+                Event::map_key("name"),
+                Event::string("Hammer"),
+                Event::MapEnd,
+                Event::MapStart,
+                Event::map_key("dimensions"),
+                Event::MapStart,
+                Event::map_key("length"),
+                Event::integer(2),
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::ListEnd,
+                Event::map_key("empty"),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            expect![[r#"
+                title = "example"
+
+                [x.y]
+                a = 1
+
+                [[products]]
+                name = "Hammer"
+
+                [[products]]
+
+                [products.dimensions]
+                length = 2
+
+                [empty]
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_keys() {
+        let events = [
+            Event::MapStart,
+            Event::MapKey(Value::Integer(1)),
+            Event::string("one"),
+            Event::ComplexKey,
+            Event::ListStart,
+            Event::integer(1),
+            Event::integer(2),
+            Event::ListEnd,
+            Event::string("list"),
+            Event::MapEnd,
+        ];
+        test_dump(
+            &events,
+            expect![[r#"
+                "1" = "one"
+                "[1, 2]" = "list"
+            "#]],
+        );
+        expect![[r#"Error: TOML keys must be strings, but found Scalar(Integer(1))"#]]
+            .assert_eq(&dump(&events, KeyPolicy::Reject));
+    }
+
+    #[test]
+    fn test_errors() {
+        test_dump(
+            &[Event::ListStart, Event::ListEnd],
+            expect![[r#"Error: TOML documents must be tables, but found List([])"#]],
+        );
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::null(),
+                Event::MapEnd,
+            ],
+            expect![[r#"Error: TOML has no null value"#]],
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let input = r#"title = "TOML Example"
+ports = [8000, 8001, 8002]
+
+[point]
+x = 1
+y = 2.5
+
+[owner]
+name = "Tom"
+dob = 1979-05-27T07:32:00-08:00
+
+[servers.alpha]
+ip = "10.0.0.1"
+
+[[fruits]]
+name = "apple"
+
+[fruits.physical]
+color = "red"
+
+[[fruits]]
+name = "banana"
+"#;
+        let mut output = vec![];
+        let mut dumper = TomlDumper::new(&mut output);
+        for event in TomlLoader::new(input) {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+}
@@ -0,0 +1,1088 @@
+use loadum::LoadumString;
+use loadum::datetime::DateTime;
+use loadum::depth::{Depth, check_depth};
+use loadum::error::{LoadumError, bail, format_err};
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::fmt::Display;
+
+/// Loads TOML documents
+///
+/// Tables may be extended anywhere in a TOML document, so the whole document is parsed before
+/// the first event is returned.
+pub struct TomlLoader<'source> {
+    source: &'source str,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> TomlLoader<'source> {
+    pub fn new(source: &'source str) -> TomlLoader<'source> {
+        TomlLoader {
+            source,
+            events: None,
+        }
+    }
+}
+
+impl<'source> Loader<'source> for TomlLoader<'source> {}
+
+impl<'source> Iterator for TomlLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                source: self.source,
+                bytes: self.source.as_bytes(),
+                position: 0,
+                depth: Depth::default(),
+            };
+            match parser.parse_document() {
+                Ok(root) => {
+                    let mut events = vec![Event::DocumentStart];
+                    table_events(root, &mut events);
+                    events.push(Event::DocumentEnd);
+                    self.events = Some(events.into_iter());
+                }
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TableKind {
+    /// Created as the parent of a table header, may still be defined by its own header
+    Implicit,
+    Header,
+    Dotted,
+    Inline,
+}
+
+struct Table<'source> {
+    entries: Vec<(Value<'source>, Item<'source>)>,
+    kind: TableKind,
+}
+
+enum Item<'source> {
+    Value(Value<'source>),
+    Table(Table<'source>),
+    Array(Vec<Item<'source>>),
+    ArrayOfTables(Vec<Table<'source>>),
+}
+
+impl<'source> Table<'source> {
+    fn new(kind: TableKind) -> Table<'source> {
+        Table {
+            entries: vec![],
+            kind,
+        }
+    }
+
+    fn position(&self, key: &Value) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(entry_key, _)| entry_key.as_str() == key.as_str())
+    }
+
+    /// Returns the index of the entry with the given key, inserting the item if it is missing
+    fn entry(&mut self, key: &Value<'source>, item: impl FnOnce() -> Item<'source>) -> usize {
+        self.position(key).unwrap_or_else(|| {
+            self.entries.push((key.clone(), item()));
+            self.entries.len() - 1
+        })
+    }
+
+    /// Walks the parents of a table header, creating missing tables
+    fn walk_header(&mut self, keys: &[Value<'source>]) -> LoadumResult<&mut Table<'source>> {
+        let mut table = self;
+        for key in keys {
+            let index = table.entry(key, || Item::Table(Table::new(TableKind::Implicit)));
+            table = match &mut table.entries[index].1 {
+                Item::Table(table) if table.kind != TableKind::Inline => table,
+                Item::ArrayOfTables(tables) => tables.last_mut().unwrap(),
+                _ => bail!("Key '{}' is already defined as a value", key_path(keys)),
+            };
+        }
+        Ok(table)
+    }
+
+    fn define_table(&mut self, keys: &[Value<'source>]) -> LoadumResult<()> {
+        let (last, parents) = keys.split_last().unwrap();
+        let table = self.walk_header(parents)?;
+        match table.position(last) {
+            None => table
+                .entries
+                .push((last.clone(), Item::Table(Table::new(TableKind::Header)))),
+            Some(index) => match &mut table.entries[index].1 {
+                Item::Table(table) if table.kind == TableKind::Implicit => {
+                    table.kind = TableKind::Header;
+                }
+                _ => bail!("Table '{}' is already defined", key_path(keys)),
+            },
+        }
+        Ok(())
+    }
+
+    fn define_array_table(&mut self, keys: &[Value<'source>]) -> LoadumResult<()> {
+        let (last, parents) = keys.split_last().unwrap();
+        let table = self.walk_header(parents)?;
+        let index = table.entry(last, || Item::ArrayOfTables(vec![]));
+        let Item::ArrayOfTables(tables) = &mut table.entries[index].1 else {
+            bail!("Key '{}' is already defined", key_path(keys));
+        };
+        tables.push(Table::new(TableKind::Header));
+        Ok(())
+    }
+
+    /// Navigates to the table defined by the last header
+    fn current(&mut self, keys: &[Value<'source>]) -> &mut Table<'source> {
+        let mut table = self;
+        for key in keys {
+            let index = table.position(key).unwrap();
+            table = match &mut table.entries[index].1 {
+                Item::Table(table) => table,
+                Item::ArrayOfTables(tables) => tables.last_mut().unwrap(),
+                _ => unreachable!("Table headers only contain tables"),
+            };
+        }
+        table
+    }
+
+    fn insert(&mut self, keys: &[Value<'source>], item: Item<'source>) -> LoadumResult<()> {
+        let (last, parents) = keys.split_last().unwrap();
+        let mut table = self;
+        for key in parents {
+            let index = table.entry(key, || Item::Table(Table::new(TableKind::Dotted)));
+            table = match &mut table.entries[index].1 {
+                Item::Table(table) if table.kind == TableKind::Dotted => table,
+                _ => bail!("Key '{}' is already defined", key_path(keys)),
+            };
+        }
+        if table.position(last).is_some() {
+            bail!("Key '{}' is already defined", key_path(keys));
+        }
+        table.entries.push((last.clone(), item));
+        Ok(())
+    }
+}
+
+fn key_path(keys: &[Value]) -> String {
+    keys.iter()
+        .map(|key| key.as_str().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn table_events<'source>(table: Table<'source>, events: &mut Vec<Event<'source>>) {
+    events.push(Event::MapStart);
+    for (key, item) in table.entries {
+        events.push(Event::MapKey(key));
+        item_events(item, events);
+    }
+    events.push(Event::MapEnd);
+}
+
+fn item_events<'source>(item: Item<'source>, events: &mut Vec<Event<'source>>) {
+    match item {
+        Item::Value(value) => events.push(Event::Literal(value)),
+        Item::Table(table) => table_events(table, events),
+        Item::Array(items) => {
+            events.push(Event::ListStart);
+            for item in items {
+                item_events(item, events);
+            }
+            events.push(Event::ListEnd);
+        }
+        Item::ArrayOfTables(tables) => {
+            events.push(Event::ListStart);
+            for table in tables {
+                table_events(table, events);
+            }
+            events.push(Event::ListEnd);
+        }
+    }
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+    depth: Depth,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Table<'source>> {
+        let mut root = Table::new(TableKind::Header);
+        let mut current = vec![];
+        loop {
+            self.skip_blank();
+            let Some(c) = self.peek() else {
+                return Ok(root);
+            };
+            let start = self.position;
+            if c == b'[' {
+                let is_array = self.bytes.get(self.position + 1) == Some(&b'[');
+                self.position += if is_array { 2 } else { 1 };
+                let keys = self.parse_key()?;
+                if is_array {
+                    self.expect("]]")?;
+                    root.define_array_table(&keys)
+                } else {
+                    self.expect("]")?;
+                    root.define_table(&keys)
+                }
+                .map_err(|error| self.error_at(start, error))?;
+                current = keys;
+            } else {
+                let (keys, item) = self.parse_key_value()?;
+                root.current(&current)
+                    .insert(&keys, item)
+                    .map_err(|error| self.error_at(start, error))?;
+            }
+            self.expect_line_end()?;
+        }
+    }
+
+    fn parse_key_value(&mut self) -> LoadumResult<(Vec<Value<'source>>, Item<'source>)> {
+        let keys = self.parse_key()?;
+        self.expect("=")?;
+        self.skip_whitespace();
+        let item = self.parse_value()?;
+        Ok((keys, item))
+    }
+
+    fn parse_key(&mut self) -> LoadumResult<Vec<Value<'source>>> {
+        let mut keys = vec![];
+        loop {
+            // Every part of a dotted key nests a table
+            check_depth(keys.len()).map_err(|error| self.error(error))?;
+            self.skip_whitespace();
+            keys.push(self.parse_simple_key()?);
+            self.skip_whitespace();
+            if self.peek() != Some(b'.') {
+                return Ok(keys);
+            }
+            self.position += 1;
+        }
+    }
+
+    fn parse_simple_key(&mut self) -> LoadumResult<Value<'source>> {
+        let rest = &self.bytes[self.position..];
+        if rest.starts_with(b"\"") && !rest.starts_with(b"\"\"\"") {
+            return self.parse_basic_string();
+        }
+        if rest.starts_with(b"'") && !rest.starts_with(b"'''") {
+            return self.parse_literal_string();
+        }
+        let length = rest
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || *c == b'_' || *c == b'-'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("Expected key"));
+        }
+        let key = &self.source[self.position..self.position + length];
+        self.position += length;
+        Ok(Value::BorrowedString(key))
+    }
+
+    fn parse_value(&mut self) -> LoadumResult<Item<'source>> {
+        let rest = &self.source[self.position..];
+        let value = match self.peek() {
+            None => return Err(self.error("Expected value")),
+            Some(b'"' | b'\'') => self.parse_string()?,
+            Some(c @ (b'[' | b'{')) => {
+                self.depth.enter().map_err(|error| self.error(error))?;
+                let item = if c == b'[' {
+                    self.parse_array()
+                } else {
+                    self.parse_inline_table()
+                };
+                self.depth.exit();
+                return item;
+            }
+            Some(b't') if rest.starts_with("true") => {
+                self.position += 4;
+                Value::Boolean(true)
+            }
+            Some(b'f') if rest.starts_with("false") => {
+                self.position += 5;
+                Value::Boolean(false)
+            }
+            Some(_) if is_date_time(rest.as_bytes()) => self.parse_date_time()?,
+            Some(_) => self.parse_number()?,
+        };
+        Ok(Item::Value(value))
+    }
+
+    fn parse_array(&mut self) -> LoadumResult<Item<'source>> {
+        self.position += 1;
+        let mut items = vec![];
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(b']') {
+                self.position += 1;
+                return Ok(Item::Array(items));
+            }
+            items.push(self.parse_value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Item::Array(items));
+                }
+                _ => return Err(self.error("Expected ',' or ']' in array")),
+            }
+        }
+    }
+
+    fn parse_inline_table(&mut self) -> LoadumResult<Item<'source>> {
+        self.position += 1;
+        let mut table = Table::new(TableKind::Inline);
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(Item::Table(table));
+        }
+        loop {
+            let start = self.position;
+            let (keys, item) = self.parse_key_value()?;
+            table
+                .insert(&keys, item)
+                .map_err(|error| self.error_at(start, error))?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Item::Table(table));
+                }
+                _ => return Err(self.error("Expected ',' or '}' in inline table")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> LoadumResult<Value<'source>> {
+        let rest = &self.bytes[self.position..];
+        if rest.starts_with(b"\"\"\"") {
+            self.parse_multi_line_string(b'"')
+        } else if rest.starts_with(b"'''") {
+            self.parse_multi_line_string(b'\'')
+        } else if rest.starts_with(b"\"") {
+            self.parse_basic_string()
+        } else {
+            self.parse_literal_string()
+        }
+    }
+
+    fn parse_literal_string(&mut self) -> LoadumResult<Value<'source>> {
+        let start = self.position + 1;
+        let mut index = start;
+        loop {
+            match self.bytes.get(index) {
+                None | Some(b'\n') => return Err(self.error("Unterminated string")),
+                Some(b'\'') => break,
+                Some(&c) if is_control(c) => {
+                    return Err(self.error_at(index, "Control character in string"));
+                }
+                Some(_) => index += 1,
+            }
+        }
+        self.position = index + 1;
+        Ok(Value::BorrowedString(&self.source[start..index]))
+    }
+
+    fn parse_basic_string(&mut self) -> LoadumResult<Value<'source>> {
+        let start = self.position + 1;
+        let mut index = start;
+        let mut has_escapes = false;
+        loop {
+            match self.bytes.get(index) {
+                None | Some(b'\n') => return Err(self.error("Unterminated string")),
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    has_escapes = true;
+                    index += 2;
+                }
+                Some(&c) if is_control(c) => {
+                    return Err(self.error_at(index, "Control character in string"));
+                }
+                Some(_) => index += 1,
+            }
+        }
+        let content = &self.source[start..index];
+        let value = if has_escapes {
+            Value::String(unescape(content, false).map_err(|error| self.error_at(start, error))?)
+        } else {
+            Value::BorrowedString(content)
+        };
+        self.position = index + 1;
+        Ok(value)
+    }
+
+    fn parse_multi_line_string(&mut self, quote: u8) -> LoadumResult<Value<'source>> {
+        let mut start = self.position + 3;
+        // A newline directly after the opening delimiter is trimmed
+        if self.bytes[start..].starts_with(b"\n") {
+            start += 1;
+        } else if self.bytes[start..].starts_with(b"\r\n") {
+            start += 2;
+        }
+        let mut index = start;
+        let mut has_escapes = false;
+        let end = loop {
+            match self.bytes.get(index) {
+                None => return Err(self.error("Unterminated multi-line string")),
+                Some(b'\\') if quote == b'"' => {
+                    has_escapes = true;
+                    index += 2;
+                }
+                Some(&c) if c == quote && self.bytes[index..].starts_with(&[quote; 3]) => {
+                    // Up to two quotes may directly precede the closing delimiter
+                    let mut quotes = 3;
+                    while quotes < 5 && self.bytes.get(index + quotes) == Some(&quote) {
+                        quotes += 1;
+                    }
+                    let end = index + quotes - 3;
+                    index += quotes;
+                    break end;
+                }
+                Some(b'\r') if self.bytes.get(index + 1) == Some(&b'\n') => index += 2,
+                Some(&c) if c != b'\n' && is_control(c) => {
+                    return Err(self.error_at(index, "Control character in string"));
+                }
+                Some(_) => index += 1,
+            }
+        };
+        let content = &self.source[start..end];
+        let value = if has_escapes {
+            Value::String(unescape(content, true).map_err(|error| self.error_at(start, error))?)
+        } else {
+            Value::BorrowedString(content)
+        };
+        self.position = index;
+        Ok(value)
+    }
+
+    fn parse_date_time(&mut self) -> LoadumResult<Value<'source>> {
+        let is_date_time_char = |c: &u8| {
+            matches!(
+                c,
+                b'0'..=b'9' | b'-' | b':' | b'.' | b'+' | b'T' | b't' | b'Z' | b'z'
+            )
+        };
+        let rest = &self.bytes[self.position..];
+        let mut length = rest
+            .iter()
+            .position(|c| !is_date_time_char(c))
+            .unwrap_or(rest.len());
+        // A space may separate date and time
+        if length == 10 && rest.get(10) == Some(&b' ') && is_date_time(&rest[11..]) {
+            length = 11
+                + rest[11..]
+                    .iter()
+                    .position(|c| !is_date_time_char(c))
+                    .unwrap_or(rest.len() - 11);
+        }
+        let text = &self.source[self.position..self.position + length];
+        let date_time = DateTime::parse(text).map_err(|error| self.error(error))?;
+        self.position += length;
+        Ok(Value::DateTime(date_time))
+    }
+
+    fn parse_number(&mut self) -> LoadumResult<Value<'source>> {
+        let rest = &self.bytes[self.position..];
+        let length = rest
+            .iter()
+            .position(|c| !(c.is_ascii_alphanumeric() || matches!(c, b'_' | b'+' | b'-' | b'.')))
+            .unwrap_or(rest.len());
+        let text = &self.source[self.position..self.position + length];
+        let Some(value) = parse_number(text) else {
+            return Err(self.error(format!("Invalid value '{}'", text)));
+        };
+        self.position += length;
+        Ok(value)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn expect(&mut self, expected: &str) -> LoadumResult<()> {
+        self.skip_whitespace();
+        if !self.bytes[self.position..].starts_with(expected.as_bytes()) {
+            return Err(self.error(format!("Expected '{}'", expected)));
+        }
+        self.position += expected.len();
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some(b'#') {
+            while !matches!(self.peek(), None | Some(b'\n')) {
+                self.position += 1;
+            }
+        }
+    }
+
+    fn skip_newline(&mut self) -> bool {
+        let rest = &self.bytes[self.position..];
+        if rest.starts_with(b"\n") {
+            self.position += 1;
+        } else if rest.starts_with(b"\r\n") {
+            self.position += 2;
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Skips whitespace, comments and newlines
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_whitespace();
+            self.skip_comment();
+            if !self.skip_newline() {
+                return;
+            }
+        }
+    }
+
+    fn expect_line_end(&mut self) -> LoadumResult<()> {
+        self.skip_whitespace();
+        self.skip_comment();
+        if self.peek().is_none() || self.skip_newline() {
+            return Ok(());
+        }
+        Err(self.error("Expected end of line"))
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+fn is_control(c: u8) -> bool {
+    (c < 0x20 && c != b'\t') || c == 0x7f
+}
+
+/// Dates start with `YYYY-`, times with `HH:`
+fn is_date_time(text: &[u8]) -> bool {
+    let digits = |range: std::ops::Range<usize>| {
+        text.get(range)
+            .is_some_and(|digits| digits.iter().all(u8::is_ascii_digit))
+    };
+    (digits(0..4) && text.get(4) == Some(&b'-')) || (digits(0..2) && text.get(2) == Some(&b':'))
+}
+
+fn parse_number(text: &str) -> Option<Value<'static>> {
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let sign = if text.starts_with('-') { -1.0 } else { 1.0 };
+    match unsigned {
+        "inf" => return Some(Value::Number(sign * f64::INFINITY)),
+        "nan" => return Some(Value::Number(f64::NAN)),
+        _ => {}
+    }
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = text.strip_prefix(prefix) {
+            if !valid_digits(digits, radix) {
+                return None;
+            }
+            let digits = digits.replace('_', "");
+            return i64::from_str_radix(&digits, radix).ok().map(Value::Integer);
+        }
+    }
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    if !valid_digits(integer, 10) || (integer.starts_with('0') && integer.len() > 1) {
+        return None;
+    }
+    if fraction.is_some_and(|fraction| !valid_digits(fraction, 10)) {
+        return None;
+    }
+    let exponent_digits =
+        exponent.map(|exponent| exponent.strip_prefix(['+', '-']).unwrap_or(exponent));
+    if exponent_digits.is_some_and(|digits| !valid_digits(digits, 10)) {
+        return None;
+    }
+    let number = text.replace('_', "");
+    if fraction.is_none() && exponent.is_none() {
+        number.parse().ok().map(Value::Integer)
+    } else {
+        number.parse().ok().map(Value::Number)
+    }
+}
+
+/// Digits with underscores only allowed between digits
+fn valid_digits(text: &str, radix: u32) -> bool {
+    !text.is_empty()
+        && !text.starts_with('_')
+        && !text.ends_with('_')
+        && !text.contains("__")
+        && text.chars().all(|c| c == '_' || c.is_digit(radix))
+}
+
+fn unescape(content: &str, multi_line: bool) -> LoadumResult<LoadumString> {
+    let mut string = LoadumString::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            string.push(c);
+            continue;
+        }
+        let c = match chars.next() {
+            Some('b') => '\u{0008}',
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('f') => '\u{000c}',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(escape @ ('u' | 'U')) => {
+                let length = if escape == 'u' { 4 } else { 8 };
+                let digits: String = chars.by_ref().take(length).collect();
+                let code_point = u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == length)
+                    .and_then(char::from_u32);
+                let Some(c) = code_point else {
+                    bail!("Invalid unicode escape '\\{}{}'", escape, digits);
+                };
+                c
+            }
+            // A backslash at the end of a line trims all following whitespace
+            Some(c @ (' ' | '\t' | '\r' | '\n')) if multi_line => {
+                let mut has_newline = c == '\n';
+                while let Some(&c @ (' ' | '\t' | '\r' | '\n')) = chars.peek() {
+                    has_newline |= c == '\n';
+                    chars.next();
+                }
+                if !has_newline {
+                    bail!("Invalid escape sequence '\\{}'", c.escape_default());
+                }
+                continue;
+            }
+            Some(c) => bail!("Invalid escape sequence '\\{}'", c.escape_default()),
+            None => bail!("Incomplete escape sequence"),
+        };
+        string.push(c);
+    }
+    Ok(string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TomlLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use std::fmt::Write;
+
+    fn test_loader(input: &str, expected: Expect) {
+        let mut output = String::new();
+        for event in TomlLoader::new(input) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        expected.assert_eq(&output);
+    }
+
+    #[test]
+    fn test_empty() {
+        test_loader(
+            "# only a comment\n",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_key_values() {
+        test_loader(
+            r#"
+bare_key-1 = "value"
+"quoted key" = 'literal \n'
+site."google.com" = true
+physical.color = "orange"
+physical.shape = "round"
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("bare_key-1"))
+                Literal(String("value"))
+                MapKey(String("quoted key"))
+                Literal(String("literal \\n"))
+                MapKey(String("site"))
+                MapStart
+                MapKey(String("google.com"))
+                Literal(Boolean(true))
+                MapEnd
+                MapKey(String("physical"))
+                MapStart
+                MapKey(String("color"))
+                Literal(String("orange"))
+                MapKey(String("shape"))
+                Literal(String("round"))
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_strings() {
+        test_loader(
+            r#"
+escaped = "tab\tquote\"unicode\u00e4\U0001F600"
+multi_line = """
+Roses are red
+Violets are blue"""
+trimmed = """\
+       The quick brown \
+       fox."""
+quotes = """Here are two quotation marks: "". Simple enough."""
+literal = '''
+The first newline is
+trimmed in raw strings.
+'''
+trailing_quotes = ''''That,' she said, 'is still pointless.''''
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("escaped"))
+                Literal(String("tab\tquote\"unicodeä😀"))
+                MapKey(String("multi_line"))
+                Literal(String("Roses are red\nViolets are blue"))
+                MapKey(String("trimmed"))
+                Literal(String("The quick brown fox."))
+                MapKey(String("quotes"))
+                Literal(String("Here are two quotation marks: \"\". Simple enough."))
+                MapKey(String("literal"))
+                Literal(String("The first newline is\ntrimmed in raw strings.\n"))
+                MapKey(String("trailing_quotes"))
+                Literal(String("'That,' she said, 'is still pointless.'"))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        test_loader(
+            r#"
+integers = [+99, 42, 0, -17, 1_000, 0xDEAD_beef, 0o755, 0b1101]
+floats = [+1.0, 3.1415, -0.01, 5e+22, 1e06, -2E-2, 6.626e-34, 224_617.445_991]
+special = [inf, -inf, nan]
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("integers"))
+                ListStart
+                Literal(Integer(99))
+                Literal(Integer(42))
+                Literal(Integer(0))
+                Literal(Integer(-17))
+                Literal(Integer(1000))
+                Literal(Integer(3735928559))
+                Literal(Integer(493))
+                Literal(Integer(13))
+                ListEnd
+                MapKey(String("floats"))
+                ListStart
+                Literal(Number(1.0))
+                Literal(Number(3.1415))
+                Literal(Number(-0.01))
+                Literal(Number(5e22))
+                Literal(Number(1000000.0))
+                Literal(Number(-0.02))
+                Literal(Number(6.626e-34))
+                Literal(Number(224617.445991))
+                ListEnd
+                MapKey(String("special"))
+                ListStart
+                Literal(Number(inf))
+                Literal(Number(-inf))
+                Literal(Number(NaN))
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_date_times() {
+        test_loader(
+            r#"
+odt1 = 1979-05-27T07:32:00Z
+odt2 = 1979-05-27 00:32:00.999999-07:00
+ldt = 1979-05-27T07:32:00
+ld = 1979-05-27
+lt = 00:32:00.999999
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("odt1"))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1979, month: 5, day: 27 }, time: Time { hour: 7, minute: 32, second: 0, nanosecond: 0 }, offset_minutes: 0 }))
+                MapKey(String("odt2"))
+                Literal(DateTime(OffsetDateTime { date: Date { year: 1979, month: 5, day: 27 }, time: Time { hour: 0, minute: 32, second: 0, nanosecond: 999999000 }, offset_minutes: -420 }))
+                MapKey(String("ldt"))
+                Literal(DateTime(LocalDateTime { date: Date { year: 1979, month: 5, day: 27 }, time: Time { hour: 7, minute: 32, second: 0, nanosecond: 0 } }))
+                MapKey(String("ld"))
+                Literal(DateTime(LocalDate(Date { year: 1979, month: 5, day: 27 })))
+                MapKey(String("lt"))
+                Literal(DateTime(LocalTime(Time { hour: 0, minute: 32, second: 0, nanosecond: 999999000 })))
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        test_loader(
+            r#"
+title = "example"
+
+[x.y.z]
+a = 1
+
+[x] # defines the implicitly created table
+b = { inline = [1, 2], nested.key = "value" }
+
+[[products]]
+name = "Hammer"
+
+[[products]] # empty table
+
+[[products]]
+name = "Nail"
+[products.dimensions]
+length = 2
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("title"))
+                Literal(String("example"))
+                MapKey(String("x"))
+                MapStart
+                MapKey(String("y"))
+                MapStart
+                MapKey(String("z"))
+                MapStart
+                MapKey(String("a"))
+                Literal(Integer(1))
+                MapEnd
+                MapEnd
+                MapKey(String("b"))
+                MapStart
+                MapKey(String("inline"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                MapKey(String("nested"))
+                MapStart
+                MapKey(String("key"))
+                Literal(String("value"))
+                MapEnd
+                MapEnd
+                MapEnd
+                MapKey(String("products"))
+                ListStart
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Hammer"))
+                MapEnd
+                MapStart
+                MapEnd
+                MapStart
+                MapKey(String("name"))
+                Literal(String("Nail"))
+                MapKey(String("dimensions"))
+                MapStart
+                MapKey(String("length"))
+                Literal(Integer(2))
+                MapEnd
+                MapEnd
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_arrays() {
+        test_loader(
+            r#"
+nested = [ [ 1, 2 ], ["a", 'b'], [ { x = 1 } ] ]
+multi_line = [
+  1, # comment
+  2,
+]
+"#,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("nested"))
+                ListStart
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                ListStart
+                Literal(String("a"))
+                Literal(String("b"))
+                ListEnd
+                ListStart
+                MapStart
+                MapKey(String("x"))
+                Literal(Integer(1))
+                MapEnd
+                ListEnd
+                ListEnd
+                MapKey(String("multi_line"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            "a = 1\na = 2",
+            expect![[r#"
+                Error: Key 'a' is already defined at line 2, column 1
+            "#]],
+        );
+        test_loader(
+            "[a]\n[a]",
+            expect![[r#"
+                Error: Table 'a' is already defined at line 2, column 1
+            "#]],
+        );
+        test_loader(
+            "a.b = 1\n[a]",
+            expect![[r#"
+                Error: Table 'a' is already defined at line 2, column 1
+            "#]],
+        );
+        test_loader(
+            "a = {}\n[a.b]",
+            expect![[r#"
+                Error: Key 'a' is already defined as a value at line 2, column 1
+            "#]],
+        );
+        test_loader(
+            "a = { b = 1, }",
+            expect![[r#"
+                Error: Expected key at line 1, column 14
+            "#]],
+        );
+        test_loader(
+            &format!("a = {}", "[".repeat(MAX_DEPTH + 1)),
+            expect![[r#"
+                Error: Document is nested deeper than 128 levels at line 1, column 133
+            "#]],
+        );
+        test_loader(
+            &format!("a = {}", "{b = ".repeat(MAX_DEPTH + 1)),
+            expect![[r#"
+                Error: Document is nested deeper than 128 levels at line 1, column 645
+            "#]],
+        );
+        test_loader(
+            &format!("a{} = 1", ".a".repeat(MAX_DEPTH)),
+            expect![[r#"
+                Error: Document is nested deeper than 128 levels at line 1, column 257
+            "#]],
+        );
+        test_loader(
+            "a = 1 b = 2",
+            expect![[r#"
+                Error: Expected end of line at line 1, column 7
+            "#]],
+        );
+        test_loader(
+            "a = 01",
+            expect![[r#"
+                Error: Invalid value '01' at line 1, column 5
+            "#]],
+        );
+        test_loader(
+            "a = \"unterminated",
+            expect![[r#"
+                Error: Unterminated string at line 1, column 5
+            "#]],
+        );
+        test_loader(
+            "a = \"\\q\"",
+            expect![[r#"
+                Error: Invalid escape sequence '\q' at line 1, column 6
+            "#]],
+        );
+        test_loader(
+            "a = 1979-13-27",
+            expect![[r#"
+                Error: Invalid date/time '1979-13-27' at line 1, column 5
+            "#]],
+        );
+        test_loader(
+            "a =",
+            expect![[r#"
+                Error: Expected value at line 1, column 4
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,11 @@
+//! UBJSON, the Universal Binary JSON format, following Draft 12 of the specification
+//!
+//! Arrays and objects are lists and maps, with or without counts and types. Strongly typed arrays
+//! of `U` (uint8) values are loaded as bytes, the usual way of storing binary data in UBJSON.
+//! High-precision numbers are loaded as strings tagged with `high-precision`.
+
+pub mod ubjson_dumper;
+pub mod ubjson_loader;
+
+/// Tag of high-precision numbers, which are stored as strings of their decimal representation
+pub(crate) const HIGH_PRECISION_TAG: &str = "high-precision";
@@ -0,0 +1,249 @@
+use crate::HIGH_PRECISION_TAG;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Write;
+
+/// Dumps UBJSON, each document is written as a top level value
+///
+/// Arrays and objects are written without counts, integers with the smallest marker that fits and
+/// floats as float64. Bytes are written as strongly typed arrays of uint8, strings tagged with
+/// `high-precision` as high-precision numbers, date-times as strings and other tags are ignored.
+pub struct UbjsonDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> UbjsonDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> UbjsonDumper<'write> {
+        UbjsonDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            key_policy: KeyPolicy::default(),
+        }
+    }
+
+    /// Sets how keys that are not strings are handled
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+
+    fn write_node(&self, output: &mut Vec<u8>, node: &Node) -> LoadumResult<()> {
+        match node {
+            Node::Scalar(value) => write_value(output, value),
+            Node::List(items) => {
+                output.push(b'[');
+                for item in items {
+                    self.write_node(output, item)?;
+                }
+                output.push(b']');
+            }
+            Node::Map(entries) => {
+                output.push(b'{');
+                for (key, value) in entries {
+                    write_string(output, &self.key_policy.key_string(key, "UBJSON keys")?);
+                    self.write_node(output, value)?;
+                }
+                output.push(b'}');
+            }
+            Node::Tagged(tag, node) => {
+                let text = match node.untagged() {
+                    Node::Scalar(value) => value.as_str(),
+                    _ => None,
+                };
+                match (tag.as_ref(), text) {
+                    (HIGH_PRECISION_TAG, Some(number)) => {
+                        output.push(b'H');
+                        write_string(output, number);
+                    }
+                    _ => self.write_node(output, node)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Dumper for UbjsonDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let mut output = vec![];
+        self.write_node(&mut output, &root)?;
+        self.write.write_all(&output)?;
+        Ok(())
+    }
+}
+
+fn write_value(output: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => output.push(b'Z'),
+        Value::Boolean(b) => output.push(if *b { b'T' } else { b'F' }),
+        Value::Integer(i) => write_integer(output, *i),
+        Value::Number(n) => {
+            output.push(b'D');
+            output.extend_from_slice(&n.to_be_bytes());
+        }
+        Value::String(s) => {
+            output.push(b'S');
+            write_string(output, s);
+        }
+        Value::BorrowedString(s) => {
+            output.push(b'S');
+            write_string(output, s);
+        }
+        Value::Bytes(bytes) => {
+            output.extend_from_slice(b"[$U#");
+            write_integer(output, bytes.len() as i64);
+            output.extend_from_slice(bytes);
+        }
+        Value::DateTime(date_time) => {
+            output.push(b'S');
+            write_string(output, &date_time.to_string());
+        }
+    }
+}
+
+fn write_integer(output: &mut Vec<u8>, i: i64) {
+    if let Ok(i) = u8::try_from(i) {
+        output.extend_from_slice(&[b'U', i]);
+    } else if let Ok(i) = i8::try_from(i) {
+        output.extend_from_slice(&[b'i', i as u8]);
+    } else if let Ok(i) = i16::try_from(i) {
+        output.push(b'I');
+        output.extend_from_slice(&i.to_be_bytes());
+    } else if let Ok(i) = i32::try_from(i) {
+        output.push(b'l');
+        output.extend_from_slice(&i.to_be_bytes());
+    } else {
+        output.push(b'L');
+        output.extend_from_slice(&i.to_be_bytes());
+    }
+}
+
+/// Writes the length and content of a string, without the `S` marker
+fn write_string(output: &mut Vec<u8>, s: &str) {
+    write_integer(output, s.len() as i64);
+    output.extend_from_slice(s.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ubjson_dumper::UbjsonDumper;
+    use crate::ubjson_loader::UbjsonLoader;
+    use expect_test::expect;
+    use loadum::datetime::DateTime;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+    use loadum::testing::{emit_events, format_dump, integer_key_document, invalid_documents};
+    use loadum::value::Value;
+
+    fn dump_with(dumper: impl FnOnce(&mut Vec<u8>) -> UbjsonDumper, events: &[Event]) -> String {
+        let mut output = vec![];
+        let result = emit_events(dumper(&mut output), events);
+        format_dump(result, output.escape_ascii())
+    }
+
+    fn dump(events: &[Event]) -> String {
+        dump_with(|output| UbjsonDumper::new(output), events)
+    }
+
+    #[test]
+    fn test_encoding() {
+        let output = dump(&[
+            Event::DocumentStart,
+            Event::ListStart,
+            Event::null(),
+            Event::bool(true),
+            Event::integer(200),
+            Event::integer(-1),
+            Event::integer(1000),
+            Event::integer(-100000),
+            Event::integer(i64::MAX),
+            Event::number(1.5),
+            Event::string("abc"),
+            Event::bytes(&b"\x00\xff"[..]),
+            Event::date_time(DateTime::parse("2024-01-02T03:04:05Z").unwrap()),
+            Event::tag("high-precision"),
+            Event::string("1.5e400"),
+            Event::tag("ignored"),
+            Event::MapStart,
+            Event::map_key("k"),
+            Event::ListStart,
+            Event::ListEnd,
+            Event::MapKey(Value::Integer(1)),
+            Event::null(),
+            Event::MapEnd,
+            Event::ListEnd,
+            Event::DocumentEnd,
+        ]);
+        expect![[r#"[ZTU\xc8i\xffI\x03\xe8l\xff\xfey`L\x7f\xff\xff\xff\xff\xff\xff\xffD?\xf8\x00\x00\x00\x00\x00\x00SU\x03abc[$U#U\x02\x00\xffSU\x142024-01-02T03:04:05ZHU\x071.5e400{U\x01k[]U\x011Z}]"#]].assert_eq(&output);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let events = [
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::map_key("integers"),
+            Event::ListStart,
+            Event::integer(0),
+            Event::integer(255),
+            Event::integer(-128),
+            Event::integer(i16::MIN as i64),
+            Event::integer(i32::MAX as i64),
+            Event::integer(i64::MIN),
+            Event::ListEnd,
+            Event::map_key("values"),
+            Event::ListStart,
+            Event::null(),
+            Event::bool(false),
+            Event::number(0.1),
+            Event::string(""),
+            Event::string("x".repeat(300)),
+            Event::bytes(vec![7u8; 300]),
+            Event::tag("high-precision"),
+            Event::string("3.14159265358979323846"),
+            Event::ListEnd,
+            Event::MapEnd,
+            Event::DocumentEnd,
+            Event::DocumentStart,
+            Event::integer(1),
+            Event::DocumentEnd,
+        ];
+        let mut output = vec![];
+        let mut dumper = UbjsonDumper::new(&mut output);
+        for event in &events {
+            dumper.emit(event).unwrap();
+        }
+        drop(dumper);
+        let loaded: Vec<Event> = UbjsonLoader::new(&output)
+            .map(|event| event.unwrap().into_owned())
+            .collect();
+        let expected: Vec<Event> = events.into_iter().map(Event::into_owned).collect();
+        assert_eq!(loaded, expected);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output = String::new();
+        for events in invalid_documents() {
+            output.push_str(&dump(&events));
+            output.push('\n');
+        }
+        output.push_str(&dump_with(
+            |output| UbjsonDumper::new(output).with_key_policy(KeyPolicy::Reject),
+            &integer_key_document(),
+        ));
+        expect![[r#"
+            Error: UBJSON keys cannot be composite, but found List([])
+            Error: Document is empty
+            Error: UBJSON keys must be strings, but found Scalar(Integer(1))"#]]
+        .assert_eq(&output);
+    }
+}
@@ -0,0 +1,433 @@
+use crate::HIGH_PRECISION_TAG;
+use loadum::byte_input::ByteInput;
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::io::Read;
+
+/// Loads UBJSON, each top level value in the input is loaded as a separate document
+///
+/// No-op markers are skipped and characters are loaded as strings.
+pub struct UbjsonLoader<'source> {
+    input: ByteInput<'source>,
+    state: LoaderState,
+    stack: Vec<Container>,
+    /// Event to return before reading more input
+    pending: Option<Event<'source>>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LoaderState {
+    BetweenDocuments,
+    InDocument,
+    DocumentEnd,
+    Done,
+}
+
+struct Container {
+    is_map: bool,
+    /// True if an object expects a key or its end, rather than a value
+    expects_key: bool,
+    /// Remaining values of a container with a count, not counting keys
+    remaining: Option<u64>,
+    /// Marker of the values of a strongly typed container, which are written without markers
+    value_type: Option<u8>,
+}
+
+impl<'source> UbjsonLoader<'source> {
+    pub fn new(source: &'source [u8]) -> UbjsonLoader<'source> {
+        Self::with_input(ByteInput::from_slice(source))
+    }
+
+    /// Loads UBJSON incrementally from a reader
+    pub fn from_reader(read: impl Read + 'source) -> UbjsonLoader<'source> {
+        Self::with_input(ByteInput::from_reader(read))
+    }
+
+    fn with_input(input: ByteInput<'source>) -> UbjsonLoader<'source> {
+        UbjsonLoader {
+            input,
+            state: LoaderState::BetweenDocuments,
+            stack: vec![],
+            pending: None,
+        }
+    }
+
+    fn load_event(&mut self) -> LoadumResult<Option<Event<'source>>> {
+        if let Some(event) = self.pending.take() {
+            return Ok(Some(event));
+        }
+        match self.state {
+            LoaderState::BetweenDocuments => {
+                self.skip_no_ops()?;
+                if self.input.is_at_end()? {
+                    self.state = LoaderState::Done;
+                    return Ok(None);
+                }
+                self.state = LoaderState::InDocument;
+                return Ok(Some(Event::DocumentStart));
+            }
+            LoaderState::DocumentEnd => {
+                self.state = LoaderState::BetweenDocuments;
+                return Ok(Some(Event::DocumentEnd));
+            }
+            LoaderState::Done => return Ok(None),
+            LoaderState::InDocument => {}
+        }
+        let Some(container) = self.stack.last_mut() else {
+            let marker = self.read_marker()?;
+            return self.read_value(marker).map(Some);
+        };
+        if container.is_map && container.expects_key {
+            if container.remaining.is_none() {
+                self.skip_no_ops()?;
+            }
+            let container = self.stack.last_mut().unwrap();
+            let is_end = match container.remaining {
+                Some(remaining) => remaining == 0,
+                None => self.input.peek_u8()? == Some(b'}'),
+            };
+            if is_end {
+                return Ok(Some(self.end_container()?));
+            }
+            container.expects_key = false;
+            let key = self.read_string()?;
+            return Ok(Some(Event::MapKey(key)));
+        }
+        if container.is_map {
+            container.expects_key = true;
+        }
+        let marker = match (container.value_type, container.remaining) {
+            (_, Some(0)) => return Ok(Some(self.end_container()?)),
+            (Some(value_type), _) => value_type,
+            (None, _) => self.read_marker()?,
+        };
+        let container = self.stack.last_mut().unwrap();
+        match (marker, container.remaining.as_mut()) {
+            (b']', None) if !container.is_map => return Ok(Some(self.end_container()?)),
+            (_, Some(remaining)) => *remaining -= 1,
+            (_, None) => {}
+        }
+        self.read_value(marker).map(Some)
+    }
+
+    fn end_container(&mut self) -> LoadumResult<Event<'source>> {
+        let container = self.stack.pop().unwrap();
+        if container.remaining.is_none() && container.is_map {
+            self.input.read_u8()?;
+        }
+        self.end_value();
+        Ok(if container.is_map {
+            Event::MapEnd
+        } else {
+            Event::ListEnd
+        })
+    }
+
+    /// Ends the document once the top level value is complete
+    fn end_value(&mut self) {
+        if self.stack.is_empty() {
+            self.state = LoaderState::DocumentEnd;
+        }
+    }
+
+    fn skip_no_ops(&mut self) -> LoadumResult<()> {
+        while self.input.peek_u8()? == Some(b'N') {
+            self.input.read_u8()?;
+        }
+        Ok(())
+    }
+
+    /// Reads the marker of a value, skipping no-op markers
+    fn read_marker(&mut self) -> LoadumResult<u8> {
+        self.skip_no_ops()?;
+        self.input.read_u8()
+    }
+
+    fn read_value(&mut self, marker: u8) -> LoadumResult<Event<'source>> {
+        let offset = self.input.offset();
+        let value = match marker {
+            b'Z' => Value::Null,
+            b'T' => Value::Boolean(true),
+            b'F' => Value::Boolean(false),
+            b'd' => Value::Number(f32::from_be_bytes(self.input.read_array()?) as f64),
+            b'D' => Value::Number(f64::from_be_bytes(self.input.read_array()?)),
+            b'i' | b'U' | b'I' | b'l' | b'L' => Value::Integer(self.read_integer(marker)?),
+            b'C' => {
+                let c = self.input.read_u8()?;
+                if !c.is_ascii() {
+                    bail!("Invalid character 0x{:02x} at offset {}", c, offset);
+                }
+                Value::string((c as char).to_string())
+            }
+            b'S' => self.read_string()?,
+            b'H' => {
+                let number = self.read_string()?;
+                self.end_value();
+                self.pending = Some(Event::Literal(number));
+                return Ok(Event::tag(HIGH_PRECISION_TAG));
+            }
+            b'[' | b'{' => return self.start_container(marker == b'{'),
+            _ => bail!(
+                "Invalid UBJSON marker '{}' at offset {}",
+                marker.escape_ascii(),
+                offset - 1
+            ),
+        };
+        self.end_value();
+        Ok(Event::Literal(value))
+    }
+
+    /// Reads the optional type and count of a container, typed arrays of uint8 are read as bytes
+    fn start_container(&mut self, is_map: bool) -> LoadumResult<Event<'source>> {
+        let mut value_type = None;
+        if self.input.peek_u8()? == Some(b'$') {
+            self.input.read_u8()?;
+            value_type = Some(self.input.read_u8()?);
+            if self.input.peek_u8()? != Some(b'#') {
+                bail!(
+                    "Missing count of typed container at offset {}",
+                    self.input.offset()
+                );
+            }
+        }
+        let mut remaining = None;
+        if self.input.peek_u8()? == Some(b'#') {
+            self.input.read_u8()?;
+            let marker = self.read_marker()?;
+            remaining = Some(self.read_length(marker)? as u64);
+        }
+        if let (false, Some(b'U'), Some(length)) = (is_map, value_type, remaining) {
+            let bytes = self.input.read_bytes(length as usize)?;
+            self.end_value();
+            return Ok(Event::Literal(Value::bytes(&*bytes)));
+        }
+        self.stack.push(Container {
+            is_map,
+            expects_key: is_map,
+            remaining,
+            value_type,
+        });
+        Ok(if is_map {
+            Event::MapStart
+        } else {
+            Event::ListStart
+        })
+    }
+
+    fn read_integer(&mut self, marker: u8) -> LoadumResult<i64> {
+        let offset = self.input.offset();
+        Ok(match marker {
+            b'i' => i8::from_be_bytes(self.input.read_array()?) as i64,
+            b'U' => self.input.read_u8()? as i64,
+            b'I' => i16::from_be_bytes(self.input.read_array()?) as i64,
+            b'l' => i32::from_be_bytes(self.input.read_array()?) as i64,
+            b'L' => i64::from_be_bytes(self.input.read_array()?),
+            _ => bail!(
+                "Expected an integer marker, but found '{}' at offset {}",
+                marker.escape_ascii(),
+                offset - 1
+            ),
+        })
+    }
+
+    /// Reads a length, which is an integer with its own marker
+    fn read_length(&mut self, marker: u8) -> LoadumResult<usize> {
+        let offset = self.input.offset();
+        let length = self.read_integer(marker)?;
+        match usize::try_from(length) {
+            Ok(length) => Ok(length),
+            Err(_) => bail!("Invalid length {} at offset {}", length, offset),
+        }
+    }
+
+    /// Reads the length and content of a string, as used for keys and after `S` and `H` markers
+    fn read_string(&mut self) -> LoadumResult<Value<'source>> {
+        let marker = self.input.read_u8()?;
+        let length = self.read_length(marker)?;
+        self.input.read_string(length)
+    }
+}
+
+impl<'source> Loader<'source> for UbjsonLoader<'source> {}
+
+impl<'source> Iterator for UbjsonLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.load_event() {
+            Ok(event) => event.map(Ok),
+            Err(error) => {
+                self.state = LoaderState::Done;
+                self.pending = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ubjson_loader::UbjsonLoader;
+    use expect_test::{Expect, expect};
+    use loadum::dumper::Dumper;
+    use loadum::testing::{collect_events, format_events};
+    use loadum_json::json_dumper::JsonDumper;
+
+    fn test_loader(input: &[u8], expected: Expect) {
+        let events = collect_events(UbjsonLoader::new(input));
+        expected.assert_eq(&format_events(&events));
+        assert_eq!(events, collect_events(UbjsonLoader::from_reader(input)));
+    }
+
+    #[test]
+    fn test_scalars() {
+        test_loader(
+            b"[ZTFNi\xffU\xc8I\x80\x00l\x00\x01\x00\x00L\x80\x00\x00\x00\x00\x00\x00\x00\
+              d\x3f\xc0\x00\x00D\x40\x09\x21\xfb\x54\x44\x2d\x18CaSU\x03abcSi\x00\
+              HU\x1412345678901234567890]",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Null)
+                Literal(Boolean(true))
+                Literal(Boolean(false))
+                Literal(Integer(-1))
+                Literal(Integer(200))
+                Literal(Integer(-32768))
+                Literal(Integer(65536))
+                Literal(Integer(-9223372036854775808))
+                Literal(Number(1.5))
+                Literal(Number(3.141592653589793))
+                Literal(String("a"))
+                Literal(BorrowedString("abc"))
+                Literal(BorrowedString(""))
+                Tag("high-precision")
+                Literal(BorrowedString("12345678901234567890"))
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_containers() {
+        test_loader(
+            b"{U\x01a{}U\x01b[#U\x02i\x01Si\x01xU\x01c[$i#U\x03\x01\x02\x03U\x01d{$Z#i\x01U\x01e\
+              U\x01f[$U#U\x02\x00\xffU\x01g[#i\x00N}",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(BorrowedString("a"))
+                MapStart
+                MapEnd
+                MapKey(BorrowedString("b"))
+                ListStart
+                Literal(Integer(1))
+                Literal(BorrowedString("x"))
+                ListEnd
+                MapKey(BorrowedString("c"))
+                ListStart
+                Literal(Integer(1))
+                Literal(Integer(2))
+                Literal(Integer(3))
+                ListEnd
+                MapKey(BorrowedString("d"))
+                MapStart
+                MapKey(BorrowedString("e"))
+                Literal(Null)
+                MapEnd
+                MapKey(BorrowedString("f"))
+                Literal(Bytes([0, 255]))
+                MapKey(BorrowedString("g"))
+                ListStart
+                ListEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_multiple_documents() {
+        test_loader(
+            b"NU\x01N[]",
+            expect![[r#"
+                DocumentStart
+                Literal(Integer(1))
+                DocumentEnd
+                DocumentStart
+                ListStart
+                ListEnd
+                DocumentEnd
+            "#]],
+        );
+        test_loader(b"N", expect![""]);
+    }
+
+    #[test]
+    fn test_errors() {
+        test_loader(
+            b"[U\x01",
+            expect![[r#"
+                DocumentStart
+                ListStart
+                Literal(Integer(1))
+                Error: Unexpected end of input at offset 3
+            "#]],
+        );
+        test_loader(
+            b"x",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid UBJSON marker 'x' at offset 0
+            "#]],
+        );
+        test_loader(
+            b"Si\xff",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid length -1 at offset 2
+            "#]],
+        );
+        test_loader(
+            b"{S\x01a}",
+            expect![[r#"
+                DocumentStart
+                MapStart
+                Error: Expected an integer marker, but found 'S' at offset 1
+            "#]],
+        );
+        test_loader(
+            b"[$U]",
+            expect![[r#"
+                DocumentStart
+                Error: Missing count of typed container at offset 3
+            "#]],
+        );
+        test_loader(
+            b"C\xe9",
+            expect![[r#"
+                DocumentStart
+                Error: Invalid character 0xe9 at offset 1
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_convert_to_json() {
+        let mut output = vec![];
+        let mut dumper = JsonDumper::new(&mut output);
+        for event in UbjsonLoader::new(b"{U\x02idU\x2aU\x04data[$U#U\x01\x01}") {
+            dumper.emit(&event.unwrap()).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            {
+            	"id": 42,
+            	"data": "AQ=="
+            }"#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,29 @@
+pub mod xml_dumper;
+pub mod xml_loader;
+
+/// How elements, attributes and text are mapped to maps, lists and strings
+///
+/// In all conventions the document is a map with the root element as its only key, and repeated
+/// child elements are collected in a list under their shared name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Convention {
+    /// Attributes are keys prefixed with `@`, text is under `#text`
+    ///
+    /// Elements with only text are loaded as strings and empty elements as null.
+    #[default]
+    Prefixed,
+    /// BadgerFish, elements are always maps, attributes are prefixed with `@` and text is under `$`
+    BadgerFish,
+    /// Child elements are keys, elements with only text are strings and attributes are ignored
+    ElementAsKey,
+}
+
+impl Convention {
+    pub(crate) fn text_key(self) -> Option<&'static str> {
+        match self {
+            Convention::Prefixed => Some("#text"),
+            Convention::BadgerFish => Some("$"),
+            Convention::ElementAsKey => None,
+        }
+    }
+}
@@ -0,0 +1,466 @@
+use crate::Convention;
+use crate::xml_loader::is_name;
+use loadum::depth::check_depth;
+use loadum::dumper::{Dumper, KeyPolicy};
+use loadum::error::bail;
+use loadum::event::Event;
+use loadum::node::{Node, NodeBuilder};
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// Dumps XML 1.0 documents, mapping maps to elements according to a [`Convention`]
+///
+/// Each document must be a map with a single key, the root element. Lists are written as
+/// repeated elements, so the document is collected and written once it is complete.
+pub struct XmlDumper<'write> {
+    write: Box<dyn Write + 'write>,
+    builder: NodeBuilder<'static>,
+    convention: Convention,
+    key_policy: KeyPolicy,
+}
+
+impl<'write> XmlDumper<'write> {
+    pub fn new(write: impl Write + 'write) -> XmlDumper<'write> {
+        XmlDumper {
+            write: Box::new(write),
+            builder: NodeBuilder::default(),
+            convention: Convention::default(),
+            key_policy: KeyPolicy::Stringify,
+        }
+    }
+
+    /// Sets how maps are mapped to elements, by default [`Convention::Prefixed`]
+    pub fn with_convention(mut self, convention: Convention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Sets how keys that are not strings are handled, by default they are stringified
+    ///
+    /// Composite keys cannot be used as element names and always fail.
+    pub fn with_key_policy(mut self, key_policy: KeyPolicy) -> Self {
+        self.key_policy = key_policy;
+        self
+    }
+}
+
+impl Dumper for XmlDumper<'_> {
+    fn emit(&mut self, event: &Event) -> LoadumResult<()> {
+        let Some(root) = self.builder.buffer(event)? else {
+            return Ok(());
+        };
+        let Node::Map(entries) = root.untagged() else {
+            bail!("XML documents must be maps with the root element as their only key");
+        };
+        let [(name, element)] = entries.as_slice() else {
+            bail!(
+                "XML documents must have a single root element, but found {} keys",
+                entries.len()
+            );
+        };
+        let mut formatter = XmlFormatter {
+            output: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+            convention: self.convention,
+            key_policy: self.key_policy,
+        };
+        let name = formatter.element_name(name)?;
+        if let Node::List(_) = element.untagged() {
+            bail!(
+                "XML documents must have a single root element, but <{}> is a list",
+                name
+            );
+        }
+        formatter.write_element(&name, element, 0)?;
+        self.write.write_all(formatter.output.as_bytes())?;
+        Ok(())
+    }
+}
+
+struct XmlFormatter {
+    output: String,
+    convention: Convention,
+    key_policy: KeyPolicy,
+}
+
+impl XmlFormatter {
+    fn write_element(&mut self, name: &str, node: &Node, depth: usize) -> LoadumResult<()> {
+        check_depth(depth)?;
+        self.write_indent(depth);
+        self.output.push('<');
+        self.output.push_str(name);
+        let entries = match node.untagged() {
+            Node::Scalar(Value::Null) => {
+                self.output.push_str("/>\n");
+                return Ok(());
+            }
+            Node::Scalar(value) => {
+                self.output.push('>');
+                self.write_text(value, false)?;
+                self.output.push_str(&format!("</{}>\n", name));
+                return Ok(());
+            }
+            Node::List(_) => bail!("XML cannot represent nested lists in <{}>", name),
+            Node::Map(entries) => entries,
+            Node::Tagged(..) => unreachable!("Tags were removed"),
+        };
+        let mut text = None;
+        let mut children = vec![];
+        for (key, value) in entries {
+            let key = self.key_policy.key_string(key, "XML element names")?;
+            if Some(key.as_ref()) == self.convention.text_key() {
+                let Node::Scalar(value) = value.untagged() else {
+                    bail!("Text of <{}> must be a scalar", name);
+                };
+                text = Some(value);
+                continue;
+            }
+            let attribute = match self.convention {
+                Convention::ElementAsKey => None,
+                _ => key.strip_prefix('@'),
+            };
+            let Some(attribute) = attribute else {
+                children.push((self.validate_name(key)?, value));
+                continue;
+            };
+            let Node::Scalar(value) = value.untagged() else {
+                bail!("Attribute '{}' of <{}> must be a scalar", attribute, name);
+            };
+            let attribute = self.validate_name(Cow::Borrowed(attribute))?;
+            self.output.push(' ');
+            self.output.push_str(&attribute);
+            self.output.push_str("=\"");
+            self.write_text(value, true)?;
+            self.output.push('"');
+        }
+        if children.is_empty() {
+            match text {
+                Some(text) => {
+                    self.output.push('>');
+                    self.write_text(text, false)?;
+                    self.output.push_str(&format!("</{}>\n", name));
+                }
+                None => self.output.push_str("/>\n"),
+            }
+            return Ok(());
+        }
+        self.output.push_str(">\n");
+        if let Some(text) = text {
+            self.write_indent(depth + 1);
+            self.write_text(text, false)?;
+            self.output.push('\n');
+        }
+        for (child, value) in children {
+            match value.untagged() {
+                Node::List(items) => {
+                    for item in items {
+                        self.write_element(&child, item, depth + 1)?;
+                    }
+                }
+                _ => self.write_element(&child, value, depth + 1)?,
+            }
+        }
+        self.write_indent(depth);
+        self.output.push_str(&format!("</{}>\n", name));
+        Ok(())
+    }
+
+    fn write_text(&mut self, value: &Value, is_attribute: bool) -> LoadumResult<()> {
+        let text = match value {
+            Value::Null => return Ok(()),
+            Value::String(s) => Cow::Borrowed(s.as_str()),
+            Value::BorrowedString(s) => Cow::Borrowed(*s),
+            value => Cow::Owned(value.to_string()),
+        };
+        for c in text.chars() {
+            match c {
+                '&' => self.output.push_str("&amp;"),
+                '<' => self.output.push_str("&lt;"),
+                '>' => self.output.push_str("&gt;"),
+                '"' if is_attribute => self.output.push_str("&quot;"),
+                // Whitespace in attributes is normalized by parsers unless it is escaped
+                '\t' | '\n' | '\r' if is_attribute => {
+                    self.output.push_str(&format!("&#{};", c as u32))
+                }
+                '\t' | '\n' | '\r' => self.output.push(c),
+                c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => {
+                    bail!("Character U+{:04X} cannot be represented in XML", c as u32);
+                }
+                c => self.output.push(c),
+            }
+        }
+        Ok(())
+    }
+
+    fn element_name<'node>(&self, key: &'node Node) -> LoadumResult<Cow<'node, str>> {
+        let key = self.key_policy.key_string(key, "XML element names")?;
+        self.validate_name(key)
+    }
+
+    fn validate_name<'node>(&self, name: Cow<'node, str>) -> LoadumResult<Cow<'node, str>> {
+        if !is_name(&name) {
+            bail!("'{}' is not a valid XML name", name);
+        }
+        Ok(name)
+    }
+
+    fn write_indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.output.push_str("  ");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Convention;
+    use crate::xml_dumper::XmlDumper;
+    use crate::xml_loader::XmlLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use loadum::dumper::{Dumper, KeyPolicy};
+    use loadum::event::Event;
+
+    fn dump(events: &[Event], convention: Convention) -> String {
+        let mut output = vec![];
+        let mut dumper = XmlDumper::new(&mut output).with_convention(convention);
+        let mut result = Ok(());
+        for event in [Event::DocumentStart]
+            .iter()
+            .chain(events)
+            .chain([&Event::DocumentEnd])
+        {
+            result = dumper.emit(event);
+            if result.is_err() {
+                break;
+            }
+        }
+        drop(dumper);
+        let mut output = String::from_utf8(output).unwrap();
+        if let Err(error) = result {
+            output.push_str(&format!("Error: {}\n", error));
+        }
+        output
+    }
+
+    fn test_dump(events: &[Event], convention: Convention, expected: Expect) {
+        expected.assert_eq(&dump(events, convention));
+    }
+
+    #[test]
+    fn test_prefixed() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("order"),
+                Event::MapStart,
+                Event::map_key("@id"),
+                Event::integer(42),
+                Event::map_key("@note"),
+                Event::string("\"quoted\"\n<&>"),
+                Event::map_key("item"),
+                Event::ListStart,
+                Event::string("a < b"),
+                Event::MapStart,
+                Event::map_key("@sku"),
+                Event::string("x"),
+                Event::map_key("#text"),
+                Event::bool(true),
+                Event::MapEnd,
+                Event::null(),
+                Event::ListEnd,
+                Event::tag("ignored"),
+                Event::map_key("empty"),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            Convention::Prefixed,
+            expect![[r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <order id="42" note="&quot;quoted&quot;&#10;&lt;&amp;&gt;">
+                  <item>a &lt; b</item>
+                  <item sku="x">true</item>
+                  <item/>
+                  <empty/>
+                </order>
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_badgerfish() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("p"),
+                Event::MapStart,
+                Event::map_key("@lang"),
+                Event::string("en"),
+                Event::map_key("$"),
+                Event::string("Hello"),
+                Event::map_key("b"),
+                Event::MapStart,
+                Event::map_key("$"),
+                Event::string("world"),
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            Convention::BadgerFish,
+            expect![[r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <p lang="en">
+                  Hello
+                  <b>world</b>
+                </p>
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_element_as_key() {
+        test_dump(
+            &[
+                Event::MapStart,
+                Event::map_key("config"),
+                Event::MapStart,
+                Event::map_key("name"),
+                Event::string("demo"),
+                Event::map_key("port"),
+                Event::integer(80),
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            Convention::ElementAsKey,
+            expect![[r#"
+                <?xml version="1.0" encoding="UTF-8"?>
+                <config>
+                  <name>demo</name>
+                  <port>80</port>
+                </config>
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut deep = vec![];
+        for _ in 0..=MAX_DEPTH {
+            deep.extend([Event::MapStart, Event::map_key("a")]);
+        }
+        deep.push(Event::null());
+        deep.extend((0..=MAX_DEPTH).map(|_| Event::MapEnd));
+        let output: String = [
+            vec![Event::string("text")],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::null(),
+                Event::map_key("b"),
+                Event::null(),
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::ListStart,
+                Event::ListEnd,
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::MapStart,
+                Event::map_key("b"),
+                Event::ListStart,
+                Event::ListStart,
+                Event::ListEnd,
+                Event::ListEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::MapStart,
+                Event::map_key("@b"),
+                Event::MapStart,
+                Event::MapEnd,
+                Event::MapEnd,
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a b"),
+                Event::null(),
+                Event::MapEnd,
+            ],
+            vec![
+                Event::MapStart,
+                Event::map_key("a"),
+                Event::string("bell \u{7}"),
+                Event::MapEnd,
+            ],
+            deep,
+        ]
+        .iter()
+        .map(|events| dump(events, Convention::Prefixed))
+        .collect();
+        expect![[r#"
+            Error: XML documents must be maps with the root element as their only key
+            Error: XML documents must have a single root element, but found 2 keys
+            Error: XML documents must have a single root element, but <a> is a list
+            Error: XML cannot represent nested lists in <b>
+            Error: Attribute 'b' of <a> must be a scalar
+            Error: 'a b' is not a valid XML name
+            Error: Character U+0007 cannot be represented in XML
+            Error: Document is nested deeper than 128 levels
+        "#]]
+        .assert_eq(&output);
+    }
+
+    #[test]
+    fn test_tagged_keys() {
+        let mut output = vec![];
+        let mut dumper = XmlDumper::new(&mut output).with_key_policy(KeyPolicy::Reject);
+        for event in [
+            Event::DocumentStart,
+            Event::MapStart,
+            Event::tag("name"),
+            Event::map_key("a"),
+            Event::string("b"),
+            Event::MapEnd,
+            Event::DocumentEnd,
+        ] {
+            dumper.emit(&event).unwrap();
+        }
+        drop(dumper);
+        expect![[r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <a>b</a>
+        "#]]
+        .assert_eq(&String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for convention in [
+            Convention::Prefixed,
+            Convention::BadgerFish,
+            Convention::ElementAsKey,
+        ] {
+            let input = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                <a>\n  <b>1 &amp; 2</b>\n  <b>3</b>\n  <c>\n    <d/>\n  </c>\n</a>\n";
+            let mut output = vec![];
+            let mut dumper = XmlDumper::new(&mut output).with_convention(convention);
+            for event in XmlLoader::new(input).with_convention(convention) {
+                dumper.emit(&event.unwrap()).unwrap();
+            }
+            drop(dumper);
+            assert_eq!(String::from_utf8(output).unwrap(), input);
+        }
+    }
+}
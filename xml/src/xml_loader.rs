@@ -0,0 +1,620 @@
+use crate::Convention;
+use loadum::LoadumString;
+use loadum::depth::check_depth;
+use loadum::error::{LoadumError, format_err};
+use loadum::event::Event;
+use loadum::loader::Loader;
+use loadum::node::Node;
+use loadum::result::LoadumResult;
+use loadum::value::Value;
+use std::borrow::Cow;
+use std::fmt::Display;
+
+/// Loads XML 1.0 documents, mapping elements to maps according to a [`Convention`]
+///
+/// Repeated child elements can only be recognized once their parent is complete, so the document
+/// is parsed before the first event is returned. All values are strings, text is trimmed and
+/// comments, processing instructions and the document type declaration are skipped.
+pub struct XmlLoader<'source> {
+    source: &'source str,
+    convention: Convention,
+    events: Option<std::vec::IntoIter<Event<'source>>>,
+}
+
+impl<'source> XmlLoader<'source> {
+    pub fn new(source: &'source str) -> XmlLoader<'source> {
+        XmlLoader {
+            source,
+            convention: Convention::default(),
+            events: None,
+        }
+    }
+
+    /// Sets how elements, attributes and text are mapped, by default [`Convention::Prefixed`]
+    pub fn with_convention(mut self, convention: Convention) -> Self {
+        self.convention = convention;
+        self
+    }
+}
+
+impl<'source> Loader<'source> for XmlLoader<'source> {}
+
+impl<'source> Iterator for XmlLoader<'source> {
+    type Item = LoadumResult<Event<'source>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.events.is_none() {
+            let mut parser = Parser {
+                bytes: self.source.as_bytes(),
+                source: self.source,
+                position: 0,
+            };
+            match parser.parse_document() {
+                Ok(root) => {
+                    let root = Node::Map(vec![(
+                        Node::Scalar(Value::BorrowedString(root.name)),
+                        element_node(root, self.convention),
+                    )]);
+                    let mut events = vec![Event::DocumentStart];
+                    root.to_events(&mut events);
+                    events.push(Event::DocumentEnd);
+                    self.events = Some(events.into_iter());
+                }
+                Err(error) => {
+                    self.events = Some(vec![].into_iter());
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.events.as_mut()?.next().map(Ok)
+    }
+}
+
+struct Element<'source> {
+    name: &'source str,
+    attributes: Vec<(&'source str, Cow<'source, str>)>,
+    children: Vec<Element<'source>>,
+    /// Text and CDATA sections, in document order
+    text: Vec<Cow<'source, str>>,
+}
+
+impl<'source> Element<'source> {
+    /// Returns the text content with leading and trailing whitespace removed
+    fn text(&mut self) -> Option<Value<'source>> {
+        let text = match self.text.len() {
+            0 => return None,
+            1 => self.text.pop().unwrap(),
+            _ => Cow::Owned(self.text.concat()),
+        };
+        let value = match text {
+            Cow::Borrowed(text) => Value::BorrowedString(text.trim()),
+            Cow::Owned(text) => Value::String(LoadumString::from(text.trim())),
+        };
+        (value.as_str() != Some("")).then_some(value)
+    }
+}
+
+fn element_node(mut element: Element, convention: Convention) -> Node {
+    let text = element.text();
+    if element.children.is_empty()
+        && (element.attributes.is_empty() || convention == Convention::ElementAsKey)
+        && convention != Convention::BadgerFish
+    {
+        return Node::Scalar(text.unwrap_or(Value::Null));
+    }
+    let mut entries = vec![];
+    if convention != Convention::ElementAsKey {
+        for (name, value) in element.attributes {
+            let value = match value {
+                Cow::Borrowed(value) => Value::BorrowedString(value),
+                Cow::Owned(value) => Value::String(value.into()),
+            };
+            entries.push((
+                Node::Scalar(Value::String(format!("@{}", name).into())),
+                Node::Scalar(value),
+            ));
+        }
+    }
+    let mut children: Vec<(&str, Vec<Node>)> = vec![];
+    for child in element.children {
+        let name = child.name;
+        let node = element_node(child, convention);
+        match children
+            .iter_mut()
+            .find(|(child_name, _)| *child_name == name)
+        {
+            Some((_, nodes)) => nodes.push(node),
+            None => children.push((name, vec![node])),
+        }
+    }
+    for (name, mut nodes) in children {
+        let node = if nodes.len() == 1 {
+            nodes.pop().unwrap()
+        } else {
+            Node::List(nodes)
+        };
+        entries.push((Node::Scalar(Value::BorrowedString(name)), node));
+    }
+    if let (Some(text), Some(text_key)) = (text, convention.text_key()) {
+        entries.push((
+            Node::Scalar(Value::BorrowedString(text_key)),
+            Node::Scalar(text),
+        ));
+    }
+    Node::Map(entries)
+}
+
+struct Parser<'source> {
+    source: &'source str,
+    bytes: &'source [u8],
+    position: usize,
+}
+
+impl<'source> Parser<'source> {
+    fn parse_document(&mut self) -> LoadumResult<Element<'source>> {
+        if self.source.starts_with('\u{feff}') {
+            self.position = '\u{feff}'.len_utf8();
+        }
+        self.skip_misc(true)?;
+        if !self.starts_with("<") {
+            return Err(self.error("Expected root element"));
+        }
+        let root = self.parse_element(0)?;
+        self.skip_misc(false)?;
+        if self.position < self.bytes.len() {
+            return Err(self.error("Unexpected content after the root element"));
+        }
+        Ok(root)
+    }
+
+    /// Skips whitespace, comments, processing instructions and, in the prolog, the doctype
+    fn skip_misc(&mut self, in_prolog: bool) -> LoadumResult<()> {
+        loop {
+            self.skip_whitespace();
+            if self.starts_with("<?") {
+                self.skip_past("?>", "Unterminated processing instruction")?;
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->", "Unterminated comment")?;
+            } else if in_prolog && self.starts_with("<!DOCTYPE") {
+                self.skip_doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_doctype(&mut self) -> LoadumResult<()> {
+        let start = self.position;
+        // The internal subset in brackets may contain '>' in its declarations
+        let mut depth = 0;
+        while let Some(c) = self.bytes.get(self.position) {
+            self.position += 1;
+            match c {
+                b'[' => depth += 1,
+                b']' => depth -= 1,
+                b'>' if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+        Err(self.error_at(start, "Unterminated document type declaration"))
+    }
+
+    fn parse_element(&mut self, depth: usize) -> LoadumResult<Element<'source>> {
+        let start = self.position;
+        check_depth(depth).map_err(|error| self.error(error))?;
+        // Skip '<'
+        self.position += 1;
+        let name = self.parse_name("element")?;
+        let mut element = Element {
+            name,
+            attributes: vec![],
+            children: vec![],
+            text: vec![],
+        };
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            if self.starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            }
+            if self.starts_with(">") {
+                self.position += 1;
+                break;
+            }
+            if !had_whitespace {
+                return Err(self.error("Expected whitespace, '>' or '/>'"));
+            }
+            let attribute_start = self.position;
+            let attribute = self.parse_name("attribute")?;
+            if element
+                .attributes
+                .iter()
+                .any(|(name, _)| *name == attribute)
+            {
+                return Err(self.error_at(
+                    attribute_start,
+                    format_args!("Duplicate attribute '{}'", attribute),
+                ));
+            }
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let value = self.parse_attribute_value()?;
+            element.attributes.push((attribute, value));
+        }
+        loop {
+            let text_start = self.position;
+            while self.bytes.get(self.position).is_some_and(|c| *c != b'<') {
+                self.position += 1;
+            }
+            if self.position > text_start {
+                element.text.push(self.unescape(text_start, self.position)?);
+            }
+            if self.position >= self.bytes.len() {
+                return Err(self.error_at(start, format_args!("Unclosed element <{}>", name)));
+            }
+            if self.starts_with("</") {
+                let closing_start = self.position;
+                self.position += 2;
+                let closing = self.parse_name("element")?;
+                if closing != name {
+                    return Err(self.error_at(
+                        closing_start,
+                        format_args!("Closing tag </{}> does not match <{}>", closing, name),
+                    ));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(element);
+            } else if self.starts_with("<!--") {
+                self.skip_past("-->", "Unterminated comment")?;
+            } else if self.starts_with("<![CDATA[") {
+                let content_start = self.position + "<![CDATA[".len();
+                self.skip_past("]]>", "Unterminated CDATA section")?;
+                let content = &self.source[content_start..self.position - "]]>".len()];
+                element.text.push(Cow::Borrowed(content));
+            } else if self.starts_with("<?") {
+                self.skip_past("?>", "Unterminated processing instruction")?;
+            } else {
+                element.children.push(self.parse_element(depth + 1)?);
+            }
+        }
+    }
+
+    fn parse_name(&mut self, kind: &str) -> LoadumResult<&'source str> {
+        let start = self.position;
+        let mut chars = self.source[start..].char_indices();
+        match chars.next() {
+            Some((_, c)) if is_name_start(c) => {}
+            _ => return Err(self.error(format_args!("Expected {} name", kind))),
+        }
+        let length = chars
+            .find(|(_, c)| !is_name_char(*c))
+            .map_or(self.source.len() - start, |(index, _)| index);
+        self.position += length;
+        Ok(&self.source[start..self.position])
+    }
+
+    fn parse_attribute_value(&mut self) -> LoadumResult<Cow<'source, str>> {
+        let start = self.position;
+        let quote = match self.bytes.get(self.position) {
+            Some(quote @ (b'"' | b'\'')) => *quote,
+            _ => return Err(self.error("Expected quoted attribute value")),
+        };
+        self.position += 1;
+        let value_start = self.position;
+        loop {
+            match self.bytes.get(self.position) {
+                None => return Err(self.error_at(start, "Unterminated attribute value")),
+                Some(b'<') => return Err(self.error("Unexpected '<' in attribute value")),
+                Some(c) if *c == quote => break,
+                Some(_) => self.position += 1,
+            }
+        }
+        let value = self.unescape(value_start, self.position)?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    /// Replaces entity and character references, borrowing the text if there are none
+    fn unescape(&self, start: usize, end: usize) -> LoadumResult<Cow<'source, str>> {
+        let text = &self.source[start..end];
+        if !text.contains('&') {
+            return Ok(Cow::Borrowed(text));
+        }
+        let mut output = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(index) = rest.find('&') {
+            output.push_str(&rest[..index]);
+            let reference_start = end - rest.len() + index;
+            let Some(length) = rest[index..].find(';') else {
+                return Err(self.error_at(reference_start, "Unterminated entity reference"));
+            };
+            let reference = &rest[index + 1..index + length];
+            let c = match reference {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => {
+                    let code = if let Some(hex) = reference.strip_prefix("#x") {
+                        u32::from_str_radix(hex, 16).ok()
+                    } else if let Some(decimal) = reference.strip_prefix('#') {
+                        decimal.parse().ok()
+                    } else {
+                        None
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            let Some(c) = c else {
+                return Err(self.error_at(
+                    reference_start,
+                    format_args!("Unknown entity '&{};'", reference),
+                ));
+            };
+            output.push(c);
+            rest = &rest[index + length + 1..];
+        }
+        output.push_str(rest);
+        Ok(Cow::Owned(output))
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|c| matches!(c, b' ' | b'\t' | b'\r' | b'\n'))
+        {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    /// Skips to just after the terminator
+    fn skip_past(&mut self, terminator: &str, message: &str) -> LoadumResult<()> {
+        match self.source[self.position..].find(terminator) {
+            Some(index) => {
+                self.position += index + terminator.len();
+                Ok(())
+            }
+            None => Err(self.error(message)),
+        }
+    }
+
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.bytes[self.position..].starts_with(prefix.as_bytes())
+    }
+
+    fn expect(&mut self, expected: &str) -> LoadumResult<()> {
+        if !self.starts_with(expected) {
+            return Err(self.error(format_args!("Expected '{}'", expected)));
+        }
+        self.position += expected.len();
+        Ok(())
+    }
+
+    fn error(&self, message: impl Display) -> LoadumError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: impl Display) -> LoadumError {
+        let before = &self.bytes[..position.min(self.bytes.len())];
+        let line = before.iter().filter(|c| **c == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|c| *c == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        format_err!("{} at line {}, column {}", message, line, column)
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == ':' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_start(c) || matches!(c, '0'..='9' | '-' | '.')
+}
+
+pub(crate) fn is_name(name: &str) -> bool {
+    name.starts_with(is_name_start) && name.chars().all(is_name_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Convention;
+    use crate::xml_loader::XmlLoader;
+    use expect_test::{Expect, expect};
+    use loadum::depth::MAX_DEPTH;
+    use std::fmt::Write;
+
+    fn load(input: &str, convention: Convention) -> String {
+        let mut output = String::new();
+        for event in XmlLoader::new(input).with_convention(convention) {
+            match event {
+                Ok(event) => writeln!(output, "{:?}", event.into_owned()).unwrap(),
+                Err(error) => writeln!(output, "Error: {}", error).unwrap(),
+            }
+        }
+        output
+    }
+
+    fn test_loader(input: &str, convention: Convention, expected: Expect) {
+        expected.assert_eq(&load(input, convention));
+    }
+
+    const ORDER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE order [ <!ELEMENT order ANY> ]>
+<!-- an order -->
+<order id="42" status='new'>
+  <item sku="a1">Widget &amp; gadget</item>
+  <item>Spring</item>
+  <note><![CDATA[<fragile>]]></note>
+  <empty/>
+</order>
+"#;
+
+    #[test]
+    fn test_prefixed() {
+        test_loader(
+            ORDER,
+            Convention::Prefixed,
+            expect![[r##"
+                DocumentStart
+                MapStart
+                MapKey(String("order"))
+                MapStart
+                MapKey(String("@id"))
+                Literal(String("42"))
+                MapKey(String("@status"))
+                Literal(String("new"))
+                MapKey(String("item"))
+                ListStart
+                MapStart
+                MapKey(String("@sku"))
+                Literal(String("a1"))
+                MapKey(String("#text"))
+                Literal(String("Widget & gadget"))
+                MapEnd
+                Literal(String("Spring"))
+                ListEnd
+                MapKey(String("note"))
+                Literal(String("<fragile>"))
+                MapKey(String("empty"))
+                Literal(Null)
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "##]],
+        );
+    }
+
+    #[test]
+    fn test_badgerfish() {
+        test_loader(
+            ORDER,
+            Convention::BadgerFish,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("order"))
+                MapStart
+                MapKey(String("@id"))
+                Literal(String("42"))
+                MapKey(String("@status"))
+                Literal(String("new"))
+                MapKey(String("item"))
+                ListStart
+                MapStart
+                MapKey(String("@sku"))
+                Literal(String("a1"))
+                MapKey(String("$"))
+                Literal(String("Widget & gadget"))
+                MapEnd
+                MapStart
+                MapKey(String("$"))
+                Literal(String("Spring"))
+                MapEnd
+                ListEnd
+                MapKey(String("note"))
+                MapStart
+                MapKey(String("$"))
+                Literal(String("<fragile>"))
+                MapEnd
+                MapKey(String("empty"))
+                MapStart
+                MapEnd
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_element_as_key() {
+        test_loader(
+            ORDER,
+            Convention::ElementAsKey,
+            expect![[r#"
+                DocumentStart
+                MapStart
+                MapKey(String("order"))
+                MapStart
+                MapKey(String("item"))
+                ListStart
+                Literal(String("Widget & gadget"))
+                Literal(String("Spring"))
+                ListEnd
+                MapKey(String("note"))
+                Literal(String("<fragile>"))
+                MapKey(String("empty"))
+                Literal(Null)
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_mixed_content() {
+        test_loader(
+            "<p lang=\"en\">Hello <b>big</b> world &#x263A;&#33;</p>",
+            Convention::Prefixed,
+            expect![[r##"
+                DocumentStart
+                MapStart
+                MapKey(String("p"))
+                MapStart
+                MapKey(String("@lang"))
+                Literal(String("en"))
+                MapKey(String("b"))
+                Literal(String("big"))
+                MapKey(String("#text"))
+                Literal(String("Hello  world ☺!"))
+                MapEnd
+                MapEnd
+                DocumentEnd
+            "##]],
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut output: String = [
+            "",
+            "text",
+            "<a>",
+            "<a></b>",
+            "<a x='1' x='2'/>",
+            "<a x=1/>",
+            "<a>&nbsp;</a>",
+            "<a/><b/>",
+            "<a><!-- open</a>",
+        ]
+        .into_iter()
+        .map(|input| load(input, Convention::Prefixed))
+        .collect();
+        output.push_str(&load(&"<a>".repeat(MAX_DEPTH + 1), Convention::Prefixed));
+        expect![[r#"
+            Error: Expected root element at line 1, column 1
+            Error: Expected root element at line 1, column 1
+            Error: Unclosed element <a> at line 1, column 1
+            Error: Closing tag </b> does not match <a> at line 1, column 4
+            Error: Duplicate attribute 'x' at line 1, column 10
+            Error: Expected quoted attribute value at line 1, column 6
+            Error: Unknown entity '&nbsp;' at line 1, column 4
+            Error: Unexpected content after the root element at line 1, column 5
+            Error: Unterminated comment at line 1, column 4
+            Error: Document is nested deeper than 128 levels at line 1, column 385
+        "#]]
+        .assert_eq(&output);
+    }
+}
@@ -1,4 +1,8 @@
+use loadum::error::bail;
+use loadum::read_buffer::ReadBuffer;
 use loadum::result::LoadumResult;
+use std::borrow::Cow;
+use std::io::Read;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Token {
@@ -9,17 +13,24 @@ pub enum Token {
     EOF,
 }
 
-#[derive(Debug)]
 pub struct Tokenizer<'source> {
-    scanner: unscanny::Scanner<'source>,
+    input: Input<'source>,
+    /// Text of the current token
+    text: Cow<'source, str>,
     start: usize,
     end: usize,
     current: Token,
 }
 
+enum Input<'source> {
+    Str(unscanny::Scanner<'source>),
+    /// Only the current token is kept in memory, it is consumed from the buffer
+    Read(ReadBuffer<Box<dyn Read + 'source>>),
+}
+
 impl<'source> Tokenizer<'source> {
-    pub fn current_str(&self) -> &'source str {
-        self.scanner.get(self.start..self.end)
+    pub fn current_str(&self) -> &str {
+        &self.text
     }
     pub fn start(&self) -> usize {
         self.start
@@ -31,8 +42,18 @@ impl<'source> Tokenizer<'source> {
 
 impl<'source> Tokenizer<'source> {
     pub fn new(source: &'source str) -> Self {
+        Self::with_input(Input::Str(unscanny::Scanner::new(source)))
+    }
+
+    /// Tokenizes incrementally from a reader, only buffering the input of the current token
+    pub fn from_reader(read: impl Read + 'source) -> Self {
+        Self::with_input(Input::Read(ReadBuffer::new(Box::new(read))))
+    }
+
+    fn with_input(input: Input<'source>) -> Self {
         Self {
-            scanner: unscanny::Scanner::new(source),
+            input,
+            text: Cow::Borrowed(""),
             start: 0,
             end: 0,
             current: Token::Initial,
@@ -46,42 +67,141 @@ impl Tokenizer<'_> {
     }
 
     pub fn advance(&mut self) -> LoadumResult<()> {
-        self.scanner.eat_whitespace();
-        self.start = self.scanner.cursor();
-        let Some(c) = self.scanner.eat() else {
-            self.current = Token::EOF;
-            return Ok(());
-        };
-        match c {
-            '"' => {
-                self.current = Token::StringDoubleQuoted;
-                self.scanner.eat_until('\"');
-                self.scanner.expect('\"');
+        match &mut self.input {
+            Input::Str(scanner) => {
+                scanner.eat_whitespace();
+                self.start = scanner.cursor();
+                self.current = match scanner.eat() {
+                    None => Token::EOF,
+                    Some('"') => {
+                        scanner.eat_until('\"');
+                        scanner.expect('\"');
+                        Token::StringDoubleQuoted
+                    }
+                    Some('\'') => {
+                        scanner.eat_until('\'');
+                        scanner.expect('\'');
+                        Token::StringSingleQuoted
+                    }
+                    Some(_) => {
+                        scanner.eat_until(": ");
+                        Token::StringPlain
+                    }
+                };
+                self.end = scanner.cursor();
+                self.text = Cow::Borrowed(scanner.from(self.start));
             }
-            '\'' => {
-                self.current = Token::StringSingleQuoted;
-                self.scanner.eat_until('\'');
-                self.scanner.expect('\'');
-            }
-            _ => {
-                self.current = Token::StringPlain;
-                self.scanner.eat_until(": ");
+            Input::Read(buffer) => {
+                let (token, text) = read_token(buffer)?;
+                self.current = token;
+                self.end = buffer.offset() as usize;
+                self.start = self.end - text.len();
+                self.text = Cow::Owned(text);
             }
         }
-        self.end = self.scanner.cursor();
         Ok(())
     }
 }
 
+/// Skips whitespace and reads the next token, consuming it from the buffer
+fn read_token<R: Read>(buffer: &mut ReadBuffer<R>) -> LoadumResult<(Token, String)> {
+    let first = loop {
+        let data = buffer.data();
+        let Some(&first) = data.first() else {
+            if buffer.fill()? {
+                continue;
+            }
+            return Ok((Token::EOF, String::new()));
+        };
+        let width = utf8_width(first);
+        if data.len() < width && !buffer.is_eof() {
+            buffer.fill()?;
+            continue;
+        }
+        let Some(c) = data
+            .get(..width)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .and_then(|c| c.chars().next())
+        else {
+            bail!("Invalid UTF-8 at offset {}", buffer.offset());
+        };
+        if !c.is_whitespace() {
+            break first;
+        }
+        buffer.consume(width);
+    };
+    let (token, terminator): (Token, &[u8]) = match first {
+        b'"' => (Token::StringDoubleQuoted, b"\""),
+        b'\'' => (Token::StringSingleQuoted, b"'"),
+        _ => (Token::StringPlain, b": "),
+    };
+    // Searches for the terminator after the first character, resuming after each refill
+    let mut index = utf8_width(first);
+    let length = loop {
+        let data = buffer.data();
+        match data
+            .get(index..)
+            .and_then(|rest| rest.windows(terminator.len()).position(|w| w == terminator))
+        {
+            // Quoted strings include their closing quote, plain ones end before `: `
+            Some(position) if token == Token::StringPlain => break index + position,
+            Some(position) => break index + position + 1,
+            None => {
+                index = index.max(data.len().saturating_sub(terminator.len() - 1));
+                if !buffer.fill()? {
+                    break buffer.data().len();
+                }
+            }
+        }
+    };
+    let Ok(text) = std::str::from_utf8(&buffer.data()[..length]) else {
+        bail!("Invalid UTF-8 at offset {}", buffer.offset());
+    };
+    let text = text.to_string();
+    buffer.consume(length);
+    Ok((token, text))
+}
+
+/// Length of the UTF-8 sequence starting with the byte
+fn utf8_width(first: u8) -> usize {
+    match first {
+        0xf0.. => 4,
+        0xe0.. => 3,
+        0xc0.. => 2,
+        _ => 1,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use expect_test::{Expect, expect};
     use std::io::Cursor;
+    use std::io::Read;
     use std::io::Write;
 
+    /// Reader returning a single byte per read call, to exercise buffer refills
+    struct TrickleReader<'a>(&'a [u8]);
+
+    impl Read for TrickleReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
     fn test_tokenizer(input: &str, expected: Expect) {
-        let mut tokenizer = Tokenizer::new(input);
+        let output = format_tokens(Tokenizer::new(input));
+        let streamed = format_tokens(Tokenizer::from_reader(TrickleReader(input.as_bytes())));
+        assert_eq!(output, streamed, "Streaming output differs");
+        expected.assert_eq(&output);
+    }
+
+    fn format_tokens(mut tokenizer: Tokenizer) -> String {
         let mut output = Cursor::new(vec![]);
         while tokenizer.current != Token::EOF {
             tokenizer.advance().unwrap();
@@ -95,7 +215,7 @@ mod tests {
             )
             .unwrap();
         }
-        expected.assert_eq(&str::from_utf8(&output.into_inner()).unwrap());
+        String::from_utf8(output.into_inner()).unwrap()
     }
 
     #[test]
@@ -152,5 +272,22 @@ mod tests {
                 EOF [7-7] 
             "#]],
         );
+        test_tokenizer(
+            "k\u{e9}y: \u{3000}'v\u{e4}lue'",
+            expect![[r#"
+                StringPlain [0-4] kéy
+                StringPlain [4-17] : 　'välue'
+                EOF [17-17] 
+            "#]],
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let mut tokenizer = Tokenizer::from_reader(&b"foo: \xff"[..]);
+        tokenizer.advance().unwrap();
+        assert_eq!(tokenizer.current_str(), "foo");
+        let error = tokenizer.advance().unwrap_err();
+        assert_eq!(error.to_string(), "Invalid UTF-8 at offset 3");
     }
 }
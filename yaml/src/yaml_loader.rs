@@ -1,20 +1,36 @@
+use crate::token::Tokenizer;
 use loadum::event::Event;
 use loadum::loader::Loader;
+use loadum::result::LoadumResult;
+use std::io::Read;
 
+/// Loads YAML, not implemented yet so no events are produced
+///
+/// The loader reads through a [`Tokenizer`], so documents from a reader are tokenized
+/// incrementally once parsing is implemented.
 pub struct YamlLoader<'source> {
-    _source: &'source str,
+    _tokenizer: Tokenizer<'source>,
 }
 
-impl YamlLoader<'_> {
-    pub fn new(source: &str) -> YamlLoader {
-        YamlLoader { _source: source }
+impl<'source> YamlLoader<'source> {
+    pub fn new(source: &'source str) -> YamlLoader<'source> {
+        YamlLoader {
+            _tokenizer: Tokenizer::new(source),
+        }
+    }
+
+    /// Loads YAML incrementally from a reader, only buffering the input of the current token
+    pub fn from_reader(read: impl Read + 'source) -> YamlLoader<'source> {
+        YamlLoader {
+            _tokenizer: Tokenizer::from_reader(read),
+        }
     }
 }
 
-impl<'source> Loader for YamlLoader<'source> {}
+impl<'source> Loader<'source> for YamlLoader<'source> {}
 
 impl<'source> Iterator for YamlLoader<'source> {
-    type Item = Event;
+    type Item = LoadumResult<Event<'source>>;
     fn next(&mut self) -> Option<Self::Item> {
         None
     }